@@ -7,8 +7,13 @@ use crate::util::clear::Clear;
 
 type HashMap<K, V> = rustc_hash::FxHashMap<K, V>;
 
+use serde::{Deserialize, Serialize};
 use smallvec::{smallvec, SmallVec};
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "Key: Serialize, ValBuild: Serialize, ValProbe: Serialize",
+    deserialize = "Key: Deserialize<'de> + Eq + std::hash::Hash, ValBuild: Deserialize<'de>, ValProbe: Deserialize<'de>"
+))]
 pub struct HalfMultisetJoinState<Key, ValBuild, ValProbe> {
     // Here a smallvec with inline storage of 1 is chosen.
     // The rationale for this decision is that, I speculate, that joins possibly have a bimodal distribution with regards to how much key contention they have.
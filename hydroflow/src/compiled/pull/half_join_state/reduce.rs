@@ -1,9 +1,15 @@
 use std::collections::hash_map::Entry::*;
 
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
 
 use crate::util::clear::Clear;
 
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "K: Serialize, A: Serialize",
+    deserialize = "K: Deserialize<'de> + Eq + std::hash::Hash, A: Deserialize<'de>"
+))]
 pub struct HalfJoinStateReduce<K, A> {
     pub table: FxHashMap<K, A>,
 }
@@ -12,3 +12,6 @@ pub use half_join_state::*;
 
 mod anti_join;
 pub use anti_join::*;
+
+mod outer_join;
+pub use outer_join::*;
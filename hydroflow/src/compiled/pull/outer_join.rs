@@ -0,0 +1,89 @@
+use super::HalfJoinState;
+
+/// Builds all of `lhs` and `rhs` into their states, then returns the full outer join of the
+/// states. Keys which are only on one side are paired with `None` for the other side.
+///
+/// Unlike [`symmetric_hash_join_into_iter`](super::symmetric_hash_join_into_iter), this must see
+/// all the inputs before emitting any unmatched rows, so it always emits the entire join of the
+/// states. With persisted (`'static`) states, this means rows which were emitted with `None` in
+/// an earlier tick are replaced by matched rows once a matching key arrives.
+pub fn full_outer_join_into_iter<'a, Key, I1, V1, I2, V2, LhsState, RhsState>(
+    lhs: I1,
+    rhs: I2,
+    lhs_state: &'a mut LhsState,
+    rhs_state: &'a mut RhsState,
+) -> impl 'a + Iterator<Item = (Key, (Option<V1>, Option<V2>))>
+where
+    Key: 'a + Eq + std::hash::Hash + Clone,
+    V1: 'a + Clone,
+    V2: 'a + Clone,
+    I1: Iterator<Item = (Key, V1)>,
+    I2: Iterator<Item = (Key, V2)>,
+    LhsState: HalfJoinState<Key, V1, V2>,
+    RhsState: HalfJoinState<Key, V2, V1>,
+{
+    for (k, v1) in lhs {
+        lhs_state.build(k.clone(), &v1);
+    }
+    for (k, v2) in rhs {
+        rhs_state.build(k.clone(), &v2);
+    }
+
+    let (lhs_state, rhs_state) = (&*lhs_state, &*rhs_state);
+
+    let lhs_rows = lhs_state.iter().flat_map(move |(k, sv)| {
+        sv.iter().flat_map(move |v1| {
+            let mut matches = rhs_state.full_probe(k).peekable();
+            let unmatched = matches
+                .peek()
+                .is_none()
+                .then(|| (k.clone(), (Some(v1.clone()), None)));
+            matches
+                .map(move |v2| (k.clone(), (Some(v1.clone()), Some(v2.clone()))))
+                .chain(unmatched)
+        })
+    });
+    let rhs_rows = rhs_state
+        .iter()
+        .filter(move |(k, _sv)| lhs_state.full_probe(k).len() == 0)
+        .flat_map(|(k, sv)| {
+            sv.iter()
+                .map(move |v2| (k.clone(), (None, Some(v2.clone()))))
+        });
+
+    lhs_rows.chain(rhs_rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::compiled::pull::{full_outer_join_into_iter, HalfSetJoinState};
+
+    #[test]
+    fn full_outer_join() {
+        let mut lhs_state = HalfSetJoinState::default();
+        let mut rhs_state = HalfSetJoinState::default();
+
+        let lhs = [(0, "a"), (1, "b"), (1, "c")];
+        let rhs = [(1, "x"), (2, "y")];
+
+        let out: HashSet<_> = full_outer_join_into_iter(
+            lhs.into_iter(),
+            rhs.into_iter(),
+            &mut lhs_state,
+            &mut rhs_state,
+        )
+        .collect();
+
+        assert_eq!(
+            HashSet::from([
+                (0, (Some("a"), None)),
+                (1, (Some("b"), Some("x"))),
+                (1, (Some("c"), Some("x"))),
+                (2, (None, Some("y"))),
+            ]),
+            out
+        );
+    }
+}
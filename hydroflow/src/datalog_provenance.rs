@@ -0,0 +1,347 @@
+//! Provenance of the facts derived by [`datalog!`](crate::datalog) programs: which rule, and which
+//! facts, each fact was derived from.
+//!
+//! Provenance is opt-in, by declaring `.provenance` with an expression evaluating to a
+//! [`Provenance`] handle. Every fact of an `.input` or `.static` relation and every derivation by a
+//! rule are then recorded into it, and [`Provenance::explain`] returns a [`ProofTree`] for any fact, even after
+//! the tick it was derived in. Fields are recorded by their [`Debug`] representation, so all
+//! relations must have `Debug` fields.
+//!
+//! ```rust
+//! use hydroflow::datalog_provenance::Provenance;
+//!
+//! let (edges_send, edges) = hydroflow::util::unbounded_channel::<(usize, usize)>();
+//! let provenance = Provenance::default();
+//! let recorder = provenance.clone();
+//!
+//! let mut flow = hydroflow::datalog!(
+//!     r#"
+//!     .input edges `source_stream(edges)`
+//!     .output path `null()`
+//!     .provenance `recorder`
+//!
+//!     path(x, y) :- edges(x, y).
+//!     path(x, z) :- path(x, y), edges(y, z).
+//!     "#
+//! );
+//! edges_send.send((1, 2)).unwrap();
+//! edges_send.send((2, 3)).unwrap();
+//! flow.run_available();
+//!
+//! let proof = provenance.explain("path", &(1, 3)).unwrap();
+//! assert_eq!(
+//!     "path(1, 3) [rule 1: path(x, z) :- path(x, y), edges(y, z).]
+//!   path(1, 2) [rule 0: path(x, y) :- edges(x, y).]
+//!     edges(1, 2) [input]
+//!   edges(2, 3) [input]
+//! ",
+//!     proof.to_string()
+//! );
+//! ```
+//!
+//! Facts derived by aggregations or with `index()` record their rule, but not the facts aggregated
+//! over. Facts sent to other nodes by async rules (`:~`) are not recorded.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+
+/// A derivation sent by the code generated by `datalog!` to [`Provenance::record`]:
+/// `(relation, fact, (rule id, rule source), premises)`. Each premise is
+/// `(relation, negated, fields)`, with `None` fields for wildcards.
+#[doc(hidden)]
+pub type Record = (
+    &'static str,
+    Vec<String>,
+    Option<(usize, &'static str)>,
+    Vec<(&'static str, bool, Vec<Option<String>>)>,
+);
+
+/// A handle to the provenance recorded by a `datalog!` program. Cloned handles share the same
+/// provenance.
+#[derive(Clone, Default)]
+pub struct Provenance {
+    relations: Rc<RefCell<HashMap<&'static str, RelationProvenance>>>,
+}
+
+/// The facts of a relation, in the order they were first recorded, and their derivations.
+#[derive(Default)]
+struct RelationProvenance {
+    facts: Vec<(Vec<String>, Vec<Derivation>)>,
+    indices: HashMap<Vec<String>, usize>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+struct Derivation {
+    rule: Option<(usize, &'static str)>,
+    premises: Vec<Premise>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+struct Premise {
+    relation: &'static str,
+    negated: bool,
+    fields: Vec<Option<String>>,
+}
+
+impl Provenance {
+    /// Records a derivation. Called by the code generated by `datalog!`.
+    #[doc(hidden)]
+    pub fn record(&self, (relation, fact, rule, premises): Record) {
+        let derivation = Derivation {
+            rule,
+            premises: premises
+                .into_iter()
+                .map(|(relation, negated, fields)| Premise {
+                    relation,
+                    negated,
+                    fields,
+                })
+                .collect(),
+        };
+
+        let mut relations = self.relations.borrow_mut();
+        let relation = relations.entry(relation).or_default();
+        let idx = *relation.indices.entry(fact).or_insert_with_key(|fact| {
+            relation.facts.push((fact.clone(), Vec::new()));
+            relation.facts.len() - 1
+        });
+        let derivations = &mut relation.facts[idx].1;
+        if !derivations.contains(&derivation) {
+            derivations.push(derivation);
+        }
+    }
+
+    /// Returns a proof of why `fact` is in `relation`, or `None` if it was never recorded.
+    ///
+    /// Each fact in the proof is justified by the first of its derivations which does not depend
+    /// on the fact itself.
+    pub fn explain(&self, relation: &str, fact: &impl DebugFields) -> Option<ProofTree> {
+        let relations = self.relations.borrow();
+        let (relation, _) = relations.get_key_value(relation)?;
+        Prover::new(&relations).prove(relation, &fact.debug_fields())
+    }
+
+    /// Forgets all recorded provenance.
+    pub fn clear(&self) {
+        self.relations.borrow_mut().clear();
+    }
+}
+
+/// A fact of a relation: `(relation, fields)`.
+type FactKey = (&'static str, Vec<String>);
+
+/// Searches for proofs within one call to [`Provenance::explain`], memoizing the facts proven and
+/// failed so each is searched at most once.
+struct Prover<'a> {
+    relations: &'a HashMap<&'static str, RelationProvenance>,
+    /// The facts being proven, outermost first. A derivation depending on one of these is cut.
+    in_progress: Vec<FactKey>,
+    /// The lowest index in `in_progress` cut since the innermost `prove` began, if any.
+    lowest_cut: Option<usize>,
+    proven: HashMap<FactKey, ProofTree>,
+    /// Facts with no proof regardless of which facts are in progress.
+    failed: HashSet<FactKey>,
+}
+
+impl<'a> Prover<'a> {
+    fn new(relations: &'a HashMap<&'static str, RelationProvenance>) -> Self {
+        Self {
+            relations,
+            in_progress: Vec::new(),
+            lowest_cut: None,
+            proven: HashMap::new(),
+            failed: HashSet::new(),
+        }
+    }
+
+    fn prove(&mut self, relation: &'static str, fact: &[String]) -> Option<ProofTree> {
+        let relations = self.relations;
+        let relation_provenance = relations.get(relation)?;
+        let &idx = relation_provenance.indices.get(fact)?;
+        let key = (relation, fact.to_vec());
+        if let Some(proof) = self.proven.get(&key) {
+            return Some(proof.clone());
+        }
+        if self.failed.contains(&key) {
+            return None;
+        }
+        if let Some(depth) = self.in_progress.iter().position(|other| *other == key) {
+            self.lowest_cut = Some(self.lowest_cut.map_or(depth, |cut| cut.min(depth)));
+            return None;
+        }
+
+        let depth = self.in_progress.len();
+        let outer_cut = self.lowest_cut.take();
+        self.in_progress.push(key);
+        let step = relation_provenance.facts[idx]
+            .1
+            .iter()
+            .find_map(|derivation| {
+                let Some((id, source)) = derivation.rule else {
+                    return Some(ProofStep::Input);
+                };
+                let premises = derivation
+                    .premises
+                    .iter()
+                    .map(|premise| self.prove_premise(premise))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(ProofStep::Rule {
+                    id,
+                    source,
+                    premises,
+                })
+            });
+        let key = self.in_progress.pop().unwrap();
+
+        // Cuts of this fact itself don't depend on the facts in progress outside of it, but cuts
+        // of those facts do, so they are passed on.
+        let cut = self.lowest_cut.filter(|&cut| cut < depth);
+        self.lowest_cut = match (outer_cut, cut) {
+            (Some(outer), Some(cut)) => Some(outer.min(cut)),
+            (outer, cut) => outer.or(cut),
+        };
+
+        let Some(step) = step else {
+            if cut.is_none() {
+                self.failed.insert(key);
+            }
+            return None;
+        };
+        let proof = ProofTree {
+            relation: relation.to_owned(),
+            fact: fact.to_vec(),
+            step,
+        };
+        self.proven.insert(key, proof.clone());
+        Some(proof)
+    }
+
+    fn prove_premise(&mut self, premise: &Premise) -> Option<ProofTree> {
+        if premise.negated {
+            return Some(ProofTree {
+                relation: premise.relation.to_owned(),
+                fact: premise
+                    .fields
+                    .iter()
+                    .map(|field| field.clone().unwrap_or_else(|| "_".to_owned()))
+                    .collect(),
+                step: ProofStep::Absent,
+            });
+        }
+
+        if let Some(fact) = premise.fields.iter().cloned().collect::<Option<Vec<_>>>() {
+            return self.prove(premise.relation, &fact);
+        }
+        // Any fact matching the wildcards will do.
+        let relations = self.relations;
+        relations
+            .get(premise.relation)?
+            .facts
+            .iter()
+            .filter(|(fact, _)| {
+                fact.len() == premise.fields.len()
+                    && fact
+                        .iter()
+                        .zip(premise.fields.iter())
+                        .all(|(value, field)| field.as_ref().map_or(true, |field| field == value))
+            })
+            .find_map(|(fact, _)| self.prove(premise.relation, fact))
+    }
+}
+
+/// A proof of why a fact was derived, returned by [`Provenance::explain`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofTree {
+    /// The relation of the fact.
+    pub relation: String,
+    /// The [`Debug`] representation of each field of the fact, or `_` for wildcards of an
+    /// [`ProofStep::Absent`] fact.
+    pub fact: Vec<String>,
+    /// How the fact was derived.
+    pub step: ProofStep,
+}
+
+/// How the fact of a [`ProofTree`] was derived.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProofStep {
+    /// The fact was received by an `.input` or `.static` relation, or from another node.
+    Input,
+    /// The fact was derived by a rule, from a proof of each of the rule's relation atoms.
+    Rule {
+        /// Index of the rule in the program, starting at 0.
+        id: usize,
+        /// Source code of the rule.
+        source: &'static str,
+        /// Proofs of the rule's relation atoms, in order. Empty for aggregations.
+        premises: Vec<ProofTree>,
+    },
+    /// The fact was required to be absent by a negated atom `!relation(..)`.
+    Absent,
+}
+
+impl ProofTree {
+    fn fmt_indented(&self, f: &mut Formatter<'_>, depth: usize) -> std::fmt::Result {
+        let negation = if let ProofStep::Absent = self.step {
+            "!"
+        } else {
+            ""
+        };
+        write!(
+            f,
+            "{:indent$}{}{}({})",
+            "",
+            negation,
+            self.relation,
+            self.fact.join(", "),
+            indent = 2 * depth
+        )?;
+        match &self.step {
+            ProofStep::Input => writeln!(f, " [input]"),
+            ProofStep::Absent => writeln!(f, " [absent]"),
+            ProofStep::Rule {
+                id,
+                source,
+                premises,
+            } => {
+                writeln!(f, " [rule {}: {}]", id, source)?;
+                for premise in premises {
+                    premise.fmt_indented(f, depth + 1)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Display for ProofTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+/// Facts which can be explained by [`Provenance::explain`], i.e. tuples of [`Debug`] fields.
+pub trait DebugFields {
+    /// The [`Debug`] representation of each field.
+    fn debug_fields(&self) -> Vec<String>;
+}
+
+macro_rules! impl_debug_fields {
+    ($($field:ident: $idx:tt),*) => {
+        impl<$($field: Debug),*> DebugFields for ($($field,)*) {
+            fn debug_fields(&self) -> Vec<String> {
+                vec![$(format!("{:?}", self.$idx)),*]
+            }
+        }
+    };
+}
+impl_debug_fields!();
+impl_debug_fields!(A: 0);
+impl_debug_fields!(A: 0, B: 1);
+impl_debug_fields!(A: 0, B: 1, C: 2);
+impl_debug_fields!(A: 0, B: 1, C: 2, D: 3);
+impl_debug_fields!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_debug_fields!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+impl_debug_fields!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+impl_debug_fields!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
@@ -0,0 +1,753 @@
+//! Compiles a parsed Datalog program into rules which can be evaluated over [`Tuple`](super::Tuple)s.
+
+use std::collections::HashMap;
+
+use hydroflow_datalog_core::grammar::datalog::{
+    Aggregation, Atom, BoolExpr, BoolOp, Declaration, Ident, IdentOrUnderscore, InputRelationExpr,
+    IntExpr, Program, Rule, RuleType, TargetExpr,
+};
+use hydroflow_datalog_core::stratify;
+
+use super::ProgramError;
+
+/// The name of the built-in relation `less_than(x, n)`, which binds `x` to each integer in `0..n`.
+const LESS_THAN: &str = "less_than";
+
+/// A relation declared or used by the program.
+#[derive(Debug)]
+pub(super) struct RelationInfo {
+    pub name: String,
+    /// Number of fields, if the relation is used by any rule.
+    pub arity: Option<usize>,
+    /// If facts are kept across ticks.
+    pub persisted: bool,
+    pub input: bool,
+    pub output: bool,
+    /// If any rule derives facts for this relation.
+    pub derived: bool,
+}
+
+/// How a field of a relation atom relates to the rule's variables, which are numbered slots.
+#[derive(Debug)]
+pub(super) enum Term {
+    /// Must equal the variable, which was bound by an earlier atom.
+    Bound(usize),
+    /// Binds the variable.
+    Bind(usize),
+    /// Must equal the variable, which was bound by an earlier field of the same atom.
+    Same(usize),
+    /// Wildcard `_`.
+    Any,
+}
+
+#[derive(Debug)]
+pub(super) enum Expr {
+    Var(usize),
+    Int(i64),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Mod(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(super) enum CmpOp {
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Eq,
+    Neq,
+}
+
+/// One step of evaluating a rule body. Each step extends or filters the variable bindings of the
+/// steps before it.
+#[derive(Debug)]
+pub(super) enum Step {
+    /// Joins with the facts of a relation.
+    Scan {
+        relation: usize,
+        terms: Vec<Term>,
+        /// Columns with [`Term::Bound`] terms, used to look up matching facts.
+        key_columns: Vec<usize>,
+        /// If the relation is in the same stratum as the rule's target, so changes while the
+        /// stratum is evaluated.
+        recursive: bool,
+    },
+    /// Keeps bindings which match no fact of a relation. All terms are [`Term::Bound`] or
+    /// [`Term::Any`].
+    Negate {
+        relation: usize,
+        terms: Vec<Term>,
+        key_columns: Vec<usize>,
+    },
+    /// Keeps bindings for which the comparison is true.
+    Filter { left: Expr, op: CmpOp, right: Expr },
+    /// `less_than(value, threshold)`: keeps or binds integer values in `0..threshold`. The value
+    /// is [`Term::Bound`], [`Term::Bind`], or [`Term::Any`].
+    LessThan { value: Term, threshold: usize },
+}
+
+#[derive(Debug)]
+pub(super) enum Agg {
+    Min(usize),
+    Max(usize),
+    Sum(usize),
+    Count,
+    CountUnique(Vec<usize>),
+    Choose(usize),
+}
+
+#[derive(Debug)]
+pub(super) enum HeadField {
+    Expr(Expr),
+    Agg(Agg),
+}
+
+#[derive(Debug)]
+pub(super) struct CompiledRule {
+    pub target: usize,
+    /// If derived facts are inserted in the next tick (`:+`).
+    pub next_tick: bool,
+    pub steps: Vec<Step>,
+    pub head: Vec<HeadField>,
+    pub num_slots: usize,
+}
+impl CompiledRule {
+    pub fn has_aggregation(&self) -> bool {
+        self.head
+            .iter()
+            .any(|field| matches!(field, HeadField::Agg(_)))
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct CompiledProgram {
+    pub relations: Vec<RelationInfo>,
+    pub rules: Vec<CompiledRule>,
+    /// Indices of the same-tick rules to evaluate in each stratum, in order.
+    pub strata: Vec<Vec<usize>>,
+    /// Indices of the next-tick rules.
+    pub next_tick_rules: Vec<usize>,
+}
+
+/// Converts byte offsets into the source to [`ProgramError`]s.
+struct Errors<'a> {
+    source: &'a str,
+    errors: Vec<ProgramError>,
+}
+impl Errors<'_> {
+    fn push(&mut self, span: (usize, usize), message: impl Into<String>) {
+        self.errors
+            .push(ProgramError::new(self.source, span, message.into()));
+    }
+}
+
+pub(super) fn compile(source: &str) -> Result<CompiledProgram, Vec<ProgramError>> {
+    let mut errors = Errors {
+        source,
+        errors: Vec::new(),
+    };
+    let program: Program = match hydroflow_datalog_core::grammar::datalog::parse(source) {
+        Ok(program) => program,
+        Err(parse_errors) => {
+            for (span, message) in hydroflow_datalog_core::flatten_parse_errors(parse_errors) {
+                errors.push(span, message);
+            }
+            return Err(errors.errors);
+        }
+    };
+
+    let mut relations = Relations::default();
+    let mut rules = Vec::new();
+    for decl in &program.rules {
+        match decl {
+            Declaration::Input(_, ident, _, _) => relations.get_or_insert(&ident.name).input = true,
+            Declaration::Output(_, ident, _, _) => {
+                relations.get_or_insert(&ident.name).output = true
+            }
+            Declaration::Persist(_, ident, _) => {
+                relations.get_or_insert(&ident.name).persisted = true
+            }
+            // Join order hints do not apply, as rules are evaluated in textual order.
+            Declaration::Size(..) => {}
+            Declaration::Async(..) => errors.push(
+                decl_span(decl),
+                "`.async` relations are not supported by the runtime interpreter",
+            ),
+            Declaration::Provenance(..) => errors.push(
+                decl_span(decl),
+                "`.provenance` is not supported by the runtime interpreter",
+            ),
+            Declaration::Static(..) => errors.push(
+                decl_span(decl),
+                "`.static` relations are not supported by the runtime interpreter, send the facts to an `.input` instead",
+            ),
+            Declaration::Rule(rule) => rules.push(rule),
+        }
+    }
+    for decl in &program.rules {
+        let (name, span) = match decl {
+            Declaration::Input(_, ident, schema, _)
+            | Declaration::Output(_, ident, schema, _)
+            | Declaration::Persist(_, ident, schema) => {
+                // Field types are not checked, as all values are dynamically typed.
+                if let Some(schema) = schema {
+                    let id = relations.id(&ident.name);
+                    relations.check_arity(id, schema.fields.len(), ident.span, &mut errors);
+                }
+                (&ident.name, ident.span)
+            }
+            Declaration::Rule(rule) => (&rule.target.name.name, rule.target.name.span),
+            _ => continue,
+        };
+        if LESS_THAN == name {
+            errors.push(span, format!("`{}` is a built-in relation", LESS_THAN));
+        }
+    }
+
+    // Targets are known before compiling bodies, which may use relations derived later.
+    for rule in &rules {
+        relations.get_or_insert(&rule.target.name.name).derived = true;
+    }
+
+    let mut compiled = Vec::new();
+    for rule in rules {
+        if let Some(rule) = compile_rule(rule, &mut relations, &mut errors) {
+            compiled.push(rule);
+        }
+    }
+    if !errors.errors.is_empty() {
+        return Err(errors.errors);
+    }
+
+    let relation_strata = stratify(&relations, &compiled, &mut errors);
+    if !errors.errors.is_empty() {
+        return Err(errors.errors);
+    }
+    let relation_strata = relation_strata.unwrap();
+
+    let num_strata = relation_strata
+        .iter()
+        .copied()
+        .max()
+        .map_or(0, |max| max + 1);
+    let mut strata = vec![Vec::new(); num_strata];
+    let mut next_tick_rules = Vec::new();
+    for (rule_idx, (rule, _)) in compiled.iter_mut().enumerate() {
+        if rule.next_tick {
+            next_tick_rules.push(rule_idx);
+            continue;
+        }
+        let stratum = relation_strata[rule.target];
+        strata[stratum].push(rule_idx);
+        for step in rule.steps.iter_mut() {
+            if let Step::Scan {
+                relation,
+                recursive,
+                ..
+            } = step
+            {
+                *recursive = relation_strata[*relation] == stratum;
+            }
+        }
+    }
+
+    Ok(CompiledProgram {
+        relations: relations.infos,
+        rules: compiled.into_iter().map(|(rule, _)| rule).collect(),
+        strata,
+        next_tick_rules,
+    })
+}
+
+fn decl_span(decl: &Declaration) -> (usize, usize) {
+    match decl {
+        Declaration::Input(_, ident, _, _)
+        | Declaration::Output(_, ident, _, _)
+        | Declaration::Persist(_, ident, _)
+        | Declaration::Size(_, ident, _)
+        | Declaration::Async(_, ident, _, _)
+        | Declaration::Static(_, ident, _) => ident.span,
+        Declaration::Provenance(_, recorder) => recorder.code.span,
+        Declaration::Rule(rule) => rule.span,
+    }
+}
+
+#[derive(Default)]
+struct Relations {
+    infos: Vec<RelationInfo>,
+    ids: HashMap<String, usize>,
+}
+impl Relations {
+    fn id(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.infos.len();
+        self.infos.push(RelationInfo {
+            name: name.to_owned(),
+            arity: None,
+            persisted: false,
+            input: false,
+            output: false,
+            derived: false,
+        });
+        self.ids.insert(name.to_owned(), id);
+        id
+    }
+
+    fn get_or_insert(&mut self, name: &str) -> &mut RelationInfo {
+        let id = self.id(name);
+        &mut self.infos[id]
+    }
+
+    /// Checks a positive or negated relation atom in a rule body, returning the relation's id.
+    fn check_body_relation(
+        &mut self,
+        relation: &InputRelationExpr,
+        span: (usize, usize),
+        errors: &mut Errors<'_>,
+    ) -> Option<usize> {
+        let id = self.id(&relation.name.name);
+        self.check_arity(id, relation.fields.len(), span, errors);
+        let info = &self.infos[id];
+        if !info.input && !info.derived {
+            errors.push(
+                relation.name.span,
+                format!(
+                    "Relation `{}` is neither an `.input` nor derived by any rule",
+                    info.name
+                ),
+            );
+            return None;
+        }
+        Some(id)
+    }
+
+    /// Checks that a use of a relation has the same arity as the others.
+    fn check_arity(
+        &mut self,
+        id: usize,
+        arity: usize,
+        span: (usize, usize),
+        errors: &mut Errors<'_>,
+    ) {
+        let info = &mut self.infos[id];
+        match info.arity {
+            None => info.arity = Some(arity),
+            Some(expected) if expected != arity => errors.push(
+                span,
+                format!(
+                    "Relation `{}` has {} fields here, but {} fields elsewhere",
+                    info.name, arity, expected
+                ),
+            ),
+            Some(_) => {}
+        }
+    }
+}
+
+/// Variables of a rule, in the order they are bound.
+#[derive(Default)]
+struct Variables {
+    slots: HashMap<String, usize>,
+}
+impl Variables {
+    fn get(&self, name: &str) -> Option<usize> {
+        self.slots.get(name).copied()
+    }
+
+    fn bind(&mut self, name: &str) -> usize {
+        let next = self.slots.len();
+        *self.slots.entry(name.to_owned()).or_insert(next)
+    }
+}
+
+/// A relation a rule body depends on: `(relation, negative, span)`. Dependencies through negation
+/// or aggregation are negative.
+type Dependency = (usize, bool, (usize, usize));
+
+/// A rule body atom which only filters or extends bindings, placed once its variables are bound.
+enum Pending<'a> {
+    Negate(&'a InputRelationExpr, (usize, usize)),
+    Predicate(&'a BoolExpr, (usize, usize)),
+    LessThan(&'a InputRelationExpr, (usize, usize)),
+}
+impl Pending<'_> {
+    /// Variables which must be bound before this is placed.
+    fn required(&self) -> Vec<&str> {
+        match self {
+            Pending::Negate(relation, _) => relation
+                .fields
+                .iter()
+                .filter_map(|field| match &field.value {
+                    IdentOrUnderscore::Ident(ident) => Some(&*ident.name),
+                    IdentOrUnderscore::Underscore(_) => None,
+                })
+                .collect(),
+            Pending::Predicate(pred, _) => pred
+                .left
+                .idents()
+                .into_iter()
+                .chain(pred.right.idents())
+                .map(|ident| &*ident.name)
+                .collect(),
+            // Only the threshold must be bound, the value may be bound by `less_than` itself.
+            Pending::LessThan(relation, _) => relation
+                .fields
+                .get(1)
+                .and_then(|field| match &field.value {
+                    IdentOrUnderscore::Ident(ident) => Some(&*ident.name),
+                    IdentOrUnderscore::Underscore(_) => None,
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+fn compile_rule(
+    rule: &Rule,
+    relations: &mut Relations,
+    errors: &mut Errors<'_>,
+) -> Option<(CompiledRule, Vec<Dependency>)> {
+    let error_count = errors.errors.len();
+    let next_tick = match &rule.rule_type.value {
+        RuleType::Sync(_) => false,
+        RuleType::NextTick(_) => true,
+        RuleType::Async(_) => {
+            errors.push(
+                rule.rule_type.span,
+                "Async rules (`:~`) are not supported by the runtime interpreter",
+            );
+            return None;
+        }
+    };
+    if let Some(at_node) = &rule.target.at_node {
+        errors.push(
+            at_node.node.span,
+            "Rule targets on other nodes (`@`) are not supported by the runtime interpreter",
+        );
+        return None;
+    }
+
+    let mut vars = Variables::default();
+    let mut steps = Vec::new();
+    let mut dependencies = Vec::new();
+    let mut pending = Vec::new();
+
+    for atom in &rule.sources {
+        match atom {
+            Atom::Relation(None, relation) if LESS_THAN == relation.name.name => {
+                if 2 != relation.fields.len() {
+                    errors.push(
+                        relation.span,
+                        format!("`{}` must have exactly two fields", LESS_THAN),
+                    );
+                }
+                pending.push(Pending::LessThan(relation, relation.span));
+            }
+            Atom::Relation(None, relation) => {
+                let Some(id) = relations.check_body_relation(relation, relation.span, errors)
+                else {
+                    continue;
+                };
+                let bound_before = vars.slots.len();
+                let terms = relation
+                    .fields
+                    .iter()
+                    .map(|field| match &field.value {
+                        IdentOrUnderscore::Ident(ident) => match vars.get(&ident.name) {
+                            Some(slot) if slot < bound_before => Term::Bound(slot),
+                            Some(slot) => Term::Same(slot),
+                            None => Term::Bind(vars.bind(&ident.name)),
+                        },
+                        IdentOrUnderscore::Underscore(_) => Term::Any,
+                    })
+                    .collect::<Vec<_>>();
+                let key_columns = key_columns(&terms);
+                steps.push(Step::Scan {
+                    relation: id,
+                    terms,
+                    key_columns,
+                    recursive: false,
+                });
+                dependencies.push((id, false, relation.span));
+            }
+            Atom::Relation(Some(()), relation) => {
+                if LESS_THAN == relation.name.name {
+                    errors.push(relation.span, format!("`{}` cannot be negated", LESS_THAN));
+                    continue;
+                }
+                let Some(id) = relations.check_body_relation(relation, relation.span, errors)
+                else {
+                    continue;
+                };
+                dependencies.push((id, true, relation.span));
+                pending.push(Pending::Negate(relation, relation.span));
+            }
+            Atom::Predicate(pred) => pending.push(Pending::Predicate(pred, pred.span)),
+        }
+        place_pending(&mut pending, &mut vars, &mut steps, relations);
+    }
+    place_pending(&mut pending, &mut vars, &mut steps, relations);
+
+    for unplaced in pending {
+        let (kind, span) = match unplaced {
+            Pending::Negate(_, span) => ("negated atom", span),
+            Pending::Predicate(_, span) => ("predicate", span),
+            Pending::LessThan(_, span) => ("`less_than` threshold", span),
+        };
+        let unbound = unplaced
+            .required()
+            .into_iter()
+            .filter(|name| vars.get(name).is_none())
+            .map(|name| format!("`{}`", name))
+            .collect::<Vec<_>>();
+        errors.push(
+            span,
+            format!(
+                "Variables {} in {} must be bound by a relation in the rule body",
+                unbound.join(", "),
+                kind
+            ),
+        );
+    }
+
+    let target = relations.id(&rule.target.name.name);
+    relations.check_arity(
+        target,
+        rule.target.fields.len(),
+        rule.target.name.span,
+        errors,
+    );
+    let head = rule
+        .target
+        .fields
+        .iter()
+        .filter_map(|field| compile_head_field(&field.value, field.span, &vars, errors))
+        .collect::<Vec<_>>();
+
+    if error_count != errors.errors.len() {
+        return None;
+    }
+
+    let rule = CompiledRule {
+        target,
+        next_tick,
+        steps,
+        head,
+        num_slots: vars.slots.len(),
+    };
+    if rule.has_aggregation() {
+        // Aggregates are only correct once all of their inputs are known.
+        for (_, negative, _) in dependencies.iter_mut() {
+            *negative = true;
+        }
+    }
+    Some((rule, dependencies))
+}
+
+/// Returns the columns with [`Term::Bound`] terms.
+fn key_columns(terms: &[Term]) -> Vec<usize> {
+    terms
+        .iter()
+        .enumerate()
+        .filter(|(_, term)| matches!(term, Term::Bound(_)))
+        .map(|(column, _)| column)
+        .collect()
+}
+
+/// Places each pending atom whose variables are all bound, until none can be placed.
+fn place_pending(
+    pending: &mut Vec<Pending<'_>>,
+    vars: &mut Variables,
+    steps: &mut Vec<Step>,
+    relations: &mut Relations,
+) {
+    while let Some(idx) = pending.iter().position(|pending| {
+        pending
+            .required()
+            .into_iter()
+            .all(|name| vars.get(name).is_some())
+    }) {
+        let step = match pending.remove(idx) {
+            Pending::Negate(relation, _) => {
+                let terms = relation
+                    .fields
+                    .iter()
+                    .map(|field| match &field.value {
+                        IdentOrUnderscore::Ident(ident) => {
+                            Term::Bound(vars.get(&ident.name).unwrap())
+                        }
+                        IdentOrUnderscore::Underscore(_) => Term::Any,
+                    })
+                    .collect::<Vec<_>>();
+                Step::Negate {
+                    relation: relations.id(&relation.name.name),
+                    key_columns: key_columns(&terms),
+                    terms,
+                }
+            }
+            Pending::Predicate(pred, _) => Step::Filter {
+                left: compile_expr(&pred.left, vars),
+                op: match pred.op {
+                    BoolOp::Lt(_) => CmpOp::Lt,
+                    BoolOp::LtEq(_) => CmpOp::LtEq,
+                    BoolOp::Gt(_) => CmpOp::Gt,
+                    BoolOp::GtEq(_) => CmpOp::GtEq,
+                    BoolOp::Eq(_) => CmpOp::Eq,
+                    BoolOp::Neq(_) => CmpOp::Neq,
+                },
+                right: compile_expr(&pred.right, vars),
+            },
+            Pending::LessThan(relation, _) => {
+                let value = match relation.fields.first().map(|field| &field.value) {
+                    Some(IdentOrUnderscore::Ident(ident)) => match vars.get(&ident.name) {
+                        Some(slot) => Term::Bound(slot),
+                        None => Term::Bind(vars.bind(&ident.name)),
+                    },
+                    _ => Term::Any,
+                };
+                let Some(IdentOrUnderscore::Ident(threshold)) =
+                    relation.fields.get(1).map(|field| &field.value)
+                else {
+                    // An `_` threshold is never bound.
+                    continue;
+                };
+                Step::LessThan {
+                    value,
+                    threshold: vars.get(&threshold.name).unwrap(),
+                }
+            }
+        };
+        steps.push(step);
+    }
+}
+
+/// Compiles an expression whose variables are all bound.
+fn compile_expr(expr: &IntExpr, vars: &Variables) -> Expr {
+    let binary = |l: &IntExpr, r: &IntExpr| {
+        (
+            Box::new(compile_expr(l, vars)),
+            Box::new(compile_expr(r, vars)),
+        )
+    };
+    match expr {
+        IntExpr::Ident(ident) => Expr::Var(vars.get(&ident.name).unwrap()),
+        IntExpr::Integer(int) => Expr::Int(int.value),
+        IntExpr::Parenthesized(_, inner, _) => compile_expr(inner, vars),
+        IntExpr::Add(l, _, r) => {
+            let (l, r) = binary(l, r);
+            Expr::Add(l, r)
+        }
+        IntExpr::Sub(l, _, r) => {
+            let (l, r) = binary(l, r);
+            Expr::Sub(l, r)
+        }
+        IntExpr::Mul(l, _, r) => {
+            let (l, r) = binary(l, r);
+            Expr::Mul(l, r)
+        }
+        IntExpr::Mod(l, _, r) => {
+            let (l, r) = binary(l, r);
+            Expr::Mod(l, r)
+        }
+    }
+}
+
+fn compile_head_field(
+    field: &TargetExpr,
+    span: (usize, usize),
+    vars: &Variables,
+    errors: &mut Errors<'_>,
+) -> Option<HeadField> {
+    let unbound = field
+        .idents()
+        .into_iter()
+        .filter(|ident| vars.get(&ident.name).is_none())
+        .map(|ident| format!("`{}`", ident.name))
+        .collect::<Vec<_>>();
+    if !unbound.is_empty() {
+        errors.push(
+            span,
+            format!(
+                "Variables {} in the rule head must be bound by a relation in the rule body",
+                unbound.join(", ")
+            ),
+        );
+        return None;
+    }
+    let slot = |ident: &Ident| vars.get(&ident.name).unwrap();
+    let field = match field {
+        TargetExpr::Expr(expr) => HeadField::Expr(compile_expr(expr, vars)),
+        TargetExpr::Aggregation(agg) => HeadField::Agg(match agg {
+            Aggregation::Min(_, _, ident, _) => Agg::Min(slot(ident)),
+            Aggregation::Max(_, _, ident, _) => Agg::Max(slot(ident)),
+            Aggregation::Sum(_, _, ident, _) => Agg::Sum(slot(ident)),
+            Aggregation::Count(_) => Agg::Count,
+            Aggregation::CountUnique(_, _, idents, _) => {
+                Agg::CountUnique(idents.iter().map(|ident| slot(ident)).collect())
+            }
+            Aggregation::Choose(_, _, ident, _) => Agg::Choose(slot(ident)),
+        }),
+        TargetExpr::Index(..) => {
+            errors.push(
+                span,
+                "`index()` is not supported by the runtime interpreter",
+            );
+            return None;
+        }
+    };
+    Some(field)
+}
+
+/// Assigns each relation to a stratum, such that each relation depends negatively only on
+/// relations in earlier strata. Returns `None` and reports an error for each negative dependency
+/// inside a cycle.
+fn stratify(
+    relations: &Relations,
+    rules: &[(CompiledRule, Vec<Dependency>)],
+    errors: &mut Errors<'_>,
+) -> Option<Vec<usize>> {
+    // Edges from each body relation to the target relation. Next-tick rules do not constrain
+    // strata, as their facts are only visible in later ticks.
+    let (dependencies, spans): (Vec<_>, Vec<_>) = rules
+        .iter()
+        .filter(|(rule, _)| !rule.next_tick)
+        .flat_map(|(rule, deps)| {
+            deps.iter().map(|&(src, negative, span)| {
+                let dependency = stratify::Dependency {
+                    src,
+                    dst: rule.target,
+                    negative,
+                };
+                (dependency, span)
+            })
+        })
+        .unzip();
+
+    match stratify::stratify(relations.infos.len(), &dependencies) {
+        Ok(strata) => Some(strata),
+        Err(negative_cycles) => {
+            for negative_cycle in negative_cycles {
+                let dependency = dependencies[negative_cycle.dependency];
+                let cycle = negative_cycle
+                    .cycle
+                    .iter()
+                    .map(|&id| format!("`{}`", relations.infos[id].name))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                errors.push(
+                    spans[negative_cycle.dependency],
+                    format!(
+                        "Program is not stratifiable: `{}` depends on `{}` through negation or aggregation, inside the recursive cycle {}",
+                        relations.infos[dependency.dst].name, relations.infos[dependency.src].name, cycle
+                    ),
+                );
+            }
+            None
+        }
+    }
+}
@@ -0,0 +1,513 @@
+//! Semi-naive evaluation of a [`CompiledProgram`], one tick at a time.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use super::compile::{Agg, CmpOp, CompiledProgram, CompiledRule, Expr, HeadField, Step, Term};
+use super::{Tuple, Value};
+
+/// The facts of a relation, with hash indexes on the column sets used to look them up.
+#[derive(Default)]
+struct Relation {
+    /// Facts in insertion order, so ranges of positions identify the facts added in each round.
+    facts: Vec<Tuple>,
+    set: HashSet<Tuple>,
+    indexes: HashMap<Vec<usize>, Index>,
+}
+
+#[derive(Default)]
+struct Index {
+    /// Positions of the facts with each key, ascending.
+    positions: HashMap<Vec<Value>, Vec<usize>>,
+    /// Number of facts indexed so far.
+    len: usize,
+}
+
+impl Relation {
+    fn insert(&mut self, fact: Tuple) {
+        if self.set.insert(fact.clone()) {
+            self.facts.push(fact);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.facts.clear();
+        self.set.clear();
+        self.indexes.clear();
+    }
+
+    /// Indexes any facts inserted since the index on `columns` was last updated.
+    fn update_index(&mut self, columns: &[usize]) {
+        if columns.is_empty() {
+            return;
+        }
+        let Self { facts, indexes, .. } = self;
+        let index = indexes.entry(columns.to_vec()).or_default();
+        for (pos, fact) in facts.iter().enumerate().skip(index.len) {
+            let key = columns.iter().map(|&col| fact[col].clone()).collect();
+            index.positions.entry(key).or_default().push(pos);
+        }
+        index.len = facts.len();
+    }
+
+    /// Facts at positions in `range` whose `columns` equal `key`. The index on `columns` must be
+    /// up to date.
+    fn lookup<'a>(
+        &'a self,
+        columns: &[usize],
+        key: &[Value],
+        (lo, hi): (usize, usize),
+    ) -> Box<dyn 'a + Iterator<Item = &'a Tuple>> {
+        if columns.is_empty() {
+            return Box::new(self.facts[lo..hi].iter());
+        }
+        let Some(positions) = self.indexes[columns].positions.get(key) else {
+            return Box::new(std::iter::empty());
+        };
+        let start = positions.partition_point(|&pos| pos < lo);
+        let end = positions.partition_point(|&pos| pos < hi);
+        Box::new(positions[start..end].iter().map(|&pos| &self.facts[pos]))
+    }
+}
+
+/// Evaluates a program, keeping the facts of each relation between ticks.
+pub(super) struct Evaluator {
+    program: Arc<CompiledProgram>,
+    relations: Vec<Relation>,
+    /// Facts derived by next-tick rules, inserted at the start of the next tick.
+    deferred: Vec<(usize, Tuple)>,
+}
+
+impl Evaluator {
+    pub fn new(program: Arc<CompiledProgram>) -> Self {
+        let relations = program
+            .relations
+            .iter()
+            .map(|_| Relation::default())
+            .collect();
+        Self {
+            program,
+            relations,
+            deferred: Vec::new(),
+        }
+    }
+
+    /// Returns the facts of a relation.
+    pub fn facts(&self, relation: usize) -> &[Tuple] {
+        &self.relations[relation].facts
+    }
+
+    /// Returns if next-tick rules derived facts for the next tick.
+    pub fn has_deferred(&self) -> bool {
+        !self.deferred.is_empty()
+    }
+
+    /// Runs a tick with the given input facts, deriving the facts of every relation.
+    pub fn run_tick(&mut self, inputs: impl IntoIterator<Item = (usize, Tuple)>) {
+        let program = Arc::clone(&self.program);
+
+        for (info, relation) in program.relations.iter().zip(self.relations.iter_mut()) {
+            if !info.persisted {
+                relation.clear();
+            }
+        }
+        let deferred = std::mem::take(&mut self.deferred);
+        for (id, fact) in deferred.into_iter().chain(inputs) {
+            let info = &program.relations[id];
+            if info.arity.is_some_and(|arity| arity != fact.len()) {
+                tracing::warn!(
+                    relation = info.name,
+                    ?fact,
+                    "Fact has the wrong number of fields, dropping it."
+                );
+                continue;
+            }
+            self.relations[id].insert(fact);
+        }
+
+        for stratum in &program.strata {
+            self.run_stratum(stratum);
+        }
+
+        for &rule_idx in &program.next_tick_rules {
+            let rule = &program.rules[rule_idx];
+            self.update_indexes(rule);
+            let ranges = self.full_ranges(rule);
+            let derived = self.eval_rule(rule, &ranges);
+            self.deferred
+                .extend(derived.into_iter().map(|fact| (rule.target, fact)));
+        }
+    }
+
+    /// Evaluates the rules of a stratum to a fixed point.
+    ///
+    /// Rules are first evaluated over all facts. Then each round joins only the facts derived in
+    /// the previous round, the delta, against the rest: for each scan of a relation in this
+    /// stratum, the rule is evaluated with that scan over the delta, earlier such scans over the
+    /// facts before the delta, and later scans over all facts.
+    fn run_stratum(&mut self, rules: &[usize]) {
+        let program = Arc::clone(&self.program);
+        let rules = rules
+            .iter()
+            .map(|&rule_idx| &program.rules[rule_idx])
+            .collect::<Vec<_>>();
+
+        let mut derived = Vec::new();
+        for rule in &rules {
+            self.update_indexes(rule);
+            let ranges = self.full_ranges(rule);
+            derived.extend(
+                self.eval_rule(rule, &ranges)
+                    .into_iter()
+                    .map(|fact| (rule.target, fact)),
+            );
+        }
+
+        loop {
+            let delta_start = self.lens();
+            for (id, fact) in derived.drain(..) {
+                self.relations[id].insert(fact);
+            }
+            let has_delta = rules
+                .iter()
+                .any(|rule| delta_start[rule.target] < self.relations[rule.target].facts.len());
+            if !has_delta {
+                break;
+            }
+
+            for rule in &rules {
+                if rule.has_aggregation() {
+                    continue;
+                }
+                self.update_indexes(rule);
+                for (delta_idx, step) in rule.steps.iter().enumerate() {
+                    let &Step::Scan {
+                        relation,
+                        recursive: true,
+                        ..
+                    } = step
+                    else {
+                        continue;
+                    };
+                    if delta_start[relation] == self.relations[relation].facts.len() {
+                        continue;
+                    }
+                    let ranges = rule
+                        .steps
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, step)| match step {
+                            &Step::Scan {
+                                relation,
+                                recursive,
+                                ..
+                            } => {
+                                let len = self.relations[relation].facts.len();
+                                if !recursive || delta_idx < idx {
+                                    (0, len)
+                                } else if delta_idx == idx {
+                                    (delta_start[relation], len)
+                                } else {
+                                    (0, delta_start[relation])
+                                }
+                            }
+                            _ => (0, 0),
+                        })
+                        .collect::<Vec<_>>();
+                    derived.extend(
+                        self.eval_rule(rule, &ranges)
+                            .into_iter()
+                            .map(|fact| (rule.target, fact)),
+                    );
+                }
+            }
+        }
+    }
+
+    fn lens(&self) -> Vec<usize> {
+        self.relations
+            .iter()
+            .map(|relation| relation.facts.len())
+            .collect()
+    }
+
+    /// Ranges covering all facts, for each step of the rule.
+    fn full_ranges(&self, rule: &CompiledRule) -> Vec<(usize, usize)> {
+        rule.steps
+            .iter()
+            .map(|step| match step {
+                Step::Scan { relation, .. } => (0, self.relations[*relation].facts.len()),
+                _ => (0, 0),
+            })
+            .collect()
+    }
+
+    fn update_indexes(&mut self, rule: &CompiledRule) {
+        for step in &rule.steps {
+            match step {
+                Step::Scan {
+                    relation,
+                    key_columns,
+                    ..
+                }
+                | Step::Negate {
+                    relation,
+                    key_columns,
+                    ..
+                } => self.relations[*relation].update_index(key_columns),
+                Step::Filter { .. } | Step::LessThan { .. } => {}
+            }
+        }
+    }
+
+    /// Evaluates a rule with each scan step over the facts at the positions in `ranges`, returning
+    /// the derived facts.
+    fn eval_rule(&self, rule: &CompiledRule, ranges: &[(usize, usize)]) -> Vec<Tuple> {
+        let mut env = vec![Value::Int(0); rule.num_slots];
+        if !rule.has_aggregation() {
+            let mut derived = Vec::new();
+            self.eval_steps(rule, 0, ranges, &mut env, &mut |env| {
+                let fact = rule
+                    .head
+                    .iter()
+                    .map(|field| match field {
+                        HeadField::Expr(expr) => expr.eval(env),
+                        HeadField::Agg(_) => unreachable!(),
+                    })
+                    .collect::<Option<Tuple>>();
+                derived.extend(fact);
+            });
+            return derived;
+        }
+
+        // Groups by the non-aggregated fields, in order of first appearance.
+        let mut groups = Vec::<(Vec<Value>, Vec<AggState>)>::new();
+        let mut group_ids = HashMap::<Vec<Value>, usize>::new();
+        self.eval_steps(rule, 0, ranges, &mut env, &mut |env| {
+            let Some(key) = rule
+                .head
+                .iter()
+                .filter_map(|field| match field {
+                    HeadField::Expr(expr) => Some(expr.eval(env)),
+                    HeadField::Agg(_) => None,
+                })
+                .collect::<Option<Vec<_>>>()
+            else {
+                return;
+            };
+            let aggs = rule.head.iter().filter_map(|field| match field {
+                HeadField::Agg(agg) => Some(agg),
+                HeadField::Expr(_) => None,
+            });
+            let group_id = *group_ids.entry(key.clone()).or_insert_with(|| {
+                groups.push((key, aggs.clone().map(AggState::new).collect()));
+                groups.len() - 1
+            });
+            for (state, agg) in groups[group_id].1.iter_mut().zip(aggs) {
+                state.update(agg, env);
+            }
+        });
+
+        groups
+            .into_iter()
+            .map(|(key, states)| {
+                let mut key = key.into_iter();
+                let mut states = states.into_iter();
+                rule.head
+                    .iter()
+                    .map(|field| match field {
+                        HeadField::Expr(_) => key.next().unwrap(),
+                        HeadField::Agg(_) => states.next().unwrap().finish(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn eval_steps(
+        &self,
+        rule: &CompiledRule,
+        step_idx: usize,
+        ranges: &[(usize, usize)],
+        env: &mut Vec<Value>,
+        emit: &mut dyn FnMut(&[Value]),
+    ) {
+        let Some(step) = rule.steps.get(step_idx) else {
+            (emit)(env);
+            return;
+        };
+        match step {
+            Step::Scan {
+                relation,
+                terms,
+                key_columns,
+                ..
+            } => {
+                let key = bound_key(terms, env);
+                'facts: for fact in
+                    self.relations[*relation].lookup(key_columns, &key, ranges[step_idx])
+                {
+                    for (term, value) in terms.iter().zip(fact.iter()) {
+                        match term {
+                            Term::Bind(slot) => env[*slot] = value.clone(),
+                            Term::Same(slot) => {
+                                if &env[*slot] != value {
+                                    continue 'facts;
+                                }
+                            }
+                            Term::Bound(_) | Term::Any => {}
+                        }
+                    }
+                    self.eval_steps(rule, step_idx + 1, ranges, env, emit);
+                }
+            }
+            Step::Negate {
+                relation,
+                terms,
+                key_columns,
+            } => {
+                let relation = &self.relations[*relation];
+                let key = bound_key(terms, env);
+                if relation
+                    .lookup(key_columns, &key, (0, relation.facts.len()))
+                    .next()
+                    .is_none()
+                {
+                    self.eval_steps(rule, step_idx + 1, ranges, env, emit);
+                }
+            }
+            Step::Filter { left, op, right } => {
+                let (Some(left), Some(right)) = (left.eval(env), right.eval(env)) else {
+                    return;
+                };
+                let keep = match op {
+                    CmpOp::Lt => left < right,
+                    CmpOp::LtEq => left <= right,
+                    CmpOp::Gt => left > right,
+                    CmpOp::GtEq => left >= right,
+                    CmpOp::Eq => left == right,
+                    CmpOp::Neq => left != right,
+                };
+                if keep {
+                    self.eval_steps(rule, step_idx + 1, ranges, env, emit);
+                }
+            }
+            Step::LessThan { value, threshold } => {
+                let Some(threshold) = env[*threshold].as_int() else {
+                    return;
+                };
+                match value {
+                    Term::Bind(slot) => {
+                        for i in 0..threshold {
+                            env[*slot] = Value::Int(i);
+                            self.eval_steps(rule, step_idx + 1, ranges, env, emit);
+                        }
+                    }
+                    Term::Bound(slot) => {
+                        if env[*slot]
+                            .as_int()
+                            .is_some_and(|i| (0..threshold).contains(&i))
+                        {
+                            self.eval_steps(rule, step_idx + 1, ranges, env, emit);
+                        }
+                    }
+                    Term::Any | Term::Same(_) => {
+                        if 0 < threshold {
+                            self.eval_steps(rule, step_idx + 1, ranges, env, emit);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Values of the [`Term::Bound`] terms, in column order.
+fn bound_key(terms: &[Term], env: &[Value]) -> Vec<Value> {
+    terms
+        .iter()
+        .filter_map(|term| match term {
+            Term::Bound(slot) => Some(env[*slot].clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Expr {
+    /// Evaluates the expression, or returns `None` if arithmetic is applied to a string, divides
+    /// by zero, or overflows.
+    fn eval(&self, env: &[Value]) -> Option<Value> {
+        let ints = |l: &Expr, r: &Expr| Some((l.eval(env)?.as_int()?, r.eval(env)?.as_int()?));
+        let int = match self {
+            Expr::Var(slot) => return Some(env[*slot].clone()),
+            Expr::Int(i) => *i,
+            Expr::Add(l, r) => ints(l, r).and_then(|(l, r)| l.checked_add(r))?,
+            Expr::Sub(l, r) => ints(l, r).and_then(|(l, r)| l.checked_sub(r))?,
+            Expr::Mul(l, r) => ints(l, r).and_then(|(l, r)| l.checked_mul(r))?,
+            Expr::Mod(l, r) => ints(l, r).and_then(|(l, r)| l.checked_rem(r))?,
+        };
+        Some(Value::Int(int))
+    }
+}
+
+enum AggState {
+    Min(Option<Value>),
+    Max(Option<Value>),
+    Sum(i64),
+    Count(i64),
+    CountUnique(HashSet<Vec<Value>>),
+    Choose(Option<Value>),
+}
+impl AggState {
+    fn new(agg: &Agg) -> Self {
+        match agg {
+            Agg::Min(_) => Self::Min(None),
+            Agg::Max(_) => Self::Max(None),
+            Agg::Sum(_) => Self::Sum(0),
+            Agg::Count => Self::Count(0),
+            Agg::CountUnique(_) => Self::CountUnique(HashSet::new()),
+            Agg::Choose(_) => Self::Choose(None),
+        }
+    }
+
+    fn update(&mut self, agg: &Agg, env: &[Value]) {
+        match (self, agg) {
+            (Self::Min(min), &Agg::Min(slot)) => {
+                if min.as_ref().map_or(true, |min| env[slot] < *min) {
+                    *min = Some(env[slot].clone());
+                }
+            }
+            (Self::Max(max), &Agg::Max(slot)) => {
+                if max.as_ref().map_or(true, |max| env[slot] > *max) {
+                    *max = Some(env[slot].clone());
+                }
+            }
+            (Self::Sum(sum), &Agg::Sum(slot)) => {
+                if let Some(int) = env[slot].as_int() {
+                    *sum = sum.wrapping_add(int);
+                } else {
+                    tracing::debug!(value = ?env[slot], "Cannot sum a non-integer value, skipping it.");
+                }
+            }
+            (Self::Count(count), Agg::Count) => *count += 1,
+            (Self::CountUnique(seen), Agg::CountUnique(slots)) => {
+                seen.insert(slots.iter().map(|&slot| env[slot].clone()).collect());
+            }
+            (Self::Choose(chosen), &Agg::Choose(slot)) => {
+                if chosen.is_none() {
+                    *chosen = Some(env[slot].clone());
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn finish(self) -> Value {
+        match self {
+            Self::Min(value) | Self::Max(value) | Self::Choose(value) => value.unwrap(),
+            Self::Sum(sum) => Value::Int(sum),
+            Self::Count(count) => Value::Int(count),
+            Self::CountUnique(seen) => Value::Int(seen.len() as i64),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! A runtime interpreter for Datalog programs, as an alternative to the `datalog!` macro for
+//! programs which are only known at runtime, such as `.dl` files loaded by a running service.
+//!
+//! Programs use the same syntax as `datalog!`, but relations hold dynamically typed [`Tuple`]s of
+//! [`Value`]s instead of Rust tuples. The Rust snippets of `.input` and `.output` declarations are
+//! ignored: instead, facts are sent to each input relation with [`DatalogInstance::input`], and
+//! each tick the full contents of each output relation are received from
+//! [`DatalogInstance::take_output`]. Likewise, relations may declare a schema such as
+//! `.input edges(src: u32, dst: u32)`, but only its number of fields is checked.
+//!
+//! ```rust
+//! use hydroflow::datalog_runtime::{tuple, DatalogProgram};
+//!
+//! let program = DatalogProgram::parse(
+//!     r#"
+//!     .input edges `source_stream(edges)`
+//!     .output path `for_each(|v| println!("{:?}", v))`
+//!
+//!     path(x, y) :- edges(x, y).
+//!     path(x, z) :- path(x, y), edges(y, z).
+//!     "#,
+//! )
+//! .unwrap();
+//!
+//! let mut instance = program.instantiate();
+//! let edges = instance.input("edges").unwrap();
+//! let mut path = instance.take_output("path").unwrap();
+//!
+//! edges.send(tuple![1, 2]).unwrap();
+//! edges.send(tuple![2, 3]).unwrap();
+//! instance.hydroflow_mut().run_available();
+//!
+//! let mut paths = hydroflow::util::collect_ready::<Vec<_>, _>(&mut path);
+//! paths.sort();
+//! assert_eq!(vec![tuple![1, 2], tuple![1, 3], tuple![2, 3]], paths);
+//! ```
+//!
+//! Not supported are `.async` and `.static` relations, `.provenance`, async rules (`:~`), `@` node
+//! targets, and `index()`. The built-in relation `less_than(x, n)` is true for each integer `x` in `0..n`.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::task::Poll;
+
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use self::compile::CompiledProgram;
+use self::eval::Evaluator;
+use crate::scheduled::graph::Hydroflow;
+use crate::{var_args, var_expr};
+
+mod compile;
+mod eval;
+mod value;
+
+pub use value::{Tuple, Value};
+
+pub use crate::tuple;
+
+/// An error in a Datalog program's source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramError {
+    /// Byte offsets of the start and end of the erroneous source.
+    pub span: (usize, usize),
+    /// Line of the start of the span, starting at 1.
+    pub line: usize,
+    /// Column of the start of the span in characters, starting at 1.
+    pub column: usize,
+    /// Description of the error.
+    pub message: String,
+}
+impl ProgramError {
+    fn new(source: &str, span: (usize, usize), message: String) -> Self {
+        let before = &source[..span.0.min(source.len())];
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        Self {
+            span,
+            line: 1 + before.matches('\n').count(),
+            column: 1 + before[line_start..].chars().count(),
+            message,
+        }
+    }
+}
+impl Display for ProgramError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+impl std::error::Error for ProgramError {}
+
+/// A parsed and checked Datalog program, which can be instantiated any number of times.
+#[derive(Clone, Debug)]
+pub struct DatalogProgram {
+    program: Arc<CompiledProgram>,
+}
+
+impl DatalogProgram {
+    /// Parses and checks a Datalog program, returning all errors found if it is invalid.
+    pub fn parse(source: &str) -> Result<Self, Vec<ProgramError>> {
+        let program = compile::compile(source)?;
+        Ok(Self {
+            program: Arc::new(program),
+        })
+    }
+
+    /// Names of the `.input` relations.
+    pub fn inputs(&self) -> impl '_ + Iterator<Item = &str> {
+        self.program
+            .relations
+            .iter()
+            .filter(|info| info.input)
+            .map(|info| &*info.name)
+    }
+
+    /// Names of the `.output` relations.
+    pub fn outputs(&self) -> impl '_ + Iterator<Item = &str> {
+        self.program
+            .relations
+            .iter()
+            .filter(|info| info.output)
+            .map(|info| &*info.name)
+    }
+
+    /// Creates a [`Hydroflow`] instance running the program.
+    ///
+    /// Each tick, the instance receives the facts sent to its inputs and evaluates the program,
+    /// then sends the full contents of each output relation. Relations are emptied at the start of
+    /// each tick, unless declared with `.persist`.
+    pub fn instantiate(&self) -> DatalogInstance {
+        let mut inputs = HashMap::new();
+        let mut receivers = Vec::<(usize, UnboundedReceiver<Tuple>)>::new();
+        let mut outputs = HashMap::new();
+        let mut senders = Vec::<(usize, UnboundedSender<Tuple>)>::new();
+        for (id, info) in self.program.relations.iter().enumerate() {
+            if info.input {
+                let (send, recv) = tokio::sync::mpsc::unbounded_channel();
+                inputs.insert(info.name.clone(), send);
+                receivers.push((id, recv));
+            }
+            if info.output {
+                let (send, recv) = crate::util::unbounded_channel();
+                outputs.insert(info.name.clone(), recv);
+                senders.push((id, send));
+            }
+        }
+
+        let mut evaluator = Evaluator::new(Arc::clone(&self.program));
+        let mut hydroflow = Hydroflow::new();
+        hydroflow.add_subgraph(
+            "datalog",
+            var_expr!(),
+            var_expr!(),
+            move |context, var_args!(), var_args!()| {
+                let waker = context.waker();
+                let mut cx = std::task::Context::from_waker(&waker);
+                let mut facts = Vec::new();
+                for (id, recv) in receivers.iter_mut() {
+                    while let Poll::Ready(Some(fact)) = recv.poll_recv(&mut cx) {
+                        facts.push((*id, fact));
+                    }
+                }
+
+                evaluator.run_tick(facts);
+
+                for (id, send) in senders.iter() {
+                    for fact in evaluator.facts(*id) {
+                        // The receiver may have been dropped if the output is unused.
+                        let _ = send.send(fact.clone());
+                    }
+                }
+                if evaluator.has_deferred() {
+                    context.schedule_subgraph(context.current_subgraph(), false);
+                }
+            },
+        );
+
+        DatalogInstance {
+            hydroflow,
+            inputs,
+            outputs,
+        }
+    }
+}
+
+/// A running instance of a [`DatalogProgram`], created with [`DatalogProgram::instantiate`].
+pub struct DatalogInstance {
+    hydroflow: Hydroflow<'static>,
+    inputs: HashMap<String, UnboundedSender<Tuple>>,
+    outputs: HashMap<String, UnboundedReceiverStream<Tuple>>,
+}
+
+impl DatalogInstance {
+    /// Returns a sender of facts to the `.input` relation `name`. Facts with the wrong number of
+    /// fields are dropped.
+    pub fn input(&self, name: &str) -> Option<UnboundedSender<Tuple>> {
+        self.inputs.get(name).cloned()
+    }
+
+    /// Takes the receiver of facts from the `.output` relation `name`. Returns `None` if there is
+    /// no such output, or it was already taken.
+    pub fn take_output(&mut self, name: &str) -> Option<UnboundedReceiverStream<Tuple>> {
+        self.outputs.remove(name)
+    }
+
+    /// Returns the [`Hydroflow`] instance, to run it.
+    pub fn hydroflow_mut(&mut self) -> &mut Hydroflow<'static> {
+        &mut self.hydroflow
+    }
+
+    /// Returns the [`Hydroflow`] instance, dropping any outputs not yet taken.
+    pub fn into_hydroflow(self) -> Hydroflow<'static> {
+        self.hydroflow
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+/// A dynamically typed value in a [`Tuple`].
+///
+/// Values of different variants compare by variant, with all integers less than all strings.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Value {
+    /// A signed integer. Integer literals in rules are `Int`s.
+    Int(i64),
+    /// A string.
+    Str(String),
+}
+
+/// A row of a relation.
+pub type Tuple = Vec<Value>;
+
+impl Value {
+    /// Returns the integer, if this is an [`Value::Int`].
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            Value::Str(_) => None,
+        }
+    }
+
+    /// Returns the string, if this is a [`Value::Str`].
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Int(_) => None,
+            Value::Str(s) => Some(s),
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Str(value)
+    }
+}
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Str(value.to_owned())
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Str(s) => write!(f, "{:?}", s),
+        }
+    }
+}
+
+/// Creates a [`Tuple`] from a list of values which can each be converted into a [`Value`].
+///
+/// ```rust
+/// use hydroflow::datalog_runtime::{tuple, Value};
+///
+/// assert_eq!(
+///     vec![Value::Int(1), Value::Str("a".to_owned())],
+///     tuple![1, "a"]
+/// );
+/// ```
+#[macro_export]
+macro_rules! tuple {
+    ($( $value:expr ),* $(,)?) => {
+        ::std::vec![$( $crate::datalog_runtime::Value::from($value) ),*]
+    };
+}
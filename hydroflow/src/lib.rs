@@ -22,6 +22,10 @@
 //! For more examples, check out the [`examples` folder on Github](https://github.com/hydro-project/hydroflow/tree/main/hydroflow/examples).
 
 pub mod compiled;
+#[cfg(feature = "hydroflow_datalog")]
+pub mod datalog_provenance;
+#[cfg(feature = "hydroflow_datalog")]
+pub mod datalog_runtime;
 pub mod props;
 pub mod scheduled;
 pub mod util;
@@ -42,8 +46,9 @@ mod declarative_macro;
 pub use hydroflow_datalog::*;
 #[cfg(feature = "hydroflow_macro")]
 pub use hydroflow_macro::{
-    hydroflow_main as main, hydroflow_parser, hydroflow_syntax, hydroflow_syntax_noemit,
-    hydroflow_test as test, monotonic_fn, morphism, DemuxEnum,
+    hydroflow_main as main, hydroflow_parser, hydroflow_syntax, hydroflow_syntax_checkpointed,
+    hydroflow_syntax_noemit, hydroflow_syntax_parallel, hydroflow_test as test, monotonic_fn,
+    morphism, DemuxEnum,
 };
 
 #[cfg(not(nightly))]
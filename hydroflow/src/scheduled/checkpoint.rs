@@ -0,0 +1,229 @@
+//! Module for checkpointing and restoring the state of a [`Hydroflow`] instance.
+//!
+//! A [`Checkpoint`] is taken at a tick boundary with [`Hydroflow::checkpoint`] and contains the
+//! contents of all the instance's state (added via the "state API") and handoff buffers. It can be
+//! loaded into a freshly-constructed instance of the same graph with [`Hydroflow::restore`].
+//!
+//! Only state and handoffs added with checkpoint support participate: see
+//! [`Hydroflow::add_state_checkpointed`] and [`Hydroflow::make_edge_checkpointed`]. Graphs built
+//! with the [`hydroflow_syntax_checkpointed!`](crate::hydroflow_syntax_checkpointed) macro add all
+//! operator state and handoffs this way, which requires all the item and state types to implement
+//! [`Serialize`] and [`Deserialize`].
+
+use std::any::Any;
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::graph::Hydroflow;
+use super::handoff::{Handoff, VecHandoff};
+use super::port::{RecvPort, SendPort};
+use super::state::StateHandle;
+use super::{HandoffId, StateId};
+
+/// A serializable snapshot of all the state in a [`Hydroflow`] instance, taken at a tick boundary.
+///
+/// See [`Hydroflow::checkpoint`] and [`Hydroflow::restore`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The tick which the instance was about to run when the checkpoint was taken.
+    pub current_tick: usize,
+    /// Bincode-serialized state, indexed by [`StateId`].
+    pub states: Vec<Vec<u8>>,
+    /// Bincode-serialized handoff buffers, indexed by [`HandoffId`]. `None` for handoffs without
+    /// checkpoint support, which must be empty when the checkpoint is taken.
+    pub handoffs: Vec<Option<Vec<u8>>>,
+}
+
+/// Error returned by [`Hydroflow::checkpoint`] and [`Hydroflow::restore`].
+#[derive(Debug)]
+pub enum CheckpointError {
+    /// The instance is in the middle of a tick.
+    NotAtTickBoundary,
+    /// The state was added without checkpoint support, i.e. via [`Hydroflow::add_state`].
+    StateNotCheckpointable(StateId),
+    /// The handoff was created without checkpoint support and contains data.
+    HandoffNotCheckpointable(HandoffId),
+    /// The checkpoint was taken from a graph with a different structure.
+    GraphMismatch(String),
+    /// Failed to serialize or deserialize state or handoff contents.
+    Serde(bincode::Error),
+}
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotAtTickBoundary => write!(f, "checkpoints can only be used between ticks"),
+            Self::StateNotCheckpointable(state_id) => {
+                write!(
+                    f,
+                    "state {:?} was added without checkpoint support",
+                    state_id
+                )
+            }
+            Self::HandoffNotCheckpointable(handoff_id) => write!(
+                f,
+                "handoff {} was created without checkpoint support and is not empty",
+                handoff_id
+            ),
+            Self::GraphMismatch(msg) => write!(f, "checkpoint does not match graph: {}", msg),
+            Self::Serde(error) => write!(f, "checkpoint serialization error: {}", error),
+        }
+    }
+}
+impl std::error::Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Serde(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+impl From<bincode::Error> for CheckpointError {
+    fn from(error: bincode::Error) -> Self {
+        Self::Serde(error)
+    }
+}
+
+/// Type-erased serialization functions for a single piece of state.
+#[derive(Clone, Copy)]
+pub(crate) struct StateCheckpointFns {
+    pub save: fn(&dyn Any) -> bincode::Result<Vec<u8>>,
+    pub load: fn(&mut dyn Any, &[u8]) -> bincode::Result<()>,
+    /// Serialized size in bytes, used for introspection.
+    pub size: fn(&dyn Any) -> bincode::Result<u64>,
+}
+impl StateCheckpointFns {
+    pub fn new<T>() -> Self
+    where
+        T: Any + Serialize + DeserializeOwned,
+    {
+        Self {
+            save: |state| bincode::serialize(downcast_ref::<T>(state)),
+            load: |state, buf| {
+                *state
+                    .downcast_mut::<T>()
+                    .expect("StateHandle wrong type T for casting.") = bincode::deserialize(buf)?;
+                Ok(())
+            },
+            size: |state| bincode::serialized_size(downcast_ref::<T>(state)),
+        }
+    }
+}
+
+/// Type-erased serialization functions for a single handoff.
+#[derive(Clone, Copy)]
+pub(crate) struct HandoffCheckpointFns {
+    pub save: fn(&dyn Any) -> bincode::Result<Vec<u8>>,
+    pub load: fn(&dyn Any, &[u8]) -> bincode::Result<()>,
+}
+impl HandoffCheckpointFns {
+    pub fn new<H>() -> Self
+    where
+        H: CheckpointHandoff,
+    {
+        Self {
+            save: |handoff| downcast_ref::<H>(handoff).save(),
+            load: |handoff, buf| downcast_ref::<H>(handoff).load(buf),
+        }
+    }
+}
+
+fn downcast_ref<T: Any>(any: &dyn Any) -> &T {
+    any.downcast_ref()
+        .expect("Checkpoint functions used with wrong type.")
+}
+
+/// Handoffs whose buffered contents can be included in a [`Checkpoint`].
+pub trait CheckpointHandoff: Handoff {
+    /// Serializes the contents of this handoff's buffer.
+    fn save(&self) -> bincode::Result<Vec<u8>>;
+    /// Deserializes items and appends them to this handoff's buffer.
+    fn load(&self, buf: &[u8]) -> bincode::Result<()>;
+}
+impl<T> CheckpointHandoff for VecHandoff<T>
+where
+    T: 'static + Serialize + DeserializeOwned,
+{
+    fn save(&self) -> bincode::Result<Vec<u8>> {
+        bincode::serialize(&*self.input.borrow())
+    }
+
+    fn load(&self, buf: &[u8]) -> bincode::Result<()> {
+        let items: Vec<T> = bincode::deserialize(buf)?;
+        self.input.borrow_mut().extend(items);
+        Ok(())
+    }
+}
+
+/// Wrapper around [`Hydroflow`] used while building a graph with
+/// [`hydroflow_syntax_checkpointed!`](crate::hydroflow_syntax_checkpointed). Shadows
+/// [`Hydroflow::add_state`], [`Hydroflow::make_edge`], and [`Hydroflow::make_edge_bounded`] with
+/// their checkpointed versions so that all operator state and handoffs are included in
+/// checkpoints.
+#[doc(hidden)]
+pub struct CheckpointedBuilder<'a>(Hydroflow<'a>);
+impl<'a> CheckpointedBuilder<'a> {
+    /// Create a new empty builder.
+    pub fn new() -> Self {
+        Self(Hydroflow::new())
+    }
+
+    /// Alias for [`Hydroflow::add_state_checkpointed`].
+    pub fn add_state<T>(&mut self, state: T) -> StateHandle<T>
+    where
+        T: Any + Serialize + DeserializeOwned,
+    {
+        self.0.add_state_checkpointed(state)
+    }
+
+    /// Alias for [`Hydroflow::make_edge_checkpointed`].
+    pub fn make_edge<Name, H>(&mut self, name: Name) -> (SendPort<H>, RecvPort<H>)
+    where
+        Name: Into<Cow<'static, str>>,
+        H: CheckpointHandoff,
+    {
+        self.0.make_edge_checkpointed(name)
+    }
+
+    /// Alias for [`Hydroflow::make_edge_checkpointed`] with a capacity, see
+    /// [`Hydroflow::make_edge_bounded`].
+    pub fn make_edge_bounded<Name, H>(
+        &mut self,
+        name: Name,
+        capacity: usize,
+    ) -> (SendPort<H>, RecvPort<H>)
+    where
+        Name: Into<Cow<'static, str>>,
+        H: CheckpointHandoff,
+    {
+        let (send_port, recv_port) = self.0.make_edge_checkpointed::<Name, H>(name);
+        self.0
+            .set_handoff_capacity(send_port.handoff_id, Some(capacity));
+        (send_port, recv_port)
+    }
+
+    /// Returns the built [`Hydroflow`] instance.
+    pub fn build(self) -> Hydroflow<'a> {
+        self.0
+    }
+}
+impl<'a> Default for CheckpointedBuilder<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<'a> Deref for CheckpointedBuilder<'a> {
+    type Target = Hydroflow<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<'a> DerefMut for CheckpointedBuilder<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -0,0 +1,308 @@
+//! Module for exporting execution traces of a [`Hydroflow`] instance in the
+//! [Chrome trace event format](https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU),
+//! which can be loaded into `chrome://tracing` or [Perfetto](https://ui.perfetto.dev/).
+//!
+//! Start recording with [`Hydroflow::start_chrome_trace`] and stop with
+//! [`Hydroflow::finish_chrome_trace`]. The trace contains one track for the scheduler, with events
+//! for each tick and stratum, and one track per subgraph, with events for each subgraph run.
+//! Handoff queue sizes (see [`HandoffMeta::item_count`](super::handoff::HandoffMeta::item_count))
+//! are recorded as counters after each subgraph run.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use instant::Instant;
+use serde::Serialize;
+use serde_json::json;
+
+use super::graph::Hydroflow;
+use super::{HandoffId, SubgraphId};
+
+/// Process ID used for all events.
+const PID: u32 = 1;
+/// Thread (track) ID used for tick and stratum events. Subgraph tracks start after this.
+const SCHEDULER_TID: usize = 0;
+
+/// A recorded trace event, before conversion to a [`ChromeTraceEvent`].
+enum RecordedEvent {
+    Tick {
+        tick: usize,
+        start: Instant,
+        end: Instant,
+    },
+    Stratum {
+        tick: usize,
+        stratum: usize,
+        start: Instant,
+        end: Instant,
+    },
+    Subgraph {
+        sg_id: SubgraphId,
+        tick: usize,
+        stratum: usize,
+        start: Instant,
+        end: Instant,
+    },
+    HandoffItemCount {
+        handoff_id: HandoffId,
+        time: Instant,
+        item_count: usize,
+    },
+}
+
+/// Records trace events while a [`Hydroflow`] instance runs.
+pub(crate) struct ChromeTraceRecorder {
+    start: Instant,
+    events: Vec<RecordedEvent>,
+    /// The tick, start, and end of the latest tick run. Emitted when the next tick starts.
+    open_tick: Option<(usize, Instant, Instant)>,
+}
+impl ChromeTraceRecorder {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            events: Vec::new(),
+            open_tick: None,
+        }
+    }
+
+    pub fn record_stratum(&mut self, tick: usize, stratum: usize, start: Instant, end: Instant) {
+        match &mut self.open_tick {
+            Some((open_tick, _open_start, open_end)) if *open_tick == tick => *open_end = end,
+            open_tick => {
+                if let Some((tick, start, end)) = open_tick.replace((tick, start, end)) {
+                    self.events.push(RecordedEvent::Tick { tick, start, end });
+                }
+            }
+        }
+        self.events.push(RecordedEvent::Stratum {
+            tick,
+            stratum,
+            start,
+            end,
+        });
+    }
+
+    pub fn record_subgraph(
+        &mut self,
+        sg_id: SubgraphId,
+        tick: usize,
+        stratum: usize,
+        start: Instant,
+        end: Instant,
+    ) {
+        self.events.push(RecordedEvent::Subgraph {
+            sg_id,
+            tick,
+            stratum,
+            start,
+            end,
+        });
+    }
+
+    pub fn record_handoff_item_count(&mut self, handoff_id: HandoffId, item_count: usize) {
+        self.events.push(RecordedEvent::HandoffItemCount {
+            handoff_id,
+            time: Instant::now(),
+            item_count,
+        });
+    }
+
+    /// Converts the recorded events into a [`ChromeTrace`], using `hydroflow` for track labels.
+    pub fn finish(mut self, hydroflow: &Hydroflow<'_>) -> ChromeTrace {
+        if let Some((tick, start, end)) = self.open_tick.take() {
+            self.events.push(RecordedEvent::Tick { tick, start, end });
+        }
+
+        let ts = |time: Instant| time.saturating_duration_since(self.start).as_secs_f64() * 1e6;
+        let dur = |start: Instant, end: Instant| (end - start).as_secs_f64() * 1e6;
+
+        let mut trace_events =
+            Vec::with_capacity(self.events.len() + hydroflow.subgraph_ids().count() + 1);
+        trace_events.push(ChromeTraceEvent::thread_name(
+            SCHEDULER_TID,
+            "Hydroflow scheduler".to_owned(),
+        ));
+        // Labels of all current subgraphs and handoffs, plus any removed since they were recorded.
+        let mut subgraph_labels: BTreeMap<SubgraphId, String> = hydroflow
+            .subgraph_ids()
+            .map(|sg_id| {
+                (
+                    sg_id,
+                    format!("{}: {}", sg_id, hydroflow.subgraph_label(sg_id)),
+                )
+            })
+            .collect();
+        let mut handoff_labels: BTreeMap<HandoffId, String> = hydroflow
+            .handoff_ids()
+            .map(|handoff_id| {
+                let label = format!("{}: {}", handoff_id, hydroflow.handoff_label(handoff_id));
+                (handoff_id, label)
+            })
+            .collect();
+        for event in self.events.iter() {
+            match *event {
+                RecordedEvent::Subgraph { sg_id, .. } => {
+                    subgraph_labels.entry(sg_id).or_insert_with(|| {
+                        format!("{}: {} (removed)", sg_id, hydroflow.subgraph_label(sg_id))
+                    });
+                }
+                RecordedEvent::HandoffItemCount { handoff_id, .. } => {
+                    handoff_labels.entry(handoff_id).or_insert_with(|| {
+                        format!(
+                            "{}: {} (removed)",
+                            handoff_id,
+                            hydroflow.handoff_label(handoff_id)
+                        )
+                    });
+                }
+                RecordedEvent::Tick { .. } | RecordedEvent::Stratum { .. } => {}
+            }
+        }
+
+        trace_events.extend(subgraph_labels.iter().map(|(&sg_id, label)| {
+            ChromeTraceEvent::thread_name(subgraph_tid(sg_id), format!("Subgraph {}", label))
+        }));
+
+        trace_events.extend(self.events.iter().map(|event| match *event {
+            RecordedEvent::Tick { tick, start, end } => ChromeTraceEvent {
+                name: format!("Tick {}", tick),
+                cat: "tick",
+                ph: "X",
+                ts: ts(start),
+                dur: Some(dur(start, end)),
+                pid: PID,
+                tid: SCHEDULER_TID,
+                args: json!({ "tick": tick }),
+            },
+            RecordedEvent::Stratum {
+                tick,
+                stratum,
+                start,
+                end,
+            } => ChromeTraceEvent {
+                name: format!("Stratum {}", stratum),
+                cat: "stratum",
+                ph: "X",
+                ts: ts(start),
+                dur: Some(dur(start, end)),
+                pid: PID,
+                tid: SCHEDULER_TID,
+                args: json!({ "tick": tick, "stratum": stratum }),
+            },
+            RecordedEvent::Subgraph {
+                sg_id,
+                tick,
+                stratum,
+                start,
+                end,
+            } => ChromeTraceEvent {
+                name: subgraph_labels[&sg_id].clone(),
+                cat: "subgraph",
+                ph: "X",
+                ts: ts(start),
+                dur: Some(dur(start, end)),
+                pid: PID,
+                tid: subgraph_tid(sg_id),
+                args: json!({ "tick": tick, "stratum": stratum }),
+            },
+            RecordedEvent::HandoffItemCount {
+                handoff_id,
+                time,
+                item_count,
+            } => ChromeTraceEvent {
+                name: format!("Handoff {}", handoff_labels[&handoff_id]),
+                cat: "handoff",
+                ph: "C",
+                ts: ts(time),
+                dur: None,
+                pid: PID,
+                tid: SCHEDULER_TID,
+                args: json!({ "item_count": item_count }),
+            },
+        }));
+
+        ChromeTrace {
+            trace_events,
+            display_time_unit: "ms",
+        }
+    }
+}
+
+fn subgraph_tid(sg_id: SubgraphId) -> usize {
+    SCHEDULER_TID + 1 + sg_id.0
+}
+
+/// A single event in the Chrome trace event format.
+#[derive(Clone, Debug, Serialize)]
+pub struct ChromeTraceEvent {
+    /// Event name, shown in the trace viewer.
+    pub name: String,
+    /// Event category: `"tick"`, `"stratum"`, `"subgraph"`, `"handoff"`, or `"__metadata"`.
+    pub cat: &'static str,
+    /// Event phase (type): `"X"` for complete (duration) events, `"C"` for counter events, and
+    /// `"M"` for metadata events.
+    pub ph: &'static str,
+    /// Timestamp in microseconds since recording started.
+    pub ts: f64,
+    /// Duration in microseconds, for complete events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dur: Option<f64>,
+    /// Process ID.
+    pub pid: u32,
+    /// Thread ID, i.e. track.
+    pub tid: usize,
+    /// Additional event data.
+    pub args: serde_json::Value,
+}
+impl ChromeTraceEvent {
+    fn thread_name(tid: usize, name: String) -> Self {
+        Self {
+            name: "thread_name".to_owned(),
+            cat: "__metadata",
+            ph: "M",
+            ts: 0.0,
+            dur: None,
+            pid: PID,
+            tid,
+            args: json!({ "name": name }),
+        }
+    }
+}
+
+/// An execution trace in the Chrome trace event format, returned by
+/// [`Hydroflow::finish_chrome_trace`].
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChromeTrace {
+    /// All the trace events.
+    pub trace_events: Vec<ChromeTraceEvent>,
+    /// Time unit used for display in the trace viewer.
+    pub display_time_unit: &'static str,
+}
+impl ChromeTrace {
+    /// Serializes the trace as a JSON string.
+    pub fn to_json_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Writes the trace as JSON, e.g. to a file which can be loaded into a trace viewer.
+    pub fn write_json(&self, writer: impl Write) -> serde_json::Result<()> {
+        serde_json::to_writer(writer, self)
+    }
+}
+
+impl<'a> Hydroflow<'a> {
+    /// Starts recording an execution trace, discarding any trace already being recorded. See
+    /// [`Self::finish_chrome_trace`].
+    pub fn start_chrome_trace(&mut self) {
+        self.chrome_trace = Some(Box::new(ChromeTraceRecorder::new()));
+    }
+
+    /// Stops recording and returns the execution trace, if [`Self::start_chrome_trace`] was
+    /// called.
+    pub fn finish_chrome_trace(&mut self) -> Option<ChromeTrace> {
+        let recorder = self.chrome_trace.take()?;
+        Some(recorder.finish(self))
+    }
+}
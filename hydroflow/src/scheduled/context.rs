@@ -1,15 +1,20 @@
 //! Module for the user-facing [`Context`] object.
 
 use std::any::Any;
+use std::cell::Cell;
 use std::future::Future;
 use std::marker::PhantomData;
 use std::pin::Pin;
 
 use instant::Instant;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::task::JoinHandle;
 
+use super::checkpoint::StateCheckpointFns;
 use super::graph::StateData;
+use super::parallel::ParallelContext;
 use super::state::StateHandle;
 use super::{StateId, SubgraphId};
 
@@ -37,6 +42,11 @@ pub struct Context {
     /// not being forwarded to a running operator, this field is (mostly)
     /// meaningless.
     pub(crate) subgraph_id: SubgraphId,
+    /// See [`Self::output_budget`]. Set by the scheduler before each subgraph is run.
+    pub(crate) output_budget: Cell<Option<usize>>,
+    /// Set by [`Self::take_output_budget`] when the budget runs out, so the scheduler blocks the
+    /// subgraph until its outputs are drained.
+    pub(crate) output_budget_exhausted: Cell<bool>,
 
     pub(crate) tasks_to_spawn: Vec<Pin<Box<dyn Future<Output = ()> + 'static>>>,
 
@@ -77,6 +87,45 @@ impl Context {
         self.event_queue_send.send((sg_id, is_external)).unwrap()
     }
 
+    /// Gets the number of items which may still be sent to the current subgraph's bounded output
+    /// handoffs before the fullest becomes full, or `None` if the subgraph has no bounded outputs.
+    /// See [`Hydroflow::make_edge_bounded`](super::graph::Hydroflow::make_edge_bounded).
+    pub fn output_budget(&self) -> Option<usize> {
+        self.output_budget.get()
+    }
+
+    /// Takes one item from the [output budget](Self::output_budget) of the current subgraph,
+    /// returning `true` if the subgraph may emit another item. Used by sources such as
+    /// `source_stream` to stop polling when downstream is backpressured.
+    ///
+    /// If the budget is exhausted, returns `false` and blocks the current subgraph, so the source
+    /// is rescheduled to resume polling once its bounded outputs have been drained.
+    pub fn take_output_budget(&self) -> bool {
+        match self.output_budget.get() {
+            None => true,
+            Some(0) => {
+                self.output_budget_exhausted.set(true);
+                false
+            }
+            Some(budget) => {
+                self.output_budget.set(Some(budget - 1));
+                true
+            }
+        }
+    }
+
+    /// Returns the [`ParallelContext`] for the current subgraph.
+    pub(crate) fn parallel_context(&self) -> ParallelContext {
+        ParallelContext {
+            event_queue_send: self.event_queue_send.clone(),
+            current_tick: self.current_tick,
+            current_stratum: self.current_stratum,
+            current_tick_start: self.current_tick_start,
+            subgraph_last_tick_run_in: self.subgraph_last_tick_run_in,
+            subgraph_id: self.subgraph_id,
+        }
+    }
+
     /// Returns a `Waker` for interacting with async Rust.
     /// Waker events are considered to be extenral.
     pub fn waker(&self) -> std::task::Waker {
@@ -137,6 +186,9 @@ impl Context {
 
         let state_data = StateData {
             state: Box::new(state),
+            checkpoint: None,
+            owner: None,
+            is_removed: false,
         };
         self.states.push(state_data);
 
@@ -146,16 +198,35 @@ impl Context {
         }
     }
 
-    /// Removes state from the context returns it as an owned heap value.
+    /// Adds state to the context and returns the handle. Unlike [`Self::add_state`], the state
+    /// will be included in [`Checkpoint`](super::checkpoint::Checkpoint)s.
+    pub fn add_state_checkpointed<T>(&mut self, state: T) -> StateHandle<T>
+    where
+        T: Any + Serialize + DeserializeOwned,
+    {
+        let handle = self.add_state(state);
+        self.states[handle.state_id.0].checkpoint = Some(StateCheckpointFns::new::<T>());
+        handle
+    }
+
+    /// Removes state from the context returns it as an owned heap value. The handle, and any copies
+    /// of it, must not be used afterwards. State IDs of removed state are not reused.
     pub fn remove_state<T>(&mut self, handle: StateHandle<T>) -> Box<T>
     where
         T: Any,
     {
-        self.states
-            .remove(handle.state_id.0)
-            .state
+        let state_data = self
+            .states
+            .get_mut(handle.state_id.0)
+            .expect("Failed to find state with given handle.");
+        assert!(!state_data.is_removed, "State already removed.");
+        let state = std::mem::replace(&mut state_data.state, Box::new(()))
             .downcast()
-            .expect("StateHandle wrong type T for casting.")
+            .expect("StateHandle wrong type T for casting.");
+        state_data.checkpoint = None;
+        state_data.owner = None;
+        state_data.is_removed = true;
+        state
     }
 
     /// Prepares an async task to be launched by [`Self::spawn_tasks`].
@@ -166,8 +237,17 @@ impl Context {
         self.tasks_to_spawn.push(Box::pin(future));
     }
 
+    /// Takes the tasks prepared by [`Self::request_task`] which have not yet been spawned, so they
+    /// can be run by a different executor.
+    pub(crate) fn take_tasks(&mut self) -> Vec<Pin<Box<dyn Future<Output = ()> + 'static>>> {
+        std::mem::take(&mut self.tasks_to_spawn)
+    }
+
     /// Launches all tasks requested with [`Self::request_task`] on the internal Tokio executor.
     pub fn spawn_tasks(&mut self) {
+        // Drop the handles of finished tasks so they do not accumulate in long-running flows.
+        self.task_join_handles
+            .retain(|handle| !handle.is_finished());
         for task in self.tasks_to_spawn.drain(..) {
             self.task_join_handles.push(tokio::task::spawn_local(task));
         }
@@ -6,21 +6,34 @@ use std::cell::Cell;
 use std::collections::VecDeque;
 use std::future::Future;
 use std::marker::PhantomData;
+use std::pin::Pin;
+use std::time::Duration;
 
+use futures::FutureExt;
 use hydroflow_lang::diagnostic::{Diagnostic, SerdeSpan};
 use hydroflow_lang::graph::HydroflowGraph;
 use instant::Instant;
 use ref_cast::RefCast;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use tokio::sync::mpsc::{self, UnboundedReceiver};
 
+use super::checkpoint::{
+    Checkpoint, CheckpointError, CheckpointHandoff, HandoffCheckpointFns, StateCheckpointFns,
+};
+use super::chrome_trace::ChromeTraceRecorder;
 use super::context::Context;
-use super::handoff::handoff_list::PortList;
-use super::handoff::{Handoff, HandoffMeta};
+use super::handoff::handoff_list::{ParallelPortList, PortList};
+use super::handoff::{Handoff, HandoffMeta, VecHandoff};
+use super::introspection::IntrospectionPublisher;
+use super::metrics::{HandoffMetrics, SubgraphMetrics};
+use super::parallel::{self, ParallelContext, ParallelSubgraph, PortListSubgraph, WorkerPool};
 use super::port::{RecvCtx, RecvPort, SendCtx, SendPort, RECV, SEND};
 use super::reactor::Reactor;
 use super::state::StateHandle;
 use super::subgraph::Subgraph;
-use super::{HandoffId, SubgraphId};
+use super::ticks::{Clock, SystemClock, TickPolicy};
+use super::{HandoffId, StateId, SubgraphId};
 use crate::Never;
 
 /// A Hydroflow graph. Owns, schedules, and runs the compiled subgraphs.
@@ -38,6 +51,20 @@ pub struct Hydroflow<'a> {
     can_start_tick: bool,
     /// If the events have been received for this tick.
     events_received_tick: bool,
+    /// See [`Self::set_worker_threads()`]. `None` if only the current thread is used.
+    worker_pool: Option<WorkerPool>,
+    /// See [`Self::set_metrics_enabled()`].
+    metrics_enabled: bool,
+    /// See [`Self::start_chrome_trace()`].
+    pub(super) chrome_trace: Option<Box<ChromeTraceRecorder>>,
+    /// See [`Self::watch_introspection()`].
+    pub(super) introspection: Option<Box<IntrospectionPublisher>>,
+    /// See [`Self::set_tick_policy()`].
+    tick_policy: TickPolicy,
+    /// See [`Self::set_clock()`].
+    clock: Box<dyn Clock>,
+    /// Time to start the next tick, for [`TickPolicy::FixedInterval`].
+    next_tick_deadline: Option<Duration>,
 
     /// See [`Self::meta_graph()`].
     meta_graph: Option<HydroflowGraph>,
@@ -60,6 +87,8 @@ impl<'a> Default for Hydroflow<'a> {
             subgraph_last_tick_run_in: None,
 
             subgraph_id: SubgraphId(0),
+            output_budget: Cell::new(None),
+            output_budget_exhausted: Cell::new(false),
 
             tasks_to_spawn: Vec::new(),
             task_join_handles: Vec::new(),
@@ -73,6 +102,13 @@ impl<'a> Default for Hydroflow<'a> {
             event_queue_recv,
             can_start_tick: false,
             events_received_tick: false,
+            worker_pool: None,
+            metrics_enabled: false,
+            chrome_trace: None,
+            introspection: None,
+            tick_policy: TickPolicy::default(),
+            clock: Box::<SystemClock>::default(),
+            next_tick_deadline: None,
 
             meta_graph: None,
             diagnostics: None,
@@ -97,6 +133,12 @@ impl<'a> Hydroflow<'a> {
 
         assert!(self.meta_graph.replace(meta_graph).is_none());
     }
+    /// Attributes the state to the subgraph named `owner`. Used by the surface syntax so
+    /// [introspection](Self::introspect) can report state sizes per subgraph.
+    #[doc(hidden)]
+    pub fn __set_state_owner<T>(&mut self, handle: StateHandle<T>, owner: &'static str) {
+        self.context.states[handle.state_id.0].owner = Some(owner);
+    }
     /// Assign the diagnostics via JSON string.
     #[doc(hidden)]
     pub fn __assign_diagnostics(&mut self, diagnostics_json: &'static str) {
@@ -137,6 +179,61 @@ impl<'a> Hydroflow<'a> {
         self.context.current_stratum
     }
 
+    /// Sets the number of threads (including the current thread) used to run subgraphs added with
+    /// [`Self::add_subgraph_parallel`]. Defaults to `1`, which runs all subgraphs on the current
+    /// thread in the usual order.
+    ///
+    /// When greater than `1`, a pool of `worker_threads - 1` threads is started, and kept until
+    /// this is called again with a different number or the instance is dropped. All the ready
+    /// parallel subgraphs of a stratum are run together as a batch, distributed among the pool and
+    /// the current thread. Other subgraphs still run one at a time on the current thread. On
+    /// `wasm32` no threads are started and batches run on the current thread.
+    ///
+    /// Only subgraphs added with [`Self::add_subgraph_parallel`] can run on worker threads. Graphs
+    /// built with [`hydroflow_syntax_parallel!`](crate::hydroflow_syntax_parallel) add their
+    /// stateless subgraphs this way. Subgraphs generated by
+    /// [`hydroflow_syntax!`](crate::hydroflow_syntax) may use the (non-[`Send`]) [`Context`], so
+    /// they always run on the current thread regardless of this setting.
+    pub fn set_worker_threads(&mut self, worker_threads: usize) {
+        if worker_threads <= 1 {
+            self.worker_pool = None;
+        } else if self.worker_threads() != worker_threads {
+            self.worker_pool = Some(WorkerPool::new(worker_threads));
+        }
+    }
+
+    /// Gets the number of worker threads, see [`Self::set_worker_threads()`].
+    pub fn worker_threads(&self) -> usize {
+        self.worker_pool
+            .as_ref()
+            .map_or(1, |worker_pool| worker_pool.worker_threads())
+    }
+
+    /// Sets the [`TickPolicy`] which controls when [`Self::run_async`] and [`Self::run_until`]
+    /// start new ticks. Defaults to [`TickPolicy::EventDriven`].
+    ///
+    /// # Panics
+    ///
+    /// If the policy is [`TickPolicy::MaxBatchOrTimeout`] with a `max_batch` of zero.
+    pub fn set_tick_policy(&mut self, tick_policy: TickPolicy) {
+        if let TickPolicy::MaxBatchOrTimeout { max_batch, .. } = tick_policy {
+            assert!(0 < max_batch, "`max_batch` must be at least one.");
+        }
+        self.tick_policy = tick_policy;
+        self.next_tick_deadline = None;
+    }
+
+    /// Gets the [`TickPolicy`], see [`Self::set_tick_policy()`].
+    pub fn tick_policy(&self) -> TickPolicy {
+        self.tick_policy
+    }
+
+    /// Sets the [`Clock`] used for time-based [`TickPolicy`]s. Defaults to [`SystemClock`].
+    pub fn set_clock(&mut self, clock: impl 'static + Clock) {
+        self.clock = Box::new(clock);
+        self.next_tick_deadline = None;
+    }
+
     /// Runs the dataflow until the next tick begins.
     /// Returns true if any work was done.
     #[tracing::instrument(level = "trace", skip(self), ret)]
@@ -180,6 +277,8 @@ impl<'a> Hydroflow<'a> {
             work_done = true;
             // Do any work.
             self.run_stratum();
+            // Launch any tasks requested by operators, e.g. `map_async`.
+            self.context.spawn_tasks();
 
             // Yield between each stratum to receive more events.
             // TODO(mingwei): really only need to yield at start of ticks though.
@@ -188,16 +287,68 @@ impl<'a> Hydroflow<'a> {
         work_done
     }
 
+    /// Runs the dataflow until the next tick begins.
+    /// Returns true if any work was done.
+    /// Yields repeatedly to allow external events to happen.
+    #[tracing::instrument(level = "trace", skip(self), ret)]
+    pub async fn run_tick_async(&mut self) -> bool {
+        let mut work_done = false;
+        // While work is immediately available *on the current tick*.
+        while self.next_stratum(true) {
+            work_done = true;
+            // Do any work.
+            self.run_stratum();
+            // Launch any tasks requested by operators, e.g. `map_async`.
+            self.context.spawn_tasks();
+
+            // Yield between each stratum to receive more events.
+            tokio::task::yield_now().await;
+        }
+        work_done
+    }
+
     /// Runs the current stratum of the dataflow until no more local work is available (does not receive events).
     /// Returns true if any work was done.
     #[tracing::instrument(level = "trace", skip(self), fields(tick = self.context.current_tick, stratum = self.context.current_stratum), ret)]
     pub fn run_stratum(&mut self) -> bool {
         let current_tick = self.context.current_tick;
+        let stratum_start = self.chrome_trace.is_some().then(Instant::now);
+        let is_timed = self.is_timed();
 
         let mut work_done = false;
 
         while let Some(sg_id) = self.stratum_queues[self.context.current_stratum].pop_front() {
+            if self.try_block_subgraph(sg_id) {
+                continue;
+            }
             work_done = true;
+
+            if self.worker_pool.is_some() && self.subgraphs[sg_id.0].subgraph.is_parallel() {
+                // Run all the currently-queued parallel subgraphs of this stratum together.
+                let mut batch = vec![sg_id];
+                let mut queue =
+                    std::mem::take(&mut self.stratum_queues[self.context.current_stratum]);
+                queue.retain(|&sg_id| {
+                    let is_parallel = self.subgraphs[sg_id.0].subgraph.is_parallel();
+                    if is_parallel && !self.try_block_subgraph(sg_id) {
+                        batch.push(sg_id);
+                    }
+                    !is_parallel
+                });
+                self.stratum_queues[self.context.current_stratum] = queue;
+                self.run_subgraphs_parallel(&batch);
+                if is_timed {
+                    for &sg_id in batch.iter() {
+                        self.record_subgraph_run(sg_id);
+                    }
+                }
+                for sg_id in batch {
+                    self.schedule_succs(sg_id);
+                    self.unblock_preds(sg_id);
+                }
+                continue;
+            }
+
             {
                 let sg_data = &mut self.subgraphs[sg_id.0];
                 // This must be true for the subgraph to be enqueued.
@@ -206,30 +357,219 @@ impl<'a> Hydroflow<'a> {
 
                 self.context.subgraph_id = sg_id;
                 self.context.subgraph_last_tick_run_in = sg_data.last_tick_run_in;
-                sg_data.subgraph.run(&mut self.context, &mut self.handoffs);
+                self.context.output_budget.set(
+                    sg_data
+                        .succs
+                        .iter()
+                        .filter_map(|&handoff_id| {
+                            let handoff = &self.handoffs[handoff_id.0];
+                            let capacity = handoff.capacity?;
+                            Some(capacity.saturating_sub(handoff.handoff.item_count()))
+                        })
+                        .min(),
+                );
+                let start = is_timed.then(Instant::now);
+                match &mut sg_data.subgraph {
+                    SubgraphFn::Local(subgraph) => {
+                        subgraph.run(&mut self.context, &mut self.handoffs);
+                    }
+                    SubgraphFn::Parallel(subgraph) => {
+                        let job = subgraph.prepare(self.context.parallel_context(), &self.handoffs);
+                        (job)();
+                    }
+                }
+                sg_data.last_run = start.map(|start| (start, Instant::now()));
                 sg_data.last_tick_run_in = Some(current_tick);
+
+                // Rescheduled by `unblock_preds` once the full output handoff is drained.
+                if self.context.output_budget_exhausted.take() {
+                    tracing::trace!(
+                        sg_id = sg_id.0,
+                        "Output budget exhausted, blocking subgraph."
+                    );
+                    sg_data.is_blocked.set(true);
+                }
             }
 
-            let sg_data = &self.subgraphs[sg_id.0];
+            if is_timed {
+                self.record_subgraph_run(sg_id);
+            }
+            self.schedule_succs(sg_id);
+            self.unblock_preds(sg_id);
+        }
+
+        if let (Some(chrome_trace), Some(stratum_start), true) =
+            (&mut self.chrome_trace, stratum_start, work_done)
+        {
+            chrome_trace.record_stratum(
+                current_tick,
+                self.context.current_stratum,
+                stratum_start,
+                Instant::now(),
+            );
+        }
+        work_done
+    }
+
+    /// Runs a batch of parallel subgraphs, all of which must be scheduled in the current stratum.
+    fn run_subgraphs_parallel(&mut self, batch: &[SubgraphId]) {
+        let current_tick = self.context.current_tick;
+        let is_timed = self.is_timed();
 
+        let mut in_batch = vec![false; self.subgraphs.len()];
+        for sg_id in batch {
+            in_batch[sg_id.0] = true;
+        }
+
+        let jobs = self
+            .subgraphs
+            .iter_mut()
+            .enumerate()
+            .filter(|(i, _)| in_batch[*i])
+            .map(|(i, sg_data)| {
+                // This must be true for the subgraph to be enqueued.
+                assert!(sg_data.is_scheduled.take());
+                tracing::trace!(sg_id = i, "Running subgraph in parallel.");
+
+                self.context.subgraph_id = SubgraphId(i);
+                self.context.subgraph_last_tick_run_in = sg_data.last_tick_run_in;
+                sg_data.last_tick_run_in = Some(current_tick);
+                let SubgraphFn::Parallel(subgraph) = &mut sg_data.subgraph else {
+                    panic!("Subgraph {} is not a parallel subgraph.", i);
+                };
+                let job = subgraph.prepare(self.context.parallel_context(), &self.handoffs);
+                let last_run = &mut sg_data.last_run;
+                Box::new(move || {
+                    let start = is_timed.then(Instant::now);
+                    (job)();
+                    *last_run = start.map(|start| (start, Instant::now()));
+                }) as parallel::Job<'_>
+            })
+            .collect();
+        self.worker_pool.as_ref().unwrap().run_jobs(jobs);
+    }
+
+    /// If subgraph runs are timed, i.e. if metrics or tracing are enabled.
+    fn is_timed(&self) -> bool {
+        self.metrics_enabled || self.chrome_trace.is_some()
+    }
+
+    /// Updates the metrics and trace (whichever are enabled) of the subgraph and its handoffs
+    /// after it has run. Only called if [`Self::is_timed`].
+    fn record_subgraph_run(&mut self, sg_id: SubgraphId) {
+        let sg_data = &mut self.subgraphs[sg_id.0];
+        let (start, end) = sg_data
+            .last_run
+            .expect("Timed subgraph run must set `last_run`.");
+        if self.metrics_enabled {
+            sg_data.metrics.wall_time += end - start;
+            sg_data.metrics.invocations += 1;
+            for &handoff_id in sg_data.preds.iter() {
+                let handoff = &mut self.handoffs[handoff_id.0];
+                let item_count = handoff.handoff.item_count();
+                let taken = handoff.metrics.item_count.saturating_sub(item_count) as u64;
+                handoff.metrics.items_out += taken;
+                handoff.metrics.item_count = item_count;
+                sg_data.metrics.items_in += taken;
+            }
             for &handoff_id in sg_data.succs.iter() {
-                let handoff = &self.handoffs[handoff_id.0];
-                if !handoff.handoff.is_bottom() {
-                    for &succ_id in handoff.succs.iter() {
-                        let succ_sg_data = &self.subgraphs[succ_id.0];
-                        // If we have sent data to the next tick, then we can start the next tick.
-                        if succ_sg_data.stratum < self.context.current_stratum && !sg_data.is_lazy {
-                            self.can_start_tick = true;
-                        }
-                        // Add subgraph to stratum queue if it is not already scheduled.
-                        if !succ_sg_data.is_scheduled.replace(true) {
-                            self.stratum_queues[succ_sg_data.stratum].push_back(succ_id);
-                        }
+                let handoff = &mut self.handoffs[handoff_id.0];
+                let item_count = handoff.handoff.item_count();
+                let given = item_count.saturating_sub(handoff.metrics.item_count) as u64;
+                handoff.metrics.items_in += given;
+                handoff.metrics.item_count = item_count;
+                sg_data.metrics.items_out += given;
+            }
+        }
+
+        if let Some(chrome_trace) = &mut self.chrome_trace {
+            chrome_trace.record_subgraph(
+                sg_id,
+                self.context.current_tick,
+                self.context.current_stratum,
+                start,
+                end,
+            );
+            for &handoff_id in sg_data.preds.iter().chain(sg_data.succs.iter()) {
+                let item_count = self.handoffs[handoff_id.0].handoff.item_count();
+                chrome_trace.record_handoff_item_count(handoff_id, item_count);
+            }
+        }
+    }
+
+    /// Schedules the successors of the subgraph if it has sent them any data.
+    fn schedule_succs(&mut self, sg_id: SubgraphId) {
+        let sg_data = &self.subgraphs[sg_id.0];
+
+        for &handoff_id in sg_data.succs.iter() {
+            let handoff = &self.handoffs[handoff_id.0];
+            if !handoff.handoff.is_bottom() {
+                for &succ_id in handoff.succs.iter() {
+                    let succ_sg_data = &self.subgraphs[succ_id.0];
+                    // If we have sent data to the next tick, then we can start the next tick.
+                    if succ_sg_data.stratum < self.context.current_stratum && !sg_data.is_lazy {
+                        self.can_start_tick = true;
+                    }
+                    // Add subgraph to stratum queue if it is not already scheduled.
+                    if !succ_sg_data.is_scheduled.replace(true) {
+                        self.stratum_queues[succ_sg_data.stratum].push_back(succ_id);
                     }
                 }
             }
         }
-        work_done
+    }
+
+    /// If any of the subgraph's bounded output handoffs are full, deschedules the subgraph instead
+    /// of running it and returns true. The subgraph will be rescheduled by [`Self::unblock_preds`]
+    /// once its outputs are drained.
+    fn try_block_subgraph(&self, sg_id: SubgraphId) -> bool {
+        let sg_data = &self.subgraphs[sg_id.0];
+        let is_full = sg_data.succs.iter().any(|&handoff_id| {
+            let handoff = &self.handoffs[handoff_id.0];
+            handoff
+                .capacity
+                .map_or(false, |capacity| capacity <= handoff.handoff.item_count())
+        });
+        if is_full {
+            tracing::trace!(sg_id = sg_id.0, "Output handoff full, blocking subgraph.");
+            // This must be true for the subgraph to be enqueued.
+            assert!(sg_data.is_scheduled.take());
+            sg_data.is_blocked.set(true);
+        }
+        is_full
+    }
+
+    /// Reschedules any subgraphs blocked by [`Self::try_block_subgraph`] on the subgraph's bounded
+    /// input handoffs, if they are no longer full.
+    fn unblock_preds(&mut self, sg_id: SubgraphId) {
+        let sg_data = &self.subgraphs[sg_id.0];
+
+        for &handoff_id in sg_data.preds.iter() {
+            let handoff = &self.handoffs[handoff_id.0];
+            let Some(capacity) = handoff.capacity else {
+                continue;
+            };
+            if capacity <= handoff.handoff.item_count() {
+                continue;
+            }
+            for &pred_id in handoff.preds.iter() {
+                let pred_sg_data = &self.subgraphs[pred_id.0];
+                if !pred_sg_data.is_blocked.replace(false) {
+                    continue;
+                }
+                tracing::trace!(
+                    sg_id = pred_id.0,
+                    "Output handoff drained, unblocking subgraph."
+                );
+                // If the blocked subgraph is in an earlier stratum, it must run next tick.
+                if pred_sg_data.stratum < self.context.current_stratum {
+                    self.can_start_tick = true;
+                }
+                if !pred_sg_data.is_scheduled.replace(true) {
+                    self.stratum_queues[pred_sg_data.stratum].push_back(pred_id);
+                }
+            }
+        }
     }
 
     /// Go to the next stratum which has work available, possibly the current stratum.
@@ -297,11 +637,13 @@ impl<'a> Hydroflow<'a> {
                 self.context.current_stratum = 0;
                 self.context.current_tick += 1;
                 self.events_received_tick = false;
+                self.publish_introspection(false);
 
                 if current_tick_only {
                     tracing::trace!(
                         "`current_tick_only` is `true`, returning `false` before receiving events."
                     );
+                    self.publish_introspection(true);
                     return false;
                 } else {
                     self.try_recv_events();
@@ -318,6 +660,7 @@ impl<'a> Hydroflow<'a> {
                             "`can_start_tick` is `false`, re-setting `events_received_tick = false`, returning `false`."
                         );
                         self.events_received_tick = false;
+                        self.publish_introspection(true);
                         return false;
                     }
                 }
@@ -332,6 +675,7 @@ impl<'a> Hydroflow<'a> {
                 // events.
                 self.events_received_tick = false;
                 self.context.current_stratum = 0;
+                self.publish_introspection(true);
                 return false;
             }
         }
@@ -347,17 +691,151 @@ impl<'a> Hydroflow<'a> {
         }
     }
 
-    /// Runs the dataflow graph forever.
+    /// Runs the dataflow graph forever, starting ticks according to the [`TickPolicy`].
     ///
     /// TODO(mingwei): Currently blockes forever, no notion of "completion."
     #[tracing::instrument(level = "trace", skip(self), ret)]
     pub async fn run_async(&mut self) -> Option<Never> {
         self.context.spawn_tasks();
         loop {
-            // Run any work which is immediately available.
-            self.run_available_async().await;
-            // When no work is available yield until more events occur.
-            self.recv_events_async().await;
+            if TickPolicy::EventDriven == self.tick_policy {
+                // Run any work which is immediately available.
+                self.run_available_async().await;
+            } else {
+                self.run_tick_async().await;
+            }
+            // When no work is available yield until the next tick should start.
+            self.recv_tick_async().await;
+        }
+    }
+
+    /// Runs the dataflow graph until `shutdown` completes, then shuts down gracefully with
+    /// [`Self::shutdown`].
+    ///
+    /// `shutdown` is only checked between ticks, so the current tick always runs to completion.
+    /// For example, `shutdown` may be a [`tokio::signal::ctrl_c`] future or a
+    /// `CancellationToken::cancelled` future from `tokio_util`.
+    #[tracing::instrument(level = "trace", skip(self, shutdown))]
+    pub async fn run_until(&mut self, shutdown: impl Future<Output = ()>) {
+        self.run_until_inner(shutdown, |_| false).await
+    }
+
+    /// Runs the dataflow graph until `predicate` returns true, then shuts down gracefully with
+    /// [`Self::shutdown`].
+    ///
+    /// `predicate` is checked between ticks, and whenever external events are received while
+    /// waiting for work.
+    #[tracing::instrument(level = "trace", skip(self, predicate))]
+    pub async fn run_until_predicate(&mut self, predicate: impl FnMut(&Self) -> bool) {
+        self.run_until_inner(futures::future::pending(), predicate)
+            .await
+    }
+
+    async fn run_until_inner(
+        &mut self,
+        shutdown: impl Future<Output = ()>,
+        mut predicate: impl FnMut(&Self) -> bool,
+    ) {
+        let mut shutdown = std::pin::pin!(shutdown.fuse());
+        self.context.spawn_tasks();
+        'run: loop {
+            // Only run one tick at a time, unless event-driven.
+            let current_tick_only = TickPolicy::EventDriven != self.tick_policy;
+            let mut tick = self.context.current_tick;
+            while self.next_stratum(current_tick_only) {
+                if tick != self.context.current_tick {
+                    // Between ticks, check for shutdown before running any of the new tick.
+                    if futures::poll!(&mut shutdown).is_ready() || (predicate)(self) {
+                        break 'run;
+                    }
+                    tick = self.context.current_tick;
+                }
+                self.run_stratum();
+                self.context.spawn_tasks();
+
+                // Yield between each stratum to receive more events.
+                tokio::task::yield_now().await;
+            }
+            if (predicate)(self) {
+                break 'run;
+            }
+            // When no work is available yield until the next tick should start, or shutdown.
+            tokio::select! {
+                biased;
+                () = &mut shutdown => break 'run,
+                () = self.recv_tick_async() => {}
+            }
+        }
+        tracing::trace!(tick = self.context.current_tick, "Shutting down.");
+        self.shutdown().await;
+    }
+
+    /// Gracefully shuts down the dataflow graph: drops all subgraphs, along with the operators
+    /// they own, and then waits for tasks requested with [`Context::request_task`] to complete.
+    ///
+    /// Dropping the operators closes the internal channels of async outputs like `dest_sink`, so
+    /// their tasks finish sending and flushing any remaining items and then exit. After this, the
+    /// instance will no longer do any work.
+    pub async fn shutdown(&mut self) {
+        for sg_data in self.subgraphs.iter_mut() {
+            sg_data.subgraph = SubgraphFn::empty();
+        }
+        self.context.spawn_tasks();
+        self.context.join_tasks().await;
+    }
+
+    /// Waits until the next tick should start, according to the [`TickPolicy`], enqueuing subgraphs
+    /// triggered by events.
+    #[tracing::instrument(level = "trace", skip(self), fields(tick_policy = ?self.tick_policy))]
+    async fn recv_tick_async(&mut self) {
+        match self.tick_policy {
+            TickPolicy::EventDriven => {
+                self.recv_events_async().await;
+            }
+            TickPolicy::FixedInterval(period) => {
+                let deadline = *self
+                    .next_tick_deadline
+                    .get_or_insert_with(|| self.clock.now() + period);
+                self.clock.sleep_until(deadline).await;
+                // Skip any periods which were missed entirely.
+                let next_deadline = (deadline + period).max(self.clock.now());
+                self.next_tick_deadline = Some(next_deadline);
+            }
+            TickPolicy::MaxBatchOrTimeout { max_batch, timeout } => {
+                // Work carried over from the previous tick does not wait.
+                if self.can_start_tick {
+                    return;
+                }
+                let mut batch_size = 0;
+                // Wait indefinitely for the first external event.
+                let mut sleep: Pin<Box<dyn Future<Output = ()>>> =
+                    Box::pin(futures::future::pending());
+                while batch_size < max_batch {
+                    tokio::select! {
+                        biased;
+                        () = &mut sleep => break,
+                        event = self.event_queue_recv.recv() => {
+                            let Some((sg_id, is_external)) = event else {
+                                break;
+                            };
+                            let sg_data = &self.subgraphs[sg_id.0];
+                            if sg_data.is_removed {
+                                continue;
+                            }
+                            if !sg_data.is_scheduled.replace(true) {
+                                self.stratum_queues[sg_data.stratum].push_back(sg_id);
+                            }
+                            if is_external {
+                                if 0 == batch_size {
+                                    sleep = self.clock.sleep_until(self.clock.now() + timeout);
+                                }
+                                batch_size += 1;
+                            }
+                        }
+                    }
+                }
+                tracing::trace!(batch_size, "Batch complete.");
+            }
         }
     }
 
@@ -375,6 +853,10 @@ impl<'a> Hydroflow<'a> {
                 sg_stratum = sg_data.stratum,
                 "Event received."
             );
+            // Leftover events for removed subgraphs, e.g. from wakers, must not start ticks.
+            if sg_data.is_removed {
+                continue;
+            }
             if !sg_data.is_scheduled.replace(true) {
                 self.stratum_queues[sg_data.stratum].push_back(sg_id);
                 enqueued_count += 1;
@@ -411,6 +893,10 @@ impl<'a> Hydroflow<'a> {
                 sg_stratum = sg_data.stratum,
                 "Event received."
             );
+            // Leftover events for removed subgraphs, e.g. from wakers, must not start ticks.
+            if sg_data.is_removed {
+                continue;
+            }
             if !sg_data.is_scheduled.replace(true) {
                 self.stratum_queues[sg_data.stratum].push_back(sg_id);
                 count += 1;
@@ -454,6 +940,10 @@ impl<'a> Hydroflow<'a> {
                 sg_stratum = sg_data.stratum,
                 "Event received."
             );
+            // Leftover events for removed subgraphs, e.g. from wakers, must not start ticks.
+            if sg_data.is_removed {
+                continue;
+            }
             if !sg_data.is_scheduled.replace(true) {
                 self.stratum_queues[sg_data.stratum].push_back(sg_id);
                 count += 1;
@@ -528,7 +1018,7 @@ impl<'a> Hydroflow<'a> {
         self.subgraphs.push(SubgraphData::new(
             name.into(),
             stratum,
-            subgraph,
+            SubgraphFn::Local(Box::new(subgraph)),
             subgraph_preds,
             subgraph_succs,
             true,
@@ -540,6 +1030,56 @@ impl<'a> Hydroflow<'a> {
         sg_id
     }
 
+    /// Adds a new compiled subgraph with the specified inputs, outputs, and stratum number, which
+    /// may be run on a worker thread in parallel with other such subgraphs of the same stratum. See
+    /// [`Self::set_worker_threads`].
+    ///
+    /// The subgraph closure and all of its handoffs must be thread-safe, e.g. by using
+    /// [`SendVecHandoff`](super::handoff::SendVecHandoff). Instead of a [`Context`] the subgraph
+    /// receives a [`ParallelContext`], which does not provide the state API, so any state should be
+    /// owned by the closure.
+    pub fn add_subgraph_parallel<Name, R, W, F>(
+        &mut self,
+        name: Name,
+        stratum: usize,
+        recv_ports: R,
+        send_ports: W,
+        subgraph: F,
+    ) -> SubgraphId
+    where
+        Name: Into<Cow<'static, str>>,
+        R: 'static + ParallelPortList<RECV>,
+        W: 'static + ParallelPortList<SEND>,
+        F: 'a
+            + Send
+            + for<'ctx> FnMut(&ParallelContext, R::ParallelCtx<'ctx>, W::ParallelCtx<'ctx>),
+    {
+        let sg_id = SubgraphId(self.subgraphs.len());
+
+        let (mut subgraph_preds, mut subgraph_succs) = Default::default();
+        recv_ports.set_graph_meta(&mut *self.handoffs, None, Some(sg_id), &mut subgraph_preds);
+        send_ports.set_graph_meta(&mut *self.handoffs, Some(sg_id), None, &mut subgraph_succs);
+
+        let subgraph = PortListSubgraph {
+            recv_ports,
+            send_ports,
+            subgraph,
+        };
+        self.subgraphs.push(SubgraphData::new(
+            name.into(),
+            stratum,
+            SubgraphFn::Parallel(Box::new(subgraph)),
+            subgraph_preds,
+            subgraph_succs,
+            true,
+            false,
+        ));
+        self.init_stratum(stratum);
+        self.stratum_queues[stratum].push_back(sg_id);
+
+        sg_id
+    }
+
     /// Adds a new compiled subgraph with a variable number of inputs and outputs of the same respective handoff types.
     pub fn add_subgraph_n_m<Name, R, W, F>(
         &mut self,
@@ -620,7 +1160,7 @@ impl<'a> Hydroflow<'a> {
         self.subgraphs.push(SubgraphData::new(
             name.into(),
             stratum,
-            subgraph,
+            SubgraphFn::Local(Box::new(subgraph)),
             subgraph_preds,
             subgraph_succs,
             true,
@@ -632,6 +1172,136 @@ impl<'a> Hydroflow<'a> {
         sg_id
     }
 
+    /// Removes a subgraph between ticks, dropping its closure along with anything the closure owns.
+    /// The subgraph's handoffs and state are not removed, see [`Self::remove_handoff`] and
+    /// [`Self::remove_state`].
+    ///
+    /// Subgraphs may also be added between ticks with [`Self::add_subgraph`] and friends, and run
+    /// starting on the next tick. IDs of removed subgraphs are not reused.
+    ///
+    /// # Panics
+    /// If not called between ticks, i.e. after a manual call to [`Self::run_stratum`], or if the
+    /// subgraph has already been removed.
+    pub fn remove_subgraph(&mut self, sg_id: SubgraphId) {
+        assert_eq!(
+            0, self.context.current_stratum,
+            "Subgraphs can only be removed between ticks."
+        );
+        let sg_data = &mut self.subgraphs[sg_id.0];
+        assert!(!sg_data.is_removed, "Subgraph {} already removed.", sg_id);
+        sg_data.is_removed = true;
+        sg_data.subgraph = SubgraphFn::empty();
+        sg_data.is_blocked.set(false);
+        // Keep `is_scheduled` set so the subgraph is never enqueued again, e.g. by a leftover
+        // waker.
+        sg_data.is_scheduled.set(true);
+        self.stratum_queues[sg_data.stratum].retain(|&id| id != sg_id);
+
+        for &handoff_id in sg_data.preds.iter() {
+            self.handoffs[handoff_id.0].succs.retain(|&id| id != sg_id);
+        }
+        for &handoff_id in sg_data.succs.iter() {
+            self.handoffs[handoff_id.0].preds.retain(|&id| id != sg_id);
+        }
+    }
+
+    /// Removes a handoff, dropping any items buffered in it. The subgraphs sending to and receiving
+    /// from the handoff must already have been removed with [`Self::remove_subgraph`].
+    ///
+    /// Handoffs may also be created after the graph has run with [`Self::make_edge`]. IDs of
+    /// removed handoffs are not reused.
+    ///
+    /// # Panics
+    /// If the handoff is still connected to a subgraph, or has already been removed.
+    pub fn remove_handoff(&mut self, handoff_id: HandoffId) {
+        let handoff_data = &mut self.handoffs[handoff_id.0];
+        assert!(
+            !handoff_data.is_removed,
+            "Handoff {} already removed.",
+            handoff_id
+        );
+        assert!(
+            handoff_data.preds.is_empty() && handoff_data.succs.is_empty(),
+            "Handoff {} is still connected to subgraphs.",
+            handoff_id
+        );
+        handoff_data.is_removed = true;
+        handoff_data.handoff = Box::<VecHandoff<()>>::default();
+        handoff_data.checkpoint = None;
+        handoff_data.capacity = None;
+        handoff_data.metrics = Default::default();
+    }
+
+    /// Returns the IDs of all subgraphs which have not been removed, in the order they were added.
+    pub fn subgraph_ids(&self) -> impl '_ + Iterator<Item = SubgraphId> {
+        self.subgraphs
+            .iter()
+            .enumerate()
+            .filter(|(_, sg_data)| !sg_data.is_removed)
+            .map(|(i, _)| SubgraphId(i))
+    }
+
+    /// Returns the IDs of all handoffs which have not been removed, in the order they were added.
+    pub fn handoff_ids(&self) -> impl '_ + Iterator<Item = HandoffId> {
+        self.handoffs
+            .iter()
+            .enumerate()
+            .filter(|(_, handoff_data)| !handoff_data.is_removed)
+            .map(|(i, _)| HandoffId(i))
+    }
+
+    /// Returns the name given to the subgraph when it was added.
+    pub fn subgraph_name(&self, sg_id: SubgraphId) -> &str {
+        &self.subgraphs[sg_id.0].name
+    }
+
+    /// Returns the name given to the handoff when it was created.
+    pub fn handoff_name(&self, handoff_id: HandoffId) -> &str {
+        &self.handoffs[handoff_id.0].name
+    }
+
+    /// Enables or disables recording of runtime metrics, see [`Self::metrics`]. Metrics are
+    /// disabled by default, as recording them times every subgraph run.
+    ///
+    /// Disabling metrics keeps the values recorded so far.
+    pub fn set_metrics_enabled(&mut self, enabled: bool) {
+        if enabled && !self.metrics_enabled {
+            // Items buffered while metrics were disabled are not counted.
+            for handoff_data in self.handoffs.iter_mut() {
+                handoff_data.metrics.item_count = handoff_data.handoff.item_count();
+            }
+        }
+        self.metrics_enabled = enabled;
+    }
+
+    /// Returns if runtime metrics are recorded, see [`Self::set_metrics_enabled`].
+    pub fn metrics_enabled(&self) -> bool {
+        self.metrics_enabled
+    }
+
+    /// Returns the runtime metrics of the subgraph. See also [`Self::metrics`].
+    pub fn subgraph_metrics(&self, sg_id: SubgraphId) -> &SubgraphMetrics {
+        &self.subgraphs[sg_id.0].metrics
+    }
+
+    /// Returns the runtime metrics of the handoff. See also [`Self::metrics`].
+    pub fn handoff_metrics(&self, handoff_id: HandoffId) -> &HandoffMetrics {
+        &self.handoffs[handoff_id.0].metrics
+    }
+
+    /// Resets all runtime metrics to zero.
+    pub fn reset_metrics(&mut self) {
+        for sg_data in self.subgraphs.iter_mut() {
+            sg_data.metrics = Default::default();
+        }
+        for handoff_data in self.handoffs.iter_mut() {
+            handoff_data.metrics = HandoffMetrics {
+                item_count: handoff_data.metrics.item_count,
+                ..Default::default()
+            };
+        }
+    }
+
     /// Makes sure stratum STRATUM is initialized.
     fn init_stratum(&mut self, stratum: usize) {
         if self.stratum_queues.len() <= stratum {
@@ -640,6 +1310,43 @@ impl<'a> Hydroflow<'a> {
         }
     }
 
+    /// Creates a handoff edge with a capacity, which provides backpressure: while `capacity` or
+    /// more items are buffered in the handoff, the subgraph sending into it will not be run. Its
+    /// sources are therefore not polled until the receiving subgraph drains the handoff. See also
+    /// [`Context::output_budget`].
+    ///
+    /// The capacity is a soft limit, as a single run of the sending subgraph may overfill the
+    /// handoff. The handoff type must report its [`HandoffMeta::item_count`], otherwise it never
+    /// becomes full.
+    pub fn make_edge_bounded<Name, H>(
+        &mut self,
+        name: Name,
+        capacity: usize,
+    ) -> (SendPort<H>, RecvPort<H>)
+    where
+        Name: Into<Cow<'static, str>>,
+        H: 'static + Handoff,
+    {
+        let (send_port, recv_port) = self.make_edge::<Name, H>(name);
+        self.set_handoff_capacity(send_port.handoff_id, Some(capacity));
+        (send_port, recv_port)
+    }
+
+    /// Returns the number of items currently buffered in the handoff.
+    pub fn handoff_item_count(&self, handoff_id: HandoffId) -> usize {
+        self.handoffs[handoff_id.0].handoff.item_count()
+    }
+
+    /// Returns the capacity of the handoff if it was created with [`Self::make_edge_bounded`].
+    pub fn handoff_capacity(&self, handoff_id: HandoffId) -> Option<usize> {
+        self.handoffs[handoff_id.0].capacity
+    }
+
+    /// Sets (or removes) the capacity of the handoff, see [`Self::make_edge_bounded`].
+    pub(crate) fn set_handoff_capacity(&mut self, handoff_id: HandoffId, capacity: Option<usize>) {
+        self.handoffs[handoff_id.0].capacity = capacity;
+    }
+
     /// Creates a handoff edge and returns the corresponding send and receive ports.
     pub fn make_edge<Name, H>(&mut self, name: Name) -> (SendPort<H>, RecvPort<H>)
     where
@@ -675,6 +1382,159 @@ impl<'a> Hydroflow<'a> {
         self.context.add_state(state)
     }
 
+    /// Removes state added by [`Self::add_state`] and returns it. Any subgraphs using the state
+    /// should be removed first, see [`Self::remove_subgraph`].
+    ///
+    /// This is part of the "state API".
+    pub fn remove_state<T>(&mut self, handle: StateHandle<T>) -> Box<T>
+    where
+        T: Any,
+    {
+        self.context.remove_state(handle)
+    }
+
+    /// Adds referenceable state into the `Hydroflow` instance, like [`Self::add_state`], but the
+    /// state will also be included in [`Checkpoint`]s taken with [`Self::checkpoint`].
+    ///
+    /// This is part of the "state API".
+    pub fn add_state_checkpointed<T>(&mut self, state: T) -> StateHandle<T>
+    where
+        T: Any + Serialize + DeserializeOwned,
+    {
+        self.context.add_state_checkpointed(state)
+    }
+
+    /// Creates a handoff edge like [`Self::make_edge`], but any items buffered in the handoff
+    /// between ticks will be included in [`Checkpoint`]s taken with [`Self::checkpoint`].
+    pub fn make_edge_checkpointed<Name, H>(&mut self, name: Name) -> (SendPort<H>, RecvPort<H>)
+    where
+        Name: Into<Cow<'static, str>>,
+        H: CheckpointHandoff,
+    {
+        let (send_port, recv_port) = self.make_edge::<Name, H>(name);
+        self.handoffs[send_port.handoff_id.0].checkpoint = Some(HandoffCheckpointFns::new::<H>());
+        (send_port, recv_port)
+    }
+
+    /// Takes a [`Checkpoint`] of all the state and buffered handoff data in this instance. Must be
+    /// called between ticks, i.e. not after a manual call to [`Self::run_stratum`].
+    ///
+    /// All state must have been added with checkpoint support, and handoffs without checkpoint
+    /// support must be empty. Graphs built with
+    /// [`hydroflow_syntax_checkpointed!`](crate::hydroflow_syntax_checkpointed) satisfy this.
+    ///
+    /// Note that sources (e.g. `source_iter` or `source_stream`) are not state and are not
+    /// included. After a restore, only inputs which arrived after the checkpoint was taken should
+    /// be provided.
+    pub fn checkpoint(&self) -> Result<Checkpoint, CheckpointError> {
+        if 0 != self.context.current_stratum {
+            return Err(CheckpointError::NotAtTickBoundary);
+        }
+
+        let states = self
+            .context
+            .states
+            .iter()
+            .enumerate()
+            .map(|(state_id, state_data)| {
+                if state_data.is_removed {
+                    return Ok(Vec::new());
+                }
+                let StateCheckpointFns { save, .. } = state_data
+                    .checkpoint
+                    .ok_or(CheckpointError::StateNotCheckpointable(StateId(state_id)))?;
+                Ok((save)(&*state_data.state)?)
+            })
+            .collect::<Result<Vec<_>, CheckpointError>>()?;
+
+        let handoffs = self
+            .handoffs
+            .iter()
+            .enumerate()
+            .map(|(handoff_id, handoff_data)| match handoff_data.checkpoint {
+                Some(HandoffCheckpointFns { save, .. }) => {
+                    Ok(Some((save)(handoff_data.handoff.any_ref())?))
+                }
+                None if handoff_data.handoff.is_bottom() => Ok(None),
+                None => Err(CheckpointError::HandoffNotCheckpointable(HandoffId(
+                    handoff_id,
+                ))),
+            })
+            .collect::<Result<Vec<_>, CheckpointError>>()?;
+
+        Ok(Checkpoint {
+            current_tick: self.context.current_tick,
+            states,
+            handoffs,
+        })
+    }
+
+    /// Restores the state and buffered handoff data from a [`Checkpoint`] taken with
+    /// [`Self::checkpoint`]. Should be called on a freshly-constructed instance of the same graph,
+    /// for example from the same `hydroflow_syntax_checkpointed!` invocation, before it is run.
+    ///
+    /// On success, the instance resumes at the tick at which the checkpoint was taken. On error,
+    /// some state may have already been overwritten so the instance should be discarded.
+    pub fn restore(&mut self, checkpoint: &Checkpoint) -> Result<(), CheckpointError> {
+        if 0 != self.context.current_stratum {
+            return Err(CheckpointError::NotAtTickBoundary);
+        }
+        if checkpoint.states.len() != self.context.states.len() {
+            return Err(CheckpointError::GraphMismatch(format!(
+                "checkpoint has {} states, graph has {}",
+                checkpoint.states.len(),
+                self.context.states.len(),
+            )));
+        }
+        if checkpoint.handoffs.len() != self.handoffs.len() {
+            return Err(CheckpointError::GraphMismatch(format!(
+                "checkpoint has {} handoffs, graph has {}",
+                checkpoint.handoffs.len(),
+                self.handoffs.len(),
+            )));
+        }
+
+        for (state_id, (state_data, buf)) in self
+            .context
+            .states
+            .iter_mut()
+            .zip(checkpoint.states.iter())
+            .enumerate()
+        {
+            if state_data.is_removed {
+                continue;
+            }
+            let StateCheckpointFns { load, .. } = state_data
+                .checkpoint
+                .ok_or(CheckpointError::StateNotCheckpointable(StateId(state_id)))?;
+            (load)(&mut *state_data.state, buf)?;
+        }
+
+        for (handoff_id, (handoff_data, buf)) in self
+            .handoffs
+            .iter_mut()
+            .zip(checkpoint.handoffs.iter())
+            .enumerate()
+        {
+            match (handoff_data.checkpoint, buf) {
+                (Some(HandoffCheckpointFns { load, .. }), Some(buf)) => {
+                    (load)(handoff_data.handoff.any_ref(), buf)?
+                }
+                (None, None) => {}
+                _ => {
+                    return Err(CheckpointError::GraphMismatch(format!(
+                        "checkpoint support differs for handoff {}",
+                        handoff_id
+                    )))
+                }
+            }
+            handoff_data.metrics.item_count = handoff_data.handoff.item_count();
+        }
+
+        self.context.current_tick = checkpoint.current_tick;
+        Ok(())
+    }
+
     /// Gets a exclusive (mut) ref to the internal context, setting the subgraph ID.
     pub fn context_mut(&mut self, sg_id: SubgraphId) -> &mut Context {
         self.context.subgraph_id = sg_id;
@@ -691,6 +1551,11 @@ impl<'a> Hydroflow<'a> {
         self.context.request_task(future);
     }
 
+    /// Alias for [`Context::take_tasks`].
+    pub(crate) fn take_tasks(&mut self) -> Vec<Pin<Box<dyn Future<Output = ()> + 'static>>> {
+        self.context.take_tasks()
+    }
+
     /// Alias for [`Context::abort_tasks`].
     pub fn abort_tasks(&mut self) {
         self.context.abort_tasks()
@@ -716,12 +1581,19 @@ impl<'a> Drop for Hydroflow<'a> {
 #[doc(hidden)]
 pub struct HandoffData {
     /// A friendly name for diagnostics.
-    #[allow(dead_code)] // TODO(mingwei): remove attr once used.
     pub(super) name: Cow<'static, str>,
     /// Crate-visible to crate for `handoff_list` internals.
     pub(super) handoff: Box<dyn HandoffMeta>,
     pub(super) preds: Vec<SubgraphId>,
     pub(super) succs: Vec<SubgraphId>,
+    /// Set if this handoff's contents are included in checkpoints.
+    pub(super) checkpoint: Option<HandoffCheckpointFns>,
+    /// See [`Hydroflow::make_edge_bounded`].
+    pub(super) capacity: Option<usize>,
+    /// Runtime metrics, see [`Hydroflow::handoff_metrics`].
+    pub(super) metrics: HandoffMetrics,
+    /// If this handoff has been removed, see [`Hydroflow::remove_handoff`].
+    pub(super) is_removed: bool,
 }
 impl std::fmt::Debug for HandoffData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
@@ -739,6 +1611,10 @@ impl HandoffData {
             handoff: Box::new(handoff),
             preds,
             succs,
+            checkpoint: None,
+            capacity: None,
+            metrics: Default::default(),
+            is_removed: false,
         }
     }
 }
@@ -749,13 +1625,11 @@ impl HandoffData {
 /// structure and scheduled state.
 pub(super) struct SubgraphData<'a> {
     /// A friendly name for diagnostics.
-    #[allow(dead_code)] // TODO(mingwei): remove attr once used.
     pub(super) name: Cow<'static, str>,
     /// This subgraph's stratum number.
     pub(super) stratum: usize,
     /// The actual execution code of the subgraph.
-    subgraph: Box<dyn Subgraph + 'a>,
-    #[allow(dead_code)]
+    subgraph: SubgraphFn<'a>,
     preds: Vec<HandoffId>,
     succs: Vec<HandoffId>,
 
@@ -764,18 +1638,28 @@ pub(super) struct SubgraphData<'a> {
     /// `Self::succs`, as all `SubgraphData` are owned by the same vec
     /// `Hydroflow::subgraphs`.
     is_scheduled: Cell<bool>,
+    /// If this subgraph is descheduled because one of its bounded output handoffs is full. See
+    /// [`Hydroflow::make_edge_bounded`].
+    pub(super) is_blocked: Cell<bool>,
 
     /// Keep track of the last tick that this subgraph was run in
     last_tick_run_in: Option<usize>,
 
     /// If this subgraph is marked as lazy, then sending data back to a lower stratum does not trigger a new tick to be run.
     is_lazy: bool,
+
+    /// Runtime metrics, see [`Hydroflow::subgraph_metrics`].
+    metrics: SubgraphMetrics,
+    /// Start and end time of the latest run of this subgraph, if it was timed.
+    last_run: Option<(Instant, Instant)>,
+    /// If this subgraph has been removed, see [`Hydroflow::remove_subgraph`].
+    pub(super) is_removed: bool,
 }
 impl<'a> SubgraphData<'a> {
     pub fn new(
         name: Cow<'static, str>,
         stratum: usize,
-        subgraph: impl Subgraph + 'a,
+        subgraph: SubgraphFn<'a>,
         preds: Vec<HandoffId>,
         succs: Vec<HandoffId>,
         is_scheduled: bool,
@@ -784,17 +1668,47 @@ impl<'a> SubgraphData<'a> {
         Self {
             name,
             stratum,
-            subgraph: Box::new(subgraph),
+            subgraph,
             preds,
             succs,
             is_scheduled: Cell::new(is_scheduled),
+            is_blocked: Cell::new(false),
             last_tick_run_in: None,
             is_lazy: laziness,
+            metrics: Default::default(),
+            last_run: None,
+            is_removed: false,
         }
     }
 }
 
+/// The execution code of a subgraph.
+pub(super) enum SubgraphFn<'a> {
+    /// Runs on the current thread with access to the [`Context`].
+    Local(Box<dyn Subgraph + 'a>),
+    /// May run on a worker thread, see [`Hydroflow::add_subgraph_parallel`].
+    Parallel(Box<dyn ParallelSubgraph + 'a>),
+}
+impl<'a> SubgraphFn<'a> {
+    /// A subgraph which does nothing, used in place of removed subgraphs.
+    fn empty() -> Self {
+        Self::Local(Box::new(
+            |_context: &mut Context, _handoffs: &mut Vec<HandoffData>| {},
+        ))
+    }
+
+    fn is_parallel(&self) -> bool {
+        matches!(self, Self::Parallel(_))
+    }
+}
+
 /// Internal struct containing a pointer to [`Hydroflow`]-owned state.
 pub(crate) struct StateData {
     pub state: Box<dyn Any>,
+    /// Set if this state is included in checkpoints.
+    pub checkpoint: Option<StateCheckpointFns>,
+    /// Name of the subgraph which this state belongs to, if known.
+    pub owner: Option<&'static str>,
+    /// If this state has been removed, see [`Context::remove_state`].
+    pub is_removed: bool,
 }
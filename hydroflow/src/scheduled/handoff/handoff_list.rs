@@ -62,19 +62,26 @@ where
     type Ctx<'a> = (&'a PortCtx<S, H>, Rest::Ctx<'a>);
     fn make_ctx<'a>(&self, handoffs: &'a [HandoffData]) -> Self::Ctx<'a> {
         let (this, rest) = self;
-        let handoff = handoffs
-            .get(this.handoff_id.0)
-            .unwrap()
-            .handoff
-            .any_ref()
-            .downcast_ref()
-            .expect("Attempted to cast handoff to wrong type.");
-
-        let ctx = RefCast::ref_cast(handoff);
+        let ctx = port_ctx(this, handoffs);
         let ctx_rest = rest.make_ctx(handoffs);
         (ctx, ctx_rest)
     }
 }
+/// Gets the [`PortCtx`] for a single port.
+fn port_ctx<'a, S, H>(port: &Port<S, H>, handoffs: &'a [HandoffData]) -> &'a PortCtx<S, H>
+where
+    S: Polarity,
+    H: Handoff,
+{
+    let handoff = handoffs
+        .get(port.handoff_id.0)
+        .unwrap()
+        .handoff
+        .any_ref()
+        .downcast_ref()
+        .expect("Attempted to cast handoff to wrong type.");
+    RefCast::ref_cast(handoff)
+}
 #[sealed]
 impl<S> PortList<S> for ()
 where
@@ -93,6 +100,44 @@ where
     fn make_ctx<'a>(&self, _handoffs: &'a [HandoffData]) -> Self::Ctx<'a> {}
 }
 
+/// Sealed trait for variadic lists of ports whose handoffs are all [`Sync`], so the ports' contexts
+/// can be sent to worker threads. Used by
+/// [`Hydroflow::add_subgraph_parallel`](crate::scheduled::graph::Hydroflow::add_subgraph_parallel).
+#[sealed]
+pub trait ParallelPortList<S>: PortList<S>
+where
+    S: Polarity,
+{
+    /// The [`Send`] [`Variadic`] return type of [`Self::make_parallel_ctx`]. Same as
+    /// [`PortList::Ctx`].
+    type ParallelCtx<'a>: Variadic + Send;
+    /// Iteratively/recursively construct a `ParallelCtx` variadic list.
+    fn make_parallel_ctx<'a>(&self, handoffs: &'a [HandoffData]) -> Self::ParallelCtx<'a>;
+}
+#[sealed]
+impl<S, Rest, H> ParallelPortList<S> for (Port<S, H>, Rest)
+where
+    S: Polarity,
+    H: Handoff + Sync,
+    Rest: ParallelPortList<S>,
+{
+    type ParallelCtx<'a> = (&'a PortCtx<S, H>, Rest::ParallelCtx<'a>);
+    fn make_parallel_ctx<'a>(&self, handoffs: &'a [HandoffData]) -> Self::ParallelCtx<'a> {
+        let (this, rest) = self;
+        let ctx = port_ctx(this, handoffs);
+        let ctx_rest = rest.make_parallel_ctx(handoffs);
+        (ctx, ctx_rest)
+    }
+}
+#[sealed]
+impl<S> ParallelPortList<S> for ()
+where
+    S: Polarity,
+{
+    type ParallelCtx<'a> = ();
+    fn make_parallel_ctx<'a>(&self, _handoffs: &'a [HandoffData]) -> Self::ParallelCtx<'a> {}
+}
+
 /// Trait for splitting a list of ports into two.
 #[sealed]
 pub trait PortListSplit<S, A>: PortList<S>
@@ -1,12 +1,14 @@
 //! Module for all [`Handoff`]-related items.
 
 pub mod handoff_list;
+mod send_vector;
 mod tee;
 mod vector;
 
 use std::any::Any;
-use std::cell::RefMut;
+use std::ops::DerefMut;
 
+pub use send_vector::SendVecHandoff;
 pub use tee::TeeingHandoff;
 pub use vector::VecHandoff;
 
@@ -35,6 +37,12 @@ pub trait HandoffMeta: Any {
     // TODO(justin): more fine-grained info here.
     /// Return if the handoff is empty.
     fn is_bottom(&self) -> bool;
+
+    /// Return the number of items buffered in the handoff, used for metrics. Defaults to `0` for
+    /// handoffs which do not track this.
+    fn item_count(&self) -> usize {
+        0
+    }
 }
 
 /// Trait for handoffs to implement.
@@ -42,13 +50,18 @@ pub trait Handoff: Default + HandoffMeta {
     /// Inner datastructure type.
     type Inner;
 
+    /// Exclusive borrow of [`Self::Inner`] returned by [`Self::borrow_mut_swap`].
+    type InnerMut<'a>: DerefMut<Target = Self::Inner>
+    where
+        Self: 'a;
+
     /// Take the inner datastructure, similar to [`std::mem::take`].
     fn take_inner(&self) -> Self::Inner;
 
     /// Take the inner datastructure by swapping input and output buffers.
     ///
     /// For better performance over [`Self::take_inner`].
-    fn borrow_mut_swap(&self) -> RefMut<Self::Inner>;
+    fn borrow_mut_swap(&self) -> Self::InnerMut<'_>;
 
     /// See [`CanReceive::give`].
     fn give<T>(&self, item: T) -> T
@@ -0,0 +1,97 @@
+use std::any::Any;
+use std::sync::{Mutex, MutexGuard};
+
+use super::{CanReceive, Handoff, HandoffMeta, Iter};
+
+/// A [Vec]-based FIFO handoff which is [`Send`] and [`Sync`] (if `T: Send`).
+///
+/// Equivalent to [`VecHandoff`](super::VecHandoff) but uses [`Mutex`]es instead of
+/// [`RefCell`](std::cell::RefCell)s, so subgraphs connected by this handoff can be added with
+/// [`Hydroflow::add_subgraph_parallel`](crate::scheduled::graph::Hydroflow::add_subgraph_parallel)
+/// and run on worker threads.
+pub struct SendVecHandoff<T>
+where
+    T: 'static,
+{
+    pub(crate) input: Mutex<Vec<T>>,
+    pub(crate) output: Mutex<Vec<T>>,
+}
+impl<T> Default for SendVecHandoff<T>
+where
+    T: 'static,
+{
+    fn default() -> Self {
+        Self {
+            input: Default::default(),
+            output: Default::default(),
+        }
+    }
+}
+impl<T> SendVecHandoff<T> {
+    fn lock_input(&self) -> MutexGuard<'_, Vec<T>> {
+        self.input
+            .lock()
+            .expect("SendVecHandoff input lock poisoned.")
+    }
+
+    fn lock_output(&self) -> MutexGuard<'_, Vec<T>> {
+        self.output
+            .lock()
+            .expect("SendVecHandoff output lock poisoned.")
+    }
+}
+impl<T> Handoff for SendVecHandoff<T> {
+    type Inner = Vec<T>;
+    type InnerMut<'a> = MutexGuard<'a, Vec<T>>;
+
+    fn take_inner(&self) -> Self::Inner {
+        std::mem::take(&mut *self.lock_input())
+    }
+
+    fn borrow_mut_swap(&self) -> Self::InnerMut<'_> {
+        let mut input = self.lock_input();
+        let mut output = self.lock_output();
+
+        std::mem::swap(&mut *input, &mut *output);
+
+        output
+    }
+}
+
+impl<T> CanReceive<Option<T>> for SendVecHandoff<T> {
+    fn give(&self, mut item: Option<T>) -> Option<T> {
+        if let Some(item) = item.take() {
+            self.lock_input().push(item)
+        }
+        None
+    }
+}
+impl<T, I> CanReceive<Iter<I>> for SendVecHandoff<T>
+where
+    I: Iterator<Item = T>,
+{
+    fn give(&self, mut iter: Iter<I>) -> Iter<I> {
+        self.lock_input().extend(&mut iter.0);
+        iter
+    }
+}
+impl<T> CanReceive<Vec<T>> for SendVecHandoff<T> {
+    fn give(&self, mut vec: Vec<T>) -> Vec<T> {
+        self.lock_input().extend(vec.drain(..));
+        vec
+    }
+}
+
+impl<T> HandoffMeta for SendVecHandoff<T> {
+    fn any_ref(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_bottom(&self) -> bool {
+        self.lock_input().is_empty()
+    }
+
+    fn item_count(&self) -> usize {
+        self.lock_input().len()
+    }
+}
@@ -2,7 +2,7 @@
 #![allow(missing_docs)]
 
 use std::any::Any;
-use std::cell::RefCell;
+use std::cell::{RefCell, RefMut};
 use std::collections::VecDeque;
 use std::rc::Rc;
 
@@ -72,16 +72,25 @@ impl<T> HandoffMeta for TeeingHandoff<T> {
     fn is_bottom(&self) -> bool {
         true
     }
+
+    fn item_count(&self) -> usize {
+        (*self.internal).borrow().readers[self.read_from]
+            .contents
+            .iter()
+            .map(Vec::len)
+            .sum()
+    }
 }
 
 impl<T> Handoff for TeeingHandoff<T> {
     type Inner = VecDeque<Vec<T>>;
+    type InnerMut<'a> = RefMut<'a, VecDeque<Vec<T>>>;
 
     fn take_inner(&self) -> Self::Inner {
         std::mem::take(&mut (*self.internal).borrow_mut().readers[self.read_from].contents)
     }
 
-    fn borrow_mut_swap(&self) -> std::cell::RefMut<Self::Inner> {
+    fn borrow_mut_swap(&self) -> Self::InnerMut<'_> {
         todo!()
     }
 }
@@ -25,12 +25,13 @@ where
 }
 impl<T> Handoff for VecHandoff<T> {
     type Inner = Vec<T>;
+    type InnerMut<'a> = RefMut<'a, Vec<T>>;
 
     fn take_inner(&self) -> Self::Inner {
         self.input.take()
     }
 
-    fn borrow_mut_swap(&self) -> RefMut<Self::Inner> {
+    fn borrow_mut_swap(&self) -> Self::InnerMut<'_> {
         let mut input = self.input.borrow_mut();
         let mut output = self.output.borrow_mut();
 
@@ -72,6 +73,10 @@ impl<T> HandoffMeta for VecHandoff<T> {
     fn is_bottom(&self) -> bool {
         (*self.input).borrow_mut().is_empty()
     }
+
+    fn item_count(&self) -> usize {
+        (*self.input).borrow().len()
+    }
 }
 
 impl<H> HandoffMeta for Rc<RefCell<H>>
@@ -85,4 +90,8 @@ where
     fn is_bottom(&self) -> bool {
         self.borrow().is_bottom()
     }
+
+    fn item_count(&self) -> usize {
+        self.borrow().item_count()
+    }
 }
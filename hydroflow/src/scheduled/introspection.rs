@@ -0,0 +1,458 @@
+//! Module for live introspection of a running [`Hydroflow`] instance.
+//!
+//! [`Hydroflow::introspect`] returns an [`IntrospectionSnapshot`] of the current tick and stratum,
+//! the number of items buffered in each handoff, and optionally the size of each subgraph's state.
+//! [`Hydroflow::watch_introspection`] publishes these snapshots to a [`watch`] channel as the
+//! instance runs.
+//!
+//! With the `introspection_server` feature on non-WASM platforms,
+//! `Hydroflow::serve_introspection` starts an embedded HTTP server for attaching to a running
+//! instance, with the following endpoints:
+//! * `GET /graph.mermaid` and `GET /graph.dot`: the graph from [`Hydroflow::meta_graph`], rendered
+//!   as Mermaid or DOT.
+//! * `GET /diagnostics`: the JSON [`Hydroflow::diagnostics`].
+//! * `GET /status`: the latest JSON [`IntrospectionSnapshot`].
+//! * `GET /ws`: a WebSocket which sends each new JSON [`IntrospectionSnapshot`] as a text message.
+//!
+//! The server has no authentication, so it only binds to loopback addresses and rejects requests
+//! from other hosts or browser origins. Use e.g. an SSH tunnel to attach to a remote instance.
+//!
+//! State sizes are the bincode-serialized size in bytes, so are only known for state added with
+//! checkpoint support, e.g. by [`hydroflow_syntax_checkpointed!`](crate::hydroflow_syntax_checkpointed).
+//! Serializing all state may be expensive, so sizes are only computed when requested.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use instant::Instant;
+use serde::Serialize;
+use tokio::sync::watch;
+
+use super::graph::Hydroflow;
+use super::{HandoffId, SubgraphId};
+
+/// A snapshot of the runtime status of a [`Hydroflow`] instance, returned by
+/// [`Hydroflow::introspect`].
+///
+/// Subgraphs and handoffs are keyed by [`SubgraphId`] and [`HandoffId`], as in
+/// [`MetricsSnapshot`](super::metrics::MetricsSnapshot).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct IntrospectionSnapshot {
+    /// The current tick.
+    pub current_tick: usize,
+    /// The current stratum.
+    pub current_stratum: usize,
+    /// Status of each handoff.
+    pub handoffs: BTreeMap<HandoffId, HandoffIntrospection>,
+    /// Status of each subgraph.
+    pub subgraphs: BTreeMap<SubgraphId, SubgraphIntrospection>,
+    /// State which does not belong to any subgraph, e.g. added with [`Hydroflow::add_state`].
+    pub other_states: Vec<StateIntrospection>,
+}
+
+/// Runtime status of a single handoff, see [`IntrospectionSnapshot`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct HandoffIntrospection {
+    /// The handoff's label, see [`Hydroflow::handoff_label`].
+    pub label: String,
+    /// Number of items buffered in the handoff, as of the last subgraph run.
+    pub item_count: usize,
+}
+
+/// Runtime status of a single subgraph, see [`IntrospectionSnapshot`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SubgraphIntrospection {
+    /// The subgraph's label, see [`Hydroflow::subgraph_label`].
+    pub label: String,
+    /// The subgraph's stratum.
+    pub stratum: usize,
+    /// If the subgraph is descheduled due to backpressure, see
+    /// [`Hydroflow::make_edge_bounded`].
+    pub is_blocked: bool,
+    /// The state belonging to the subgraph's operators.
+    pub states: Vec<StateIntrospection>,
+}
+
+/// The size of a single piece of state, see [`IntrospectionSnapshot`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct StateIntrospection {
+    /// Index of the state in the instance.
+    pub state_id: usize,
+    /// Serialized size in bytes, if requested and the state was added with checkpoint support.
+    pub size_bytes: Option<u64>,
+}
+
+/// Publishes [`IntrospectionSnapshot`]s, see [`Hydroflow::watch_introspection`].
+pub(crate) struct IntrospectionPublisher {
+    send: watch::Sender<Arc<IntrospectionSnapshot>>,
+    /// Minimum time between snapshots while ticks are running.
+    interval: Duration,
+    /// If snapshots include state sizes.
+    state_sizes: bool,
+    /// Time and tick of the latest snapshot.
+    last_publish: (Instant, usize),
+}
+
+impl<'a> Hydroflow<'a> {
+    /// Returns a snapshot of the current tick and stratum, handoff sizes, and state. If
+    /// `state_sizes`, also serializes each piece of checkpointed state to compute its size.
+    pub fn introspect(&self, state_sizes: bool) -> IntrospectionSnapshot {
+        let mut subgraphs: BTreeMap<SubgraphId, SubgraphIntrospection> = self
+            .subgraph_ids()
+            .map(|sg_id| {
+                let sg_data = &self.subgraphs[sg_id.0];
+                let sg_introspection = SubgraphIntrospection {
+                    label: self.subgraph_label(sg_id),
+                    stratum: sg_data.stratum,
+                    is_blocked: sg_data.is_blocked.get(),
+                    states: Vec::new(),
+                };
+                (sg_id, sg_introspection)
+            })
+            .collect();
+        // State owners are subgraph names, which are usually but not necessarily unique. If not,
+        // attribute the state to the first subgraph with the name.
+        let mut subgraph_names: HashMap<&str, SubgraphId> = HashMap::new();
+        for sg_id in self.subgraph_ids() {
+            subgraph_names
+                .entry(self.subgraph_name(sg_id))
+                .or_insert(sg_id);
+        }
+
+        let mut other_states = Vec::new();
+        for (state_id, state_data) in self.context.states.iter().enumerate() {
+            if state_data.is_removed {
+                continue;
+            }
+            let state_introspection = StateIntrospection {
+                state_id,
+                size_bytes: state_data
+                    .checkpoint
+                    .filter(|_| state_sizes)
+                    .and_then(|fns| (fns.size)(&*state_data.state).ok()),
+            };
+            let owner = state_data
+                .owner
+                .and_then(|owner| subgraph_names.get(owner))
+                .and_then(|sg_id| subgraphs.get_mut(sg_id));
+            match owner {
+                Some(sg_introspection) => sg_introspection.states.push(state_introspection),
+                None => other_states.push(state_introspection),
+            }
+        }
+
+        IntrospectionSnapshot {
+            current_tick: self.current_tick(),
+            current_stratum: self.current_stratum(),
+            handoffs: self
+                .handoff_ids()
+                .map(|handoff_id| {
+                    let handoff_introspection = HandoffIntrospection {
+                        label: self.handoff_label(handoff_id),
+                        item_count: self.handoff_item_count(handoff_id),
+                    };
+                    (handoff_id, handoff_introspection)
+                })
+                .collect(),
+            subgraphs,
+            other_states,
+        }
+    }
+
+    /// Starts publishing [`IntrospectionSnapshot`]s to the returned channel: at the end of a tick if
+    /// at least `interval` has passed since the last snapshot, and whenever the instance runs out
+    /// of work. Replaces any previous channel. See [`Self::introspect`] for `state_sizes`.
+    pub fn watch_introspection(
+        &mut self,
+        interval: Duration,
+        state_sizes: bool,
+    ) -> watch::Receiver<Arc<IntrospectionSnapshot>> {
+        let (send, recv) = watch::channel(Arc::new(self.introspect(state_sizes)));
+        self.introspection = Some(Box::new(IntrospectionPublisher {
+            send,
+            interval,
+            state_sizes,
+            last_publish: (Instant::now(), self.current_tick()),
+        }));
+        recv
+    }
+
+    /// Publishes a snapshot if [`Self::watch_introspection`] is enabled and it is due. If `idle`,
+    /// the instance has run out of work, so a snapshot is due if any ticks have run since the last
+    /// one.
+    pub(super) fn publish_introspection(&mut self, idle: bool) {
+        let Some(publisher) = &self.introspection else {
+            return;
+        };
+        let (last_time, last_tick) = publisher.last_publish;
+        if last_tick == self.current_tick() || !(idle || publisher.interval <= last_time.elapsed())
+        {
+            return;
+        }
+        let snapshot = Arc::new(self.introspect(publisher.state_sizes));
+        let publisher = self.introspection.as_mut().unwrap();
+        publisher.last_publish = (Instant::now(), snapshot.current_tick);
+        // Ignore error if all receivers have been dropped.
+        let _ = publisher.send.send(snapshot);
+    }
+}
+
+#[cfg(all(feature = "introspection_server", not(target_arch = "wasm32")))]
+mod server {
+    use std::io;
+    use std::net::IpAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+    use tokio::sync::watch;
+    use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+    use tokio_tungstenite::tungstenite::protocol::Role;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::WebSocketStream;
+
+    use super::IntrospectionSnapshot;
+    use crate::scheduled::graph::Hydroflow;
+
+    /// Minimum time between published snapshots while ticks are running.
+    const SERVER_INTERVAL: Duration = Duration::from_millis(100);
+    /// Time to wait before accepting again after an accept error, e.g. running out of file
+    /// descriptors.
+    const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+    /// Maximum size of an HTTP request head.
+    const MAX_REQUEST_LEN: usize = 16 * 1024;
+
+    /// Static pages which do not change while the instance runs.
+    struct Pages {
+        mermaid: Option<String>,
+        dot: Option<String>,
+        diagnostics: String,
+    }
+
+    impl<'a> Hydroflow<'a> {
+        /// Starts an HTTP/WebSocket server on `addr` for introspecting this instance while it runs,
+        /// returning the bound address. See the [module-level docs](super) for the endpoints.
+        ///
+        /// Returns an error if `addr` is not a loopback address. The server runs as a Tokio task
+        /// until this instance is dropped. Snapshots are published as in
+        /// [`Self::watch_introspection`], at most every 100ms while ticks are running. See
+        /// [`Self::introspect`] for `state_sizes`.
+        pub async fn serve_introspection(
+            &mut self,
+            addr: impl ToSocketAddrs,
+            state_sizes: bool,
+        ) -> io::Result<std::net::SocketAddr> {
+            let listener = TcpListener::bind(addr).await?;
+            let local_addr = listener.local_addr()?;
+            if !local_addr.ip().is_loopback() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "introspection server must bind to a loopback address, not {}",
+                        local_addr
+                    ),
+                ));
+            }
+
+            let pages = Arc::new(Pages {
+                mermaid: self
+                    .meta_graph()
+                    .map(|graph| graph.to_mermaid(&Default::default())),
+                dot: self
+                    .meta_graph()
+                    .map(|graph| graph.to_dot(&Default::default())),
+                diagnostics: serde_json::to_string(self.diagnostics().unwrap_or_default()).unwrap(),
+            });
+            let mut snapshots = self.watch_introspection(SERVER_INTERVAL, state_sizes);
+
+            tokio::spawn(async move {
+                loop {
+                    tokio::select! {
+                        accepted = listener.accept() => {
+                            let (stream, _peer_addr) = match accepted {
+                                Ok(accepted) => accepted,
+                                Err(err) => {
+                                    tracing::debug!(error = %err, "Introspection accept error, retrying.");
+                                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                                    continue;
+                                }
+                            };
+                            let pages = pages.clone();
+                            let snapshots = snapshots.clone();
+                            tokio::spawn(async move {
+                                if let Err(err) = handle_connection(stream, pages, snapshots).await {
+                                    tracing::debug!(error = %err, "Introspection connection error.");
+                                }
+                            });
+                        }
+                        changed = snapshots.changed() => {
+                            if changed.is_err() {
+                                // Instance dropped.
+                                break;
+                            }
+                        }
+                    }
+                }
+            });
+
+            Ok(local_addr)
+        }
+    }
+
+    async fn handle_connection(
+        mut stream: TcpStream,
+        pages: Arc<Pages>,
+        mut snapshots: watch::Receiver<Arc<IntrospectionSnapshot>>,
+    ) -> io::Result<()> {
+        // Read the request head.
+        let mut buf = Vec::new();
+        while !buf.windows(4).any(|window| window == b"\r\n\r\n") {
+            if MAX_REQUEST_LEN < buf.len() {
+                return write_response(
+                    &mut stream,
+                    "431 Request Header Fields Too Large",
+                    "text/plain",
+                    "",
+                )
+                .await;
+            }
+            let mut chunk = [0; 1024];
+            let len = stream.read(&mut chunk).await?;
+            if 0 == len {
+                return Ok(());
+            }
+            buf.extend_from_slice(&chunk[..len]);
+        }
+        let head = String::from_utf8_lossy(&buf);
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next().unwrap_or_default().split(' ');
+        let (method, path) = (
+            request_line.next().unwrap_or_default(),
+            request_line.next().unwrap_or_default(),
+        );
+        let header = |name: &str| {
+            lines.clone().find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.trim()
+                    .eq_ignore_ascii_case(name)
+                    .then_some(value.trim())
+            })
+        };
+
+        // Reject requests from other hosts or from web pages on other origins, including via DNS
+        // rebinding.
+        if !header("Host").map_or(false, is_loopback_host)
+            || !header("Origin").map_or(true, |origin| {
+                origin
+                    .split_once("://")
+                    .map_or(false, |(_scheme, host)| is_loopback_host(host))
+            })
+        {
+            return write_response(&mut stream, "403 Forbidden", "text/plain", "").await;
+        }
+        if "GET" != method {
+            return write_response(&mut stream, "405 Method Not Allowed", "text/plain", "").await;
+        }
+        match path {
+            "/graph.mermaid" | "/graph.dot" => {
+                let graph = if "/graph.mermaid" == path {
+                    &pages.mermaid
+                } else {
+                    &pages.dot
+                };
+                match graph {
+                    Some(graph) => write_response(&mut stream, "200 OK", "text/plain", graph).await,
+                    None => write_response(&mut stream, "404 Not Found", "text/plain", "").await,
+                }
+            }
+            "/diagnostics" => {
+                write_response(
+                    &mut stream,
+                    "200 OK",
+                    "application/json",
+                    &pages.diagnostics,
+                )
+                .await
+            }
+            "/status" => {
+                let snapshot = serde_json::to_string(&**snapshots.borrow()).unwrap();
+                write_response(&mut stream, "200 OK", "application/json", &snapshot).await
+            }
+            "/ws" => {
+                let Some(key) = header("Sec-WebSocket-Key") else {
+                    return write_response(&mut stream, "400 Bad Request", "text/plain", "").await;
+                };
+                let accept = derive_accept_key(key.as_bytes());
+                stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                            accept
+                        )
+                        .as_bytes(),
+                    )
+                    .await?;
+                let mut websocket =
+                    WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+                loop {
+                    let snapshot = serde_json::to_string(&**snapshots.borrow_and_update()).unwrap();
+                    websocket
+                        .send(Message::Text(snapshot))
+                        .await
+                        .map_err(io::Error::other)?;
+                    loop {
+                        tokio::select! {
+                            changed = snapshots.changed() => {
+                                if changed.is_err() {
+                                    // Instance dropped.
+                                    return Ok(());
+                                }
+                                break;
+                            }
+                            msg = websocket.next() => match msg {
+                                // Ignore messages from the client.
+                                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                                Some(Ok(_)) => continue,
+                            }
+                        }
+                    }
+                }
+            }
+            _ => write_response(&mut stream, "404 Not Found", "text/plain", "").await,
+        }
+    }
+
+    async fn write_response(
+        stream: &mut TcpStream,
+        status: &str,
+        content_type: &str,
+        body: &str,
+    ) -> io::Result<()> {
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            content_type,
+            body.len(),
+        );
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    /// Returns if the `Host` header value (or origin host) `host`, with an optional port, is
+    /// `localhost` or a loopback IP address.
+    fn is_loopback_host(host: &str) -> bool {
+        let hostname = match host.strip_prefix('[') {
+            // IPv6, e.g. `[::1]:8080`.
+            Some(rest) => rest.split(']').next().unwrap_or_default(),
+            None => host.split(':').next().unwrap_or_default(),
+        };
+        hostname.eq_ignore_ascii_case("localhost")
+            || hostname
+                .parse::<IpAddr>()
+                .map_or(false, |ip| ip.is_loopback())
+    }
+}
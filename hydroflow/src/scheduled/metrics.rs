@@ -0,0 +1,165 @@
+//! Module for runtime metrics of a [`Hydroflow`] instance, see [`Hydroflow::metrics`].
+//!
+//! Metrics are only recorded once enabled with [`Hydroflow::set_metrics_enabled`]. Item counts are
+//! measured from the number of items buffered in each handoff before and after each subgraph run,
+//! so handoffs which do not report their
+//! [`HandoffMeta::item_count`](super::handoff::HandoffMeta::item_count) are not counted. When
+//! parallel subgraphs run concurrently (see [`Hydroflow::set_worker_threads`]) item counts are
+//! measured once for the whole batch, so may be approximate if a batch contains both the producer
+//! and consumer of a handoff.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::graph::Hydroflow;
+use super::{HandoffId, SubgraphId};
+
+/// Runtime metrics for a single subgraph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SubgraphMetrics {
+    /// Number of times the subgraph has been run.
+    pub invocations: u64,
+    /// Total wall time spent running the subgraph.
+    pub wall_time: Duration,
+    /// Number of items taken from the subgraph's input handoffs.
+    pub items_in: u64,
+    /// Number of items given to the subgraph's output handoffs.
+    pub items_out: u64,
+}
+
+/// Runtime metrics for a single handoff.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct HandoffMetrics {
+    /// Number of items given to the handoff.
+    pub items_in: u64,
+    /// Number of items taken from the handoff.
+    pub items_out: u64,
+    /// Number of items buffered in the handoff, as of the last subgraph run.
+    pub item_count: usize,
+}
+
+/// A snapshot of all the metrics in a [`Hydroflow`] instance, returned by [`Hydroflow::metrics`].
+///
+/// Metrics are keyed by [`SubgraphId`] and [`HandoffId`], with a human-readable label for each. For
+/// graphs built with the surface syntax, the label is the operator names in
+/// [`Hydroflow::meta_graph`]: `"source_iter -> map -> for_each"` for a subgraph, or `"map -> join"`
+/// for a handoff between the two operators. Otherwise the label is the name given when the
+/// subgraph or handoff was added.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct MetricsSnapshot {
+    /// The current tick when the snapshot was taken.
+    pub current_tick: usize,
+    /// Metrics for each subgraph.
+    pub subgraphs: BTreeMap<SubgraphId, SubgraphMetrics>,
+    /// Metrics for each handoff.
+    pub handoffs: BTreeMap<HandoffId, HandoffMetrics>,
+    /// Label of each subgraph, see [`Hydroflow::subgraph_label`].
+    pub subgraph_labels: BTreeMap<SubgraphId, String>,
+    /// Label of each handoff, see [`Hydroflow::handoff_label`].
+    pub handoff_labels: BTreeMap<HandoffId, String>,
+}
+impl MetricsSnapshot {
+    /// Returns the metrics of all subgraphs which contain the operator named `op_name`, e.g.
+    /// `"join"`.
+    pub fn subgraphs_with_operator<'a>(
+        &'a self,
+        op_name: &'a str,
+    ) -> impl 'a + Iterator<Item = (SubgraphId, &'a SubgraphMetrics)> {
+        self.subgraphs
+            .iter()
+            .filter(move |(sg_id, _)| {
+                self.subgraph_labels
+                    .get(sg_id)
+                    .map_or(false, |label| label.split(" -> ").any(|op| op == op_name))
+            })
+            .map(|(&sg_id, metrics)| (sg_id, metrics))
+    }
+
+    /// Returns the metrics of the handoff with the given label, e.g. `"map -> join"`, if there is
+    /// exactly one.
+    pub fn handoff_with_label(&self, label: &str) -> Option<(HandoffId, &HandoffMetrics)> {
+        let mut matching = self
+            .handoff_labels
+            .iter()
+            .filter(|(_, handoff_label)| label == *handoff_label);
+        let (&handoff_id, _) = matching.next()?;
+        if matching.next().is_some() {
+            return None;
+        }
+        Some((handoff_id, self.handoffs.get(&handoff_id)?))
+    }
+}
+
+/// Name prefix given to subgraphs by `HydroflowGraph::as_code`, followed by the `GraphSubgraphId`.
+const SURFACE_SUBGRAPH_PREFIX: &str = "Subgraph ";
+/// Name prefix given to handoffs by `HydroflowGraph::as_code`, followed by the `GraphNodeId`.
+const SURFACE_HANDOFF_PREFIX: &str = "handoff ";
+
+impl<'a> Hydroflow<'a> {
+    /// Returns a human-readable label for the subgraph, using the operator names from
+    /// [`Self::meta_graph`] if available, otherwise the subgraph's name. Labels are not
+    /// necessarily unique. Used for [`MetricsSnapshot::subgraph_labels`].
+    pub fn subgraph_label(&self, sg_id: SubgraphId) -> String {
+        let name = self.subgraph_name(sg_id);
+        let label = self.meta_graph().and_then(|meta_graph| {
+            let (_, nodes) = meta_graph.subgraphs().find(|(graph_sg_id, _)| {
+                name.strip_prefix(SURFACE_SUBGRAPH_PREFIX) == Some(&*format!("{:?}", graph_sg_id))
+            })?;
+            let ops: Vec<_> = nodes
+                .iter()
+                .map(|&node_id| meta_graph.node(node_id).to_name_string())
+                .collect();
+            Some(ops.join(" -> "))
+        });
+        label.unwrap_or_else(|| name.to_owned())
+    }
+
+    /// Returns a human-readable label for the handoff, using the operator names from
+    /// [`Self::meta_graph`] if available, otherwise the handoff's name. Labels are not necessarily
+    /// unique. Used for [`MetricsSnapshot::handoff_labels`].
+    pub fn handoff_label(&self, handoff_id: HandoffId) -> String {
+        let name = self.handoff_name(handoff_id);
+        let label = self.meta_graph().and_then(|meta_graph| {
+            let node_id = meta_graph.node_ids().find(|node_id| {
+                name.strip_prefix(SURFACE_HANDOFF_PREFIX) == Some(&*format!("{:?}", node_id))
+            })?;
+            let src = meta_graph
+                .node_predecessor_nodes(node_id)
+                .map(|pred_id| meta_graph.node(pred_id).to_name_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let dst = meta_graph
+                .node_successor_nodes(node_id)
+                .map(|succ_id| meta_graph.node(succ_id).to_name_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            Some(format!("{} -> {}", src, dst))
+        });
+        label.unwrap_or_else(|| name.to_owned())
+    }
+
+    /// Returns a snapshot of the runtime metrics of all subgraphs and handoffs.
+    pub fn metrics(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            current_tick: self.current_tick(),
+            subgraphs: self
+                .subgraph_ids()
+                .map(|sg_id| (sg_id, *self.subgraph_metrics(sg_id)))
+                .collect(),
+            handoffs: self
+                .handoff_ids()
+                .map(|handoff_id| (handoff_id, *self.handoff_metrics(handoff_id)))
+                .collect(),
+            subgraph_labels: self
+                .subgraph_ids()
+                .map(|sg_id| (sg_id, self.subgraph_label(sg_id)))
+                .collect(),
+            handoff_labels: self
+                .handoff_ids()
+                .map(|handoff_id| (handoff_id, self.handoff_label(handoff_id)))
+                .collect(),
+        }
+    }
+}
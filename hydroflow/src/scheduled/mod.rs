@@ -7,21 +7,27 @@ use std::fmt::{Display, Formatter, Result};
 
 use serde::Serialize;
 
+pub mod checkpoint;
+pub mod chrome_trace;
 pub mod context;
 pub mod graph;
 pub mod graph_ext;
 pub mod handoff;
 pub mod input;
+pub mod introspection;
+pub mod metrics;
 pub mod net;
+pub mod parallel;
 pub mod port;
 pub mod query;
 pub mod reactor;
 pub mod state;
 pub(crate) mod subgraph;
+pub mod ticks;
 
 /// A subgraph's ID. Invalid if used in a different [`graph::Hydroflow`]
 /// instance than the original that created it.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 #[repr(transparent)]
 pub struct SubgraphId(pub(crate) usize);
 impl Display for SubgraphId {
@@ -32,7 +38,7 @@ impl Display for SubgraphId {
 
 /// A handoff's ID. Invalid if used in a different [`graph::Hydroflow`]
 /// instance than the original that created it.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 #[repr(transparent)]
 pub struct HandoffId(pub(crate) usize);
 impl Display for HandoffId {
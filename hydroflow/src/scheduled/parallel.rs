@@ -0,0 +1,167 @@
+//! Module for running subgraphs on worker threads, see
+//! [`Hydroflow::add_subgraph_parallel`](super::graph::Hydroflow::add_subgraph_parallel) and
+//! [`Hydroflow::set_worker_threads`](super::graph::Hydroflow::set_worker_threads).
+//!
+//! Subgraphs added this way may run concurrently with other parallel subgraphs of the same
+//! stratum. Stratum and tick semantics are unchanged: a stratum does not complete until all of its
+//! subgraphs (parallel or not) have run to quiescence. Parallel subgraphs must only be connected to
+//! handoffs which are [`Sync`], such as [`SendVecHandoff`](super::handoff::SendVecHandoff).
+//!
+//! Graphs built with [`hydroflow_syntax_parallel!`](crate::hydroflow_syntax_parallel) use
+//! `add_subgraph_parallel` for their stateless subgraphs, and split `tee()` branches into separate
+//! subgraphs. Graphs built with [`hydroflow_syntax!`](crate::hydroflow_syntax) always run on the
+//! current thread.
+
+use instant::Instant;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::graph::HandoffData;
+use super::handoff::handoff_list::ParallelPortList;
+use super::port::{RECV, SEND};
+use super::SubgraphId;
+
+/// A [`Send`]-able subset of [`Context`](super::context::Context), provided to subgraphs added
+/// with [`Hydroflow::add_subgraph_parallel`](super::graph::Hydroflow::add_subgraph_parallel).
+///
+/// Does not provide access to the state API or task spawning, as those are owned by the main
+/// thread.
+#[derive(Clone, Debug)]
+pub struct ParallelContext {
+    pub(crate) event_queue_send: UnboundedSender<(SubgraphId, bool)>,
+
+    pub(crate) current_tick: usize,
+    pub(crate) current_stratum: usize,
+
+    pub(crate) current_tick_start: Instant,
+    pub(crate) subgraph_last_tick_run_in: Option<usize>,
+
+    pub(crate) subgraph_id: SubgraphId,
+}
+impl ParallelContext {
+    /// Gets the current tick (local time) count.
+    pub fn current_tick(&self) -> usize {
+        self.current_tick
+    }
+
+    /// Gets the timestamp of the beginning of the current tick.
+    pub fn current_tick_start(&self) -> Instant {
+        self.current_tick_start
+    }
+
+    /// Gets whether this is the first time this subgraph is being scheduled for this tick
+    pub fn is_first_run_this_tick(&self) -> bool {
+        self.subgraph_last_tick_run_in
+            .map_or(true, |tick_last_run_in| {
+                self.current_tick > tick_last_run_in
+            })
+    }
+
+    /// Gets the current stratum nubmer.
+    pub fn current_stratum(&self) -> usize {
+        self.current_stratum
+    }
+
+    /// Gets the ID of the current subgraph.
+    pub fn current_subgraph(&self) -> SubgraphId {
+        self.subgraph_id
+    }
+
+    /// Schedules a subgraph.
+    pub fn schedule_subgraph(&self, sg_id: SubgraphId, is_external: bool) {
+        self.event_queue_send.send((sg_id, is_external)).unwrap()
+    }
+}
+
+/// A single run of a parallel subgraph, which may be sent to a worker thread.
+pub(crate) type Job<'s> = Box<dyn 's + Send + FnOnce()>;
+
+/// Represents a compiled subgraph which can be run on a worker thread. Used internally by
+/// [`Hydroflow`](super::graph::Hydroflow) to erase the input/output handoff types.
+pub(crate) trait ParallelSubgraph {
+    /// Prepares a [`Job`] to run this subgraph once. Called on the main thread, as preparing
+    /// requires accessing the (non-[`Sync`]) handoff list.
+    fn prepare<'s>(&'s mut self, context: ParallelContext, handoffs: &'s [HandoffData]) -> Job<'s>;
+}
+
+/// [`ParallelSubgraph`] implementation for a subgraph closure with variadic port lists.
+pub(crate) struct PortListSubgraph<R, W, F> {
+    pub recv_ports: R,
+    pub send_ports: W,
+    pub subgraph: F,
+}
+impl<R, W, F> ParallelSubgraph for PortListSubgraph<R, W, F>
+where
+    R: ParallelPortList<RECV>,
+    W: ParallelPortList<SEND>,
+    F: Send + for<'ctx> FnMut(&ParallelContext, R::ParallelCtx<'ctx>, W::ParallelCtx<'ctx>),
+{
+    fn prepare<'s>(&'s mut self, context: ParallelContext, handoffs: &'s [HandoffData]) -> Job<'s> {
+        let recv = self.recv_ports.make_parallel_ctx(handoffs);
+        let send = self.send_ports.make_parallel_ctx(handoffs);
+        let subgraph = &mut self.subgraph;
+        Box::new(move || (subgraph)(&context, recv, send))
+    }
+}
+
+/// A persistent pool of worker threads for running parallel subgraphs, see
+/// [`Hydroflow::set_worker_threads`](super::graph::Hydroflow::set_worker_threads).
+///
+/// The threads are started once when the pool is created and reused for every batch, until the
+/// pool is dropped.
+pub(crate) struct WorkerPool {
+    /// Number of threads, including the current thread.
+    worker_threads: usize,
+    /// The other `worker_threads - 1` threads.
+    #[cfg(not(target_arch = "wasm32"))]
+    pool: rayon::ThreadPool,
+}
+impl WorkerPool {
+    /// Starts a pool of `worker_threads` threads, including the current thread.
+    pub fn new(worker_threads: usize) -> Self {
+        Self {
+            worker_threads,
+            #[cfg(not(target_arch = "wasm32"))]
+            pool: rayon::ThreadPoolBuilder::new()
+                .num_threads(worker_threads - 1)
+                .thread_name(|i| format!("hydroflow-worker-{}", i))
+                .build()
+                .expect("Failed to start worker threads."),
+        }
+    }
+
+    /// Number of threads, including the current thread.
+    pub fn worker_threads(&self) -> usize {
+        self.worker_threads
+    }
+
+    /// Runs all the `jobs` to completion, on the worker threads and the current thread. Jobs are
+    /// distributed round-robin.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run_jobs(&self, jobs: Vec<Job<'_>>) {
+        let worker_threads = self.worker_threads.min(jobs.len());
+        if worker_threads <= 1 {
+            jobs.into_iter().for_each(|job| (job)());
+            return;
+        }
+
+        let mut batches: Vec<Vec<Job<'_>>> = (0..worker_threads).map(|_| Vec::new()).collect();
+        for (i, job) in jobs.into_iter().enumerate() {
+            batches[i % worker_threads].push(job);
+        }
+        let mut batches = batches.into_iter();
+        let local_batch = batches.next().unwrap();
+        self.pool.in_place_scope(|scope| {
+            for batch in batches {
+                scope.spawn(move |_| batch.into_iter().for_each(|job| (job)()));
+            }
+            local_batch.into_iter().for_each(|job| (job)());
+        });
+    }
+
+    /// Runs all the `jobs` to completion on the current thread, as there are no threads on
+    /// `wasm32`.
+    #[cfg(target_arch = "wasm32")]
+    pub fn run_jobs(&self, jobs: Vec<Job<'_>>) {
+        jobs.into_iter().for_each(|job| (job)());
+    }
+}
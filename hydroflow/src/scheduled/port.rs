@@ -1,5 +1,4 @@
 //! Organizational module for Hydroflow Send/RecvCtx structs and Input/OutputPort structs.
-use std::cell::RefMut;
 use std::marker::PhantomData;
 
 use ref_cast::RefCast;
@@ -50,7 +49,7 @@ pub type RecvPort<H> = Port<RECV, H>;
 #[repr(transparent)]
 pub struct PortCtx<S: Polarity, H> {
     pub(crate) handoff: H,
-    pub(crate) _marker: PhantomData<*const S>,
+    pub(crate) _marker: PhantomData<fn() -> S>,
 }
 /// Send-specific [`PortCtx`]. Output to send into a handoff.
 pub type SendCtx<H> = PortCtx<SEND, H>;
@@ -84,7 +83,7 @@ impl<H: Handoff> RecvCtx<H> {
     }
 
     /// See [`Handoff::borrow_mut_swap`].
-    pub fn borrow_mut_swap(&self) -> RefMut<H::Inner> {
+    pub fn borrow_mut_swap(&self) -> H::InnerMut<'_> {
         self.handoff.borrow_mut_swap()
     }
 }
@@ -0,0 +1,136 @@
+//! Module for [`TickPolicy`] and [`Clock`], which control when ticks start when running a
+//! [`Hydroflow`](super::graph::Hydroflow) instance asynchronously.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use instant::Instant;
+
+/// Policy for when new ticks start, set with
+/// [`Hydroflow::set_tick_policy`](super::graph::Hydroflow::set_tick_policy).
+///
+/// Only applies to [`Hydroflow::run_async`](super::graph::Hydroflow::run_async) and
+/// [`Hydroflow::run_until`](super::graph::Hydroflow::run_until). The synchronous methods such as
+/// [`Hydroflow::run_available`](super::graph::Hydroflow::run_available) always run ticks
+/// immediately.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TickPolicy {
+    /// Start a new tick as soon as external events arrive. This is the default.
+    #[default]
+    EventDriven,
+    /// Start a new tick every period, even if there are no external events. Input which arrives
+    /// during a period is batched into the following tick.
+    FixedInterval(Duration),
+    /// Start a new tick once `max_batch` external events have arrived, or once `timeout` has passed
+    /// since the first external event, whichever is sooner.
+    ///
+    /// An external event is a wake-up of a subgraph, e.g. a `source_stream` which was waiting for
+    /// input, so one event may carry many items.
+    MaxBatchOrTimeout {
+        /// Maximum number of external events to batch into one tick. Must be at least one.
+        max_batch: usize,
+        /// Maximum time to wait after the first external event.
+        timeout: Duration,
+    },
+}
+
+/// A source of time for [`TickPolicy`], set with
+/// [`Hydroflow::set_clock`](super::graph::Hydroflow::set_clock). Defaults to [`SystemClock`].
+///
+/// Times are [`Duration`]s since an arbitrary, fixed epoch chosen by the clock.
+pub trait Clock {
+    /// Returns the current time.
+    fn now(&self) -> Duration;
+
+    /// Returns a future which completes once the time is at least `deadline`.
+    fn sleep_until(&self, deadline: Duration) -> Pin<Box<dyn Future<Output = ()>>>;
+}
+
+/// A [`Clock`] using the system time, with Tokio timers. The epoch is when the clock is created.
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep_until(&self, deadline: Duration) -> Pin<Box<dyn Future<Output = ()>>> {
+        Box::pin(tokio::time::sleep(deadline.saturating_sub(self.now())))
+    }
+}
+
+/// A [`Clock`] which only advances when told to, for tests. Clones share the same time.
+#[derive(Clone, Debug, Default)]
+pub struct MockClock {
+    inner: Rc<RefCell<MockClockInner>>,
+}
+#[derive(Debug, Default)]
+struct MockClockInner {
+    now: Duration,
+    /// Wakers of pending [`MockSleep`]s, with their deadlines.
+    sleepers: Vec<(Duration, Waker)>,
+}
+impl MockClock {
+    /// Creates a new mock clock at time zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advances the time by `duration`, waking any sleeps which have reached their deadline.
+    pub fn advance(&self, duration: Duration) {
+        let mut inner = self.inner.borrow_mut();
+        inner.now += duration;
+        let now = inner.now;
+        let (ready, pending) = std::mem::take(&mut inner.sleepers)
+            .into_iter()
+            .partition::<Vec<_>, _>(|&(deadline, _)| deadline <= now);
+        inner.sleepers = pending;
+        drop(inner);
+        for (_deadline, waker) in ready {
+            waker.wake();
+        }
+    }
+}
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        self.inner.borrow().now
+    }
+
+    fn sleep_until(&self, deadline: Duration) -> Pin<Box<dyn Future<Output = ()>>> {
+        Box::pin(MockSleep {
+            clock: self.clone(),
+            deadline,
+        })
+    }
+}
+
+/// Future returned by [`MockClock::sleep_until`].
+struct MockSleep {
+    clock: MockClock,
+    deadline: Duration,
+}
+impl Future for MockSleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.clock.inner.borrow_mut();
+        if self.deadline <= inner.now {
+            return Poll::Ready(());
+        }
+        inner.sleepers.push((self.deadline, cx.waker().clone()));
+        Poll::Pending
+    }
+}
@@ -0,0 +1,148 @@
+//! State for the `batch` operator.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::scheduled::context::Context;
+
+/// Buffers items into batches of at most `max_items`, releasing a partial batch once its oldest
+/// item has waited `max_delay`.
+#[derive(Debug)]
+pub struct BatchState<T> {
+    max_items: usize,
+    max_delay: Duration,
+    buffer: Vec<T>,
+    /// Shared with the operator's timer task, if one is running.
+    timer: Rc<BatchTimer>,
+}
+
+/// Deadline of a [`BatchState`], shared with its timer task.
+#[derive(Debug, Default)]
+pub struct BatchTimer {
+    /// When the buffered items must be released, if any are buffered.
+    deadline: Cell<Option<Instant>>,
+    /// If a timer task is outstanding. At most one is outstanding per operator, which sleeps until
+    /// the latest `deadline`.
+    running: Cell<bool>,
+}
+
+impl<T> BatchState<T> {
+    /// Creates a new state. Panics if `max_items` is zero.
+    pub fn new(max_items: usize, max_delay: Duration) -> Self {
+        assert!(0 < max_items, "`max_items` must be positive.");
+        Self {
+            max_items,
+            max_delay,
+            buffer: Vec::new(),
+            timer: Default::default(),
+        }
+    }
+
+    /// Buffers `items`, returning every full batch, followed by the remaining items if their
+    /// deadline has passed at time `now`.
+    pub fn take_batches(
+        &mut self,
+        items: impl IntoIterator<Item = T>,
+        now: Instant,
+    ) -> Vec<Vec<T>> {
+        let mut batches = Vec::new();
+        for item in items {
+            if self.buffer.is_empty() {
+                self.timer.deadline.set(Some(now + self.max_delay));
+            }
+            self.buffer.push(item);
+            if self.max_items <= self.buffer.len() {
+                batches.push(std::mem::take(&mut self.buffer));
+                self.timer.deadline.set(None);
+            }
+        }
+        if self
+            .timer
+            .deadline
+            .get()
+            .is_some_and(|deadline| deadline <= now)
+        {
+            batches.push(std::mem::take(&mut self.buffer));
+            self.timer.deadline.set(None);
+        }
+        batches
+    }
+
+    /// Returns the timer to pass to [`wake_at_deadline`] if items are buffered and no timer task
+    /// is outstanding.
+    pub fn take_timer_request(&mut self) -> Option<Rc<BatchTimer>> {
+        if self.timer.deadline.get().is_none() || self.timer.running.get() {
+            return None;
+        }
+        self.timer.running.set(true);
+        Some(self.timer.clone())
+    }
+
+    /// Returns the number of buffered items.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns `true` if no items are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+}
+
+/// Requests a task on `context` which wakes the current subgraph at the `timer`'s deadline. The
+/// task follows the deadline if it moves, and ends once it wakes the subgraph or the deadline is
+/// cleared.
+pub fn wake_at_deadline(context: &mut Context, timer: Rc<BatchTimer>) {
+    let waker = context.waker();
+    context.request_task(async move {
+        while let Some(deadline) = timer.deadline.get() {
+            if deadline <= Instant::now() {
+                waker.wake();
+                break;
+            }
+            tokio::time::sleep_until(deadline).await;
+        }
+        timer.running.set(false);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_state() {
+        let start = Instant::now();
+        let delay = Duration::from_millis(100);
+        let mut state = BatchState::new(3, delay);
+
+        assert_eq!(vec![vec![0, 1, 2]], state.take_batches(0..5, start));
+        let timer = state.take_timer_request().unwrap();
+        assert_eq!(Some(start + delay), timer.deadline.get());
+        assert!(state.take_timer_request().is_none());
+        assert_eq!(2, state.len());
+
+        // Deadline is kept from the oldest buffered item.
+        let later = start + delay / 2;
+        assert_eq!(Vec::<Vec<i32>>::new(), state.take_batches([], later));
+        assert!(state.take_timer_request().is_none());
+
+        assert_eq!(vec![vec![3, 4]], state.take_batches([], start + delay));
+        assert!(state.is_empty());
+
+        assert_eq!(
+            Vec::<Vec<i32>>::new(),
+            state.take_batches([5], start + delay)
+        );
+        // The outstanding timer task follows the new deadline instead of a second one starting.
+        assert!(state.take_timer_request().is_none());
+        assert_eq!(Some(start + 2 * delay), timer.deadline.get());
+
+        // Once the task ends, a new one may be requested.
+        timer.running.set(false);
+        assert!(state.take_timer_request().is_some());
+    }
+}
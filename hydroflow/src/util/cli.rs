@@ -19,24 +19,45 @@ pub async fn launch<T: DeserializeOwned + Default>(
     launch_flow(flow).await;
 }
 
+/// Runs `flow` until a `stop` line is received on stdin (or stdin is closed), or the process
+/// receives `SIGTERM`, then shuts it down gracefully with [`Hydroflow::run_until`].
 pub async fn launch_flow(mut flow: Hydroflow<'_>) {
-    let stop = tokio::sync::oneshot::channel();
-    tokio::task::spawn_blocking(|| {
+    // Register the handler before the flow starts so an early `SIGTERM` is not missed.
+    #[cfg(unix)]
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .expect("Failed to register SIGTERM handler.");
+
+    let (stop_send, stop_recv) = tokio::sync::oneshot::channel();
+    // Stdin is read on a detached thread rather than with `spawn_blocking` (or `tokio::io::stdin`,
+    // which uses `spawn_blocking` internally) so a pending read does not block runtime shutdown.
+    std::thread::spawn(move || {
         let mut line = String::new();
-        std::io::stdin().read_line(&mut line).unwrap();
-        assert!(line.starts_with("stop"));
-        stop.0.send(()).unwrap();
+        loop {
+            line.clear();
+            match std::io::stdin().read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) if line.starts_with("stop") => break,
+                Ok(_) => {}
+            }
+        }
+        // Receiver may already be gone if we were stopped by `SIGTERM`.
+        let _ = stop_send.send(());
     });
 
-    let local_set = tokio::task::LocalSet::new();
-    let flow = local_set.run_until(async move {
-        flow.run_async().await;
-    });
+    let shutdown = async move {
+        #[cfg(unix)]
+        let terminate = terminate.recv();
+        #[cfg(not(unix))]
+        let terminate = std::future::pending::<()>();
 
-    tokio::select! {
-        _ = stop.1 => {},
-        _ = flow => {}
-    }
+        tokio::select! {
+            _ = stop_recv => {},
+            _ = terminate => {}
+        }
+    };
+
+    let local_set = tokio::task::LocalSet::new();
+    local_set.run_until(flow.run_until(shutdown)).await;
 }
 
 pub struct HydroCLI<T = Option<()>> {
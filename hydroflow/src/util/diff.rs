@@ -0,0 +1,370 @@
+//! State for the differential operators: `join_diff`, `fold_keyed_diff`, `unique_diff`, and
+//! `difference_diff`.
+//!
+//! In differential mode a stream carries `(T, isize)` diffs, where a positive multiplicity inserts
+//! copies of `T` and a negative multiplicity retracts them. Each operator keeps the accumulated
+//! multiplicities of its inputs across ticks, and each tick emits only the diffs to its output
+//! caused by that tick's input diffs. Output diffs are consolidated: each output item is emitted
+//! at most once per tick, and never with a multiplicity of zero.
+
+use std::collections::hash_map::Entry;
+use std::hash::Hash;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+
+/// Sums the multiplicities of equal items, dropping any which sum to zero.
+pub fn consolidate<T>(diffs: impl IntoIterator<Item = (T, isize)>) -> FxHashMap<T, isize>
+where
+    T: Eq + Hash,
+{
+    let mut consolidated = FxHashMap::default();
+    for (item, diff) in diffs {
+        add_count(&mut consolidated, item, diff);
+    }
+    consolidated
+}
+
+/// Adds `diff` to the count of `item`, removing it if the count becomes zero. Returns the old
+/// count.
+fn add_count<T>(counts: &mut FxHashMap<T, isize>, item: T, diff: isize) -> isize
+where
+    T: Eq + Hash,
+{
+    match counts.entry(item) {
+        Entry::Occupied(mut occupied) => {
+            let old = *occupied.get();
+            *occupied.get_mut() += diff;
+            if 0 == *occupied.get() {
+                occupied.remove();
+            }
+            old
+        }
+        Entry::Vacant(vacant) => {
+            if 0 != diff {
+                vacant.insert(diff);
+            }
+            0
+        }
+    }
+}
+
+/// State for `unique_diff`: emits `+1` when an item's count becomes positive, and `-1` when it
+/// stops being positive.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize",
+    deserialize = "T: Eq + Hash + Deserialize<'de>"
+))]
+pub struct UniqueDiffState<T> {
+    counts: FxHashMap<T, isize>,
+}
+impl<T> Default for UniqueDiffState<T> {
+    fn default() -> Self {
+        Self {
+            counts: FxHashMap::default(),
+        }
+    }
+}
+impl<T> UniqueDiffState<T>
+where
+    T: Clone + Eq + Hash,
+{
+    /// Applies a tick's input diffs, returning the output diffs.
+    pub fn apply(&mut self, diffs: impl IntoIterator<Item = (T, isize)>) -> Vec<(T, isize)> {
+        let mut output = Vec::new();
+        for (item, diff) in consolidate(diffs) {
+            let old = add_count(&mut self.counts, item.clone(), diff);
+            let new = old + diff;
+            match (0 < old, 0 < new) {
+                (false, true) => output.push((item, 1)),
+                (true, false) => output.push((item, -1)),
+                _ => {}
+            }
+        }
+        output
+    }
+}
+
+/// State for `difference_diff`: the output multiplicity of each item is its `pos` count, or zero
+/// if its `neg` count is positive.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize",
+    deserialize = "T: Eq + Hash + Deserialize<'de>"
+))]
+pub struct DifferenceDiffState<T> {
+    pos: FxHashMap<T, isize>,
+    neg: FxHashMap<T, isize>,
+}
+impl<T> Default for DifferenceDiffState<T> {
+    fn default() -> Self {
+        Self {
+            pos: FxHashMap::default(),
+            neg: FxHashMap::default(),
+        }
+    }
+}
+impl<T> DifferenceDiffState<T>
+where
+    T: Clone + Eq + Hash,
+{
+    /// Applies a tick's input diffs, returning the output diffs.
+    pub fn apply(
+        &mut self,
+        pos_diffs: impl IntoIterator<Item = (T, isize)>,
+        neg_diffs: impl IntoIterator<Item = (T, isize)>,
+    ) -> Vec<(T, isize)> {
+        let pos_diffs = consolidate(pos_diffs);
+        let neg_diffs = consolidate(neg_diffs);
+        let touched = pos_diffs
+            .keys()
+            .chain(neg_diffs.keys())
+            .cloned()
+            .collect::<FxHashSet<_>>();
+
+        let output_count = |this: &Self, item: &T| {
+            if 0 < this.neg.get(item).copied().unwrap_or(0) {
+                0
+            } else {
+                this.pos.get(item).copied().unwrap_or(0).max(0)
+            }
+        };
+
+        let mut output = Vec::new();
+        for item in touched {
+            let old = output_count(self, &item);
+            if let Some(&diff) = pos_diffs.get(&item) {
+                add_count(&mut self.pos, item.clone(), diff);
+            }
+            if let Some(&diff) = neg_diffs.get(&item) {
+                add_count(&mut self.neg, item.clone(), diff);
+            }
+            let new = output_count(self, &item);
+            if old != new {
+                output.push((item, new - old));
+            }
+        }
+        output
+    }
+}
+
+/// State for `join_diff`: the output multiplicity of each joined pair is the product of the
+/// multiplicities of its two halves.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "K: Serialize, V1: Serialize, V2: Serialize",
+    deserialize = "K: Eq + Hash + Deserialize<'de>, V1: Eq + Hash + Deserialize<'de>, V2: Eq + Hash + Deserialize<'de>"
+))]
+pub struct JoinDiffState<K, V1, V2> {
+    lhs: FxHashMap<K, FxHashMap<V1, isize>>,
+    rhs: FxHashMap<K, FxHashMap<V2, isize>>,
+}
+impl<K, V1, V2> Default for JoinDiffState<K, V1, V2> {
+    fn default() -> Self {
+        Self {
+            lhs: FxHashMap::default(),
+            rhs: FxHashMap::default(),
+        }
+    }
+}
+impl<K, V1, V2> JoinDiffState<K, V1, V2>
+where
+    K: Clone + Eq + Hash,
+    V1: Clone + Eq + Hash,
+    V2: Clone + Eq + Hash,
+{
+    /// Applies a tick's input diffs, returning the output diffs.
+    ///
+    /// Computes `ΔL ⋈ R + (L + ΔL) ⋈ ΔR`, so only the keys which changed are joined.
+    #[allow(clippy::type_complexity)]
+    pub fn apply(
+        &mut self,
+        lhs_diffs: impl IntoIterator<Item = ((K, V1), isize)>,
+        rhs_diffs: impl IntoIterator<Item = ((K, V2), isize)>,
+    ) -> Vec<((K, (V1, V2)), isize)> {
+        let lhs_diffs = consolidate(lhs_diffs);
+        let rhs_diffs = consolidate(rhs_diffs);
+        let mut output = FxHashMap::default();
+
+        for ((key, v1), diff) in lhs_diffs {
+            if let Some(rhs_vals) = self.rhs.get(&key) {
+                for (v2, &count) in rhs_vals {
+                    add_count(
+                        &mut output,
+                        (key.clone(), (v1.clone(), v2.clone())),
+                        diff * count,
+                    );
+                }
+            }
+            add_count(self.lhs.entry(key.clone()).or_default(), v1, diff);
+            if self.lhs[&key].is_empty() {
+                self.lhs.remove(&key);
+            }
+        }
+        for ((key, v2), diff) in rhs_diffs {
+            if let Some(lhs_vals) = self.lhs.get(&key) {
+                for (v1, &count) in lhs_vals {
+                    add_count(
+                        &mut output,
+                        (key.clone(), (v1.clone(), v2.clone())),
+                        count * diff,
+                    );
+                }
+            }
+            add_count(self.rhs.entry(key.clone()).or_default(), v2, diff);
+            if self.rhs[&key].is_empty() {
+                self.rhs.remove(&key);
+            }
+        }
+        output.into_iter().collect()
+    }
+}
+
+/// State for `fold_keyed_diff`: keeps the multiset of values in each group, and refolds only the
+/// groups which changed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "K: Serialize, V: Serialize, A: Serialize",
+    deserialize = "K: Eq + Hash + Deserialize<'de>, V: Eq + Hash + Deserialize<'de>, A: Deserialize<'de>"
+))]
+pub struct FoldKeyedDiffState<K, V, A> {
+    values: FxHashMap<K, FxHashMap<V, isize>>,
+    /// The aggregate last emitted for each group.
+    aggregates: FxHashMap<K, A>,
+}
+impl<K, V, A> Default for FoldKeyedDiffState<K, V, A> {
+    fn default() -> Self {
+        Self {
+            values: FxHashMap::default(),
+            aggregates: FxHashMap::default(),
+        }
+    }
+}
+impl<K, V, A> FoldKeyedDiffState<K, V, A>
+where
+    K: Clone + Eq + Hash,
+    V: Clone + Eq + Hash,
+    A: Clone + Eq,
+{
+    /// Applies a tick's input diffs, returning the output diffs. When a group's aggregate changes,
+    /// the old aggregate is retracted and the new one inserted. A group with no values with
+    /// positive multiplicity has no aggregate.
+    ///
+    /// Each value is folded in once per copy, in no particular order. Since a fold cannot be
+    /// undone, each touched group is refolded from scratch, so this costs O(total multiplicity of
+    /// the touched groups), not O(number of diffs). Prefer groups which stay small, or a
+    /// non-differential `fold_keyed` when retractions are not needed.
+    pub fn apply(
+        &mut self,
+        diffs: impl IntoIterator<Item = ((K, V), isize)>,
+        mut init: impl FnMut() -> A,
+        mut fold: impl FnMut(&mut A, V),
+    ) -> Vec<((K, A), isize)> {
+        let mut touched = FxHashSet::default();
+        for ((key, value), diff) in consolidate(diffs) {
+            add_count(self.values.entry(key.clone()).or_default(), value, diff);
+            touched.insert(key);
+        }
+
+        let mut output = Vec::new();
+        for key in touched {
+            let new = match self.values.get(&key) {
+                Some(values) if values.values().any(|&count| 0 < count) => {
+                    let mut aggregate = (init)();
+                    for (value, &count) in values {
+                        for _ in 0..count {
+                            (fold)(&mut aggregate, value.clone());
+                        }
+                    }
+                    Some(aggregate)
+                }
+                Some(values) if values.is_empty() => {
+                    self.values.remove(&key);
+                    None
+                }
+                _ => None,
+            };
+            let old = self.aggregates.remove(&key);
+            if old == new {
+                if let Some(new) = new {
+                    self.aggregates.insert(key, new);
+                }
+                continue;
+            }
+            if let Some(old) = old {
+                output.push(((key.clone(), old), -1));
+            }
+            if let Some(new) = new {
+                output.push(((key.clone(), new.clone()), 1));
+                self.aggregates.insert(key, new);
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted<T: Ord>(mut vec: Vec<T>) -> Vec<T> {
+        vec.sort();
+        vec
+    }
+
+    #[test]
+    fn test_join_diff() {
+        let mut state = JoinDiffState::default();
+        assert_eq!(
+            vec![(("a", (1, 10)), 1)],
+            state.apply([(("a", 1), 1), (("b", 2), 1)], [(("a", 10), 1)])
+        );
+        // Both sides changing in the same tick.
+        assert_eq!(
+            sorted(vec![(("a", (1, 10)), -1), (("b", (2, 20)), 1)]),
+            sorted(state.apply([(("a", 1), -1)], [(("b", 20), 1)]))
+        );
+        assert_eq!(
+            vec![(("b", (2, 20)), -1)],
+            state.apply([(("b", 2), 1), (("b", 2), -1)], [(("b", 20), -1)])
+        );
+        assert!(state.lhs.contains_key("b"));
+        assert!(!state.rhs.contains_key("b"));
+    }
+
+    #[test]
+    fn test_unique_and_difference_diff() {
+        let mut unique = UniqueDiffState::default();
+        assert_eq!(vec![(1, 1)], unique.apply([(1, 1), (1, 1)]));
+        assert_eq!(Vec::<(i32, isize)>::new(), unique.apply([(1, -1)]));
+        assert_eq!(vec![(1, -1)], unique.apply([(1, -1)]));
+
+        let mut difference = DifferenceDiffState::default();
+        assert_eq!(vec![(1, 2)], difference.apply([(1, 2)], []));
+        assert_eq!(vec![(1, -2)], difference.apply([], [(1, 1)]));
+        assert_eq!(vec![(1, 2)], difference.apply([], [(1, -1)]));
+    }
+
+    #[test]
+    fn test_fold_keyed_diff() {
+        let mut state = FoldKeyedDiffState::default();
+        let mut apply = |diffs: Vec<((&'static str, u32), isize)>| {
+            sorted(state.apply(diffs, || 0, |sum: &mut u32, v| *sum += v))
+        };
+        assert_eq!(
+            vec![(("a", 3), 1), (("b", 5), 1)],
+            apply(vec![(("a", 1), 1), (("a", 2), 1), (("b", 5), 1)])
+        );
+        assert_eq!(
+            vec![(("a", 2), 1), (("a", 3), -1)],
+            apply(vec![(("a", 1), -1)])
+        );
+        // Aggregate is unchanged.
+        assert_eq!(
+            Vec::<((&str, u32), isize)>::new(),
+            apply(vec![(("b", 5), -1), (("b", 2), 1), (("b", 3), 1)])
+        );
+        assert_eq!(vec![(("a", 2), -1)], apply(vec![(("a", 2), -1)]));
+    }
+}
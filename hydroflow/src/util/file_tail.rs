@@ -0,0 +1,312 @@
+//! Following a growing file, for the `source_file_tail` operator.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use futures::Stream;
+
+/// How often [`tail_file`] checks the file for new data by default.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Follows a file as it grows, yielding complete lines as they are appended.
+///
+/// Each line is yielded with the byte offset just past its line ending, which is where reading
+/// should resume to continue after that line. Lines do NOT include the line ending. A final line
+/// without a line ending is held back until it is completed. Invalid UTF-8 is replaced with
+/// `U+FFFD REPLACEMENT CHARACTER`.
+///
+/// Log rotation is handled:
+/// * Truncation: if the file becomes shorter than the current offset, reading restarts from the
+///   beginning of the file.
+/// * Rename (on unix): if the path refers to a different file than the one being read, the rest of
+///   the old file is read (including any final line without a line ending), then reading continues
+///   from the beginning of the new file.
+///
+/// Offsets are always relative to the current file, so they start again from zero after rotation.
+/// If the path does not exist, for example between a rename and the new file being created, it is
+/// checked again on the next poll.
+#[derive(Debug)]
+pub struct FileTail {
+    path: PathBuf,
+    file: Option<File>,
+    /// Offset just past the last complete line returned.
+    offset: u64,
+    /// Bytes read past `offset` which don't yet form a complete line.
+    partial: Vec<u8>,
+}
+impl FileTail {
+    /// Creates a new `FileTail`, which will start reading `path` from `start_offset`. Use an offset
+    /// previously returned alongside a line to resume after that line. If the file is shorter than
+    /// `start_offset` it is assumed to have been truncated, and is read from the beginning.
+    pub fn new(path: impl AsRef<Path>, start_offset: u64) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            file: None,
+            offset: start_offset,
+            partial: Vec::new(),
+        }
+    }
+
+    /// The path being followed.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The offset just past the last complete line returned, where reading would resume.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Reads all complete lines which have been appended since the last call, along with the
+    /// offset just past each.
+    pub fn read_lines(&mut self) -> io::Result<Vec<(u64, String)>> {
+        let mut lines = Vec::new();
+        self.read_lines_into(&mut lines)?;
+        Ok(lines)
+    }
+
+    /// Like [`Self::read_lines`], but appends to `lines`. If an error occurs, `lines` contains the
+    /// lines read before it, and the next call continues after them.
+    pub fn read_lines_into(&mut self, lines: &mut Vec<(u64, String)>) -> io::Result<()> {
+        if self.file.is_none() && !self.open()? {
+            return Ok(());
+        }
+        let file = self.file.as_mut().unwrap();
+
+        // Truncated.
+        let len = file.metadata()?.len();
+        if len < self.offset + self.partial.len() as u64 {
+            self.offset = 0;
+            self.partial.clear();
+        }
+        let read_pos = self.offset + self.partial.len() as u64;
+        file.seek(SeekFrom::Start(read_pos))?;
+        file.read_to_end(&mut self.partial)?;
+        self.take_lines(lines);
+
+        if self.is_rotated()? {
+            // Read anything appended to the old file between the read above and the rename.
+            let file = self.file.as_mut().unwrap();
+            file.read_to_end(&mut self.partial)?;
+            self.take_lines(lines);
+            // The old file is finished, so any final line without a line ending is complete.
+            if !self.partial.is_empty() {
+                self.partial.push(b'\n');
+                self.take_lines(lines);
+            }
+            self.file = None;
+            self.offset = 0;
+            if self.open()? {
+                self.read_lines_into(lines)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens the file at `path`, returning `false` if it does not exist.
+    fn open(&mut self) -> io::Result<bool> {
+        match File::open(&self.path) {
+            Ok(file) => {
+                self.file = Some(file);
+                Ok(true)
+            }
+            Err(err) if io::ErrorKind::NotFound == err.kind() => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Moves complete lines out of `partial` into `lines`, advancing `offset`.
+    fn take_lines(&mut self, lines: &mut Vec<(u64, String)>) {
+        let Some(end) = self.partial.iter().rposition(|&b| b'\n' == b) else {
+            return;
+        };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        for line in complete.split_inclusive(|&b| b'\n' == b) {
+            self.offset += line.len() as u64;
+            let line = line.strip_suffix(b"\n").unwrap_or(line);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            lines.push((self.offset, String::from_utf8_lossy(line).into_owned()));
+        }
+    }
+
+    /// Returns if `path` now refers to a different file than the one open.
+    #[cfg(unix)]
+    fn is_rotated(&self) -> io::Result<bool> {
+        use std::os::unix::fs::MetadataExt;
+
+        let Some(file) = &self.file else {
+            return Ok(false);
+        };
+        let open = file.metadata()?;
+        match std::fs::metadata(&self.path) {
+            Ok(current) => Ok((open.dev(), open.ino()) != (current.dev(), current.ino())),
+            Err(err) if io::ErrorKind::NotFound == err.kind() => Ok(true),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns if `path` now refers to a different file than the one open. Only truncation is
+    /// detected on this platform.
+    #[cfg(not(unix))]
+    fn is_rotated(&self) -> io::Result<bool> {
+        Ok(false)
+    }
+}
+
+/// Returns a stream of lines appended to the file at `path`, starting from `start_offset`, along
+/// with the offset just past each line. Checks for new data every `poll_interval`. See
+/// [`FileTail`].
+///
+/// The stream never ends. Reads run on Tokio's blocking thread pool. If the file could not be
+/// read, a warning is logged and reading is retried on the next poll.
+pub fn tail_file(
+    path: impl AsRef<Path>,
+    start_offset: u64,
+    poll_interval: Duration,
+) -> impl Stream<Item = (u64, String)> + Unpin {
+    let tail = FileTail::new(path, start_offset);
+    let mut interval = tokio::time::interval(poll_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let stream = futures::stream::unfold((tail, interval), |(tail, mut interval)| async {
+        interval.tick().await;
+        let (tail, lines) = tokio::task::spawn_blocking(move || {
+            let mut tail = tail;
+            let mut lines = Vec::new();
+            if let Err(err) = tail.read_lines_into(&mut lines) {
+                tracing::warn!(
+                    path = %tail.path().display(),
+                    error = %err,
+                    "Failed to read file, retrying."
+                );
+            }
+            (tail, lines)
+        })
+        .await
+        .expect("Failed to join file read task");
+        Some((futures::stream::iter(lines), (tail, interval)))
+    });
+    futures::StreamExt::flatten(Box::pin(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn append(path: &Path, data: &str) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(data.as_bytes()).unwrap();
+    }
+
+    fn lines(tail: &mut FileTail) -> Vec<String> {
+        tail.read_lines()
+            .unwrap()
+            .into_iter()
+            .map(|(_, line)| line)
+            .collect()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "hydroflow-file-tail-{}-{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_tail_truncation() {
+        let dir = temp_dir("truncation");
+        let path = dir.join("app.log");
+
+        let mut tail = FileTail::new(&path, 0);
+        assert!(lines(&mut tail).is_empty(), "File doesn't exist yet.");
+
+        append(&path, "a\nb");
+        assert_eq!(vec!["a"], lines(&mut tail));
+        assert_eq!(2, tail.offset());
+        append(&path, "c\r\nd\n");
+        assert_eq!(vec!["bc", "d"], lines(&mut tail));
+        assert!(lines(&mut tail).is_empty());
+
+        // Resume from a stored offset.
+        let mut resumed = FileTail::new(&path, 2);
+        assert_eq!(vec!["bc", "d"], lines(&mut resumed));
+
+        std::fs::write(&path, "e\n").unwrap();
+        assert_eq!(vec!["e"], lines(&mut tail));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tail_invalid_utf8() {
+        let dir = temp_dir("invalid-utf8");
+        let path = dir.join("app.log");
+
+        std::fs::write(&path, b"a\xFFb\nc\n").unwrap();
+        let mut tail = FileTail::new(&path, 0);
+        assert_eq!(vec!["a\u{FFFD}b", "c"], lines(&mut tail));
+        assert_eq!(6, tail.offset());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tail_rename() {
+        let dir = temp_dir("rename");
+        let path = dir.join("app.log");
+
+        append(&path, "a\n");
+        let mut tail = FileTail::new(&path, 0);
+        assert_eq!(vec!["a"], lines(&mut tail));
+
+        // The final unterminated line of the old file is emitted.
+        append(&path, "b");
+        std::fs::rename(&path, dir.join("app.log.1")).unwrap();
+        assert_eq!(vec!["b"], lines(&mut tail));
+        assert!(lines(&mut tail).is_empty(), "New file doesn't exist yet.");
+
+        append(&path, "c\n");
+        assert_eq!(vec!["c"], lines(&mut tail));
+        assert_eq!(2, tail.offset());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tail_append_then_rename() {
+        let dir = temp_dir("append-then-rename");
+        let path = dir.join("app.log");
+
+        append(&path, "a\n");
+        let mut tail = FileTail::new(&path, 0);
+        assert_eq!(vec!["a"], lines(&mut tail));
+
+        // A writer appends, the file is rotated, and the writer appends to the old file through its
+        // open handle, all within one poll.
+        let mut writer = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        writer.write_all(b"b\n").unwrap();
+        std::fs::rename(&path, dir.join("app.log.1")).unwrap();
+        writer.write_all(b"c\nd").unwrap();
+        append(&path, "e\n");
+        assert_eq!(vec!["b", "c", "d", "e"], lines(&mut tail));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
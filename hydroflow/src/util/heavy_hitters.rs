@@ -0,0 +1,83 @@
+//! State for the `heavy_hitters` operator.
+
+use std::hash::Hash;
+
+use lattices::sketch::CountMinSketch;
+use rustc_hash::FxHashMap;
+
+/// Tracks the `k` most frequent items in bounded space, using a [`CountMinSketch`] to estimate
+/// counts and keeping only the `k` items with the greatest estimates as candidates.
+#[derive(Clone, Debug)]
+pub struct HeavyHitters<T> {
+    k: usize,
+    sketch: CountMinSketch,
+    /// Candidate items with their estimated counts when last inserted.
+    candidates: FxHashMap<T, u64>,
+}
+impl<T> HeavyHitters<T>
+where
+    T: Clone + Eq + Hash,
+{
+    /// Creates a new `HeavyHitters` which tracks `k` items, using a Count-Min sketch with `depth`
+    /// rows of `width` counters.
+    pub fn new(k: usize, width: usize, depth: usize) -> Self {
+        Self {
+            k,
+            sketch: CountMinSketch::new(width, depth),
+            candidates: FxHashMap::default(),
+        }
+    }
+
+    /// Inserts one occurrence of an item.
+    pub fn insert(&mut self, item: T) {
+        self.sketch.insert(&item);
+        let estimate = self.sketch.estimate(&item);
+        if let Some(count) = self.candidates.get_mut(&item) {
+            *count = estimate;
+        } else if self.candidates.len() < self.k {
+            self.candidates.insert(item, estimate);
+        } else if let Some((min_item, &min_count)) =
+            self.candidates.iter().min_by_key(|&(_, &count)| count)
+        {
+            if min_count < estimate {
+                let min_item = min_item.clone();
+                self.candidates.remove(&min_item);
+                self.candidates.insert(item, estimate);
+            }
+        }
+    }
+
+    /// The underlying sketch.
+    pub fn sketch(&self) -> &CountMinSketch {
+        &self.sketch
+    }
+
+    /// Returns up to `k` items with their estimated counts, from greatest to least count.
+    pub fn top(&self) -> Vec<(T, u64)> {
+        let mut top = self
+            .candidates
+            .keys()
+            .map(|item| (item.clone(), self.sketch.estimate(item)))
+            .collect::<Vec<_>>();
+        top.sort_by(|(_, a), (_, b)| b.cmp(a));
+        top
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heavy_hitters() {
+        let mut heavy_hitters = HeavyHitters::new(2, 1024, 4);
+        for i in 0..100 {
+            heavy_hitters.insert(i);
+            heavy_hitters.insert(1000);
+            if 0 == i % 2 {
+                heavy_hitters.insert(2000);
+            }
+        }
+        assert_eq!(vec![(1000, 100), (2000, 50)], heavy_hitters.top());
+    }
+}
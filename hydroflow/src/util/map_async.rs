@@ -0,0 +1,169 @@
+//! State for the `map_async` and `map_async_unordered` operators.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::rc::Rc;
+
+use crate::scheduled::context::Context;
+
+/// Queues input items, runs futures for them, and collects their outputs. At most `max_in_flight`
+/// items are started but not yet emitted at once. In ordered mode this includes completed outputs
+/// waiting on an earlier item, so one slow item stops further items from starting, rather than
+/// letting completed outputs pile up behind it.
+///
+/// Futures are run as tasks requested with [`Context::request_task`]. When a task completes, it
+/// wakes the subgraph which started it with [`Context::waker`], so outputs are emitted in a later
+/// tick. Clones share the same state.
+pub struct MapAsyncState<In, Out> {
+    inputs: Rc<RefCell<MapAsyncInputs<In>>>,
+    outputs: Rc<RefCell<MapAsyncOutputs<Out>>>,
+}
+impl<In, Out> Clone for MapAsyncState<In, Out> {
+    fn clone(&self) -> Self {
+        Self {
+            inputs: Rc::clone(&self.inputs),
+            outputs: Rc::clone(&self.outputs),
+        }
+    }
+}
+
+struct MapAsyncInputs<In> {
+    max_in_flight: usize,
+    /// Input items which have not yet been started.
+    pending: VecDeque<In>,
+    /// Sequence number of the next item to be started.
+    next_start: u64,
+}
+
+/// Shared with the running tasks.
+struct MapAsyncOutputs<Out> {
+    in_flight: usize,
+    completed: Completed<Out>,
+}
+
+/// Completed outputs which have not yet been emitted.
+enum Completed<Out> {
+    Ordered {
+        /// Sequence number of the next output to be emitted.
+        next_emit: u64,
+        /// Outputs by sequence number.
+        by_seq: BTreeMap<u64, Out>,
+    },
+    /// Outputs in the order they completed.
+    Unordered(Vec<Out>),
+}
+impl<Out> Completed<Out> {
+    fn insert(&mut self, seq: u64, out: Out) {
+        match self {
+            Self::Ordered { by_seq, .. } => {
+                by_seq.insert(seq, out);
+            }
+            Self::Unordered(outs) => outs.push(out),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Ordered { by_seq, .. } => by_seq.len(),
+            Self::Unordered(outs) => outs.len(),
+        }
+    }
+}
+
+impl<In, Out> MapAsyncState<In, Out>
+where
+    Out: 'static,
+{
+    /// Creates a new state. If `ordered`, outputs are emitted in the order of their inputs,
+    /// otherwise in the order they complete.
+    pub fn new(max_in_flight: usize, ordered: bool) -> Self {
+        assert!(0 < max_in_flight, "`max_in_flight` must be positive.");
+        Self {
+            inputs: Rc::new(RefCell::new(MapAsyncInputs {
+                max_in_flight,
+                pending: VecDeque::new(),
+                next_start: 0,
+            })),
+            outputs: Rc::new(RefCell::new(MapAsyncOutputs {
+                in_flight: 0,
+                completed: if ordered {
+                    Completed::Ordered {
+                        next_emit: 0,
+                        by_seq: BTreeMap::new(),
+                    }
+                } else {
+                    Completed::Unordered(Vec::new())
+                },
+            })),
+        }
+    }
+
+    /// Queues an input item to be started.
+    pub fn push(&self, item: In) {
+        self.inputs.borrow_mut().pending.push_back(item);
+    }
+
+    /// Starts futures for queued items until `max_in_flight` are started but not yet emitted,
+    /// requesting each as a task on `context`. Call after [`Self::take_completed`], so emitted
+    /// outputs make room for new items.
+    pub fn start<Fut>(&self, context: &mut Context, mut f: impl FnMut(In) -> Fut)
+    where
+        Fut: 'static + Future<Output = Out>,
+    {
+        let mut inputs = self.inputs.borrow_mut();
+        loop {
+            {
+                let outputs = self.outputs.borrow();
+                if inputs.max_in_flight <= outputs.in_flight + outputs.completed.len() {
+                    break;
+                }
+            }
+            let Some(item) = inputs.pending.pop_front() else {
+                break;
+            };
+            let seq = inputs.next_start;
+            inputs.next_start += 1;
+            self.outputs.borrow_mut().in_flight += 1;
+
+            let future = (f)(item);
+            let outputs = Rc::clone(&self.outputs);
+            let waker = context.waker();
+            context.request_task(async move {
+                let out = future.await;
+                {
+                    let mut outputs = outputs.borrow_mut();
+                    outputs.in_flight -= 1;
+                    outputs.completed.insert(seq, out);
+                }
+                waker.wake();
+            });
+        }
+    }
+
+    /// Takes the outputs which are ready to be emitted.
+    pub fn take_completed(&self) -> Vec<Out> {
+        match &mut self.outputs.borrow_mut().completed {
+            Completed::Ordered { next_emit, by_seq } => {
+                let mut output = Vec::new();
+                while let Some(out) = by_seq.remove(next_emit) {
+                    output.push(out);
+                    *next_emit += 1;
+                }
+                output
+            }
+            Completed::Unordered(outs) => std::mem::take(outs),
+        }
+    }
+
+    /// Returns the number of items which have been queued or started but not yet emitted.
+    pub fn len(&self) -> usize {
+        let outputs = self.outputs.borrow();
+        self.inputs.borrow().pending.len() + outputs.in_flight + outputs.completed.len()
+    }
+
+    /// Returns `true` if there are no items which have been queued or started but not yet emitted.
+    pub fn is_empty(&self) -> bool {
+        0 == self.len()
+    }
+}
@@ -1,13 +1,24 @@
 #![warn(missing_docs)]
 //! Helper utilities for the Hydroflow surface syntax.
 
+pub mod batch;
 pub mod clear;
 #[cfg(feature = "hydroflow_macro")]
 pub mod demux_enum;
+pub mod diff;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod file_tail;
+pub mod heavy_hitters;
+pub mod map_async;
 pub mod monotonic_map;
 pub mod multiset;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod serde_file;
+pub mod simulation;
 pub mod sparse_vec;
+pub mod top_k;
 pub mod unsync;
+pub mod window;
 
 mod monotonic;
 pub use monotonic::*;
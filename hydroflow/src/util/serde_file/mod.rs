@@ -0,0 +1,193 @@
+//! Reading and writing files of serde records, for the `source_csv`, `source_jsonl`,
+//! `source_file_serde`, `dest_csv`, `dest_jsonl`, and `dest_file_serde` operators.
+//!
+//! Three formats are supported:
+//! * CSV: a header row, then one row per record, using the [`csv`] crate. Struct records are matched
+//!   with the header row by field name, other records (such as tuples) use the fields in order.
+//!   Fields must be scalars, such as numbers, strings, or `Option`s of those.
+//! * [JSON Lines](https://jsonlines.org/): one JSON value per line.
+//! * Length-delimited bincode: each record is [`bincode`] encoded and framed with a four-byte
+//!   big-endian length, as in [`LengthDelimitedCodec`].
+//!
+//! The readers are synchronous iterators which panic if the file cannot be read or a record cannot
+//! be deserialized. The writers are [`Encoder`]s, which are used with
+//! [`FramedWrite`] to get an async `Sink` for `dest_sink`.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio_util::codec::{Encoder, FramedWrite, LengthDelimitedCodec};
+
+/// An async `Sink` of records into a file, with the given [`Encoder`].
+pub type FileSink<E> = FramedWrite<tokio::io::BufWriter<tokio::fs::File>, E>;
+
+fn open_for_reading(path: &Path) -> BufReader<File> {
+    let file = File::open(path).expect("Failed to open file for reading");
+    BufReader::new(file)
+}
+
+/// Reads records from a CSV file. Struct records are deserialized by matching their field names
+/// with the header row, other records by field order.
+pub fn read_csv<T>(path: impl AsRef<Path>) -> impl Iterator<Item = T>
+where
+    T: DeserializeOwned,
+{
+    let reader = open_for_reading(path.as_ref());
+    csv::Reader::from_reader(reader)
+        .into_deserialize()
+        .map(|result| result.expect("Failed to deserialize CSV record"))
+}
+
+/// Reads records from a JSON Lines file.
+pub fn read_jsonl<T>(path: impl AsRef<Path>) -> impl Iterator<Item = T>
+where
+    T: DeserializeOwned,
+{
+    let reader = open_for_reading(path.as_ref());
+    serde_json::Deserializer::from_reader(reader)
+        .into_iter()
+        .map(|result| result.expect("Failed to deserialize JSON Lines record"))
+}
+
+/// Reads records from a file of length-delimited bincode frames.
+pub fn read_bincode_frames<T>(path: impl AsRef<Path>) -> impl Iterator<Item = T>
+where
+    T: DeserializeOwned,
+{
+    let mut reader = open_for_reading(path.as_ref());
+    std::iter::from_fn(move || {
+        let frame = read_frame(&mut reader).expect("Failed to read bincode frame")?;
+        Some(bincode::deserialize(&frame).expect("Failed to deserialize bincode record"))
+    })
+}
+
+/// Reads the next frame, or `None` at the end.
+fn read_frame(reader: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let mut frame = vec![0; u32::from_be_bytes(len) as usize];
+    reader.read_exact(&mut frame)?;
+    Ok(Some(frame))
+}
+
+/// Opens a file for writing, returning it and if it was empty.
+fn open_for_writing(path: &Path, append: bool) -> (tokio::fs::File, bool) {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+        .expect("Failed to open file for writing");
+    let is_empty = 0 == file.metadata().map_or(0, |metadata| metadata.len());
+    (tokio::fs::File::from_std(file), is_empty)
+}
+
+/// Creates a sink writing records to a CSV file. If `append` is true and the file is not empty,
+/// records are appended without writing another header row, otherwise the file is truncated.
+pub fn csv_file_sink(path: impl AsRef<Path>, append: bool) -> FileSink<CsvEncoder> {
+    let (file, is_empty) = open_for_writing(path.as_ref(), append);
+    let encoder = CsvEncoder {
+        write_header: is_empty,
+    };
+    FramedWrite::new(tokio::io::BufWriter::new(file), encoder)
+}
+
+/// Creates a sink writing records to a JSON Lines file. If `append` is false the file is
+/// truncated.
+pub fn jsonl_file_sink(path: impl AsRef<Path>, append: bool) -> FileSink<JsonLinesEncoder> {
+    let (file, _is_empty) = open_for_writing(path.as_ref(), append);
+    FramedWrite::new(tokio::io::BufWriter::new(file), JsonLinesEncoder)
+}
+
+/// Creates a sink writing records to a file of length-delimited bincode frames. If `append` is
+/// false the file is truncated.
+pub fn bincode_file_sink(path: impl AsRef<Path>, append: bool) -> FileSink<BincodeFrameEncoder> {
+    let (file, _is_empty) = open_for_writing(path.as_ref(), append);
+    FramedWrite::new(
+        tokio::io::BufWriter::new(file),
+        BincodeFrameEncoder::default(),
+    )
+}
+
+/// [`Encoder`] of CSV rows, which writes a header row before the first record: the field names of
+/// a struct record, otherwise the field indices.
+#[derive(Debug)]
+pub struct CsvEncoder {
+    write_header: bool,
+}
+impl<T> Encoder<T> for CsvEncoder
+where
+    T: Serialize,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if !std::mem::replace(&mut self.write_header, false) {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(dst.writer());
+            writer.serialize(&item)?;
+            return writer.flush();
+        }
+
+        // `csv` only writes a header row for struct records, so read the row back to find out if
+        // it wrote one.
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.serialize(&item)?;
+        let rows = writer.into_inner().map_err(|err| err.into_error())?;
+        let records = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(&*rows)
+            .into_records()
+            .collect::<Result<Vec<_>, _>>()?;
+        if let [fields] = &*records {
+            let mut writer = csv::Writer::from_writer(dst.writer());
+            writer.write_record((0..fields.len()).map(|i| i.to_string()))?;
+            writer.flush()?;
+        }
+        dst.extend_from_slice(&rows);
+        Ok(())
+    }
+}
+
+/// [`Encoder`] of JSON Lines.
+#[derive(Debug, Default)]
+pub struct JsonLinesEncoder;
+impl<T> Encoder<T> for JsonLinesEncoder
+where
+    T: Serialize,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        serde_json::to_writer(dst.writer(), &item)?;
+        dst.put_u8(b'\n');
+        Ok(())
+    }
+}
+
+/// [`Encoder`] of length-delimited bincode frames.
+#[derive(Debug, Default)]
+pub struct BincodeFrameEncoder {
+    codec: LengthDelimitedCodec,
+}
+impl<T> Encoder<T> for BincodeFrameEncoder
+where
+    T: Serialize,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let bytes =
+            bincode::serialize(&item).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.codec.encode(Bytes::from(bytes), dst)
+    }
+}
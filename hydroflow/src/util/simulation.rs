@@ -0,0 +1,564 @@
+//! Deterministic simulation of networked Hydroflow programs.
+//!
+//! A [`Simulation`] runs several [`Hydroflow`] instances in one process, on a single thread, with
+//! a simulated network ([`SimNetwork`]) and clock ([`SimClock`]) in place of real sockets and
+//! timers. All nondeterminism (message delay, reordering, duplication, and drops) comes from an RNG
+//! seeded by [`Simulation::new`], so a failing interleaving can be replayed exactly from its seed.
+//!
+//! [`SimNetwork::bind`] returns a [`Sink`] and [`Stream`] pair which plug into `dest_sink` and
+//! `source_stream` in place of [`bind_udp_bytes`](super::bind_udp_bytes) and friends. Instead of
+//! being spawned onto Tokio, the `dest_sink` tasks of each instance are run by the simulation.
+//!
+//! ```rust
+//! use hydroflow::hydroflow_syntax;
+//! use hydroflow::util::simulation::Simulation;
+//!
+//! let mut sim = Simulation::new(0);
+//! let network = sim.network::<u32>();
+//!
+//! let (ping_send, ping_recv) = network.bind("127.0.0.1:1".parse().unwrap());
+//! let (pong_send, pong_recv) = network.bind("127.0.0.1:2".parse().unwrap());
+//! let pong_addr = "127.0.0.1:2".parse().unwrap();
+//!
+//! let (output_send, mut output_recv) = hydroflow::util::unbounded_channel();
+//! sim.add_node(hydroflow_syntax! {
+//!     source_iter(0..3) -> map(|x| (x, pong_addr)) -> dest_sink(ping_send);
+//!     source_stream(ping_recv) -> for_each(|(x, _addr)| output_send.send(x).unwrap());
+//! });
+//! sim.add_node(hydroflow_syntax! {
+//!     source_stream(pong_recv) -> map(|(x, addr)| (x + 10, addr)) -> dest_sink(pong_send);
+//! });
+//!
+//! sim.run_until_quiescent();
+//! let mut output = hydroflow::util::collect_ready::<Vec<_>, _>(&mut output_recv);
+//! output.sort();
+//! assert_eq!(&[10, 11, 12], &*output);
+//! ```
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use futures::task::ArcWake;
+use futures::{Sink, Stream};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::scheduled::graph::Hydroflow;
+use crate::scheduled::ticks::Clock;
+
+/// Configuration of the simulated network.
+#[derive(Clone, Debug)]
+pub struct SimConfig {
+    /// Minimum delay before a message is delivered.
+    pub min_delay: Duration,
+    /// Maximum delay before a message is delivered. Must not be less than `min_delay`.
+    pub max_delay: Duration,
+    /// Probability that a message is dropped, between `0.0` and `1.0`.
+    pub drop_probability: f64,
+    /// Probability that a message is delivered twice, between `0.0` and `1.0`.
+    pub duplicate_probability: f64,
+    /// If messages between the same pair of addresses may be delivered in a different order than
+    /// they were sent.
+    pub reorder: bool,
+}
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            min_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+            drop_probability: 0.0,
+            duplicate_probability: 0.0,
+            reorder: true,
+        }
+    }
+}
+
+/// State shared by the [`Simulation`] and its [`SimNetwork`]s and [`SimClock`]s.
+struct SimState {
+    rng: StdRng,
+    config: SimConfig,
+    now: Duration,
+    next_seq: u64,
+    /// Pending events (message deliveries and timers), ordered by time then by scheduling order.
+    events: BTreeMap<(Duration, u64), Box<dyn FnOnce()>>,
+}
+impl SimState {
+    fn schedule(&mut self, time: Duration, event: Box<dyn FnOnce()>) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.events.insert((time, seq), event);
+    }
+
+    fn next_event_time(&self) -> Option<Duration> {
+        self.events.keys().next().map(|&(time, _seq)| time)
+    }
+}
+
+/// A deterministic scheduler for running several [`Hydroflow`] instances in one process. See the
+/// [module-level docs](self).
+pub struct Simulation<'a> {
+    state: Rc<RefCell<SimState>>,
+    nodes: Vec<SimNode<'a>>,
+}
+impl<'a> Simulation<'a> {
+    /// Creates a new simulation with the default [`SimConfig`] and an RNG seeded with `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self::with_config(seed, SimConfig::default())
+    }
+
+    /// Creates a new simulation with the given `config` and an RNG seeded with `seed`.
+    pub fn with_config(seed: u64, config: SimConfig) -> Self {
+        let state = SimState {
+            rng: StdRng::seed_from_u64(seed),
+            config,
+            now: Duration::ZERO,
+            next_seq: 0,
+            events: BTreeMap::new(),
+        };
+        Self {
+            state: Rc::new(RefCell::new(state)),
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Creates a new simulated network carrying messages of type `T`.
+    pub fn network<T>(&self) -> SimNetwork<T> {
+        SimNetwork {
+            state: self.state.clone(),
+            inner: Default::default(),
+        }
+    }
+
+    /// Returns a handle to the simulated clock.
+    pub fn clock(&self) -> SimClock {
+        SimClock {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Returns the current simulated time, since the start of the simulation.
+    pub fn now(&self) -> Duration {
+        self.state.borrow().now
+    }
+
+    /// Adds a [`Hydroflow`] instance to the simulation, returning its index. Instances are run in
+    /// the order they were added.
+    pub fn add_node(&mut self, hydroflow: Hydroflow<'a>) -> usize {
+        self.nodes.push(SimNode {
+            hydroflow,
+            tasks: Vec::new(),
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Returns the [`Hydroflow`] instance at `index`.
+    pub fn node(&self, index: usize) -> &Hydroflow<'a> {
+        &self.nodes[index].hydroflow
+    }
+
+    /// Returns the [`Hydroflow`] instance at `index`.
+    pub fn node_mut(&mut self, index: usize) -> &mut Hydroflow<'a> {
+        &mut self.nodes[index].hydroflow
+    }
+
+    /// Runs all instances and their tasks until no more work can be done without advancing the
+    /// simulated time. Returns true if any work was done.
+    pub fn run_available(&mut self) -> bool {
+        let mut work_done = false;
+        loop {
+            let mut progress = false;
+            for node in self.nodes.iter_mut() {
+                progress |= node.run_available();
+            }
+            if !progress {
+                return work_done;
+            }
+            work_done = true;
+        }
+    }
+
+    /// Runs all available work, then advances the simulated time to the next pending message
+    /// delivery or timer and fires all events at that time. Returns false if there are no pending
+    /// events.
+    pub fn step(&mut self) -> bool {
+        self.run_available();
+        let Some(time) = self.state.borrow().next_event_time() else {
+            return false;
+        };
+        self.advance_to(time);
+        true
+    }
+
+    /// Runs the simulation until the simulated time reaches `time`, or until there is no more
+    /// work and no pending events.
+    pub fn run_until(&mut self, time: Duration) {
+        loop {
+            self.run_available();
+            let next_event_time = self.state.borrow().next_event_time();
+            match next_event_time {
+                Some(next_time) if next_time <= time => self.advance_to(next_time),
+                _ => break,
+            }
+        }
+        let mut state = self.state.borrow_mut();
+        state.now = state.now.max(time);
+    }
+
+    /// Runs the simulation for `duration` of simulated time, see [`Self::run_until`].
+    pub fn run_for(&mut self, duration: Duration) {
+        self.run_until(self.now() + duration);
+    }
+
+    /// Runs the simulation until there is no more work and no pending events. Returns the final
+    /// simulated time. Never returns if there are periodic timers, e.g. [`SimClock::interval`].
+    pub fn run_until_quiescent(&mut self) -> Duration {
+        while self.step() {}
+        self.now()
+    }
+
+    /// Sets the simulated time to `time` and fires all events at or before `time`.
+    fn advance_to(&mut self, time: Duration) {
+        self.state.borrow_mut().now = time;
+        loop {
+            // Release the borrow before running the event.
+            let event = {
+                let mut state = self.state.borrow_mut();
+                match state.events.first_entry() {
+                    Some(entry) if entry.key().0 <= time => entry.remove(),
+                    _ => break,
+                }
+            };
+            (event)();
+        }
+    }
+}
+
+/// A [`Hydroflow`] instance in a [`Simulation`], along with its `dest_sink` (etc.) tasks.
+struct SimNode<'a> {
+    hydroflow: Hydroflow<'a>,
+    tasks: Vec<SimTask>,
+}
+impl<'a> SimNode<'a> {
+    /// Runs the instance and polls any woken tasks. Returns true if any work was done.
+    fn run_available(&mut self) -> bool {
+        let mut work_done = self.hydroflow.run_available();
+
+        self.tasks.extend(
+            self.hydroflow
+                .take_tasks()
+                .into_iter()
+                .map(|future| SimTask {
+                    future,
+                    woken: Arc::new(TaskWaker(AtomicBool::new(true))),
+                }),
+        );
+        self.tasks.retain_mut(|task| {
+            if !task.woken.0.swap(false, Ordering::Relaxed) {
+                return true;
+            }
+            work_done = true;
+            let waker = futures::task::waker(task.woken.clone());
+            let mut cx = Context::from_waker(&waker);
+            task.future.as_mut().poll(&mut cx).is_pending()
+        });
+        work_done
+    }
+}
+
+/// A task requested by a [`Hydroflow`] instance, polled by the [`Simulation`] when woken.
+struct SimTask {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    woken: Arc<TaskWaker>,
+}
+
+/// Waker which marks a [`SimTask`] as needing to be polled.
+struct TaskWaker(AtomicBool);
+impl ArcWake for TaskWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A simulated network carrying messages of type `T` between [`SocketAddr`]s. Created by
+/// [`Simulation::network`].
+pub struct SimNetwork<T> {
+    state: Rc<RefCell<SimState>>,
+    inner: Rc<RefCell<NetworkInner<T>>>,
+}
+impl<T> Clone for SimNetwork<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            inner: self.inner.clone(),
+        }
+    }
+}
+impl<T> SimNetwork<T>
+where
+    T: 'static + Clone,
+{
+    /// Binds `addr`, returning a [`Sink`] for sending `(message, destination)` pairs and a
+    /// [`Stream`] of received `(message, source)` pairs. Messages sent to unbound addresses are
+    /// dropped.
+    ///
+    /// Panics if `addr` is already bound.
+    pub fn bind(&self, addr: SocketAddr) -> (SimSink<T>, SimStream<T>) {
+        let prev = self
+            .inner
+            .borrow_mut()
+            .inboxes
+            .insert(addr, Inbox::default());
+        assert!(prev.is_none(), "Address {} is already bound.", addr);
+        (
+            SimSink {
+                network: self.clone(),
+                addr,
+            },
+            SimStream {
+                network: self.clone(),
+                addr,
+            },
+        )
+    }
+
+    /// Sends a message, which will be delayed, duplicated, or dropped according to the
+    /// [`SimConfig`].
+    fn send(&self, src: SocketAddr, dst: SocketAddr, msg: T) {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+        if state.rng.gen_bool(state.config.drop_probability) {
+            return;
+        }
+        let copies = if state.rng.gen_bool(state.config.duplicate_probability) {
+            2
+        } else {
+            1
+        };
+        for _ in 0..copies {
+            let delay = state
+                .rng
+                .gen_range(state.config.min_delay..=state.config.max_delay);
+            let mut time = state.now + delay;
+            if !state.config.reorder {
+                let mut inner = self.inner.borrow_mut();
+                let last_time = inner.link_last_time.entry((src, dst)).or_default();
+                time = time.max(*last_time);
+                *last_time = time;
+            }
+            let inner = self.inner.clone();
+            let msg = msg.clone();
+            state.schedule(
+                time,
+                Box::new(move || inner.borrow_mut().deliver(src, dst, msg)),
+            );
+        }
+    }
+}
+
+struct NetworkInner<T> {
+    inboxes: HashMap<SocketAddr, Inbox<T>>,
+    /// Latest delivery time for each `(src, dst)` link, used if [`SimConfig::reorder`] is false.
+    link_last_time: HashMap<(SocketAddr, SocketAddr), Duration>,
+}
+impl<T> Default for NetworkInner<T> {
+    fn default() -> Self {
+        Self {
+            inboxes: Default::default(),
+            link_last_time: Default::default(),
+        }
+    }
+}
+impl<T> NetworkInner<T> {
+    fn deliver(&mut self, src: SocketAddr, dst: SocketAddr, msg: T) {
+        if let Some(inbox) = self.inboxes.get_mut(&dst) {
+            inbox.queue.push_back((msg, src));
+            if let Some(waker) = inbox.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+struct Inbox<T> {
+    queue: VecDeque<(T, SocketAddr)>,
+    waker: Option<Waker>,
+}
+impl<T> Default for Inbox<T> {
+    fn default() -> Self {
+        Self {
+            queue: Default::default(),
+            waker: None,
+        }
+    }
+}
+
+/// Sending half of an address bound with [`SimNetwork::bind`]. Accepts `(message, destination)`
+/// pairs, for use with `dest_sink`.
+pub struct SimSink<T> {
+    network: SimNetwork<T>,
+    addr: SocketAddr,
+}
+impl<T> Sink<(T, SocketAddr)> for SimSink<T>
+where
+    T: 'static + Clone,
+{
+    type Error = Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, (msg, dst): (T, SocketAddr)) -> Result<(), Self::Error> {
+        self.network.send(self.addr, dst, msg);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Receiving half of an address bound with [`SimNetwork::bind`]. Yields `(message, source)`
+/// pairs, for use with `source_stream`.
+pub struct SimStream<T> {
+    network: SimNetwork<T>,
+    addr: SocketAddr,
+}
+impl<T> Stream for SimStream<T> {
+    type Item = (T, SocketAddr);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut inner = self.network.inner.borrow_mut();
+        let inbox = inner.inboxes.get_mut(&self.addr).unwrap();
+        match inbox.queue.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None => {
+                inbox.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A handle to the simulated clock of a [`Simulation`]. Created by [`Simulation::clock`].
+///
+/// Implements [`Clock`], so can drive time-based [`TickPolicy`](crate::scheduled::ticks::TickPolicy)s.
+#[derive(Clone)]
+pub struct SimClock {
+    state: Rc<RefCell<SimState>>,
+}
+impl SimClock {
+    /// Returns the current simulated time, since the start of the simulation.
+    pub fn now(&self) -> Duration {
+        self.state.borrow().now
+    }
+
+    /// Returns a future which completes after `duration` of simulated time.
+    pub fn sleep(&self, duration: Duration) -> SimSleep {
+        SimSleep {
+            timer: SimTimer::new(self.clone(), self.now() + duration),
+        }
+    }
+
+    /// Returns a stream which yields the simulated time every `period`, starting after one
+    /// `period`. For use with `source_stream`, in place of `source_interval`.
+    pub fn interval(&self, period: Duration) -> SimInterval {
+        SimInterval {
+            timer: SimTimer::new(self.clone(), self.now() + period),
+            period,
+        }
+    }
+}
+
+impl Clock for SimClock {
+    fn now(&self) -> Duration {
+        self.now()
+    }
+
+    fn sleep_until(&self, deadline: Duration) -> Pin<Box<dyn Future<Output = ()>>> {
+        Box::pin(SimSleep {
+            timer: SimTimer::new(self.clone(), deadline),
+        })
+    }
+}
+
+/// A timer which wakes a task at a deadline.
+struct SimTimer {
+    clock: SimClock,
+    deadline: Duration,
+    /// Waker to wake at the deadline. Set if the timer event has been scheduled.
+    waker: Option<Rc<RefCell<Option<Waker>>>>,
+}
+impl SimTimer {
+    fn new(clock: SimClock, deadline: Duration) -> Self {
+        Self {
+            clock,
+            deadline,
+            waker: None,
+        }
+    }
+
+    fn poll_elapsed(&mut self, cx: &mut Context<'_>) -> Poll<Duration> {
+        let now = self.clock.now();
+        if self.deadline <= now {
+            self.waker = None;
+            return Poll::Ready(now);
+        }
+        let waker = self.waker.get_or_insert_with(|| {
+            let waker: Rc<RefCell<Option<Waker>>> = Default::default();
+            let event_waker = waker.clone();
+            self.clock.state.borrow_mut().schedule(
+                self.deadline,
+                Box::new(move || {
+                    if let Some(waker) = event_waker.take() {
+                        waker.wake();
+                    }
+                }),
+            );
+            waker
+        });
+        *waker.borrow_mut() = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Future returned by [`SimClock::sleep`].
+pub struct SimSleep {
+    timer: SimTimer,
+}
+impl Future for SimSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.timer.poll_elapsed(cx).map(|_now| ())
+    }
+}
+
+/// Stream returned by [`SimClock::interval`].
+pub struct SimInterval {
+    timer: SimTimer,
+    period: Duration,
+}
+impl Stream for SimInterval {
+    type Item = Duration;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let now = futures::ready!(self.timer.poll_elapsed(cx));
+        let next_deadline = self.timer.deadline + self.period;
+        self.timer.deadline = next_deadline;
+        Poll::Ready(Some(now))
+    }
+}
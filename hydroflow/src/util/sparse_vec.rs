@@ -3,7 +3,14 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::iter::FusedIterator;
 
+use serde::{Deserialize, Serialize};
+
 /// A vector that supports efficient deletion without reordering all subsequent items.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize",
+    deserialize = "T: Deserialize<'de> + Eq + Hash"
+))]
 pub struct SparseVec<T> {
     items: Vec<Option<T>>,
     item_locs: HashMap<T, Vec<usize>>,
@@ -2,12 +2,17 @@
 
 use std::cell::RefCell;
 use std::collections::hash_map::Entry::{Occupied, Vacant};
-use std::collections::HashMap;
-use std::net::SocketAddr;
-use std::pin::pin;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::pin::{pin, Pin};
 use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
 
-use futures::{SinkExt, StreamExt};
+use bytes::{Buf, BytesMut};
+use futures::stream::{FuturesUnordered, LocalBoxStream, SelectAll};
+use futures::{SinkExt, Stream, StreamExt};
+use tokio::io::AsyncWriteExt;
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::{TcpListener, TcpSocket, TcpStream};
 use tokio::task::spawn_local;
@@ -174,3 +179,314 @@ pub fn connect_tcp<T: 'static, Codec: 'static + Clone + Decoder + Encoder<T>>(
 
     (tx_egress, rx_ingress)
 }
+
+/// The stream of `(item, peer_addr)` pairs received on one connection.
+type TcpConnectionStream<Item> = LocalBoxStream<'static, (Item, SocketAddr)>;
+
+/// A `Stream` of the items received on all connections accepted by a TCP listener. Returned by
+/// [`bind_tcp_source`].
+struct TcpSource<Item> {
+    listener: TcpListener,
+    connections: SelectAll<TcpConnectionStream<Item>>,
+    new_connection: Box<dyn Fn(TcpStream, SocketAddr) -> TcpConnectionStream<Item>>,
+}
+impl<Item> Stream for TcpSource<Item> {
+    type Item = (Item, SocketAddr);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        while let Poll::Ready(accepted) = this.listener.poll_accept(cx) {
+            match accepted {
+                Ok((stream, peer_addr)) => {
+                    let connection = (this.new_connection)(stream, peer_addr);
+                    this.connections.push(connection);
+                }
+                Err(err) => {
+                    tracing::debug!(error = %err, "Failed to accept TCP connection.");
+                    // `poll_accept` only registers the waker when it returns `Pending`, so ask to
+                    // be polled again rather than stalling until a connection has data.
+                    cx.waker().wake_by_ref();
+                    break;
+                }
+            }
+        }
+        // `SelectAll` is done when it has no connections, but more may be accepted later.
+        match this.connections.poll_next_unpin(cx) {
+            Poll::Ready(Some(item)) => Poll::Ready(Some(item)),
+            Poll::Ready(None) | Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Binds a TCP listener to `addr`, returning a `Stream` of the `(item, peer_addr)` pairs received
+/// on every accepted connection, along with the bound address. Each connection is framed with its
+/// own clone of `codec`.
+///
+/// A connection is dropped when it closes or when its data fails to decode, and the peer may
+/// reconnect at any time. The listener is bound immediately, so this must be called within a Tokio
+/// runtime.
+pub fn bind_tcp_source<Codec>(
+    addr: impl ToSocketAddrs,
+    codec: Codec,
+) -> Result<
+    (
+        impl Stream<Item = (<Codec as Decoder>::Item, SocketAddr)> + Unpin,
+        SocketAddr,
+    ),
+    std::io::Error,
+>
+where
+    Codec: 'static + Clone + Decoder,
+    <Codec as Decoder>::Error: std::fmt::Debug,
+{
+    let listener = std::net::TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    let listener = TcpListener::from_std(listener)?;
+    let bound_endpoint = listener.local_addr()?;
+
+    let new_connection = move |stream, peer_addr| {
+        FramedRead::new(stream, codec.clone())
+            .take_while(move |result| {
+                if let Err(err) = result {
+                    tracing::debug!(%peer_addr, error = ?err, "Dropping TCP connection.");
+                }
+                std::future::ready(result.is_ok())
+            })
+            .filter_map(move |result| std::future::ready(result.ok().map(|item| (item, peer_addr))))
+            .boxed_local()
+    };
+    let source = TcpSource {
+        listener,
+        connections: SelectAll::new(),
+        new_connection: Box::new(new_connection),
+    };
+    Ok((source, bound_endpoint))
+}
+
+/// Number of times [`tcp_pooled_sender`] tries to connect and write to a destination before
+/// dropping the items buffered for it.
+pub const TCP_SEND_ATTEMPTS: u32 = 5;
+/// Delay before the first reconnection attempt in [`tcp_pooled_sender`], doubled after each
+/// further failure.
+pub const TCP_RECONNECT_BACKOFF: Duration = Duration::from_millis(50);
+/// How long [`tcp_pooled_sender`] waits for a connection to be established before counting the
+/// attempt as failed.
+pub const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connects to `addr`, failing with [`std::io::ErrorKind::TimedOut`] after
+/// [`TCP_CONNECT_TIMEOUT`].
+async fn connect_with_timeout(addr: SocketAddr) -> std::io::Result<TcpStream> {
+    tokio::time::timeout(TCP_CONNECT_TIMEOUT, TcpStream::connect(addr))
+        .await
+        .unwrap_or_else(|_elapsed| {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "timed out connecting over TCP",
+            ))
+        })
+}
+
+/// Writes `buffer[*written..]` to `stream`, advancing `written` as bytes are accepted so the caller
+/// knows how far the write got if it fails.
+async fn write_tracked(
+    stream: &mut TcpStream,
+    buffer: &[u8],
+    written: &mut usize,
+) -> std::io::Result<()> {
+    while *written < buffer.len() {
+        match stream.write(&buffer[*written..]).await? {
+            0 => return Err(std::io::ErrorKind::WriteZero.into()),
+            n => *written += n,
+        }
+    }
+    Ok(())
+}
+
+/// A pooled connection to one destination, and the encoded items waiting to be written to it.
+#[derive(Default)]
+struct PooledConnection {
+    stream: Option<TcpStream>,
+    buffer: BytesMut,
+    /// The offset in `buffer` at which each encoded item ends.
+    frame_ends: VecDeque<usize>,
+}
+impl PooledConnection {
+    /// Encodes `item` onto the end of the buffer. On failure the buffer is left unchanged.
+    fn push<T, Codec: Encoder<T>>(
+        &mut self,
+        codec: &mut Codec,
+        item: T,
+    ) -> Result<(), Codec::Error> {
+        let len = self.buffer.len();
+        if let Err(err) = codec.encode(item, &mut self.buffer) {
+            self.buffer.truncate(len);
+            return Err(err);
+        }
+        if self.buffer.len() > len {
+            self.frame_ends.push_back(self.buffer.len());
+        }
+        Ok(())
+    }
+
+    /// Removes the items which were written whole within the first `written` bytes of the buffer.
+    /// An item which was only partly written stays buffered, to be resent whole.
+    fn discard_written(&mut self, written: usize) {
+        let complete = self
+            .frame_ends
+            .iter()
+            .take_while(|&&end| end <= written)
+            .count();
+        let Some(cut) = complete.checked_sub(1).map(|last| self.frame_ends[last]) else {
+            return;
+        };
+        self.frame_ends.drain(..complete);
+        self.buffer.advance(cut);
+        for end in self.frame_ends.iter_mut() {
+            *end -= cut;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear();
+        self.frame_ends.clear();
+    }
+
+    /// Writes the buffered items to `addr`, connecting or reconnecting as needed.
+    async fn flush(&mut self, addr: SocketAddr) {
+        let mut backoff = TCP_RECONNECT_BACKOFF;
+        for attempt in 1..=TCP_SEND_ATTEMPTS {
+            let mut written = 0;
+            let result = match &mut self.stream {
+                Some(stream) => write_tracked(stream, &self.buffer, &mut written).await,
+                None => match connect_with_timeout(addr).await {
+                    Ok(stream) => {
+                        let stream = self.stream.insert(stream);
+                        write_tracked(stream, &self.buffer, &mut written).await
+                    }
+                    Err(err) => Err(err),
+                },
+            };
+            match result {
+                Ok(()) => {
+                    self.clear();
+                    return;
+                }
+                Err(err) => {
+                    tracing::debug!(%addr, attempt, error = %err, "Failed to send over TCP.");
+                    // Don't resend items which were already written, and write a partly written
+                    // item whole to a fresh connection.
+                    self.discard_written(written);
+                    self.stream = None;
+                    if attempt < TCP_SEND_ATTEMPTS {
+                        tokio::time::sleep(backoff).await;
+                        backoff *= 2;
+                    }
+                }
+            }
+        }
+        tracing::warn!(%addr, "Dropping items after failing to send over TCP.");
+        self.clear();
+    }
+}
+
+/// Sends each `(item, addr)` pair received on `items` to `addr` over TCP, framed with `codec`,
+/// until `items` closes.
+///
+/// One connection is kept per destination and reused for all items sent to it. Items received
+/// together are encoded into a single buffer per destination and written at once. If connecting or
+/// writing fails, the connection is re-established with exponential backoff, starting from
+/// [`TCP_RECONNECT_BACKOFF`], and the items are dropped after [`TCP_SEND_ATTEMPTS`] failures.
+/// Connecting fails if it takes longer than [`TCP_CONNECT_TIMEOUT`]. Items already written before
+/// a failure are not resent, but an item cut off partway is resent whole on the new connection, so
+/// the destination may see a truncated copy of it on the old connection.
+/// Each destination is written to independently, so an unreachable destination does not delay the
+/// others while it backs off. Items received for a destination while it is being written to are
+/// buffered and written once that write finishes. Data sent back by destinations is ignored.
+pub async fn tcp_pooled_sender<T, Codec>(
+    mut items: tokio::sync::mpsc::UnboundedReceiver<(T, SocketAddr)>,
+    mut codec: Codec,
+) where
+    Codec: Encoder<T>,
+    Codec::Error: std::fmt::Debug,
+{
+    /// Flushes `connection`, handing it back once done.
+    async fn flush_owned(
+        addr: SocketAddr,
+        mut connection: PooledConnection,
+    ) -> (SocketAddr, PooledConnection) {
+        connection.flush(addr).await;
+        (addr, connection)
+    }
+
+    // Destinations being flushed are taken out of the pool, leaving an entry which buffers items
+    // received in the meantime.
+    let mut pool = HashMap::<SocketAddr, PooledConnection>::new();
+    let mut flushing = FuturesUnordered::new();
+    let mut busy = HashSet::new();
+    let mut dirty = Vec::new();
+    let mut items_open = true;
+    loop {
+        tokio::select! {
+            first = items.recv(), if items_open => {
+                let Some(first) = first else {
+                    items_open = false;
+                    continue;
+                };
+                let mut next = Some(first);
+                while let Some((item, addr)) = next {
+                    let connection = pool.entry(addr).or_default();
+                    if connection.buffer.is_empty() {
+                        dirty.push(addr);
+                    }
+                    if let Err(err) = connection.push(&mut codec, item) {
+                        tracing::warn!(%addr, error = ?err, "Failed to encode item, dropping it.");
+                    }
+                    next = items.try_recv().ok();
+                }
+            }
+            Some(flushed) = flushing.next() => {
+                let (addr, flushed): (SocketAddr, PooledConnection) = flushed;
+                busy.remove(&addr);
+                // Reuse the connection for the items buffered while flushing.
+                pool.get_mut(&addr).unwrap().stream = flushed.stream;
+                dirty.push(addr);
+            }
+            else => break,
+        }
+        for addr in dirty.drain(..) {
+            let connection = pool.get_mut(&addr).unwrap();
+            if !connection.buffer.is_empty() && busy.insert(addr) {
+                flushing.push(flush_owned(addr, std::mem::take(connection)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio_util::codec::LinesCodec;
+
+    use super::*;
+
+    #[test]
+    fn test_discard_written() {
+        let mut connection = PooledConnection::default();
+        for line in ["a", "bb", "ccc"] {
+            connection.push(&mut LinesCodec::new(), line).unwrap();
+        }
+        assert_eq!(b"a\nbb\nccc\n", &connection.buffer[..]);
+
+        // Nothing whole was written.
+        connection.discard_written(1);
+        assert_eq!(b"a\nbb\nccc\n", &connection.buffer[..]);
+
+        // "a" was written whole, "bb" only partly.
+        connection.discard_written(3);
+        assert_eq!(b"bb\nccc\n", &connection.buffer[..]);
+        assert_eq!([3, 7], *connection.frame_ends.make_contiguous());
+
+        connection.discard_written(7);
+        assert!(connection.buffer.is_empty());
+        assert!(connection.frame_ends.is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+//! Bounded state for the `top_k` and `top_k_keyed` operators.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use serde::{Deserialize, Serialize};
+
+/// Keeps the `k` items with the greatest keys seen so far, in `O(log k)` time per insert and
+/// `O(k)` space.
+///
+/// Among items with equal keys, the earliest inserted are kept.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "K: Serialize, T: Serialize",
+    deserialize = "K: Ord + Deserialize<'de>, T: Deserialize<'de>"
+))]
+pub struct TopK<K, T> {
+    k: usize,
+    /// Min-heap, so the lowest ranked item is the first to be evicted.
+    heap: BinaryHeap<Reverse<TopKEntry<K, T>>>,
+    next_seq: u64,
+}
+
+impl<K, T> TopK<K, T>
+where
+    K: Ord,
+{
+    /// Creates a new empty `TopK` which keeps at most `k` items.
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::with_capacity(k),
+            next_seq: 0,
+        }
+    }
+
+    /// Inserts an item with the given key. Returns `true` if the item is now in the top `k`, in
+    /// which case the lowest ranked item may have been evicted.
+    pub fn insert(&mut self, key: K, item: T) -> bool {
+        let entry = TopKEntry {
+            key,
+            seq: self.next_seq,
+            item,
+        };
+        self.next_seq += 1;
+
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(entry));
+            return true;
+        }
+        match self.heap.peek_mut() {
+            Some(mut lowest) if lowest.0 < entry => {
+                *lowest = Reverse(entry);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the items, from greatest to least key.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = &T> {
+        let mut entries = self
+            .heap
+            .iter()
+            .map(|Reverse(entry)| entry)
+            .collect::<Vec<_>>();
+        entries.sort_unstable_by(|a, b| b.cmp(a));
+        entries.into_iter().map(|entry| &entry.item)
+    }
+
+    /// Returns the items, from greatest to least key, consuming `self`.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        // `Reverse` makes the ascending sort descending.
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(entry)| entry.item)
+            .collect()
+    }
+
+    /// Returns the number of items kept, at most `k`.
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Returns `true` if no items are kept.
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct TopKEntry<K, T> {
+    key: K,
+    seq: u64,
+    item: T,
+}
+impl<K, T> Ord for TopKEntry<K, T>
+where
+    K: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Earlier items rank higher among equal keys.
+        self.key
+            .cmp(&other.key)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+impl<K, T> PartialOrd for TopKEntry<K, T>
+where
+    K: Ord,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<K, T> PartialEq for TopKEntry<K, T>
+where
+    K: Ord,
+{
+    fn eq(&self, other: &Self) -> bool {
+        Ordering::Equal == self.cmp(other)
+    }
+}
+impl<K, T> Eq for TopKEntry<K, T> where K: Ord {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_k() {
+        let mut top_k = TopK::new(3);
+        for (i, x) in [5, 1, 7, 3, 7, 9, 2].into_iter().enumerate() {
+            top_k.insert(x, (x, i));
+        }
+        assert_eq!(
+            vec![&(9, 5), &(7, 2), &(7, 4)],
+            top_k.iter_sorted().collect::<Vec<_>>()
+        );
+        assert_eq!(vec![(9, 5), (7, 2), (7, 4)], top_k.into_sorted_vec());
+
+        let mut top_0 = TopK::new(0);
+        assert!(!top_0.insert(1, ()));
+        assert!(top_0.is_empty());
+    }
+}
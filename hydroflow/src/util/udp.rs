@@ -1,9 +1,10 @@
 #![cfg(not(target_arch = "wasm32"))]
 
-use std::net::SocketAddr;
+use std::net::{SocketAddr, ToSocketAddrs};
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use futures::stream::{SplitSink, SplitStream};
+use futures::{Stream, StreamExt};
 use tokio::net::UdpSocket;
 use tokio_util::codec::length_delimited::LengthDelimitedCodec;
 use tokio_util::codec::{BytesCodec, Decoder, Encoder, LinesCodec};
@@ -66,3 +67,78 @@ pub fn udp_lines(
 ) {
     udp_framed(socket, LinesCodec::new())
 }
+
+/// Binds a UDP socket to `addr`, returning a `Stream` of the `(item, sender_addr)` pairs it
+/// receives, along with the bound address. Datagrams which fail to decode are skipped.
+///
+/// The socket is bound immediately, so this must be called within a Tokio runtime.
+pub fn bind_udp_source<Codec>(
+    addr: impl ToSocketAddrs,
+    codec: Codec,
+) -> Result<
+    (
+        impl Stream<Item = (<Codec as Decoder>::Item, SocketAddr)> + Unpin,
+        SocketAddr,
+    ),
+    std::io::Error,
+>
+where
+    Codec: Decoder,
+    <Codec as Decoder>::Error: std::fmt::Debug,
+{
+    let socket = std::net::UdpSocket::bind(addr)?;
+    socket.set_nonblocking(true)?;
+    let socket = UdpSocket::from_std(socket)?;
+    let bound_endpoint = socket.local_addr()?;
+
+    let source = UdpFramed::new(socket, codec).filter_map(|result| {
+        std::future::ready(
+            result
+                .map_err(|err| tracing::debug!(error = ?err, "Skipping UDP datagram."))
+                .ok(),
+        )
+    });
+    Ok((source, bound_endpoint))
+}
+
+/// Sends each `(item, addr)` pair received on `items` to `addr` as a UDP datagram encoded with
+/// `codec`, until `items` closes.
+///
+/// Datagrams are sent from an ephemeral port, bound on first use for each of IPv4 and IPv6. As
+/// UDP is unreliable, items which fail to encode or send are dropped.
+pub async fn udp_sender<T, Codec>(
+    mut items: tokio::sync::mpsc::UnboundedReceiver<(T, SocketAddr)>,
+    mut codec: Codec,
+) where
+    Codec: Encoder<T>,
+    Codec::Error: std::fmt::Debug,
+{
+    let mut socket_v4 = None;
+    let mut socket_v6 = None;
+    let mut buffer = BytesMut::new();
+    while let Some((item, addr)) = items.recv().await {
+        buffer.clear();
+        if let Err(err) = codec.encode(item, &mut buffer) {
+            tracing::warn!(%addr, error = ?err, "Failed to encode item, dropping it.");
+            continue;
+        }
+        let (socket, bind_addr) = if addr.is_ipv4() {
+            (&mut socket_v4, "0.0.0.0:0")
+        } else {
+            (&mut socket_v6, "[::]:0")
+        };
+        let socket = match socket {
+            Some(socket) => socket,
+            None => match UdpSocket::bind(bind_addr).await {
+                Ok(bound) => socket.insert(bound),
+                Err(err) => {
+                    tracing::warn!(error = %err, "Failed to bind UDP socket, dropping item.");
+                    continue;
+                }
+            },
+        };
+        if let Err(err) = socket.send_to(&buffer, addr).await {
+            tracing::debug!(%addr, error = %err, "Failed to send UDP datagram.");
+        }
+    }
+}
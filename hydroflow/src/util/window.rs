@@ -0,0 +1,277 @@
+//! Event-time window state for the `window_tumbling`, `window_sliding`, and `window_session`
+//! operators.
+//!
+//! Event times and watermarks are `u64`s in any unit (e.g. milliseconds since the epoch). A window
+//! fires, emitting its result for each key, once the watermark reaches its end. Windows are kept
+//! for `allowed_lateness` after their end; late items which arrive in that time update the window
+//! and its result is emitted again. Items which arrive after that are dropped.
+
+use std::collections::BTreeMap;
+use std::hash::Hash;
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+/// A half-open event-time interval `[start, end)`.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Window {
+    /// Start time of the window, inclusive.
+    pub start: u64,
+    /// End time of the window, exclusive.
+    pub end: u64,
+}
+
+/// Result of a window for one key, and if it has changed since it was last emitted.
+#[derive(Debug, Serialize, Deserialize)]
+struct Pane<A> {
+    acc: A,
+    dirty: bool,
+}
+
+/// State for fixed-size windows, either tumbling or sliding. Folds items into an accumulator per
+/// key and window as they arrive.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FixedWindows<K, A>
+where
+    K: Eq + Hash,
+{
+    size: u64,
+    slide: u64,
+    allowed_lateness: u64,
+    watermark: Option<u64>,
+    /// Ordered by start time, and so also by end time as all windows are the same size.
+    windows: BTreeMap<Window, FxHashMap<K, Pane<A>>>,
+}
+impl<K, A> FixedWindows<K, A>
+where
+    K: Eq + Hash,
+{
+    /// Creates state for tumbling windows, which are non-overlapping and `size` long.
+    pub fn tumbling(size: u64, allowed_lateness: u64) -> Self {
+        Self::sliding(size, size, allowed_lateness)
+    }
+
+    /// Creates state for sliding windows, which are `size` long and start every `slide`.
+    pub fn sliding(size: u64, slide: u64, allowed_lateness: u64) -> Self {
+        assert!(0 < size, "Window size must be positive.");
+        assert!(0 < slide, "Window slide must be positive.");
+        Self {
+            size,
+            slide,
+            allowed_lateness,
+            watermark: None,
+            windows: BTreeMap::new(),
+        }
+    }
+
+    /// Folds `value` into each window containing `time` for `key`, unless it is too late.
+    pub fn insert<V>(
+        &mut self,
+        key: K,
+        time: u64,
+        value: V,
+        init: impl Fn() -> A,
+        fold: impl Fn(&mut A, V),
+    ) where
+        K: Clone,
+        V: Clone,
+    {
+        let last_start = time - time % self.slide;
+        let mut start = Some(last_start);
+        let mut windows = Vec::new();
+        while let Some(window_start) = start.filter(|&start| time < start.saturating_add(self.size))
+        {
+            windows.push(Window {
+                start: window_start,
+                end: window_start.saturating_add(self.size),
+            });
+            start = window_start.checked_sub(self.slide);
+        }
+        let mut value = Some(value);
+        let count = windows.len();
+        for (i, window) in windows.into_iter().enumerate() {
+            if is_expired(window, self.watermark, self.allowed_lateness) {
+                continue;
+            }
+            let value = if i + 1 == count {
+                value.take().unwrap()
+            } else {
+                value.clone().unwrap()
+            };
+            let pane = self
+                .windows
+                .entry(window)
+                .or_default()
+                .entry(key.clone())
+                .or_insert_with(|| Pane {
+                    acc: (init)(),
+                    dirty: true,
+                });
+            (fold)(&mut pane.acc, value);
+            pane.dirty = true;
+        }
+    }
+
+    /// Advances the watermark to `watermark`, if it is later than the current watermark.
+    pub fn advance_watermark(&mut self, watermark: u64) {
+        self.watermark = Some(self.watermark.map_or(watermark, |wm| wm.max(watermark)));
+    }
+
+    /// Returns the results of windows which have fired since the last call, and of fired windows
+    /// which have been updated by late items. Then drops windows past their allowed lateness.
+    pub fn take_output(&mut self) -> Vec<(K, Window, A)>
+    where
+        K: Clone,
+        A: Clone,
+    {
+        let Some(watermark) = self.watermark else {
+            return Vec::new();
+        };
+        let mut output = Vec::new();
+        for (&window, panes) in self.windows.iter_mut() {
+            if watermark < window.end {
+                break;
+            }
+            for (key, pane) in panes.iter_mut() {
+                if pane.dirty {
+                    output.push((key.clone(), window, pane.acc.clone()));
+                    pane.dirty = false;
+                }
+            }
+        }
+        self.windows
+            .retain(|&window, _| !is_expired(window, self.watermark, self.allowed_lateness));
+        output
+    }
+}
+
+/// State for session windows, which group items for a key that are less than `gap` apart. Each
+/// item starts a session `[time, time + gap)`, and overlapping sessions are merged.
+///
+/// As sessions may merge, items are stored and folded when the session fires.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionWindows<K, V>
+where
+    K: Eq + Hash,
+{
+    gap: u64,
+    allowed_lateness: u64,
+    watermark: Option<u64>,
+    /// Sessions of each key, by start time.
+    sessions: FxHashMap<K, BTreeMap<u64, Session<V>>>,
+}
+#[derive(Debug, Serialize, Deserialize)]
+struct Session<V> {
+    end: u64,
+    values: Vec<V>,
+    /// If the session has changed since it was last emitted.
+    dirty: bool,
+}
+impl<K, V> SessionWindows<K, V>
+where
+    K: Eq + Hash,
+{
+    /// Creates state for session windows.
+    pub fn new(gap: u64, allowed_lateness: u64) -> Self {
+        assert!(0 < gap, "Session gap must be positive.");
+        Self {
+            gap,
+            allowed_lateness,
+            watermark: None,
+            sessions: FxHashMap::default(),
+        }
+    }
+
+    /// Adds `value` at `time` to the sessions of `key`, unless it is too late.
+    pub fn insert(&mut self, key: K, time: u64, value: V) {
+        let mut window = Window {
+            start: time,
+            end: time.saturating_add(self.gap),
+        };
+        if is_expired(window, self.watermark, self.allowed_lateness) {
+            return;
+        }
+        let sessions = self.sessions.entry(key).or_default();
+        // Merge with any overlapping sessions.
+        let overlapping: Vec<u64> = sessions
+            .range(..window.end)
+            .rev()
+            .take_while(|(_, session)| window.start < session.end)
+            .map(|(&start, _)| start)
+            .collect();
+        let mut values = Vec::new();
+        for start in overlapping.into_iter().rev() {
+            let session = sessions.remove(&start).unwrap();
+            window.start = window.start.min(start);
+            window.end = window.end.max(session.end);
+            values.extend(session.values);
+        }
+        values.push(value);
+        sessions.insert(
+            window.start,
+            Session {
+                end: window.end,
+                values,
+                dirty: true,
+            },
+        );
+    }
+
+    /// Advances the watermark to `watermark`, if it is later than the current watermark.
+    pub fn advance_watermark(&mut self, watermark: u64) {
+        self.watermark = Some(self.watermark.map_or(watermark, |wm| wm.max(watermark)));
+    }
+
+    /// Folds and returns the results of sessions which have fired since the last call, and of
+    /// fired sessions which have been updated by late items. Then drops sessions past their allowed
+    /// lateness.
+    pub fn take_output<A>(
+        &mut self,
+        init: impl Fn() -> A,
+        fold: impl Fn(&mut A, V),
+    ) -> Vec<(K, Window, A)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let Some(watermark) = self.watermark else {
+            return Vec::new();
+        };
+        let mut output = Vec::new();
+        for (key, sessions) in self.sessions.iter_mut() {
+            for (&start, session) in sessions.iter_mut() {
+                if session.dirty && session.end <= watermark {
+                    let mut acc = (init)();
+                    for value in session.values.iter().cloned() {
+                        (fold)(&mut acc, value);
+                    }
+                    let window = Window {
+                        start,
+                        end: session.end,
+                    };
+                    output.push((key.clone(), window, acc));
+                    session.dirty = false;
+                }
+            }
+            sessions.retain(|&start, session| {
+                let window = Window {
+                    start,
+                    end: session.end,
+                };
+                !is_expired(window, self.watermark, self.allowed_lateness)
+            });
+        }
+        self.sessions.retain(|_, sessions| !sessions.is_empty());
+        output.sort_by_key(|(_, window, _)| *window);
+        output
+    }
+}
+
+/// If the window is past its allowed lateness, so should be dropped.
+fn is_expired(window: Window, watermark: Option<u64>, allowed_lateness: u64) -> bool {
+    watermark.map_or(false, |watermark| {
+        window.end.saturating_add(allowed_lateness) <= watermark
+    })
+}
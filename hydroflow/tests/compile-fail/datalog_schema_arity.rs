@@ -0,0 +1,11 @@
+use hydroflow::datalog;
+
+fn main() {
+    let mut df = datalog!(r#"
+        .input edges(src: u32, dst: u32) `source_iter(0..10) -> map(|x| (x, x + 1))`
+        .output out `null::<(u32,)>()`
+        out(a) :- edges(a, b, c)
+        out(a, b) :- edges(a, b)
+    "#);
+    df.run_available();
+}
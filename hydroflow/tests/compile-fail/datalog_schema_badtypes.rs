@@ -0,0 +1,12 @@
+use hydroflow::datalog;
+
+fn main() {
+    let mut df = datalog!(r#"
+        .input edges(src: u32, dst: u32) `source_iter(0..10) -> map(|x| (x, x + 1))`
+        .input names(id: u32, name: String) `source_iter(0..10) -> map(|x| (x, x.to_string()))`
+        .output out(name: String) `null()`
+        out(a) :- edges(a, b), names(b, a)
+        out(b) :- edges(a, b)
+    "#);
+    df.run_available();
+}
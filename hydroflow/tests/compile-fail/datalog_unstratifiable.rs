@@ -0,0 +1,18 @@
+use hydroflow::datalog;
+
+fn main() {
+    let mut df = datalog!(r#"
+        .input nodes `source_iter(0..10) -> map(|x| (x,))`
+        .input edges `source_iter(0..10) -> map(|x| (x, x + 1))`
+        .input seed `source_iter([(0, 0)])`
+        .output winning `null::<(u32,)>()`
+        .output dist `null::<(u32, u32)>()`
+
+        winning(x) :- nodes(x), !losing(x)
+        losing(x) :- nodes(x), !winning(x)
+
+        dist(x, min(d)) :- seed(x, d)
+        dist(y, min(d)) :- dist(x, d), edges(x, y)
+    "#);
+    df.run_available();
+}
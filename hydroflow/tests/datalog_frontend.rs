@@ -271,6 +271,230 @@ pub fn test_triple_relation_join() {
     );
 }
 
+#[multiplatform_test]
+pub fn test_join_order_size_hints() {
+    let (big_send, big) = hydroflow::util::unbounded_channel::<(usize, usize)>();
+    let (other_send, other) = hydroflow::util::unbounded_channel::<(usize, usize)>();
+    let (small_send, small) = hydroflow::util::unbounded_channel::<(usize, usize)>();
+    let (out, mut out_recv) = hydroflow::util::unbounded_channel::<(usize, usize)>();
+
+    for a in 0..10 {
+        big_send.send((a, a % 2)).unwrap();
+    }
+    other_send.send((1, 10)).unwrap();
+    other_send.send((2, 20)).unwrap();
+    small_send.send((1, 1)).unwrap();
+    small_send.send((0, 3)).unwrap();
+
+    let mut flow = datalog!(
+        r#"
+        .input big `source_stream(big)`
+        .input other `source_stream(other)`
+        .input small `source_stream(small)`
+        .output out `for_each(|v| out.send(v).unwrap())`
+
+        .size big 1000000
+        .size small 10
+
+        out(a, c) :- big(a, b), other(c, d), small(b, c), (a > 5).
+        "#
+    );
+    hydroflow::assert_graphvis_snapshots!(flow);
+
+    flow.run_tick();
+
+    let mut out = collect_ready::<Vec<_>, _>(&mut out_recv);
+    out.sort();
+    assert_eq!(&*out, &[(7, 1), (9, 1)]);
+}
+
+#[multiplatform_test]
+pub fn test_typed_relations() {
+    let (edges_send, edges) = hydroflow::util::unbounded_channel::<(u32, u32)>();
+    let (names_send, names) = hydroflow::util::unbounded_channel::<(u32, String)>();
+    let (out, mut out_recv) = hydroflow::util::unbounded_channel::<(String, String)>();
+
+    edges_send.send((1, 2)).unwrap();
+    edges_send.send((2, 3)).unwrap();
+    for (id, name) in [(1, "a"), (2, "b"), (3, "c")] {
+        names_send.send((id, name.to_owned())).unwrap();
+    }
+
+    let mut flow = datalog!(
+        r#"
+        .input edges(src: u32, dst: u32) `source_stream(edges)`
+        .input names(id: u32, name: String) `source_stream(names)`
+        .output out(src: String, dst: String) `for_each(|v| out.send(v).unwrap())`
+
+        .persist path(src: u32, dst: u32)
+
+        path(x, y) :- edges(x, y).
+        path(x, z) :- path(x, y), edges(y, z).
+        out(a, b) :- path(x, y), names(x, a), names(y, b).
+        "#
+    );
+
+    flow.run_tick();
+
+    let mut out = collect_ready::<Vec<_>, _>(&mut out_recv);
+    out.sort();
+    assert_eq!(
+        &*out,
+        &[
+            ("a".to_owned(), "b".to_owned()),
+            ("a".to_owned(), "c".to_owned()),
+            ("b".to_owned(), "c".to_owned()),
+        ]
+    );
+}
+
+#[multiplatform_test]
+pub fn test_provenance() {
+    use hydroflow::datalog_provenance::{ProofStep, Provenance};
+
+    let (edges_send, edges) = hydroflow::util::unbounded_channel::<(usize, usize)>();
+    let provenance = Provenance::default();
+    let recorder = provenance.clone();
+
+    edges_send.send((1, 2)).unwrap();
+    edges_send.send((2, 3)).unwrap();
+
+    let mut flow = datalog!(
+        r#"
+        .input edges `source_stream(edges)`
+        .static nodes `vec![(1,), (2,), (3,), (4,)]`
+        .output unreachable `null()`
+        .output num_paths `null()`
+        .provenance `recorder`
+
+        path(x, y) :- edges(x, y).
+        path(x, z) :- path(x, y), edges(y, z).
+        source(x) :- edges(x, _).
+        unreachable(y) :- source(x), nodes(y), !path(x, y).
+        num_paths(count(y)) :- path(_, y).
+        "#
+    );
+
+    flow.run_tick();
+
+    assert_eq!(
+        provenance.explain("path", &(1, 3)).unwrap().to_string(),
+        "\
+path(1, 3) [rule 1: path(x, z) :- path(x, y), edges(y, z).]
+  path(1, 2) [rule 0: path(x, y) :- edges(x, y).]
+    edges(1, 2) [input]
+  edges(2, 3) [input]
+"
+    );
+    assert_eq!(
+        provenance
+            .explain("unreachable", &(4,))
+            .unwrap()
+            .to_string(),
+        "\
+unreachable(4) [rule 3: unreachable(y) :- source(x), nodes(y), !path(x, y).]
+  source(1) [rule 2: source(x) :- edges(x, _).]
+    edges(1, 2) [input]
+  nodes(4) [input]
+  !path(1, 4) [absent]
+"
+    );
+
+    let num_paths = provenance.explain("num_paths", &(2,)).unwrap();
+    assert!(matches!(
+        num_paths.step,
+        ProofStep::Rule { id: 4, ref premises, .. } if premises.is_empty()
+    ));
+
+    assert_eq!(provenance.explain("path", &(3, 1)), None);
+    assert_eq!(provenance.explain("missing", &(1, 2)), None);
+
+    provenance.clear();
+    assert_eq!(provenance.explain("path", &(1, 3)), None);
+}
+
+#[multiplatform_test]
+pub fn test_provenance_cycles() {
+    use hydroflow::datalog_provenance::{ProofStep, ProofTree, Provenance};
+
+    /// Checks that no fact depends on itself.
+    fn assert_acyclic<'a>(proof: &'a ProofTree, ancestors: &mut Vec<&'a ProofTree>) {
+        assert!(
+            !ancestors
+                .iter()
+                .any(|ancestor| ancestor.relation == proof.relation && ancestor.fact == proof.fact),
+            "{} depends on itself",
+            proof
+        );
+        if let ProofStep::Rule { premises, .. } = &proof.step {
+            ancestors.push(proof);
+            for premise in premises {
+                assert_acyclic(premise, ancestors);
+            }
+            ancestors.pop();
+        }
+    }
+
+    let (edges_send, edges) = hydroflow::util::unbounded_channel::<(usize, usize)>();
+    let provenance = Provenance::default();
+    let recorder = provenance.clone();
+
+    let mut flow = datalog!(
+        r#"
+        .input edges `source_stream(edges)`
+        .output path `null()`
+        .provenance `recorder`
+
+        path(x, y) :- edges(x, y).
+        path(x, z) :- path(x, y), path(y, z).
+        "#
+    );
+
+    // A complete graph, so every path has many derivations, most of them cyclic.
+    for x in 0..8 {
+        for y in 0..8 {
+            if x != y {
+                edges_send.send((x, y)).unwrap();
+            }
+        }
+    }
+    flow.run_tick();
+
+    for x in 0..8 {
+        for y in 0..8 {
+            let proof = provenance.explain("path", &(x, y)).unwrap();
+            assert_acyclic(&proof, &mut Vec::new());
+        }
+    }
+}
+
+#[multiplatform_test]
+pub fn test_generic_and_tuple_typed_relations() {
+    let (edges_send, edges) = hydroflow::util::unbounded_channel::<(u32, Vec<u32>)>();
+    let (names_send, names) = hydroflow::util::unbounded_channel::<(u32, (String, u8))>();
+    let (out, mut out_recv) = hydroflow::util::unbounded_channel::<(Vec<u32>, (String, u8))>();
+
+    edges_send.send((1, vec![2, 3])).unwrap();
+    names_send.send((1, ("a".to_owned(), 7))).unwrap();
+
+    let mut flow = datalog!(
+        r#"
+        .input edges(src: u32, dsts: Vec<u32>) `source_stream(edges)`
+        .input names(id: u32, name: (String, u8)) `source_stream(names)`
+        .output out(dsts: Vec<u32>, name: (String, u8)) `for_each(|v| out.send(v).unwrap())`
+
+        out(d, n) :- edges(x, d), names(x, n).
+        "#
+    );
+
+    flow.run_tick();
+
+    assert_eq!(
+        &*collect_ready::<Vec<_>, _>(&mut out_recv),
+        &[(vec![2, 3], ("a".to_owned(), 7))]
+    );
+}
+
 #[multiplatform_test]
 pub fn test_local_constraints() {
     let (in_send, input) = hydroflow::util::unbounded_channel::<(usize, usize)>();
@@ -0,0 +1,224 @@
+use hydroflow::datalog_runtime::{tuple, DatalogProgram, Tuple};
+use hydroflow::tokio_stream::wrappers::UnboundedReceiverStream;
+use hydroflow::util::collect_ready;
+use multiplatform_test::multiplatform_test;
+
+fn collect_sorted(recv: &mut UnboundedReceiverStream<Tuple>) -> Vec<Tuple> {
+    let mut tuples = collect_ready::<Vec<_>, _>(recv);
+    tuples.sort();
+    tuples
+}
+
+#[multiplatform_test]
+pub fn test_minimal() {
+    let program = DatalogProgram::parse(
+        r#"
+        .input input `source_stream(input)`
+        .output out `for_each(|v| out.send(v).unwrap())`
+
+        out(y, x) :- input(x, y).
+        "#,
+    )
+    .unwrap();
+    assert_eq!(vec!["input"], program.inputs().collect::<Vec<_>>());
+    assert_eq!(vec!["out"], program.outputs().collect::<Vec<_>>());
+
+    let mut instance = program.instantiate();
+    let input = instance.input("input").unwrap();
+    let mut out = instance.take_output("out").unwrap();
+    assert!(instance.take_output("out").is_none());
+
+    input.send(tuple![1, "a"]).unwrap();
+    // Wrong arity, dropped.
+    input.send(tuple![1]).unwrap();
+    instance.hydroflow_mut().run_available();
+    assert_eq!(vec![tuple!["a", 1]], collect_sorted(&mut out));
+
+    // Not persisted.
+    input.send(tuple![2, "b"]).unwrap();
+    instance.hydroflow_mut().run_available();
+    assert_eq!(vec![tuple!["b", 2]], collect_sorted(&mut out));
+}
+
+#[multiplatform_test]
+pub fn test_transitive_closure() {
+    let program = DatalogProgram::parse(
+        r#"
+        .input edges ``
+        .input seed_reachable ``
+        .output reachable ``
+
+        reachable(x) :- seed_reachable(x).
+        reachable(y) :- reachable(x), edges(x, y).
+        "#,
+    )
+    .unwrap();
+    let mut instance = program.instantiate();
+    let edges = instance.input("edges").unwrap();
+    let seed = instance.input("seed_reachable").unwrap();
+    let mut reachable = instance.take_output("reachable").unwrap();
+
+    for (src, dst) in [(1, 2), (2, 3), (3, 4), (4, 2), (5, 6)] {
+        edges.send(tuple![src, dst]).unwrap();
+    }
+    seed.send(tuple![1]).unwrap();
+    instance.hydroflow_mut().run_available();
+    assert_eq!(
+        vec![tuple![1], tuple![2], tuple![3], tuple![4]],
+        collect_sorted(&mut reachable)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_negation_and_predicates() {
+    let program = DatalogProgram::parse(
+        r#"
+        .input ints ``
+        .input excluded ``
+        .output result ``
+
+        result(x, x * 2) :- ints(x), !excluded(x), (x % 2 == 1), less_than(x, limit), ints(limit), (limit > 4).
+        "#,
+    )
+    .unwrap();
+    let mut instance = program.instantiate();
+    let ints = instance.input("ints").unwrap();
+    let excluded = instance.input("excluded").unwrap();
+    let mut result = instance.take_output("result").unwrap();
+
+    for x in 0..6 {
+        ints.send(tuple![x]).unwrap();
+    }
+    excluded.send(tuple![3]).unwrap();
+    instance.hydroflow_mut().run_available();
+    assert_eq!(vec![tuple![1, 2]], collect_sorted(&mut result));
+}
+
+#[multiplatform_test]
+pub fn test_aggregations() {
+    let program = DatalogProgram::parse(
+        r#"
+        .input purchases ``
+        .output totals ``
+
+        totals(user, count(*), count(item), sum(price), min(price), max(price)) :- purchases(user, item, price).
+        "#,
+    )
+    .unwrap();
+    let mut instance = program.instantiate();
+    let purchases = instance.input("purchases").unwrap();
+    let mut totals = instance.take_output("totals").unwrap();
+
+    purchases.send(tuple!["ann", "pen", 3]).unwrap();
+    purchases.send(tuple!["ann", "pen", 5]).unwrap();
+    purchases.send(tuple!["ann", "ink", 2]).unwrap();
+    purchases.send(tuple!["bob", "pad", 7]).unwrap();
+    // Non-integers are skipped by `sum` only.
+    purchases.send(tuple!["cat", "gum", "free"]).unwrap();
+    instance.hydroflow_mut().run_available();
+    assert_eq!(
+        vec![
+            tuple!["ann", 3, 2, 10, 2, 5],
+            tuple!["bob", 1, 1, 7, 7, 7],
+            tuple!["cat", 1, 1, 0, "free", "free"],
+        ],
+        collect_sorted(&mut totals)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_persist_and_next_tick() {
+    let program = DatalogProgram::parse(
+        r#"
+        .input input ``
+        .output seen ``
+        .output delayed ``
+        .persist seen
+
+        seen(x) :- input(x).
+        delayed(x) :+ input(x).
+        "#,
+    )
+    .unwrap();
+    let mut instance = program.instantiate();
+    let input = instance.input("input").unwrap();
+    let mut seen = instance.take_output("seen").unwrap();
+    let mut delayed = instance.take_output("delayed").unwrap();
+
+    input.send(tuple![1]).unwrap();
+    instance.hydroflow_mut().run_tick();
+    assert_eq!(vec![tuple![1]], collect_sorted(&mut seen));
+    assert!(collect_sorted(&mut delayed).is_empty());
+
+    input.send(tuple![2]).unwrap();
+    instance.hydroflow_mut().run_tick();
+    assert_eq!(vec![tuple![1], tuple![2]], collect_sorted(&mut seen));
+    assert_eq!(vec![tuple![1]], collect_sorted(&mut delayed));
+
+    // The subgraph reschedules itself to insert the deferred facts.
+    instance.hydroflow_mut().run_available();
+    assert_eq!(vec![tuple![1], tuple![2]], collect_sorted(&mut seen));
+    assert_eq!(vec![tuple![2]], collect_sorted(&mut delayed));
+}
+
+#[multiplatform_test]
+pub fn test_errors() {
+    let errors = DatalogProgram::parse(
+        r#"
+        .input edges ``
+        .output out ``
+
+        out(x, y) :- edges(x).
+        out(x) :- edges(x, y), missing(y).
+        "#,
+    )
+    .unwrap_err();
+    let messages = errors
+        .iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            "5:16: Variables `y` in the rule head must be bound by a relation in the rule body",
+            "6:19: Relation `edges` has 2 fields here, but 1 fields elsewhere",
+            "6:32: Relation `missing` is neither an `.input` nor derived by any rule",
+            "6:9: Relation `out` has 1 fields here, but 2 fields elsewhere",
+        ],
+        messages
+    );
+
+    let errors = DatalogProgram::parse(
+        r#"
+        .input nodes ``
+        .output winning ``
+
+        winning(x) :- nodes(x), !losing(x).
+        losing(x) :- nodes(x), !winning(x).
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(2, errors.len());
+    assert_eq!(
+        "5:34: Program is not stratifiable: `winning` depends on `losing` through negation or aggregation, inside the recursive cycle `winning` -> `losing` -> `winning`",
+        errors[0].to_string()
+    );
+
+    let errors = DatalogProgram::parse(
+        r#"
+        .input edges(src: u32, dst: u32) ``
+        .output out ``
+
+        out(x) :- edges(x).
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        vec!["5:19: Relation `edges` has 1 fields here, but 2 fields elsewhere"],
+        errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>()
+    );
+
+    assert!(DatalogProgram::parse("out(x) :- ").is_err());
+}
@@ -8,6 +8,7 @@ use std::net::{Ipv4Addr, SocketAddr};
 use std::time::Duration;
 
 use bytes::Bytes;
+use futures::StreamExt;
 use hydroflow::scheduled::graph::Hydroflow;
 use hydroflow::util::{collect_ready_async, ready_iter, tcp_lines};
 use hydroflow::{assert_graphvis_snapshots, hydroflow_syntax, rassert, rassert_eq};
@@ -536,3 +537,75 @@ async fn asynctest_udp() {
     let seen: Vec<_> = collect_ready_async(rx_out).await;
     assert_eq!(&["Hello".to_owned()], &*seen);
 }
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn asynctest_run_until_flushes_dest_sink() {
+    let output = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    // Slow sink, which would not be flushed before shutdown without a graceful drain.
+    let sink = futures::sink::unfold(output.clone(), |output, item: usize| async move {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        output.borrow_mut().push(item);
+        Ok::<_, ()>(output)
+    });
+    let sink = Box::pin(sink);
+
+    let mut flow = hydroflow_syntax! {
+        source_iter(0..10) -> dest_sink(sink);
+    };
+    flow.run_until_predicate(|flow| 1 <= flow.current_tick())
+        .await;
+
+    assert_eq!(1, flow.current_tick());
+    assert_eq!(&(0..10).collect::<Vec<_>>(), &*output.borrow());
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn asynctest_run_until_shutdown() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let (output_send, mut output_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let (shutdown_send, shutdown_recv) = tokio::sync::oneshot::channel::<()>();
+
+    let mut flow = hydroflow_syntax! {
+        source_stream(input_recv) -> map(|x| 2 * x) -> for_each(|x| output_send.send(x).unwrap());
+    };
+    let driver = async {
+        input_send.send(1).unwrap();
+        input_send.send(2).unwrap();
+        let out = vec![
+            output_recv.next().await.unwrap(),
+            output_recv.next().await.unwrap(),
+        ];
+        shutdown_send.send(()).unwrap();
+        out
+    };
+    let ((), out) = tokio::join!(
+        flow.run_until(async {
+            shutdown_recv.await.unwrap();
+        }),
+        driver
+    );
+    assert_eq!(&[2, 4], &*out);
+
+    // Operators, including the input stream, are dropped on shutdown.
+    assert!(input_send.send(3).is_err());
+    flow.run_available();
+    assert_eq!(None, output_recv.next().await);
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn asynctest_run_until_finishes_tick() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let (output_send, mut output_recv) = hydroflow::util::unbounded_channel::<usize>();
+
+    let mut flow = hydroflow_syntax! {
+        source_stream(input_recv) -> map(|x| 2 * x) -> for_each(|x| output_send.send(x).unwrap());
+    };
+    input_send.send(1).unwrap();
+    input_send.send(2).unwrap();
+    // Shutdown requested immediately, but the first tick still runs to completion.
+    flow.run_until(async {}).await;
+
+    assert_eq!(1, flow.current_tick());
+    let out: Vec<_> = collect_ready_async(&mut output_recv).await;
+    assert_eq!(&[2, 4], &*out);
+}
@@ -0,0 +1,69 @@
+use std::cell::Cell;
+
+use futures::StreamExt;
+use hydroflow::util::collect_ready;
+use hydroflow::{assert_graphvis_snapshots, hydroflow_syntax};
+use multiplatform_test::multiplatform_test;
+
+#[multiplatform_test]
+pub fn test_bounded_source_stream() {
+    let polled = &Cell::new(0);
+    let consumed = &Cell::new(0);
+    let max_outstanding = &Cell::new(0);
+    let stream = futures::stream::iter(0..100).inspect(|_| polled.set(polled.get() + 1));
+
+    let (output_send, mut output_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let mut df = hydroflow_syntax! {
+        source_stream(stream)
+            -> bounded(10)
+            -> for_each(|x| {
+                max_outstanding.set(max_outstanding.get().max(polled.get() - consumed.get()));
+                consumed.set(consumed.get() + 1);
+                output_send.send(x).unwrap();
+            });
+    };
+    assert_graphvis_snapshots!(df);
+    df.run_available();
+
+    assert_eq!(
+        (0..100).collect::<Vec<_>>(),
+        collect_ready::<Vec<_>, _>(&mut output_recv)
+    );
+    assert_eq!(10, max_outstanding.get());
+    // Backpressure does not start extra ticks.
+    assert_eq!(1, df.current_tick());
+}
+
+#[multiplatform_test]
+pub fn test_bounded_join() {
+    let (lhs_send, lhs_recv) = hydroflow::util::unbounded_channel::<(usize, usize)>();
+    let (rhs_send, rhs_recv) = hydroflow::util::unbounded_channel::<(usize, char)>();
+    let (output_send, mut output_recv) = hydroflow::util::unbounded_channel();
+    let mut df = hydroflow_syntax! {
+        source_stream(lhs_recv) -> bounded(4) -> [0]my_join;
+        source_stream(rhs_recv) -> [1]my_join;
+        my_join = join::<'tick, 'static>() -> for_each(|x| output_send.send(x).unwrap());
+    };
+
+    let bounded_handoffs = df
+        .handoff_ids()
+        .filter(|&handoff_id| df.handoff_capacity(handoff_id).is_some())
+        .collect::<Vec<_>>();
+    assert_eq!(1, bounded_handoffs.len());
+    assert_eq!(Some(4), df.handoff_capacity(bounded_handoffs[0]));
+
+    rhs_send.send((0, 'a')).unwrap();
+    rhs_send.send((1, 'b')).unwrap();
+    for x in 0..20 {
+        lhs_send.send((x % 2, x)).unwrap();
+    }
+    df.run_available();
+
+    let mut output = collect_ready::<Vec<_>, _>(&mut output_recv);
+    output.sort();
+    let mut expected: Vec<_> = (0..20)
+        .map(|x| (x % 2, (x, if 0 == x % 2 { 'a' } else { 'b' })))
+        .collect();
+    expected.sort();
+    assert_eq!(expected, output);
+}
@@ -0,0 +1,67 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::time::Duration;
+
+use hydroflow::hydroflow_syntax;
+use hydroflow::util::collect_ready_async;
+use multiplatform_test::multiplatform_test;
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_batch_max_items() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<Vec<usize>>();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> batch(3, Duration::from_secs(60))
+            -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    for x in 0..7 {
+        input_send.send(x).unwrap();
+    }
+    df.run_available();
+    assert_eq!(
+        vec![vec![0, 1, 2], vec![3, 4, 5]],
+        collect_ready_async::<Vec<_>, _>(&mut out_recv).await
+    );
+
+    // Batches span ticks.
+    input_send.send(7).unwrap();
+    df.run_available();
+    input_send.send(8).unwrap();
+    df.run_available();
+    assert_eq!(
+        vec![vec![6, 7, 8]],
+        collect_ready_async::<Vec<_>, _>(&mut out_recv).await
+    );
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_batch_max_delay() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<Vec<usize>>();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> batch(10, Duration::from_millis(50))
+            -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    input_send.send(0).unwrap();
+    input_send.send(1).unwrap();
+    df.run_available();
+    assert_eq!(
+        Vec::<Vec<usize>>::new(),
+        collect_ready_async::<Vec<_>, _>(&mut out_recv).await
+    );
+
+    // The timer wakes the operator with no further input.
+    tokio::time::timeout(Duration::from_millis(200), df.run_async())
+        .await
+        .expect_err("Expected time out");
+    assert_eq!(
+        vec![vec![0, 1]],
+        collect_ready_async::<Vec<_>, _>(&mut out_recv).await
+    );
+}
@@ -0,0 +1,159 @@
+use hydroflow::scheduled::checkpoint::{Checkpoint, CheckpointError};
+use hydroflow::scheduled::graph::Hydroflow;
+use hydroflow::util::{collect_ready, unbounded_channel};
+use hydroflow::{hydroflow_syntax, hydroflow_syntax_checkpointed};
+use multiplatform_test::multiplatform_test;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+fn make_graph(
+    input_recv: UnboundedReceiverStream<(u32, u32)>,
+    output_send: UnboundedSender<(u32, u32, usize)>,
+) -> Hydroflow<'static> {
+    hydroflow_syntax_checkpointed! {
+        inp = source_stream(input_recv) -> tee();
+
+        // Running sum per key.
+        sums = inp -> fold_keyed::<'static>(|| 0, |acc: &mut u32, val| *acc += val);
+        // Number of distinct values seen per key.
+        counts = inp
+            -> unique::<'static>()
+            -> map(|(k, _v)| (k, ()))
+            -> fold_keyed::<'static, u32, usize>(|| 0, |acc: &mut usize, ()| *acc += 1);
+        sums -> [0]joined;
+        counts -> [1]joined;
+        joined = join::<'tick, 'tick>()
+            -> map(|(k, (sum, count))| (k, sum, count))
+            -> defer_tick()
+            -> for_each(|x| output_send.send(x).unwrap());
+    }
+}
+
+#[multiplatform_test]
+pub fn test_checkpoint_restore() {
+    fn collect_sorted(
+        recv: &mut UnboundedReceiverStream<(u32, u32, usize)>,
+    ) -> Vec<(u32, u32, usize)> {
+        let mut out = collect_ready::<Vec<_>, _>(recv);
+        out.sort_unstable();
+        out
+    }
+
+    // Reference instance, runs uninterrupted.
+    let (ref_input_send, ref_input_recv) = unbounded_channel();
+    let (ref_output_send, mut ref_output_recv) = unbounded_channel();
+    let mut ref_hf = make_graph(ref_input_recv, ref_output_send);
+
+    // Instance which will be checkpointed.
+    let (input_send, input_recv) = unbounded_channel();
+    let (output_send, mut output_recv) = unbounded_channel();
+    let mut hf = make_graph(input_recv, output_send);
+
+    for (k, v) in [(1, 10), (1, 10), (2, 20), (1, 11)] {
+        ref_input_send.send((k, v)).unwrap();
+        input_send.send((k, v)).unwrap();
+    }
+    ref_hf.run_tick();
+    hf.run_tick();
+    assert_eq!(
+        collect_sorted(&mut ref_output_recv),
+        collect_sorted(&mut output_recv)
+    );
+
+    // Output is deferred, so it is in a handoff when the checkpoint is taken.
+    let checkpoint = hf.checkpoint().unwrap();
+    let checkpoint_json = serde_json::to_string(&checkpoint).unwrap();
+    drop(hf);
+
+    let (input_send, input_recv) = unbounded_channel();
+    let (output_send, mut output_recv) = unbounded_channel();
+    let mut hf = make_graph(input_recv, output_send);
+    let checkpoint: Checkpoint = serde_json::from_str(&checkpoint_json).unwrap();
+    hf.restore(&checkpoint).unwrap();
+    assert_eq!(ref_hf.current_tick(), hf.current_tick());
+
+    for (k, v) in [(1, 12), (2, 20), (3, 30)] {
+        ref_input_send.send((k, v)).unwrap();
+        input_send.send((k, v)).unwrap();
+    }
+    ref_hf.run_tick();
+    hf.run_tick();
+    ref_hf.run_tick();
+    hf.run_tick();
+
+    let ref_output = collect_sorted(&mut ref_output_recv);
+    assert_eq!(
+        &[(1, 31, 2), (1, 43, 3), (2, 20, 1), (2, 40, 1), (3, 30, 1)],
+        &*ref_output
+    );
+    assert_eq!(ref_output, collect_sorted(&mut output_recv));
+}
+
+#[multiplatform_test]
+pub fn test_checkpoint_top_k() {
+    fn make_graph(
+        input_recv: UnboundedReceiverStream<(u32, u32)>,
+        output_send: UnboundedSender<Vec<(u32, u32)>>,
+    ) -> Hydroflow<'static> {
+        hydroflow_syntax_checkpointed! {
+            inp = source_stream(input_recv) -> tee();
+            inp -> top_k::<'static>(2, |&(_k, v)| v)
+                -> fold(Vec::new, Vec::push)
+                -> for_each(|x| output_send.send(x).unwrap());
+            inp -> top_k_keyed::<'static>(1, |&v| v) -> null();
+        }
+    }
+
+    let (input_send, input_recv) = unbounded_channel();
+    let (output_send, _output_recv) = unbounded_channel();
+    let mut hf = make_graph(input_recv, output_send);
+    for x in [(1, 10), (2, 30), (1, 20)] {
+        input_send.send(x).unwrap();
+    }
+    hf.run_tick();
+    let checkpoint = hf.checkpoint().unwrap();
+    drop(hf);
+
+    let (input_send, input_recv) = unbounded_channel();
+    let (output_send, mut output_recv) = unbounded_channel();
+    let mut hf = make_graph(input_recv, output_send);
+    hf.restore(&checkpoint).unwrap();
+    input_send.send((3, 25)).unwrap();
+    hf.run_tick();
+    assert_eq!(
+        &[vec![(2, 30), (3, 25)]],
+        &*collect_ready::<Vec<_>, _>(&mut output_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_checkpoint_not_checkpointable() {
+    let (input_send, input_recv) = unbounded_channel::<u32>();
+    let mut hf = hydroflow_syntax! {
+        source_stream(input_recv) -> unique::<'static>() -> null();
+    };
+    input_send.send(1).unwrap();
+    hf.run_available();
+
+    assert!(matches!(
+        hf.checkpoint(),
+        Err(CheckpointError::StateNotCheckpointable(_))
+    ));
+}
+
+#[multiplatform_test]
+pub fn test_checkpoint_graph_mismatch() {
+    let (_input_send, input_recv) = unbounded_channel::<u32>();
+    let hf = hydroflow_syntax_checkpointed! {
+        source_stream(input_recv) -> unique::<'static>() -> null();
+    };
+    let checkpoint = hf.checkpoint().unwrap();
+
+    let mut hf = hydroflow_syntax_checkpointed! {
+        source_iter([1, 2, 3]) -> null();
+    };
+    assert!(matches!(
+        hf.restore(&checkpoint),
+        Err(CheckpointError::GraphMismatch(_))
+    ));
+}
@@ -0,0 +1,134 @@
+use hydroflow::hydroflow_syntax;
+use hydroflow::util::{collect_ready, unbounded_channel};
+use multiplatform_test::multiplatform_test;
+
+#[multiplatform_test]
+pub fn test_chrome_trace() {
+    let (input_send, input_recv) = unbounded_channel::<u32>();
+    let (output_send, mut output_recv) = unbounded_channel::<u32>();
+    let mut hf = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> fold::<'tick>(|| 0, |acc: &mut u32, x| *acc += x)
+            -> for_each(|x| output_send.send(x).unwrap());
+    };
+    assert!(hf.finish_chrome_trace().is_none());
+
+    hf.start_chrome_trace();
+    input_send.send(1).unwrap();
+    hf.run_tick();
+    input_send.send(2).unwrap();
+    hf.run_tick();
+    assert_eq!(&[1, 2], &*collect_ready::<Vec<_>, _>(&mut output_recv));
+
+    let trace = hf.finish_chrome_trace().unwrap();
+    assert!(hf.finish_chrome_trace().is_none());
+
+    // Round trip through JSON.
+    let trace: serde_json::Value = serde_json::from_str(&trace.to_json_string()).unwrap();
+    let events = trace["traceEvents"].as_array().unwrap();
+    let names_with_ph = |ph: &str| -> Vec<String> {
+        events
+            .iter()
+            .filter(|event| event["ph"] == ph)
+            .map(|event| event["name"].as_str().unwrap().to_owned())
+            .collect()
+    };
+
+    // Scheduler track and one track per subgraph.
+    let track_names: Vec<_> = events
+        .iter()
+        .filter(|event| event["ph"] == "M")
+        .map(|event| event["args"]["name"].as_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(
+        &[
+            "Hydroflow scheduler",
+            "Subgraph 0: source_stream",
+            "Subgraph 1: fold -> for_each"
+        ],
+        &*track_names
+    );
+
+    let complete_names = names_with_ph("X");
+    assert_eq!(
+        2,
+        complete_names
+            .iter()
+            .filter(|name| name.starts_with("Tick "))
+            .count()
+    );
+    assert!(complete_names.iter().any(|name| name == "Stratum 0"));
+    assert!(complete_names.iter().any(|name| name == "Stratum 1"));
+    assert_eq!(
+        2,
+        complete_names
+            .iter()
+            .filter(|&name| name == "1: fold -> for_each")
+            .count()
+    );
+
+    let counter_names = names_with_ph("C");
+    assert!(!counter_names.is_empty());
+    assert!(counter_names
+        .iter()
+        .all(|name| name == "Handoff 0: source_stream -> fold"));
+
+    // Subgraph events are on their own tracks, and nested within their tick.
+    for event in events.iter().filter(|event| event["cat"] == "subgraph") {
+        assert_ne!(0, event["tid"].as_u64().unwrap());
+        let tick = event["args"]["tick"].as_u64().unwrap();
+        let tick_event = events
+            .iter()
+            .find(|e| e["cat"] == "tick" && e["args"]["tick"] == tick)
+            .unwrap();
+        let (ts, dur) = (
+            event["ts"].as_f64().unwrap(),
+            event["dur"].as_f64().unwrap(),
+        );
+        let (tick_ts, tick_dur) = (
+            tick_event["ts"].as_f64().unwrap(),
+            tick_event["dur"].as_f64().unwrap(),
+        );
+        assert!(tick_ts <= ts + 1e-3 && ts + dur <= tick_ts + tick_dur + 1e-3);
+    }
+}
+
+#[multiplatform_test]
+pub fn test_chrome_trace_removed() {
+    let mut hf = hydroflow_syntax! {
+        source_iter(0..3)
+            -> fold::<'tick>(|| 0, |acc: &mut u32, x| *acc += x)
+            -> null();
+    };
+
+    hf.start_chrome_trace();
+    hf.run_available();
+    // Remove everything which has recorded events.
+    for sg_id in hf.subgraph_ids().collect::<Vec<_>>() {
+        hf.remove_subgraph(sg_id);
+    }
+    for handoff_id in hf.handoff_ids().collect::<Vec<_>>() {
+        hf.remove_handoff(handoff_id);
+    }
+    let trace = hf.finish_chrome_trace().unwrap();
+
+    let track_names: Vec<_> = trace
+        .trace_events
+        .iter()
+        .filter(|event| "M" == event.ph)
+        .map(|event| event.args["name"].as_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(
+        &[
+            "Hydroflow scheduler",
+            "Subgraph 0: source_iter (removed)",
+            "Subgraph 1: fold -> null (removed)"
+        ],
+        &*track_names
+    );
+    assert!(trace
+        .trace_events
+        .iter()
+        .filter(|event| "handoff" == event.cat)
+        .all(|event| event.name == "Handoff 0: source_iter -> fold (removed)"));
+}
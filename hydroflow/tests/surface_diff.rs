@@ -0,0 +1,171 @@
+use std::collections::BTreeSet;
+
+use hydroflow::scheduled::graph::Hydroflow;
+use hydroflow::util::{collect_ready, Persistence};
+use hydroflow::{hydroflow_syntax, hydroflow_syntax_checkpointed};
+use multiplatform_test::multiplatform_test;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+#[multiplatform_test]
+pub fn test_join_diff_retraction() {
+    let (edges_send, edges_recv) =
+        hydroflow::util::unbounded_channel::<Persistence<(usize, usize)>>();
+    let (out_send, mut out_recv) =
+        hydroflow::util::unbounded_channel::<((usize, (usize, usize)), isize)>();
+
+    // Two-hop paths, maintained incrementally.
+    let mut df = hydroflow_syntax! {
+        edges = source_stream(edges_recv)
+            -> map(|event| match event {
+                Persistence::Persist(x) => (x, 1),
+                Persistence::Delete(x) => (x, -1),
+            })
+            -> tee();
+        edges -> map(|((a, b), diff)| ((b, a), diff)) -> [0]paths;
+        edges -> [1]paths;
+        paths = join_diff() -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    edges_send.send(Persistence::Persist((1, 2))).unwrap();
+    edges_send.send(Persistence::Persist((2, 3))).unwrap();
+    edges_send.send(Persistence::Persist((2, 4))).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::from([((2, (1, 3)), 1), ((2, (1, 4)), 1)]),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+
+    // Deleting a base fact retracts every derived path through it.
+    edges_send.send(Persistence::Delete((1, 2))).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::from([((2, (1, 3)), -1), ((2, (1, 4)), -1)]),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+
+    // An insert and delete in the same tick cancel out.
+    edges_send.send(Persistence::Persist((0, 2))).unwrap();
+    edges_send.send(Persistence::Delete((0, 2))).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::<((usize, (usize, usize)), isize)>::new(),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_fold_keyed_diff_retraction() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<((&str, u32), isize)>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<((&str, u32), isize)>();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> fold_keyed_diff(|| 0, |sum: &mut u32, val: u32| *sum += val)
+            -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    input_send.send((("toy", 1), 1)).unwrap();
+    input_send.send((("toy", 2), 1)).unwrap();
+    input_send.send((("shoe", 11), 1)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::from([(("toy", 3), 1), (("shoe", 11), 1)]),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+
+    input_send.send((("toy", 1), -1)).unwrap();
+    input_send.send((("shoe", 11), -1)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::from([(("toy", 3), -1), (("toy", 2), 1), (("shoe", 11), -1)]),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+
+    // No input, no output.
+    df.run_tick();
+    assert_eq!(
+        Vec::<((&str, u32), isize)>::new(),
+        collect_ready::<Vec<_>, _>(&mut out_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_unique_difference_diff() {
+    let (pos_send, pos_recv) = hydroflow::util::unbounded_channel::<(u32, isize)>();
+    let (neg_send, neg_recv) = hydroflow::util::unbounded_channel::<(u32, isize)>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<(u32, isize)>();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(pos_recv) -> unique_diff() -> [pos]diff;
+        source_stream(neg_recv) -> [neg]diff;
+        diff = difference_diff() -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    pos_send.send((1, 1)).unwrap();
+    pos_send.send((1, 1)).unwrap();
+    pos_send.send((2, 1)).unwrap();
+    neg_send.send((2, 1)).unwrap();
+    df.run_tick();
+    assert_eq!(vec![(1, 1)], collect_ready::<Vec<_>, _>(&mut out_recv));
+
+    neg_send.send((2, -1)).unwrap();
+    neg_send.send((1, 1)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::from([(1, -1), (2, 1)]),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+
+    pos_send.send((2, -1)).unwrap();
+    df.run_tick();
+    assert_eq!(vec![(2, -1)], collect_ready::<Vec<_>, _>(&mut out_recv));
+}
+
+#[multiplatform_test]
+pub fn test_diff_checkpoint_restore() {
+    fn make_graph(
+        input_recv: UnboundedReceiverStream<(u32, isize)>,
+        out_send: UnboundedSender<(u32, isize)>,
+    ) -> Hydroflow<'static> {
+        hydroflow_syntax_checkpointed! {
+            inp = source_stream(input_recv) -> tee();
+            inp -> unique_diff() -> for_each(|x| out_send.send(x).unwrap());
+
+            // The other differential operators' states must be checkpointable too.
+            keyed = inp -> map(|(x, diff)| ((x, x), diff)) -> tee();
+            keyed -> [0]joined;
+            keyed -> [1]joined;
+            joined = join_diff() -> null();
+            inp -> [pos]diff;
+            inp -> [neg]diff;
+            diff = difference_diff() -> null();
+            keyed -> fold_keyed_diff(|| 0, |sum: &mut u32, val: u32| *sum += val) -> null();
+        }
+    }
+
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel();
+    let mut df = make_graph(input_recv, out_send);
+    input_send.send((1, 2)).unwrap();
+    df.run_tick();
+    assert_eq!(&[(1, 1)], &*collect_ready::<Vec<_>, _>(&mut out_recv));
+    let checkpoint = df.checkpoint().unwrap();
+    drop(df);
+
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel();
+    let mut df = make_graph(input_recv, out_send);
+    df.restore(&checkpoint).unwrap();
+
+    // The restored multiplicity of 2 needs two retractions.
+    input_send.send((1, -1)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        Vec::<(u32, isize)>::new(),
+        collect_ready::<Vec<_>, _>(&mut out_recv)
+    );
+    input_send.send((1, -1)).unwrap();
+    df.run_tick();
+    assert_eq!(&[(1, -1)], &*collect_ready::<Vec<_>, _>(&mut out_recv));
+}
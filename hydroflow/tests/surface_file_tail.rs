@@ -0,0 +1,122 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use hydroflow::hydroflow_syntax;
+use hydroflow::scheduled::graph::Hydroflow;
+use hydroflow::util::collect_ready_async;
+use multiplatform_test::multiplatform_test;
+
+/// Runs the flow long enough for the file to be polled.
+async fn run_for_a_bit(df: &mut Hydroflow<'_>) {
+    tokio::time::timeout(Duration::from_millis(300), df.run_async())
+        .await
+        .expect_err("Expected time out");
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "hydroflow-surface-file-tail-{}-{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn append(path: &Path, data: &str) {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .unwrap();
+    file.write_all(data.as_bytes()).unwrap();
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_file_tail_across_ticks() {
+    let dir = temp_dir("ticks");
+    let path = dir.join("app.log");
+    append(&path, "skipped\nfirst\n");
+
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<(usize, u64, String)>();
+    let mut df = hydroflow_syntax! {
+        source_file_tail(&path, 8)
+            -> for_each(|(offset, line)| out_send.send((context.current_tick(), offset, line)).unwrap());
+    };
+
+    run_for_a_bit(&mut df).await;
+    let out: Vec<_> = collect_ready_async(&mut out_recv).await;
+    assert_eq!(
+        vec![(14, "first".to_owned())],
+        out.iter()
+            .map(|(_, offset, line)| (*offset, line.clone()))
+            .collect::<Vec<_>>()
+    );
+    let first_tick = out[0].0;
+
+    // An incomplete line is held back until it is completed.
+    append(&path, "second\nthi");
+    run_for_a_bit(&mut df).await;
+    append(&path, "rd\n");
+    run_for_a_bit(&mut df).await;
+    let out: Vec<_> = collect_ready_async(&mut out_recv).await;
+    assert_eq!(
+        vec![(21, "second".to_owned()), (27, "third".to_owned())],
+        out.iter()
+            .map(|(_, offset, line)| (*offset, line.clone()))
+            .collect::<Vec<_>>()
+    );
+    assert!(out.iter().all(|&(tick, _, _)| first_tick < tick));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_file_tail_truncation() {
+    let dir = temp_dir("truncation");
+    let path = dir.join("app.log");
+    append(&path, "a\nb\n");
+
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<String>();
+    let mut df = hydroflow_syntax! {
+        source_file_tail(&path, 0) -> for_each(|(_, line)| out_send.send(line).unwrap());
+    };
+    run_for_a_bit(&mut df).await;
+
+    std::fs::write(&path, "c\n").unwrap();
+    run_for_a_bit(&mut df).await;
+
+    assert_eq!(
+        vec!["a", "b", "c"],
+        collect_ready_async::<Vec<_>, _>(&mut out_recv).await
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_file_tail_rename_rotation() {
+    let dir = temp_dir("rename");
+    let path = dir.join("app.log");
+    append(&path, "a\n");
+
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<String>();
+    let mut df = hydroflow_syntax! {
+        source_file_tail(&path, 0) -> for_each(|(_, line)| out_send.send(line).unwrap());
+    };
+    run_for_a_bit(&mut df).await;
+
+    append(&path, "b\n");
+    std::fs::rename(&path, dir.join("app.log.1")).unwrap();
+    append(&path, "c\n");
+    run_for_a_bit(&mut df).await;
+
+    assert_eq!(
+        vec!["a", "b", "c"],
+        collect_ready_async::<Vec<_>, _>(&mut out_recv).await
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
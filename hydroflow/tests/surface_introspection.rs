@@ -0,0 +1,115 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+#[cfg(feature = "introspection_server")]
+use std::net::{Ipv4Addr, SocketAddr};
+
+#[cfg(feature = "introspection_server")]
+use futures::StreamExt;
+use hydroflow::hydroflow_syntax_checkpointed;
+use hydroflow::scheduled::graph::Hydroflow;
+use multiplatform_test::multiplatform_test;
+#[cfg(feature = "introspection_server")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "introspection_server")]
+use tokio::net::TcpStream;
+
+#[cfg(feature = "introspection_server")]
+async fn http_get(addr: SocketAddr, path: &str, host: &str) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(format!("GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, host).as_bytes())
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[cfg(feature = "introspection_server")]
+fn body(response: &str) -> &str {
+    response.split_once("\r\n\r\n").unwrap().1
+}
+
+#[multiplatform_test]
+pub fn test_introspect() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let mut df: Hydroflow = hydroflow_syntax_checkpointed! {
+        source_stream(input_recv) -> unique::<'static>() -> null();
+    };
+    for x in 0..10 {
+        input_send.send(x).unwrap();
+    }
+    df.run_available();
+
+    let snapshot = df.introspect(false);
+    assert_eq!(df.current_tick(), snapshot.current_tick);
+    assert_eq!(df.handoff_ids().count(), snapshot.handoffs.len());
+    assert_eq!(df.subgraph_ids().count(), snapshot.subgraphs.len());
+    let unique_sg = snapshot
+        .subgraphs
+        .values()
+        .find(|sg_introspection| sg_introspection.label.contains("unique"))
+        .unwrap();
+    assert_eq!(1, unique_sg.states.len());
+    assert_eq!(None, unique_sg.states[0].size_bytes);
+
+    let snapshot = df.introspect(true);
+    let unique_sg = snapshot
+        .subgraphs
+        .values()
+        .find(|sg_introspection| sg_introspection.label.contains("unique"))
+        .unwrap();
+    assert!(0 < unique_sg.states[0].size_bytes.unwrap());
+}
+
+#[cfg(feature = "introspection_server")]
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_serve_introspection() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let mut df: Hydroflow = hydroflow_syntax_checkpointed! {
+        source_stream(input_recv) -> unique::<'static>() -> null();
+    };
+    df.serve_introspection((Ipv4Addr::UNSPECIFIED, 0), false)
+        .await
+        .unwrap_err();
+    let addr = df
+        .serve_introspection((Ipv4Addr::LOCALHOST, 0), false)
+        .await
+        .unwrap();
+
+    input_send.send(0).unwrap();
+    df.run_available();
+
+    let status = http_get(addr, "/status", "localhost").await;
+    assert!(status.starts_with("HTTP/1.1 200 OK\r\n"), "{}", status);
+    let status: serde_json::Value = serde_json::from_str(body(&status)).unwrap();
+    assert_eq!(df.current_tick() as u64, status["current_tick"]);
+    assert!(status["subgraphs"]["0"]["label"].is_string(), "{}", status);
+
+    let mermaid = http_get(addr, "/graph.mermaid", "localhost").await;
+    assert!(
+        body(&mermaid).contains("unique::&lt;'static&gt;()"),
+        "{}",
+        mermaid
+    );
+
+    let not_found = http_get(addr, "/nonexistent", "localhost").await;
+    assert!(not_found.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+    let forbidden = http_get(addr, "/status", "example.com").await;
+    assert!(forbidden.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+
+    let (mut websocket, _response) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr))
+        .await
+        .unwrap();
+    let first: serde_json::Value =
+        serde_json::from_str(websocket.next().await.unwrap().unwrap().to_text().unwrap()).unwrap();
+    assert_eq!(df.current_tick() as u64, first["current_tick"]);
+
+    input_send.send(1).unwrap();
+    df.run_available();
+    let second: serde_json::Value =
+        serde_json::from_str(websocket.next().await.unwrap().unwrap().to_text().unwrap()).unwrap();
+    assert_eq!(df.current_tick() as u64, second["current_tick"]);
+    assert!(first["current_tick"].as_u64() < second["current_tick"].as_u64());
+}
@@ -0,0 +1,127 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use hydroflow::hydroflow_syntax;
+use hydroflow::scheduled::graph::Hydroflow;
+use hydroflow::util::ready_iter;
+use multiplatform_test::multiplatform_test;
+use tokio::sync::oneshot;
+
+/// Runs the flow, including any spawned tasks, until no more progress is made.
+async fn run_for_a_bit(df: &mut Hydroflow<'_>) {
+    tokio::time::timeout(Duration::from_millis(50), df.run_async())
+        .await
+        .expect_err("Expected time out");
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_map_async_ordered() {
+    let (senders, receivers): (Vec<_>, Vec<_>) = (0..3).map(|_| oneshot::channel::<()>()).unzip();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let started = Rc::new(Cell::new(0));
+    let started_inner = Rc::clone(&started);
+
+    let mut df = hydroflow_syntax! {
+        source_iter(receivers.into_iter().enumerate())
+            -> map_async(|(i, recv): (usize, oneshot::Receiver<()>)| {
+                started_inner.set(started_inner.get() + 1);
+                async move {
+                    recv.await.unwrap();
+                    i
+                }
+            }, 2)
+            -> for_each(|x| out_send.send(x).unwrap());
+    };
+    let mut senders = senders.into_iter().map(Some).collect::<Vec<_>>();
+
+    run_for_a_bit(&mut df).await;
+    assert_eq!(
+        2,
+        started.get(),
+        "Expected only `max_in_flight` futures to start."
+    );
+
+    // Completes out of order, so is held until the first completes, and still counts against
+    // `max_in_flight`.
+    senders[1].take().unwrap().send(()).unwrap();
+    run_for_a_bit(&mut df).await;
+    assert_eq!(
+        Vec::<usize>::new(),
+        ready_iter(&mut out_recv).collect::<Vec<_>>()
+    );
+    assert_eq!(2, started.get());
+
+    senders[0].take().unwrap().send(()).unwrap();
+    run_for_a_bit(&mut df).await;
+    assert_eq!(vec![0, 1], ready_iter(&mut out_recv).collect::<Vec<_>>());
+    assert_eq!(3, started.get());
+
+    senders[2].take().unwrap().send(()).unwrap();
+    run_for_a_bit(&mut df).await;
+    assert_eq!(vec![2], ready_iter(&mut out_recv).collect::<Vec<_>>());
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_map_async_unordered() {
+    let (senders, receivers): (Vec<_>, Vec<_>) = (0..3).map(|_| oneshot::channel::<()>()).unzip();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<usize>();
+
+    let mut df = hydroflow_syntax! {
+        source_iter(receivers.into_iter().enumerate())
+            -> map_async_unordered(|(i, recv): (usize, oneshot::Receiver<()>)| async move {
+                recv.await.unwrap();
+                i
+            }, 3)
+            -> for_each(|x| out_send.send(x).unwrap());
+    };
+    let mut senders = senders.into_iter().map(Some).collect::<Vec<_>>();
+
+    run_for_a_bit(&mut df).await;
+    assert_eq!(
+        Vec::<usize>::new(),
+        ready_iter(&mut out_recv).collect::<Vec<_>>()
+    );
+
+    senders[2].take().unwrap().send(()).unwrap();
+    run_for_a_bit(&mut df).await;
+    assert_eq!(vec![2], ready_iter(&mut out_recv).collect::<Vec<_>>());
+
+    // Outputs completed before the same tick are emitted in completion order.
+    senders[1].take().unwrap().send(()).unwrap();
+    senders[0].take().unwrap().send(()).unwrap();
+    run_for_a_bit(&mut df).await;
+    assert_eq!(vec![1, 0], ready_iter(&mut out_recv).collect::<Vec<_>>());
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_map_async_later_tick() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<(usize, usize)>();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> map_async(|x| async move { x * 10 }, 1)
+            -> for_each(|x| out_send.send((context.current_tick(), x)).unwrap());
+    };
+
+    input_send.send(1).unwrap();
+    input_send.send(2).unwrap();
+    df.run_available();
+    // No tasks are spawned by the synchronous `run_available`.
+    assert_eq!(
+        Vec::<(usize, usize)>::new(),
+        ready_iter(&mut out_recv).collect::<Vec<_>>()
+    );
+
+    let tick = df.current_tick();
+    run_for_a_bit(&mut df).await;
+    let out = ready_iter(&mut out_recv).collect::<Vec<_>>();
+    assert_eq!(
+        vec![10, 20],
+        out.iter().map(|&(_, x)| x).collect::<Vec<_>>()
+    );
+    assert!(out.iter().all(|&(t, _)| tick < t), "{:?}", out);
+}
@@ -0,0 +1,73 @@
+use hydroflow::hydroflow_syntax;
+use hydroflow::scheduled::metrics::HandoffMetrics;
+use hydroflow::util::{collect_ready, unbounded_channel};
+use multiplatform_test::multiplatform_test;
+
+#[multiplatform_test]
+pub fn test_metrics() {
+    let (input_send, input_recv) = unbounded_channel::<u32>();
+    let (output_send, mut output_recv) = unbounded_channel::<u32>();
+    let mut hf = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> filter(|x| x % 2 == 0)
+            -> fold::<'tick>(|| 0, |acc: &mut u32, x| *acc += x)
+            -> for_each(|x| output_send.send(x).unwrap());
+    };
+    hf.set_metrics_enabled(true);
+
+    input_send.send(1).unwrap();
+    input_send.send(2).unwrap();
+    hf.run_tick();
+    (3..=6).for_each(|x| input_send.send(x).unwrap());
+    hf.run_tick();
+    assert_eq!(&[2, 10], &*collect_ready::<Vec<_>, _>(&mut output_recv));
+
+    let metrics = hf.metrics();
+    assert_eq!(2, metrics.current_tick);
+
+    let (sg_id, source_metrics) = metrics.subgraphs_with_operator("filter").next().unwrap();
+    assert_eq!("source_stream -> filter", metrics.subgraph_labels[&sg_id]);
+    assert_eq!(2, source_metrics.invocations);
+    assert_eq!(0, source_metrics.items_in);
+    assert_eq!(3, source_metrics.items_out);
+
+    let (sg_id, fold_metrics) = metrics.subgraphs_with_operator("fold").next().unwrap();
+    assert_eq!("fold -> for_each", metrics.subgraph_labels[&sg_id]);
+    assert_eq!(2, fold_metrics.invocations);
+    assert_eq!(3, fold_metrics.items_in);
+    assert_eq!(0, fold_metrics.items_out);
+
+    assert_eq!(
+        Some(&HandoffMetrics {
+            items_in: 3,
+            items_out: 3,
+            item_count: 0,
+        }),
+        metrics
+            .handoff_with_label("filter -> fold")
+            .map(|(_, handoff_metrics)| handoff_metrics),
+    );
+
+    hf.reset_metrics();
+    let metrics = hf.metrics();
+    assert!(metrics
+        .subgraphs
+        .values()
+        .all(|sg_metrics| 0 == sg_metrics.invocations));
+}
+
+#[multiplatform_test]
+pub fn test_metrics_disabled() {
+    let mut hf = hydroflow_syntax! {
+        source_iter(0..10) -> filter(|x| x % 2 == 0) -> null();
+    };
+    assert!(!hf.metrics_enabled());
+    hf.run_available();
+
+    // Nothing is recorded until metrics are enabled.
+    let metrics = hf.metrics();
+    assert!(metrics
+        .subgraphs
+        .values()
+        .all(|sg_metrics| 0 == sg_metrics.invocations && sg_metrics.wall_time.is_zero()));
+}
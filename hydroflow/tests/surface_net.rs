@@ -0,0 +1,152 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use hydroflow::hydroflow_syntax;
+use hydroflow::scheduled::graph::Hydroflow;
+use hydroflow::util::{bind_tcp_source, bind_udp_source, collect_ready_async};
+use multiplatform_test::multiplatform_test;
+use tokio_util::codec::LinesCodec;
+
+/// Runs the flow long enough for messages to arrive over localhost.
+async fn run_for_a_bit(df: &mut Hydroflow<'_>) {
+    tokio::time::timeout(Duration::from_millis(200), df.run_async())
+        .await
+        .expect_err("Expected time out");
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_tcp_loopback() {
+    let (source, addr) = bind_tcp_source("127.0.0.1:0", LinesCodec::new()).unwrap();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<(String, SocketAddr)>();
+    let mut df_recv = hydroflow_syntax! {
+        source_stream(source)
+            -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    let mut df_send = hydroflow_syntax! {
+        source_iter(["hello", "world", "!"])
+            -> map(|line| (line, addr))
+            -> dest_tcp(LinesCodec::new());
+    };
+    run_for_a_bit(&mut df_send).await;
+    run_for_a_bit(&mut df_recv).await;
+
+    let out = collect_ready_async::<Vec<_>, _>(&mut out_recv).await;
+    assert_eq!(
+        vec!["hello", "world", "!"],
+        out.iter().map(|(line, _)| &**line).collect::<Vec<_>>()
+    );
+    // All items to one destination share a pooled connection.
+    assert_eq!(
+        1,
+        out.iter()
+            .map(|&(_, peer_addr)| peer_addr)
+            .collect::<HashSet<_>>()
+            .len()
+    );
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_tcp_reconnect() {
+    let make_df_recv = |addr: SocketAddr, out_send: tokio::sync::mpsc::UnboundedSender<String>| {
+        hydroflow_syntax! {
+            source_tcp(addr, LinesCodec::new())
+                -> for_each(|(line, _peer_addr)| out_send.send(line).unwrap());
+        }
+    };
+
+    // Find a free port to reuse.
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<String>();
+    let mut df_recv = make_df_recv(addr, out_send);
+
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<String>();
+    let mut df_send = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> map(|line| (line, addr))
+            -> dest_tcp(LinesCodec::new());
+    };
+
+    input_send.send("before".to_owned()).unwrap();
+    run_for_a_bit(&mut df_send).await;
+    run_for_a_bit(&mut df_recv).await;
+    assert_eq!(
+        vec!["before"],
+        collect_ready_async::<Vec<_>, _>(&mut out_recv).await
+    );
+
+    // Restart the receiver on the same address, closing the pooled connection.
+    drop(df_recv);
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<String>();
+    let mut df_recv = make_df_recv(addr, out_send);
+
+    // Writes into the closed connection may be lost until the sender notices and reconnects.
+    let mut received = Vec::new();
+    for i in 0..10 {
+        input_send.send(format!("after {}", i)).unwrap();
+        run_for_a_bit(&mut df_send).await;
+        run_for_a_bit(&mut df_recv).await;
+        received.extend(collect_ready_async::<Vec<_>, _>(&mut out_recv).await);
+        if !received.is_empty() {
+            break;
+        }
+    }
+    assert!(!received.is_empty(), "Sender did not reconnect.");
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_tcp_unreachable_destination() {
+    // Nothing listens here, so connecting fails and the sender backs off.
+    let dead_addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let (source, live_addr) = bind_tcp_source("127.0.0.1:0", LinesCodec::new()).unwrap();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<String>();
+    let mut df_recv = hydroflow_syntax! {
+        source_stream(source)
+            -> for_each(|(line, _peer_addr)| out_send.send(line).unwrap());
+    };
+
+    let mut df_send = hydroflow_syntax! {
+        source_iter([("dead", dead_addr), ("live", live_addr)])
+            -> dest_tcp(LinesCodec::new());
+    };
+    // Much shorter than the dead destination's backoff.
+    run_for_a_bit(&mut df_send).await;
+    run_for_a_bit(&mut df_recv).await;
+
+    assert_eq!(
+        vec!["live"],
+        collect_ready_async::<Vec<_>, _>(&mut out_recv).await
+    );
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_udp_loopback() {
+    let (source, addr) = bind_udp_source("127.0.0.1:0", LinesCodec::new()).unwrap();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<String>();
+    let mut df_recv = hydroflow_syntax! {
+        source_stream(source)
+            -> for_each(|(line, _sender_addr)| out_send.send(line).unwrap());
+    };
+
+    let mut df_send = hydroflow_syntax! {
+        source_iter(["hello", "world"])
+            -> map(|line| (line, addr))
+            -> dest_udp(LinesCodec::new());
+    };
+    run_for_a_bit(&mut df_send).await;
+    run_for_a_bit(&mut df_recv).await;
+
+    assert_eq!(
+        HashSet::from(["hello".to_owned(), "world".to_owned()]),
+        collect_ready_async::<HashSet<_>, _>(&mut out_recv).await
+    );
+}
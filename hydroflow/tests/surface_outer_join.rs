@@ -0,0 +1,158 @@
+use std::collections::BTreeSet;
+
+use hydroflow::hydroflow_syntax;
+use hydroflow::util::collect_ready;
+use multiplatform_test::multiplatform_test;
+
+#[multiplatform_test]
+pub fn test_join_left_right_full() {
+    let (left_send, mut left_recv) = hydroflow::util::unbounded_channel();
+    let (right_send, mut right_recv) = hydroflow::util::unbounded_channel();
+    let (full_send, mut full_recv) = hydroflow::util::unbounded_channel();
+
+    let mut df = hydroflow_syntax! {
+        lhs = source_iter([("a", 0), ("b", 1), ("b", 2)]) -> tee();
+        rhs = source_iter([("a", 3), ("a", 4), ("c", 5)]) -> tee();
+
+        lhs -> [0]left;
+        rhs -> [1]left;
+        left = join_left() -> for_each(|x| left_send.send(x).unwrap());
+
+        lhs -> [0]right;
+        rhs -> [1]right;
+        right = join_right() -> for_each(|x| right_send.send(x).unwrap());
+
+        lhs -> [0]full;
+        rhs -> [1]full;
+        full = join_full() -> for_each(|x| full_send.send(x).unwrap());
+    };
+    df.run_available();
+
+    assert_eq!(
+        BTreeSet::from([
+            ("a", (0, Some(3))),
+            ("a", (0, Some(4))),
+            ("b", (1, None)),
+            ("b", (2, None)),
+        ]),
+        collect_ready::<BTreeSet<_>, _>(&mut left_recv)
+    );
+    assert_eq!(
+        BTreeSet::from([("a", (Some(0), 3)), ("a", (Some(0), 4)), ("c", (None, 5))]),
+        collect_ready::<BTreeSet<_>, _>(&mut right_recv)
+    );
+    assert_eq!(
+        BTreeSet::from([
+            ("a", (Some(0), Some(3))),
+            ("a", (Some(0), Some(4))),
+            ("b", (Some(1), None)),
+            ("b", (Some(2), None)),
+            ("c", (None, Some(5))),
+        ]),
+        collect_ready::<BTreeSet<_>, _>(&mut full_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_join_left_tick() {
+    let (lhs_send, lhs_recv) = hydroflow::util::unbounded_channel::<(&str, u32)>();
+    let (rhs_send, rhs_recv) = hydroflow::util::unbounded_channel::<(&str, u32)>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(lhs_recv) -> [0]my_join;
+        source_stream(rhs_recv) -> [1]my_join;
+        my_join = join_left::<'tick>() -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    lhs_send.send(("a", 0)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::from([("a", (0, None))]),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+
+    // The left side is forgotten after each tick.
+    rhs_send.send(("a", 1)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::<(&str, (u32, Option<u32>))>::new(),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_join_full_static() {
+    let (lhs_send, lhs_recv) = hydroflow::util::unbounded_channel::<(&str, u32)>();
+    let (rhs_send, rhs_recv) = hydroflow::util::unbounded_channel::<(&str, u32)>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(lhs_recv) -> [0]my_join;
+        source_stream(rhs_recv) -> [1]my_join;
+        my_join = join_full::<'static>() -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    lhs_send.send(("a", 0)).unwrap();
+    rhs_send.send(("b", 1)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::from([("a", (Some(0), None)), ("b", (None, Some(1)))]),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+
+    // A matching key arrives in a later tick, replacing the `None` row.
+    rhs_send.send(("a", 2)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::from([("a", (Some(0), Some(2))), ("b", (None, Some(1)))]),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+
+    // With no new input, the join does not run again.
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::<(&str, (Option<u32>, Option<u32>))>::new(),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+
+    // New input re-emits the entire join.
+    lhs_send.send(("c", 3)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::from([
+            ("a", (Some(0), Some(2))),
+            ("b", (None, Some(1))),
+            ("c", (Some(3), None))
+        ]),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_join_left_tick_static() {
+    let (lhs_send, lhs_recv) = hydroflow::util::unbounded_channel::<(&str, u32)>();
+    let (rhs_send, rhs_recv) = hydroflow::util::unbounded_channel::<(&str, u32)>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(lhs_recv) -> [0]my_join;
+        source_stream(rhs_recv) -> [1]my_join;
+        my_join = join_left::<'tick, 'static>() -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    rhs_send.send(("a", 1)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::<(&str, (u32, Option<u32>))>::new(),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+
+    lhs_send.send(("a", 0)).unwrap();
+    lhs_send.send(("b", 2)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::from([("a", (0, Some(1))), ("b", (2, None))]),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+}
@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+use hydroflow::hydroflow_syntax_parallel;
+use hydroflow::util::{collect_ready, unbounded_channel};
+use multiplatform_test::multiplatform_test;
+
+/// Waits until `count` callers have arrived. Returns `false` if that takes more than ten seconds,
+/// i.e. the callers are not running concurrently.
+fn rendezvous(arrived: &AtomicUsize, count: usize) -> bool {
+    arrived.fetch_add(1, Ordering::SeqCst);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while arrived.load(Ordering::SeqCst) < count {
+        if deadline < Instant::now() {
+            return false;
+        }
+        std::thread::yield_now();
+    }
+    true
+}
+
+#[test]
+// #[multiplatform_test]  // no threads on WASM
+fn test_parallel_tee_branches() {
+    let arrived = Arc::new(AtomicUsize::new(0));
+    let thread_ids = Arc::new(Mutex::new(HashSet::<ThreadId>::new()));
+    let (result_send, mut result_recv) = unbounded_channel::<(&str, bool)>();
+
+    let (arrived_a, arrived_b) = (arrived.clone(), arrived.clone());
+    let (thread_ids_a, thread_ids_b) = (thread_ids.clone(), thread_ids.clone());
+    let (result_send_a, result_send_b) = (result_send.clone(), result_send);
+    let mut df = hydroflow_syntax_parallel! {
+        branches = source_iter([()]) -> tee();
+        branches
+            -> inspect(|()| { thread_ids_a.lock().unwrap().insert(std::thread::current().id()); })
+            -> map(|()| rendezvous(&arrived_a, 2))
+            -> for_each(|met| result_send_a.send(("a", met)).unwrap());
+        branches
+            -> inspect(|()| { thread_ids_b.lock().unwrap().insert(std::thread::current().id()); })
+            -> map(|()| rendezvous(&arrived_b, 2))
+            -> for_each(|met| result_send_b.send(("b", met)).unwrap());
+    };
+    df.set_worker_threads(2);
+    df.run_available();
+
+    let mut results = collect_ready::<Vec<_>, _>(&mut result_recv);
+    results.sort_unstable();
+    // Each branch saw the other one running at the same time.
+    assert_eq!(&[("a", true), ("b", true)], &*results);
+    assert_eq!(2, thread_ids.lock().unwrap().len());
+}
+
+#[multiplatform_test]
+fn test_parallel_single_thread() {
+    let (result_send, mut result_recv) = unbounded_channel::<(usize, usize)>();
+
+    let mut df = hydroflow_syntax_parallel! {
+        nums = source_iter(0..5) -> tee();
+        nums -> map(|x| x * 10) -> [0]joined;
+        nums -> map(|x| x * 100) -> [1]joined;
+        // Stateful operators still run on the current thread.
+        joined = zip() -> for_each(|pair| result_send.send(pair).unwrap());
+    };
+    df.run_available();
+
+    let results = collect_ready::<Vec<_>, _>(&mut result_recv);
+    assert_eq!(
+        &[(0, 0), (10, 100), (20, 200), (30, 300), (40, 400)],
+        &*results
+    );
+}
@@ -0,0 +1,139 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::path::PathBuf;
+
+use hydroflow::hydroflow_syntax;
+use hydroflow::util::collect_ready_async;
+use multiplatform_test::multiplatform_test;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Record {
+    name: String,
+    count: u32,
+    note: Option<String>,
+}
+
+fn records() -> Vec<Record> {
+    vec![
+        Record {
+            name: "hello".to_owned(),
+            count: 1,
+            note: None,
+        },
+        Record {
+            name: "world, \"quoted\"\nnewline".to_owned(),
+            count: 2,
+            note: Some("note".to_owned()),
+        },
+    ]
+}
+
+/// A path in the temp dir which is unique to this test process.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("hydroflow-{}-{}", std::process::id(), name))
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_csv_roundtrip() {
+    let path = temp_path("roundtrip.csv");
+
+    let input = records();
+    let mut df = hydroflow_syntax! {
+        source_iter(input) -> dest_csv(&path, false);
+    };
+    df.run_until(std::future::ready(())).await;
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.starts_with("name,count,note\n"), "{:?}", contents);
+
+    // Appending to a non-empty file does not write another header.
+    let input = records();
+    let mut df = hydroflow_syntax! {
+        source_iter(input) -> dest_csv(&path, true);
+    };
+    df.run_until(std::future::ready(())).await;
+
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<Record>();
+    let mut df = hydroflow_syntax! {
+        source_csv(&path) -> for_each(|x| out_send.send(x).unwrap());
+    };
+    df.run_available();
+
+    let expected = records().into_iter().chain(records()).collect::<Vec<_>>();
+    assert_eq!(
+        expected,
+        collect_ready_async::<Vec<_>, _>(&mut out_recv).await
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_csv_tuples() {
+    let path = temp_path("tuples.csv");
+
+    let mut df = hydroflow_syntax! {
+        source_iter([("a", 1), ("b", 2)]) -> dest_csv(&path, false);
+    };
+    df.run_until(std::future::ready(())).await;
+    assert_eq!("0,1\na,1\nb,2\n", std::fs::read_to_string(&path).unwrap());
+
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<(String, u32)>();
+    let mut df = hydroflow_syntax! {
+        source_csv::<(String, u32)>(&path) -> for_each(|x| out_send.send(x).unwrap());
+    };
+    df.run_available();
+
+    assert_eq!(
+        vec![("a".to_owned(), 1), ("b".to_owned(), 2)],
+        collect_ready_async::<Vec<_>, _>(&mut out_recv).await
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_jsonl_roundtrip() {
+    let path = temp_path("roundtrip.jsonl");
+
+    let input = records();
+    let mut df = hydroflow_syntax! {
+        source_iter(input) -> dest_jsonl(&path, false);
+    };
+    df.run_until(std::future::ready(())).await;
+    assert_eq!(2, std::fs::read_to_string(&path).unwrap().lines().count());
+
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<Record>();
+    let mut df = hydroflow_syntax! {
+        source_jsonl(&path) -> for_each(|x| out_send.send(x).unwrap());
+    };
+    df.run_available();
+
+    assert_eq!(
+        records(),
+        collect_ready_async::<Vec<_>, _>(&mut out_recv).await
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_file_serde_roundtrip() {
+    let path = temp_path("roundtrip.bin");
+
+    let input = records();
+    let mut df = hydroflow_syntax! {
+        source_iter(input) -> dest_file_serde(&path, false);
+    };
+    df.run_until(std::future::ready(())).await;
+
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<Record>();
+    let mut df = hydroflow_syntax! {
+        source_file_serde(&path) -> for_each(|x| out_send.send(x).unwrap());
+    };
+    df.run_available();
+
+    assert_eq!(
+        records(),
+        collect_ready_async::<Vec<_>, _>(&mut out_recv).await
+    );
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,104 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use hydroflow::hydroflow_syntax;
+use hydroflow::util::simulation::{SimConfig, Simulation};
+use hydroflow::util::{collect_ready, unbounded_channel};
+use multiplatform_test::multiplatform_test;
+
+/// Sends `0..100` from a client to an echo server, returning the echoed messages in the order
+/// received and the final simulated time.
+fn run_echo(seed: u64, config: SimConfig) -> (Vec<u32>, Duration) {
+    let mut sim = Simulation::with_config(seed, config);
+    let network = sim.network::<u32>();
+
+    let client_addr: SocketAddr = "127.0.0.1:1".parse().unwrap();
+    let server_addr: SocketAddr = "127.0.0.1:2".parse().unwrap();
+    let (client_send, client_recv) = network.bind(client_addr);
+    let (server_send, server_recv) = network.bind(server_addr);
+
+    let (output_send, mut output_recv) = unbounded_channel();
+    sim.add_node(hydroflow_syntax! {
+        source_iter(0..100) -> map(|x| (x, server_addr)) -> dest_sink(client_send);
+        source_stream(client_recv) -> for_each(|(x, addr)| {
+            assert_eq!(server_addr, addr);
+            output_send.send(x).unwrap();
+        });
+    });
+    sim.add_node(hydroflow_syntax! {
+        source_stream(server_recv) -> dest_sink(server_send);
+    });
+
+    let end_time = sim.run_until_quiescent();
+    (collect_ready(&mut output_recv), end_time)
+}
+
+#[multiplatform_test]
+pub fn test_echo_reliable() {
+    let (mut output, end_time) = run_echo(0, SimConfig::default());
+    assert!(Duration::from_millis(2) <= end_time && end_time <= Duration::from_millis(20));
+    output.sort();
+    assert_eq!((0..100).collect::<Vec<_>>(), output);
+}
+
+#[multiplatform_test]
+pub fn test_echo_fifo() {
+    let config = SimConfig {
+        reorder: false,
+        ..Default::default()
+    };
+    let (output, _end_time) = run_echo(0, config);
+    assert_eq!((0..100).collect::<Vec<_>>(), output);
+}
+
+#[multiplatform_test]
+pub fn test_echo_lossy_deterministic() {
+    let config = SimConfig {
+        drop_probability: 0.2,
+        duplicate_probability: 0.2,
+        ..Default::default()
+    };
+    let (output, end_time) = run_echo(1234, config.clone());
+
+    // Some messages dropped, some duplicated, some reordered.
+    let mut distinct = output.clone();
+    distinct.sort();
+    distinct.dedup();
+    assert!(distinct.len() < 100);
+    assert!(distinct.len() < output.len());
+    assert!(output.windows(2).any(|w| w[0] > w[1]));
+
+    // Same seed, same run.
+    assert_eq!((output.clone(), end_time), run_echo(1234, config.clone()));
+    // Different seed, different run.
+    assert_ne!(output, run_echo(4321, config).0);
+}
+
+#[multiplatform_test]
+pub fn test_clock_interval() {
+    let mut sim = Simulation::new(0);
+    let clock = sim.clock();
+
+    let (output_send, mut output_recv) = unbounded_channel();
+    sim.add_node(hydroflow_syntax! {
+        source_stream(clock.interval(Duration::from_secs(1)))
+            -> for_each(|time| output_send.send(time).unwrap());
+    });
+
+    sim.run_until(Duration::from_millis(3500));
+    assert_eq!(Duration::from_millis(3500), sim.now());
+    assert_eq!(
+        &[
+            Duration::from_secs(1),
+            Duration::from_secs(2),
+            Duration::from_secs(3)
+        ],
+        &*collect_ready::<Vec<_>, _>(&mut output_recv)
+    );
+
+    sim.run_for(Duration::from_secs(1));
+    assert_eq!(
+        &[Duration::from_secs(4)],
+        &*collect_ready::<Vec<_>, _>(&mut output_recv)
+    );
+}
@@ -0,0 +1,143 @@
+use hydroflow::hydroflow_syntax;
+use hydroflow::lattices::sketch::HyperLogLog;
+use hydroflow::util::collect_ready;
+use multiplatform_test::multiplatform_test;
+
+/// Asserts that a single estimate was received, within 5% of `expected`.
+fn assert_estimate(expected: u64, out: Vec<u64>) {
+    assert_eq!(1, out.len());
+    let diff = out[0].abs_diff(expected);
+    assert!(
+        diff * 20 <= expected,
+        "expected {} got {}",
+        expected,
+        out[0]
+    );
+}
+
+#[multiplatform_test]
+pub fn test_count_distinct_approx() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<u32>();
+    let (tick_send, mut tick_recv) = hydroflow::util::unbounded_channel::<u64>();
+    let (static_send, mut static_recv) = hydroflow::util::unbounded_channel::<u64>();
+
+    let mut df = hydroflow_syntax! {
+        inp = source_stream(input_recv) -> tee();
+        inp -> count_distinct_approx(12) -> for_each(|x| tick_send.send(x).unwrap());
+        inp -> count_distinct_approx::<'static>(12) -> for_each(|x| static_send.send(x).unwrap());
+    };
+
+    for x in (0..100).chain(0..100) {
+        input_send.send(x).unwrap();
+    }
+    df.run_tick();
+    assert_estimate(100, collect_ready(&mut tick_recv));
+    assert_estimate(100, collect_ready(&mut static_recv));
+
+    for x in 50..150 {
+        input_send.send(x).unwrap();
+    }
+    df.run_tick();
+    assert_estimate(100, collect_ready(&mut tick_recv));
+    assert_estimate(150, collect_ready(&mut static_recv));
+}
+
+#[multiplatform_test]
+pub fn test_hyperloglog_lattice_fold() {
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<u64>();
+
+    // Sketches from two "nodes" with overlapping items.
+    let sketches = [0..60, 40..100].map(|items| {
+        let mut sketch = HyperLogLog::default();
+        for x in items {
+            sketch.insert(&x);
+        }
+        sketch
+    });
+
+    let mut df = hydroflow_syntax! {
+        source_iter_delta(sketches)
+            -> lattice_fold(HyperLogLog::default)
+            -> map(|sketch| sketch.estimate())
+            -> for_each(|x| out_send.send(x).unwrap());
+    };
+    df.run_available();
+
+    assert_estimate(100, collect_ready(&mut out_recv));
+}
+
+#[multiplatform_test]
+pub fn test_heavy_hitters_static() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<&str>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<Vec<(&str, u64)>>();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> heavy_hitters::<'static>(2, 1024, 4)
+            -> fold(Vec::new, Vec::push)
+            -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    for item in ["a", "b", "a", "c"] {
+        input_send.send(item).unwrap();
+    }
+    df.run_tick();
+    let out = collect_ready::<Vec<_>, _>(&mut out_recv);
+    assert_eq!(1, out.len());
+    assert_eq!(("a", 2), out[0][0]);
+
+    for item in ["c", "c", "c"] {
+        input_send.send(item).unwrap();
+    }
+    df.run_tick();
+    assert_eq!(
+        vec![vec![("c", 4), ("a", 2)]],
+        collect_ready::<Vec<_>, _>(&mut out_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_quantiles_approx() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<f64>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<(f64, f64)>();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> quantiles_approx::<'static>(0.01, [0.0, 0.5, 1.0])
+            -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    df.run_tick();
+    assert_eq!(
+        Vec::<(f64, f64)>::new(),
+        collect_ready::<Vec<_>, _>(&mut out_recv)
+    );
+
+    for x in 1..=100 {
+        input_send.send(x as f64).unwrap();
+    }
+    df.run_tick();
+    let out = collect_ready::<Vec<_>, _>(&mut out_recv);
+    let expected = [(0.0, 1.0), (0.5, 50.0), (1.0, 100.0)];
+    assert_eq!(expected.len(), out.len());
+    for ((q, value), (expected_q, expected_value)) in out.into_iter().zip(expected) {
+        assert_eq!(expected_q, q);
+        assert!((value - expected_value).abs() <= 0.01 * expected_value);
+    }
+
+    for _ in 0..200 {
+        input_send.send(1000.0).unwrap();
+    }
+    df.run_tick();
+    let out = collect_ready::<Vec<_>, _>(&mut out_recv);
+    assert!((out[1].1 - 1000.0).abs() <= 10.0, "{:?}", out);
+}
+
+#[multiplatform_test]
+#[should_panic(expected = "HyperLogLog precision must be between 4 and 16")]
+pub fn test_count_distinct_approx_invalid_precision() {
+    // Panics when the graph is built, before any tick runs.
+    let _df = hydroflow_syntax! {
+        source_iter([1, 2, 3]) -> count_distinct_approx(2) -> for_each(|_| {});
+    };
+}
@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use hydroflow::hydroflow_syntax;
+use hydroflow::scheduled::ticks::{MockClock, TickPolicy};
+use hydroflow::util::ready_iter;
+use multiplatform_test::multiplatform_test;
+
+async fn yield_many() {
+    for _ in 0..10 {
+        tokio::task::yield_now().await;
+    }
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_fixed_interval() {
+    let clock = MockClock::new();
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let (output_send, mut output_recv) = hydroflow::util::unbounded_channel::<(usize, usize)>();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> map(|x| (context.current_tick(), x))
+            -> for_each(|x| output_send.send(x).unwrap());
+    };
+    df.set_tick_policy(TickPolicy::FixedInterval(Duration::from_millis(10)));
+    df.set_clock(clock.clone());
+
+    let (shutdown_send, shutdown_recv) = tokio::sync::oneshot::channel();
+    let driver = async {
+        yield_many().await;
+        input_send.send(1).unwrap();
+        input_send.send(2).unwrap();
+        yield_many().await;
+        // Input is batched until the next tick.
+        assert_eq!(
+            Vec::<(usize, usize)>::new(),
+            ready_iter(&mut output_recv).collect::<Vec<_>>()
+        );
+
+        clock.advance(Duration::from_millis(10));
+        yield_many().await;
+        assert_eq!(
+            &[(1, 1), (1, 2)],
+            &*ready_iter(&mut output_recv).collect::<Vec<_>>()
+        );
+
+        // Ticks happen every period, even without input.
+        clock.advance(Duration::from_millis(10));
+        yield_many().await;
+        input_send.send(3).unwrap();
+        clock.advance(Duration::from_millis(10));
+        yield_many().await;
+        assert_eq!(
+            &[(3, 3)],
+            &*ready_iter(&mut output_recv).collect::<Vec<_>>()
+        );
+
+        shutdown_send.send(()).unwrap();
+    };
+    tokio::join!(
+        df.run_until(async {
+            shutdown_recv.await.unwrap();
+        }),
+        driver
+    );
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_max_batch_or_timeout() {
+    let clock = MockClock::new();
+    let (a_send, a_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let (b_send, b_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let (output_send, mut output_recv) = hydroflow::util::unbounded_channel::<(usize, usize)>();
+
+    let mut df = hydroflow_syntax! {
+        out = union()
+            -> map(|x| (context.current_tick(), x))
+            -> for_each(|x| output_send.send(x).unwrap());
+        source_stream(a_recv) -> out;
+        source_stream(b_recv) -> out;
+    };
+    df.set_tick_policy(TickPolicy::MaxBatchOrTimeout {
+        max_batch: 2,
+        timeout: Duration::from_millis(10),
+    });
+    df.set_clock(clock.clone());
+
+    let (shutdown_send, shutdown_recv) = tokio::sync::oneshot::channel();
+    let driver = async {
+        yield_many().await;
+        // One event, waits for the timeout.
+        a_send.send(1).unwrap();
+        yield_many().await;
+        assert_eq!(
+            Vec::<(usize, usize)>::new(),
+            ready_iter(&mut output_recv).collect::<Vec<_>>()
+        );
+        clock.advance(Duration::from_millis(10));
+        yield_many().await;
+        assert_eq!(
+            &[(1, 1)],
+            &*ready_iter(&mut output_recv).collect::<Vec<_>>()
+        );
+
+        // Two events, starts the tick without waiting.
+        a_send.send(2).unwrap();
+        b_send.send(3).unwrap();
+        yield_many().await;
+        let mut output = ready_iter(&mut output_recv).collect::<Vec<_>>();
+        output.sort();
+        assert_eq!(&[(2, 2), (2, 3)], &*output);
+
+        shutdown_send.send(()).unwrap();
+    };
+    tokio::join!(
+        df.run_until(async {
+            shutdown_recv.await.unwrap();
+        }),
+        driver
+    );
+}
+
+#[multiplatform_test(hydroflow, env_tracing)]
+async fn test_max_batch_one_does_not_spin() {
+    let clock = MockClock::new();
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let (output_send, mut output_recv) = hydroflow::util::unbounded_channel::<(usize, usize)>();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> map(|x| (context.current_tick(), x))
+            -> for_each(|x| output_send.send(x).unwrap());
+    };
+    df.set_tick_policy(TickPolicy::MaxBatchOrTimeout {
+        max_batch: 1,
+        timeout: Duration::from_millis(10),
+    });
+    df.set_clock(clock.clone());
+
+    let (shutdown_send, shutdown_recv) = tokio::sync::oneshot::channel();
+    let driver = async {
+        // No ticks start while idle, however much time passes.
+        for _ in 0..10 {
+            clock.advance(Duration::from_millis(10));
+            yield_many().await;
+        }
+        input_send.send(1).unwrap();
+        yield_many().await;
+        assert_eq!(
+            &[(1, 1)],
+            &*ready_iter(&mut output_recv).collect::<Vec<_>>()
+        );
+
+        shutdown_send.send(()).unwrap();
+    };
+    tokio::join!(
+        df.run_until(async {
+            shutdown_recv.await.unwrap();
+        }),
+        driver
+    );
+}
+
+#[multiplatform_test]
+#[should_panic(expected = "`max_batch` must be at least one.")]
+fn test_max_batch_zero() {
+    let mut df = hydroflow_syntax! {
+        source_iter([1]) -> for_each(|_| {});
+    };
+    df.set_tick_policy(TickPolicy::MaxBatchOrTimeout {
+        max_batch: 0,
+        timeout: Duration::from_millis(10),
+    });
+}
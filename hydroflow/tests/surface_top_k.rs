@@ -0,0 +1,142 @@
+use std::collections::BTreeSet;
+
+use hydroflow::hydroflow_syntax;
+use hydroflow::util::collect_ready;
+use multiplatform_test::multiplatform_test;
+
+#[multiplatform_test]
+pub fn test_top_k_tick() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<(&str, u32)>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<(&str, u32)>();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> top_k(2, |&(_name, score)| score)
+            -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    input_send.send(("alice", 30)).unwrap();
+    input_send.send(("bob", 50)).unwrap();
+    input_send.send(("carol", 30)).unwrap();
+    df.run_tick();
+    // Ties keep the earliest item.
+    assert_eq!(
+        vec![("bob", 50), ("alice", 30)],
+        collect_ready::<Vec<_>, _>(&mut out_recv)
+    );
+
+    input_send.send(("dave", 10)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        vec![("dave", 10)],
+        collect_ready::<Vec<_>, _>(&mut out_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_top_k_static() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<(&str, u32)>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<Vec<(&str, u32)>>();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> top_k::<'static>(2, |&(_name, score)| score)
+            -> fold(Vec::new, Vec::push)
+            -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    input_send.send(("alice", 30)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        vec![vec![("alice", 30)]],
+        collect_ready::<Vec<_>, _>(&mut out_recv)
+    );
+
+    input_send.send(("bob", 50)).unwrap();
+    input_send.send(("carol", 10)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        vec![vec![("bob", 50), ("alice", 30)]],
+        collect_ready::<Vec<_>, _>(&mut out_recv)
+    );
+
+    input_send.send(("dave", 40)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        vec![vec![("bob", 50), ("dave", 40)]],
+        collect_ready::<Vec<_>, _>(&mut out_recv)
+    );
+
+    // The top k is emitted again even with no new input.
+    df.run_tick();
+    assert_eq!(
+        vec![vec![("bob", 50), ("dave", 40)]],
+        collect_ready::<Vec<_>, _>(&mut out_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_top_k_keyed_static() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<(&str, u64)>();
+    let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<(&str, u64)>();
+
+    let mut df = hydroflow_syntax! {
+        source_stream(input_recv)
+            -> top_k_keyed::<'static>(2, |&timestamp| timestamp)
+            -> for_each(|x| out_send.send(x).unwrap());
+    };
+
+    input_send.send(("a", 1)).unwrap();
+    input_send.send(("a", 2)).unwrap();
+    input_send.send(("b", 5)).unwrap();
+    df.run_tick();
+    assert_eq!(
+        BTreeSet::from([("a", 1), ("a", 2), ("b", 5)]),
+        collect_ready::<BTreeSet<_>, _>(&mut out_recv)
+    );
+
+    input_send.send(("a", 3)).unwrap();
+    df.run_tick();
+    let out = collect_ready::<Vec<_>, _>(&mut out_recv);
+    assert_eq!(
+        vec![("a", 3), ("a", 2)],
+        out.iter()
+            .copied()
+            .filter(|&(g, _)| "a" == g)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        BTreeSet::from([("a", 2), ("a", 3), ("b", 5)]),
+        out.into_iter().collect::<BTreeSet<_>>()
+    );
+}
+
+#[multiplatform_test]
+pub fn test_limit() {
+    let (input_send, input_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let (tick_send, mut tick_recv) = hydroflow::util::unbounded_channel::<usize>();
+    let (static_send, mut static_recv) = hydroflow::util::unbounded_channel::<usize>();
+
+    let mut df = hydroflow_syntax! {
+        inp = source_stream(input_recv) -> tee();
+        inp -> limit(2) -> for_each(|x| tick_send.send(x).unwrap());
+        inp -> limit::<'static>(3) -> for_each(|x| static_send.send(x).unwrap());
+    };
+
+    for x in 0..3 {
+        input_send.send(x).unwrap();
+    }
+    df.run_tick();
+    assert_eq!(vec![0, 1], collect_ready::<Vec<_>, _>(&mut tick_recv));
+    assert_eq!(vec![0, 1, 2], collect_ready::<Vec<_>, _>(&mut static_recv));
+
+    for x in 3..6 {
+        input_send.send(x).unwrap();
+    }
+    df.run_tick();
+    assert_eq!(vec![3, 4], collect_ready::<Vec<_>, _>(&mut tick_recv));
+    assert_eq!(
+        Vec::<usize>::new(),
+        collect_ready::<Vec<_>, _>(&mut static_recv)
+    );
+}
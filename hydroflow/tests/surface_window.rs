@@ -0,0 +1,203 @@
+use std::collections::BTreeSet;
+
+use hydroflow::util::collect_ready;
+use hydroflow::util::window::Window;
+use multiplatform_test::multiplatform_test;
+
+#[multiplatform_test]
+pub fn test_window_tumbling() {
+    let (items_send, items_recv) = hydroflow::util::unbounded_channel::<(&str, u64)>();
+    let (watermark_send, watermark_recv) = hydroflow::util::unbounded_channel::<u64>();
+    let (result_send, mut result_recv) =
+        hydroflow::util::unbounded_channel::<(&str, Window, usize)>();
+
+    let mut df = hydroflow::hydroflow_syntax! {
+        windows = window_tumbling(10, 0, |&time: &u64| time, || 0, |count: &mut usize, _time| *count += 1)
+            -> for_each(|x| result_send.send(x).unwrap());
+        source_stream(items_recv) -> [input]windows;
+        source_stream(watermark_recv) -> [watermark]windows;
+    };
+
+    items_send.send(("a", 1)).unwrap();
+    items_send.send(("b", 2)).unwrap();
+    items_send.send(("a", 9)).unwrap();
+    items_send.send(("a", 10)).unwrap();
+    df.run_available();
+    assert_eq!(
+        Vec::<(&str, Window, usize)>::new(),
+        collect_ready::<Vec<_>, _>(&mut result_recv)
+    );
+
+    watermark_send.send(10).unwrap();
+    df.run_available();
+    let window = Window { start: 0, end: 10 };
+    assert_eq!(
+        BTreeSet::from([("a", window, 2), ("b", window, 1)]),
+        collect_ready::<BTreeSet<_>, _>(&mut result_recv)
+    );
+
+    // Late item, window has expired with no allowed lateness.
+    items_send.send(("a", 5)).unwrap();
+    watermark_send.send(25).unwrap();
+    df.run_available();
+    assert_eq!(
+        vec![("a", Window { start: 10, end: 20 }, 1)],
+        collect_ready::<Vec<_>, _>(&mut result_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_window_tumbling_allowed_lateness() {
+    let (items_send, items_recv) = hydroflow::util::unbounded_channel::<(&str, u64)>();
+    let (watermark_send, watermark_recv) = hydroflow::util::unbounded_channel::<u64>();
+    let (result_send, mut result_recv) =
+        hydroflow::util::unbounded_channel::<(&str, Window, usize)>();
+
+    let mut df = hydroflow::hydroflow_syntax! {
+        windows = window_tumbling(10, 5, |&time: &u64| time, || 0, |count: &mut usize, _time| *count += 1)
+            -> for_each(|x| result_send.send(x).unwrap());
+        source_stream(items_recv) -> [input]windows;
+        source_stream(watermark_recv) -> [watermark]windows;
+    };
+
+    let window = Window { start: 0, end: 10 };
+
+    items_send.send(("a", 1)).unwrap();
+    watermark_send.send(12).unwrap();
+    df.run_available();
+    assert_eq!(
+        vec![("a", window, 1)],
+        collect_ready::<Vec<_>, _>(&mut result_recv)
+    );
+
+    // Late, but within the allowed lateness, so the window is updated and emitted again.
+    items_send.send(("a", 3)).unwrap();
+    df.run_available();
+    assert_eq!(
+        vec![("a", window, 2)],
+        collect_ready::<Vec<_>, _>(&mut result_recv)
+    );
+
+    // Past the allowed lateness, so dropped.
+    watermark_send.send(15).unwrap();
+    df.run_available();
+    items_send.send(("a", 4)).unwrap();
+    df.run_available();
+    assert_eq!(
+        Vec::<(&str, Window, usize)>::new(),
+        collect_ready::<Vec<_>, _>(&mut result_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_window_sliding() {
+    let (result_send, mut result_recv) =
+        hydroflow::util::unbounded_channel::<(&str, Window, Vec<u64>)>();
+
+    let mut df = hydroflow::hydroflow_syntax! {
+        windows = window_sliding(10, 5, 0, |&time: &u64| time, Vec::new, |times: &mut Vec<u64>, time| times.push(time))
+            -> for_each(|x| result_send.send(x).unwrap());
+        source_iter([("a", 3), ("a", 7), ("b", 12), ("a", 17)]) -> [input]windows;
+        source_iter([20]) -> [watermark]windows;
+    };
+    df.run_available();
+
+    assert_eq!(
+        BTreeSet::from([
+            ("a", Window { start: 0, end: 10 }, vec![3, 7]),
+            ("a", Window { start: 5, end: 15 }, vec![7]),
+            ("b", Window { start: 5, end: 15 }, vec![12]),
+            ("a", Window { start: 10, end: 20 }, vec![17]),
+            ("b", Window { start: 10, end: 20 }, vec![12]),
+        ]),
+        collect_ready::<BTreeSet<_>, _>(&mut result_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_window_session() {
+    let (items_send, items_recv) = hydroflow::util::unbounded_channel::<(&str, u64)>();
+    let (watermark_send, watermark_recv) = hydroflow::util::unbounded_channel::<u64>();
+    let (result_send, mut result_recv) =
+        hydroflow::util::unbounded_channel::<(&str, Window, BTreeSet<u64>)>();
+
+    let mut df = hydroflow::hydroflow_syntax! {
+        windows = window_session(5, 10, |&time: &u64| time, BTreeSet::new, |times: &mut BTreeSet<u64>, time| { times.insert(time); })
+            -> for_each(|x| result_send.send(x).unwrap());
+        source_stream(items_recv) -> [input]windows;
+        source_stream(watermark_recv) -> [watermark]windows;
+    };
+
+    items_send.send(("a", 1)).unwrap();
+    items_send.send(("a", 9)).unwrap();
+    items_send.send(("b", 3)).unwrap();
+    watermark_send.send(10).unwrap();
+    df.run_available();
+    assert_eq!(
+        BTreeSet::from([
+            ("a", Window { start: 1, end: 6 }, BTreeSet::from([1])),
+            ("b", Window { start: 3, end: 8 }, BTreeSet::from([3])),
+        ]),
+        collect_ready::<BTreeSet<_>, _>(&mut result_recv)
+    );
+
+    // A late item bridges the gap, merging the sessions of `"a"`.
+    items_send.send(("a", 5)).unwrap();
+    watermark_send.send(20).unwrap();
+    df.run_available();
+    assert_eq!(
+        vec![("a", Window { start: 1, end: 14 }, BTreeSet::from([1, 5, 9]))],
+        collect_ready::<Vec<_>, _>(&mut result_recv)
+    );
+}
+
+#[multiplatform_test]
+pub fn test_window_end_of_time() {
+    let (items_send, items_recv) = hydroflow::util::unbounded_channel::<(&str, u64)>();
+    let (watermark_send, watermark_recv) = hydroflow::util::unbounded_channel::<u64>();
+    let (tumbling_send, mut tumbling_recv) =
+        hydroflow::util::unbounded_channel::<(&str, Window, usize)>();
+    let (session_send, mut session_recv) =
+        hydroflow::util::unbounded_channel::<(&str, Window, usize)>();
+
+    let mut df = hydroflow::hydroflow_syntax! {
+        items = source_stream(items_recv) -> tee();
+        watermarks = source_stream(watermark_recv) -> tee();
+        tumbling = window_tumbling(10, 0, |&time: &u64| time, || 0, |count: &mut usize, _time| *count += 1)
+            -> for_each(|x| tumbling_send.send(x).unwrap());
+        items -> [input]tumbling;
+        watermarks -> [watermark]tumbling;
+        session = window_session(10, 0, |&time: &u64| time, || 0, |count: &mut usize, _time| *count += 1)
+            -> for_each(|x| session_send.send(x).unwrap());
+        items -> [input]session;
+        watermarks -> [watermark]session;
+    };
+
+    // Window ends saturate at `u64::MAX` instead of overflowing.
+    items_send.send(("a", u64::MAX - 2)).unwrap();
+    watermark_send.send(u64::MAX).unwrap();
+    df.run_available();
+    let start = u64::MAX - u64::MAX % 10;
+    assert_eq!(
+        vec![(
+            "a",
+            Window {
+                start,
+                end: u64::MAX
+            },
+            1
+        )],
+        collect_ready::<Vec<_>, _>(&mut tumbling_recv)
+    );
+    assert_eq!(
+        vec![(
+            "a",
+            Window {
+                start: u64::MAX - 2,
+                end: u64::MAX
+            },
+            1
+        )],
+        collect_ready::<Vec<_>, _>(&mut session_recv)
+    );
+}
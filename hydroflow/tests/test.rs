@@ -1,12 +1,12 @@
 use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
 
 use hydroflow::scheduled::graph::Hydroflow;
 use hydroflow::scheduled::graph_ext::GraphExt;
-use hydroflow::scheduled::handoff::VecHandoff;
-use hydroflow::scheduled::port::{RecvCtx, SendCtx};
+use hydroflow::scheduled::handoff::{Iter, SendVecHandoff, VecHandoff};
+use hydroflow::scheduled::port::{RecvCtx, SendCtx, SendPort};
 use hydroflow::{var_args, var_expr};
 use multiplatform_test::multiplatform_test;
 
@@ -431,3 +431,225 @@ fn test_input_channel() {
     }
     assert_eq!(result, expected);
 }
+
+fn make_parallel_fan_out(
+    df: &mut Hydroflow,
+    branches: usize,
+    thread_ids: Arc<Mutex<HashSet<std::thread::ThreadId>>>,
+) -> Rc<RefCell<Vec<usize>>> {
+    let mut sink_recvs = Vec::new();
+    for branch in 0..branches {
+        let (source_send, branch_recv) =
+            df.make_edge::<_, SendVecHandoff<usize>>("source -> branch");
+        df.add_subgraph_parallel(
+            "source",
+            0,
+            var_expr!(),
+            var_expr!(source_send),
+            move |_ctx, var_args!(), var_args!(send)| {
+                send.give(Iter(0..10));
+            },
+        );
+        let (sink_send, sink_recv) = df.make_edge::<_, SendVecHandoff<usize>>("branch -> sink");
+        sink_recvs.push(sink_recv);
+        let thread_ids = thread_ids.clone();
+        df.add_subgraph_parallel(
+            "branch",
+            0,
+            var_expr!(branch_recv),
+            var_expr!(sink_send),
+            move |_ctx, var_args!(recv), var_args!(send)| {
+                thread_ids
+                    .lock()
+                    .unwrap()
+                    .insert(std::thread::current().id());
+                send.give(Iter(recv.take_inner().into_iter().map(|x| 10 * branch + x)));
+            },
+        );
+    }
+
+    // Stratum 1 only runs once all the parallel branches are done.
+    let output = <Rc<RefCell<Vec<usize>>>>::default();
+    let output_ref = output.clone();
+    df.add_subgraph_stratified_n_m(
+        "sink",
+        1,
+        sink_recvs,
+        Vec::<SendPort<SendVecHandoff<usize>>>::new(),
+        move |_ctx, recvs, _sends| {
+            let count = recvs.iter().map(|recv| recv.take_inner().len()).sum();
+            output_ref.borrow_mut().push(count);
+        },
+    );
+    output
+}
+
+#[multiplatform_test]
+fn test_parallel_subgraphs_single_thread() {
+    let thread_ids = <Arc<Mutex<HashSet<_>>>>::default();
+    let mut df = Hydroflow::new();
+    let output = make_parallel_fan_out(&mut df, 4, thread_ids.clone());
+    df.run_available();
+
+    assert_eq!(&[40], &**output.borrow());
+    assert_eq!(1, thread_ids.lock().unwrap().len());
+}
+
+#[test]
+// #[multiplatform_test]  // no threads on WASM
+fn test_parallel_subgraphs_worker_threads() {
+    let thread_ids = <Arc<Mutex<HashSet<_>>>>::default();
+    let mut df = Hydroflow::new();
+    df.set_worker_threads(4);
+    let output = make_parallel_fan_out(&mut df, 4, thread_ids.clone());
+    df.run_available();
+
+    assert_eq!(&[40], &**output.borrow());
+    let main_thread_id = std::thread::current().id();
+    assert!(thread_ids
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|&thread_id| main_thread_id != thread_id));
+
+    // Later batches reuse the same worker threads.
+    for _ in 0..3 {
+        for sg_id in df.subgraph_ids().collect::<Vec<_>>() {
+            df.reactor().trigger(sg_id).unwrap();
+        }
+        df.run_available();
+    }
+    assert_eq!(&[40, 40, 40, 40], &**output.borrow());
+    assert!(thread_ids.lock().unwrap().len() <= 4);
+}
+
+#[multiplatform_test]
+fn test_make_edge_bounded() {
+    let mut df = Hydroflow::new();
+    let (send_port, recv_port) = df.make_edge_bounded::<_, VecHandoff<usize>>("handoff", 3);
+    assert_eq!(
+        Some(3),
+        df.handoff_capacity(df.handoff_ids().next().unwrap())
+    );
+
+    // Producer gives five items each time it is run.
+    let runs = Rc::new(Cell::new(0));
+    let runs_inner = runs.clone();
+    let producer = df.add_subgraph_source("producer", send_port, move |_ctx, send| {
+        let run = runs_inner.get();
+        send.give(Iter(5 * run..5 * (run + 1)));
+        runs_inner.set(run + 1);
+    });
+
+    let consumed = Rc::new(RefCell::new(Vec::new()));
+    let consumed_inner = consumed.clone();
+    df.add_subgraph_stratified(
+        "consumer",
+        1,
+        var_expr!(recv_port),
+        var_expr!(),
+        false,
+        move |_ctx, var_args!(recv), var_args!()| {
+            consumed_inner.borrow_mut().extend(recv.take_inner());
+        },
+    );
+
+    // Producer overfills the handoff.
+    df.run_stratum();
+    assert_eq!(1, runs.get());
+
+    // Producer is blocked while the handoff is full.
+    df.reactor().trigger(producer).unwrap();
+    df.try_recv_events();
+    df.run_stratum();
+    assert_eq!(1, runs.get());
+    assert!(consumed.borrow().is_empty());
+
+    // Consumer drains the handoff, unblocking the producer.
+    df.run_available();
+    assert_eq!(2, runs.get());
+    assert_eq!((0..10).collect::<Vec<_>>(), *consumed.borrow());
+}
+
+#[multiplatform_test]
+fn test_removed_subgraph_wake() {
+    // A stream which never yields, but keeps the waker it is polled with.
+    let waker = Rc::new(RefCell::new(None::<std::task::Waker>));
+    let waker_inner = Rc::clone(&waker);
+    let stream = futures::stream::poll_fn(move |cx| {
+        *waker_inner.borrow_mut() = Some(cx.waker().clone());
+        std::task::Poll::<Option<usize>>::Pending
+    });
+    let mut df = hydroflow::hydroflow_syntax! {
+        source_stream(stream) -> null();
+    };
+    df.run_available();
+    for sg_id in df.subgraph_ids().collect::<Vec<_>>() {
+        df.remove_subgraph(sg_id);
+    }
+    let tick = df.current_tick();
+    assert!(!df.run_available());
+    let idle_ticks = df.current_tick() - tick;
+
+    // Waking the removed `source_stream` does not start any extra ticks.
+    waker.borrow_mut().take().unwrap().wake();
+    let tick = df.current_tick();
+    assert!(!df.run_available());
+    assert_eq!(idle_ticks, df.current_tick() - tick);
+}
+
+#[multiplatform_test]
+fn test_add_remove_subgraphs() {
+    let mut df = Hydroflow::new();
+    let (send_port, recv_port) = df.make_edge::<_, VecHandoff<usize>>("input handoff");
+    let input = df.add_input("input", send_port);
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let output_inner = output.clone();
+    df.add_subgraph_sink("sink", recv_port, move |_ctx, recv| {
+        output_inner.borrow_mut().extend(recv.take_inner());
+    });
+    df.run_available();
+    let (subgraphs, handoffs) = (df.subgraph_ids().count(), df.handoff_ids().count());
+
+    // Attach a new pipeline to the running instance.
+    let (sub_send_port, sub_recv_port) = df.make_edge::<_, VecHandoff<usize>>("subscriber handoff");
+    let sub_input = df.add_input("subscriber input", sub_send_port);
+    let total = df.add_state(RefCell::new(0));
+    let sub_output = Rc::new(RefCell::new(Vec::new()));
+    let sub_output_inner = sub_output.clone();
+    let sub_sink = df.add_subgraph_sink("subscriber sink", sub_recv_port, move |ctx, recv| {
+        for x in recv.take_inner() {
+            *ctx.state_ref(total).borrow_mut() += x;
+            sub_output_inner.borrow_mut().push(x);
+        }
+    });
+    let sub_handoff = df.handoff_ids().last().unwrap();
+    let sub_source = df.subgraph_ids().nth(subgraphs).unwrap();
+
+    input.give(Iter(0..3));
+    input.flush();
+    sub_input.give(Iter(10..13));
+    sub_input.flush();
+    df.run_available();
+    assert_eq!(&[0, 1, 2], &**output.borrow());
+    assert_eq!(&[10, 11, 12], &**sub_output.borrow());
+
+    // Detach the new pipeline.
+    df.remove_subgraph(sub_source);
+    df.remove_subgraph(sub_sink);
+    df.remove_handoff(sub_handoff);
+    assert_eq!(33, df.remove_state(total).into_inner());
+    assert_eq!(subgraphs, df.subgraph_ids().count());
+    assert_eq!(handoffs, df.handoff_ids().count());
+    // The removed closures, along with their references, have been dropped.
+    assert_eq!(1, Rc::strong_count(&sub_output));
+
+    // Input to the removed pipeline is ignored, the original pipeline is unaffected.
+    input.give(Iter(3..6));
+    input.flush();
+    sub_input.give(Iter(13..16));
+    sub_input.flush();
+    df.run_available();
+    assert_eq!(&[0, 1, 2, 3, 4, 5], &**output.borrow());
+    assert_eq!(&[10, 11, 12], &**sub_output.borrow());
+}
@@ -14,14 +14,33 @@ pub mod datalog {
         Input(
             #[rust_sitter::leaf(text = ".input")] (),
             Spanned<Ident>,
+            Option<RelationSchema>,
             RustSnippet,
         ),
         Output(
             #[rust_sitter::leaf(text = ".output")] (),
             Spanned<Ident>,
+            Option<RelationSchema>,
+            RustSnippet,
+        ),
+        Persist(
+            #[rust_sitter::leaf(text = ".persist")] (),
+            Spanned<Ident>,
+            Option<RelationSchema>,
+        ),
+        /// An estimate of the number of facts in a relation, used to order joins.
+        Size(
+            #[rust_sitter::leaf(text = ".size")] (),
+            Spanned<Ident>,
+            #[rust_sitter::leaf(pattern = r"[0-9]+", transform = |s| s.parse().unwrap())]
+            Spanned<u64>,
+        ),
+        /// Records the provenance of every fact into a `hydroflow::datalog_provenance::Provenance`.
+        Provenance(
+            #[rust_sitter::leaf(text = ".provenance")] (),
+            /// An expression evaluating to the `Provenance` handle.
             RustSnippet,
         ),
-        Persist(#[rust_sitter::leaf(text = ".persist")] (), Spanned<Ident>),
         Async(
             #[rust_sitter::leaf(text = ".async")] (),
             Spanned<Ident>,
@@ -38,6 +57,72 @@ pub mod datalog {
         ),
     }
 
+    /// The names and types of the fields of a relation, i.e. `(src: u32, dst: u32)`.
+    #[derive(Debug, Clone)]
+    pub struct RelationSchema {
+        #[rust_sitter::leaf(text = "(")]
+        _l_paren: (),
+
+        #[rust_sitter::delimited(
+            #[rust_sitter::leaf(text = ",")]
+            ()
+        )]
+        pub fields: Vec<Spanned<SchemaField>>,
+
+        #[rust_sitter::leaf(text = ")")]
+        _r_paren: (),
+    }
+
+    #[derive(Debug, Clone)]
+    #[allow(clippy::manual_non_exhaustive)]
+    pub struct SchemaField {
+        pub name: Spanned<Ident>,
+
+        #[rust_sitter::leaf(text = ":")]
+        _colon: (),
+
+        /// A Rust type, such as `u32`, `Vec<u8>`, or `(u32, String)`.
+        pub ty: Spanned<RustType>,
+    }
+
+    /// The tokens of a Rust type, with balanced brackets. Parsed with `syn` once the program has
+    /// been parsed.
+    #[derive(Debug, Clone)]
+    pub struct RustType {
+        #[rust_sitter::repeat(non_empty = true)]
+        pub tokens: Vec<TypeToken>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub enum TypeToken {
+        Text(
+            #[rust_sitter::leaf(pattern = r"[^()<>\[\],\s`#]+", transform = |s| s.to_string())]
+            String,
+        ),
+        Parens(
+            #[rust_sitter::leaf(text = "(")] (),
+            Vec<NestedTypeToken>,
+            #[rust_sitter::leaf(text = ")")] (),
+        ),
+        Angles(
+            #[rust_sitter::leaf(text = "<")] (),
+            Vec<NestedTypeToken>,
+            #[rust_sitter::leaf(text = ">")] (),
+        ),
+        Brackets(
+            #[rust_sitter::leaf(text = "[")] (),
+            Vec<NestedTypeToken>,
+            #[rust_sitter::leaf(text = "]")] (),
+        ),
+    }
+
+    /// A token within brackets, where commas do not end the field.
+    #[derive(Debug, Clone)]
+    pub enum NestedTypeToken {
+        Token(TypeToken),
+        Comma(#[rust_sitter::leaf(text = ",")] ()),
+    }
+
     #[derive(Debug, Clone)]
     pub struct RustSnippet {
         #[rust_sitter::leaf(text = "`")]
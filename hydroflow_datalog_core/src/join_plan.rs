@@ -1,5 +1,5 @@
 use std::collections::btree_map::Entry;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::ops::Deref;
 
 use hydroflow_lang::diagnostic::{Diagnostic, Level};
@@ -9,7 +9,9 @@ use proc_macro2::Span;
 use rust_sitter::Spanned;
 use syn::{self, parse_quote, parse_quote_spanned};
 
-use crate::grammar::datalog::{BoolExpr, BoolOp, IdentOrUnderscore, InputRelationExpr, IntExpr};
+use crate::grammar::datalog::{
+    Atom, BoolExpr, BoolOp, IdentOrUnderscore, InputRelationExpr, IntExpr,
+};
 use crate::util::{repeat_tuple, Counter};
 
 /// Captures the tree of joins used to compute contributions from a single rule.
@@ -17,15 +19,320 @@ pub enum JoinPlan<'a> {
     /// A single relation without any joins, leaves of the tree.
     /// Second element is whether this is a persisted relation.
     Source(&'a Spanned<InputRelationExpr>, bool),
-    /// A join between two subtrees.
-    Join(Box<JoinPlan<'a>>, Box<JoinPlan<'a>>),
-    AntiJoin(Box<JoinPlan<'a>>, Box<JoinPlan<'a>>),
+    /// A join between two subtrees, with the estimated number of rows it outputs.
+    Join(Box<JoinPlan<'a>>, Box<JoinPlan<'a>>, f64),
+    AntiJoin(Box<JoinPlan<'a>>, Box<JoinPlan<'a>>, f64),
     Predicate(Vec<&'a Spanned<BoolExpr>>, Box<JoinPlan<'a>>),
     /// A join between some relation and a magic relation that emits values between
     /// 0 and some value in the input relation (upper-exclusive).
     MagicNatLt(Box<JoinPlan<'a>>, IdentOrUnderscore, IdentOrUnderscore),
 }
 
+impl JoinPlan<'_> {
+    /// The name of the relation this plan reads, if it reads a single relation.
+    fn relation_name(&self) -> Option<&str> {
+        match self {
+            JoinPlan::Source(relation, _) => Some(&relation.name.name),
+            JoinPlan::Predicate(_, inner) => inner.relation_name(),
+            _ => None,
+        }
+    }
+
+    /// The relations this plan reads, in the order they are joined. Negated relations are prefixed
+    /// with `!`.
+    fn join_order(&self) -> Vec<String> {
+        match self {
+            JoinPlan::Source(relation, _) => vec![relation.name.name.clone()],
+            JoinPlan::Join(lhs, rhs, _) => {
+                let mut order = lhs.join_order();
+                order.extend(rhs.join_order());
+                order
+            }
+            JoinPlan::AntiJoin(lhs, rhs, _) => {
+                let mut order = lhs.join_order();
+                order.extend(
+                    rhs.join_order()
+                        .into_iter()
+                        .map(|name| format!("!{}", name)),
+                );
+                order
+            }
+            JoinPlan::Predicate(_, inner) => inner.join_order(),
+            JoinPlan::MagicNatLt(inner, _, _) => {
+                let mut order = inner.join_order();
+                order.push("less_than".to_owned());
+                order
+            }
+        }
+    }
+}
+
+/// Describes the join order and estimated output size of a join, to label its node in the graph.
+fn join_label(plan: &JoinPlan, estimate: f64) -> String {
+    let estimate = if estimate < 1.0 {
+        format!("{:.2}", estimate)
+    } else {
+        format!("{:.0}", estimate)
+    };
+    format!(
+        "join order: {}; est. {} rows",
+        plan.join_order().join(", "),
+        estimate
+    )
+}
+
+/// Estimated number of facts in a relation without a `.size` hint.
+const DEFAULT_RELATION_SIZE: f64 = 1000.0;
+/// How many times larger a `.persist` relation without a `.size` hint is estimated to be, as its
+/// facts accumulate across ticks.
+const PERSIST_SIZE_FACTOR: f64 = 10.0;
+/// Estimated fraction of rows kept by each equality between variables, from a join key or a
+/// repeated variable within a relation.
+const EQUALITY_SELECTIVITY: f64 = 0.01;
+/// Estimated fraction of rows kept by each predicate or anti-join.
+const FILTER_SELECTIVITY: f64 = 0.5;
+/// Estimated number of values generated by `less_than` for each row.
+const LESS_THAN_SIZE: f64 = 10.0;
+
+/// Statistics about the relations of a program, used to order joins.
+pub struct RelationStats<'a> {
+    /// Relations declared with `.persist`.
+    pub persisted: &'a HashSet<String>,
+    /// Estimated numbers of facts, declared with `.size`.
+    pub size_hints: &'a HashMap<String, u64>,
+}
+
+impl RelationStats<'_> {
+    fn estimated_size(&self, relation: &str) -> f64 {
+        match self.size_hints.get(relation) {
+            Some(&size) => size as f64,
+            None if self.persisted.contains(relation) => {
+                DEFAULT_RELATION_SIZE * PERSIST_SIZE_FACTOR
+            }
+            None => DEFAULT_RELATION_SIZE,
+        }
+    }
+}
+
+fn relation_variables(relation: &InputRelationExpr) -> BTreeSet<&str> {
+    relation
+        .fields
+        .iter()
+        .filter_map(|field| match field.deref() {
+            IdentOrUnderscore::Ident(ident) => Some(&*ident.name),
+            IdentOrUnderscore::Underscore(_) => None,
+        })
+        .collect()
+}
+
+fn predicate_variables(predicate: &BoolExpr) -> BTreeSet<&str> {
+    predicate
+        .left
+        .idents()
+        .into_iter()
+        .chain(predicate.right.idents())
+        .map(|ident| &*ident.name)
+        .collect()
+}
+
+/// Computes a left-deep join plan for the body of a rule, choosing the join order greedily to keep
+/// the estimated intermediate results small.
+///
+/// The plan starts from the relation with the smallest estimated size, then repeatedly joins the
+/// relation which gives the smallest estimated result, so relations sharing bound variables are
+/// joined before any cross products. Sizes come from `.size` hints, with `.persist` relations
+/// estimated larger than others, and are reduced by local constraints and by predicates over only
+/// that relation's variables, which are applied as the relation is read. Other predicates,
+/// anti-joins, and `less_than` are applied as soon as their variables are bound. Ties keep the
+/// textual order.
+pub fn compute_join_plan<'a>(sources: &'a [Atom], stats: &RelationStats) -> JoinPlan<'a> {
+    let mut relations = Vec::new();
+    let mut negations = Vec::new();
+    let mut predicates = Vec::new();
+    let mut magics = Vec::new();
+    for atom in sources {
+        match atom {
+            Atom::Relation(negated, relation)
+                if crate::MAGIC_RELATIONS.contains(&relation.name.name.as_str()) =>
+            {
+                match relation.name.name.as_str() {
+                    "less_than" => {
+                        assert!(negated.is_none());
+                        magics.push(relation);
+                    }
+                    o => panic!("Unknown magic relation {}", o),
+                }
+            }
+            Atom::Relation(Some(_), relation) => negations.push(relation),
+            Atom::Relation(None, relation) => relations.push(relation),
+            Atom::Predicate(predicate) => predicates.push(predicate),
+        }
+    }
+
+    // Filters wait until all of their variables which can be bound are bound.
+    let bindable = relations
+        .iter()
+        .flat_map(|relation| relation_variables(relation))
+        .chain(
+            magics
+                .iter()
+                .filter_map(|magic| match magic.fields[0].deref() {
+                    IdentOrUnderscore::Ident(ident) => Some(&*ident.name),
+                    IdentOrUnderscore::Underscore(_) => None,
+                }),
+        )
+        .collect::<BTreeSet<_>>();
+    let is_ready = |vars: BTreeSet<&str>, bound: &BTreeSet<&str>| {
+        vars.into_iter()
+            .all(|var| bound.contains(var) || !bindable.contains(var))
+    };
+
+    let source_estimate = |relation: &InputRelationExpr, predicates: &[&Spanned<BoolExpr>]| {
+        let vars = relation_variables(relation);
+        let named_fields = relation
+            .fields
+            .iter()
+            .filter(|field| matches!(field.value, IdentOrUnderscore::Ident(_)))
+            .count();
+        let local_constraints = named_fields - vars.len();
+        let own_predicates = predicates
+            .iter()
+            .filter(|predicate| predicate_variables(predicate).is_subset(&vars))
+            .count();
+        stats.estimated_size(&relation.name.name)
+            * EQUALITY_SELECTIVITY.powi(local_constraints as i32)
+            * FILTER_SELECTIVITY.powi(own_predicates as i32)
+    };
+
+    let mut plan: Option<JoinPlan> = None;
+    let mut bound = BTreeSet::new();
+    let mut estimate = 1.0;
+    loop {
+        enum Next {
+            Relation(usize),
+            Magic(usize),
+        }
+        let mut best: Option<(Next, f64)> = None;
+        for (idx, relation) in relations.iter().enumerate() {
+            let shared = relation_variables(relation).intersection(&bound).count();
+            let join_estimate = match plan {
+                None => source_estimate(relation, &predicates),
+                Some(_) => {
+                    estimate
+                        * source_estimate(relation, &predicates)
+                        * EQUALITY_SELECTIVITY.powi(shared as i32)
+                }
+            };
+            if best
+                .as_ref()
+                .map_or(true, |(_, best)| join_estimate < *best)
+            {
+                best = Some((Next::Relation(idx), join_estimate));
+            }
+        }
+        for (idx, magic) in magics.iter().enumerate() {
+            let threshold_bound = match magic.fields[1].deref() {
+                IdentOrUnderscore::Ident(threshold) => bound.contains(&*threshold.name),
+                IdentOrUnderscore::Underscore(_) => false,
+            };
+            let magic_estimate = estimate * LESS_THAN_SIZE;
+            if threshold_bound
+                && best
+                    .as_ref()
+                    .map_or(true, |(_, best)| magic_estimate < *best)
+            {
+                best = Some((Next::Magic(idx), magic_estimate));
+            }
+        }
+        let Some((next, next_estimate)) = best else {
+            break;
+        };
+
+        plan = Some(match next {
+            Next::Relation(idx) => {
+                let relation = relations.remove(idx);
+                let vars = relation_variables(relation);
+                let (own_predicates, rest) =
+                    predicates.into_iter().partition::<Vec<_>, _>(|predicate| {
+                        predicate_variables(predicate).is_subset(&vars)
+                    });
+                predicates = rest;
+                let mut source =
+                    JoinPlan::Source(relation, stats.persisted.contains(&relation.name.name));
+                if !own_predicates.is_empty() {
+                    source = JoinPlan::Predicate(own_predicates, Box::new(source));
+                }
+                bound.extend(vars);
+                match plan {
+                    None => source,
+                    Some(plan) => JoinPlan::Join(Box::new(plan), Box::new(source), next_estimate),
+                }
+            }
+            Next::Magic(idx) => {
+                let magic = magics.remove(idx);
+                if let IdentOrUnderscore::Ident(less_than) = magic.fields[0].deref() {
+                    bound.insert(&*less_than.name);
+                }
+                JoinPlan::MagicNatLt(
+                    Box::new(plan.unwrap()),
+                    magic.fields[0].value.clone(),
+                    magic.fields[1].value.clone(),
+                )
+            }
+        });
+        estimate = next_estimate;
+
+        let (ready, rest) = predicates
+            .into_iter()
+            .partition::<Vec<_>, _>(|predicate| is_ready(predicate_variables(predicate), &bound));
+        predicates = rest;
+        if !ready.is_empty() {
+            estimate *= FILTER_SELECTIVITY.powi(ready.len() as i32);
+            plan = Some(JoinPlan::Predicate(ready, Box::new(plan.unwrap())));
+        }
+        let (ready, rest) = negations
+            .into_iter()
+            .partition::<Vec<_>, _>(|negation| is_ready(relation_variables(negation), &bound));
+        negations = rest;
+        for negation in ready {
+            estimate *= FILTER_SELECTIVITY;
+            plan = Some(JoinPlan::AntiJoin(
+                Box::new(plan.unwrap()),
+                Box::new(JoinPlan::Source(
+                    negation,
+                    stats.persisted.contains(&negation.name.name),
+                )),
+                estimate,
+            ));
+        }
+    }
+
+    // Anything left has a `less_than` threshold which is never bound, which fails to expand.
+    let mut plan = plan.expect("Rule must contain at least one positive relation");
+    for magic in magics {
+        plan = JoinPlan::MagicNatLt(
+            Box::new(plan),
+            magic.fields[0].value.clone(),
+            magic.fields[1].value.clone(),
+        );
+    }
+    for negation in negations {
+        estimate *= FILTER_SELECTIVITY;
+        plan = JoinPlan::AntiJoin(
+            Box::new(plan),
+            Box::new(JoinPlan::Source(
+                negation,
+                stats.persisted.contains(&negation.name.name),
+            )),
+            estimate,
+        );
+    }
+    if !predicates.is_empty() {
+        plan = JoinPlan::Predicate(predicates, Box::new(plan));
+    }
+    plan
+}
+
 /// Tracks the Hydroflow node that corresponds to a subtree of a join plan.
 pub struct IntermediateJoinNode {
     /// The name of the Hydroflow node that this join outputs to.
@@ -45,6 +352,20 @@ pub struct IntermediateJoinNode {
     pub span: Span,
 }
 
+impl IntermediateJoinNode {
+    /// The pipeline reading this node's output, from its tee index if it has one.
+    fn output_pipeline(&self) -> Pipeline {
+        let name = &self.name;
+        match self.tee_idx {
+            Some(i) => {
+                let index = syn::LitInt::new(&format!("{}", i), Span::call_site());
+                parse_quote_spanned!(self.span=> #name [#index])
+            }
+            None => parse_quote_spanned!(self.span=> #name),
+        }
+    }
+}
+
 enum JoinSide {
     Left,
     Right,
@@ -218,6 +539,7 @@ fn gen_predicate_value_expr(
 }
 
 /// Generates a Hydroflow pipeline that computes the output to a given [`JoinPlan`].
+#[allow(clippy::too_many_arguments)]
 pub fn expand_join_plan(
     // The plan we are converting to a Hydroflow pipeline.
     plan: &JoinPlan,
@@ -225,6 +547,8 @@ pub fn expand_join_plan(
     flat_graph_builder: &mut FlatGraphBuilder,
     tee_counter: &mut HashMap<String, Counter>,
     next_join_idx: &mut Counter,
+    // Labels for the join nodes emitted, by varname.
+    join_labels: &mut Vec<(syn::Ident, String)>,
     rule_span: (usize, usize),
     diagnostics: &mut Vec<Diagnostic>,
     get_span: &impl Fn((usize, usize)) -> Span,
@@ -297,14 +621,15 @@ pub fn expand_join_plan(
                 span: get_span(target.span),
             }
         }
-        JoinPlan::Join(lhs, rhs) | JoinPlan::AntiJoin(lhs, rhs) => {
-            let is_anti = matches!(plan, JoinPlan::AntiJoin(_, _));
+        JoinPlan::Join(lhs, rhs, estimate) | JoinPlan::AntiJoin(lhs, rhs, estimate) => {
+            let is_anti = matches!(plan, JoinPlan::AntiJoin(..));
 
             let left_expanded = expand_join_plan(
                 lhs,
                 flat_graph_builder,
                 tee_counter,
                 next_join_idx,
+                join_labels,
                 rule_span,
                 diagnostics,
                 get_span,
@@ -314,6 +639,7 @@ pub fn expand_join_plan(
                 flat_graph_builder,
                 tee_counter,
                 next_join_idx,
+                join_labels,
                 rule_span,
                 diagnostics,
                 get_span,
@@ -355,10 +681,15 @@ pub fn expand_join_plan(
                 right_non_joined_identifiers.len() + right_expanded.wildcard_indices.len(),
             );
 
+            // Named after the joined relation, so the join order shows in the graph's varnames.
             let join_node = syn::Ident::new(
                 &format!(
-                    "join_{}",
-                    next_join_idx.next().expect("Out of join indices")
+                    "{}_{}{}",
+                    if is_anti { "anti_join" } else { "join" },
+                    next_join_idx.next().expect("Out of join indices"),
+                    rhs.relation_name()
+                        .map(|name| format!("_{}", name))
+                        .unwrap_or_default(),
                 ),
                 Span::call_site(),
             );
@@ -450,6 +781,8 @@ pub fn expand_join_plan(
                     _ => (parse_quote!('tick), parse_quote!('tick), false),
                 };
 
+            join_labels.push((join_node.clone(), join_label(plan, *estimate)));
+
             if is_anti {
                 // this is always a 'tick join, so we place a persist operator in the join input pipeline
                 flat_graph_builder.add_statement(parse_quote_spanned! {get_span(rule_span)=>
@@ -525,11 +858,12 @@ pub fn expand_join_plan(
                 flat_graph_builder,
                 tee_counter,
                 next_join_idx,
+                join_labels,
                 rule_span,
                 diagnostics,
                 get_span,
             );
-            let inner_name = inner_expanded.name.clone();
+            let inner_pipeline = inner_expanded.output_pipeline();
             let row_type = inner_expanded.tuple_type;
             let variable_mapping = &inner_expanded.variable_mapping;
 
@@ -562,7 +896,7 @@ pub fn expand_join_plan(
             );
 
             flat_graph_builder.add_statement(parse_quote_spanned! { get_span(rule_span)=>
-                #predicate_filter_node = #inner_name -> filter(|row: &#row_type| #conditions );
+                #predicate_filter_node = #inner_pipeline -> filter(|row: &#row_type| #conditions );
             });
 
             IntermediateJoinNode {
@@ -589,11 +923,12 @@ pub fn expand_join_plan(
                 flat_graph_builder,
                 tee_counter,
                 next_join_idx,
+                join_labels,
                 rule_span,
                 diagnostics,
                 get_span,
             );
-            let inner_name = inner_expanded.name.clone();
+            let inner_pipeline = inner_expanded.output_pipeline();
             let row_type = inner_expanded.tuple_type;
 
             if let IdentOrUnderscore::Ident(less_than) = less_than {
@@ -646,7 +981,7 @@ pub fn expand_join_plan(
             flattened_elements.push(parse_quote!(v));
 
             flat_graph_builder.add_statement(parse_quote_spanned! {get_span(rule_span)=>
-                #magic_node = #inner_name -> flat_map(|row: #row_type| (0..(row.#threshold_index)).map(move |v| (#(#flattened_elements, )*)) );
+                #magic_node = #inner_pipeline -> flat_map(|row: #row_type| (0..(row.#threshold_index)).map(move |v| (#(#flattened_elements, )*)) );
             });
 
             IntermediateJoinNode {
@@ -13,14 +13,18 @@ use proc_macro2::{Span, TokenStream};
 use rust_sitter::errors::{ParseError, ParseErrorReason};
 use syn::{parse_quote, parse_quote_spanned, Token};
 
-mod grammar;
+pub mod grammar;
 mod join_plan;
+mod provenance;
+pub mod stratify;
+mod typecheck;
 mod util;
 
 use grammar::datalog::{
-    Aggregation, Atom, Declaration, Ident, IntExpr, Program, Rule, RuleType, TargetExpr,
+    Aggregation, Declaration, Ident, IntExpr, Program, Rule, RuleType, TargetExpr,
 };
-use join_plan::{IntermediateJoinNode, JoinPlan};
+use join_plan::{compute_join_plan, IntermediateJoinNode, JoinPlan, RelationStats};
+use provenance::ProvenanceSink;
 use util::{repeat_tuple, Counter};
 
 static MAGIC_RELATIONS: [&str; 1] = ["less_than"];
@@ -88,23 +92,38 @@ pub fn gen_hydroflow_graph(
     let mut inputs = Vec::new();
     let mut outputs = Vec::new();
     let mut persists = HashSet::new();
+    let mut size_hints = HashMap::new();
     let mut asyncs = Vec::new();
     let mut rules = Vec::new();
     let mut statics = Vec::new();
+    let mut provenance = None;
 
     for stmt in &program.rules {
         match stmt {
-            Declaration::Input(_, ident, hf_code) => {
+            Declaration::Input(_, ident, _, hf_code) => {
                 assert!(!MAGIC_RELATIONS.contains(&ident.name.as_str()));
                 inputs.push((ident, hf_code))
             }
-            Declaration::Output(_, ident, hf_code) => {
+            Declaration::Output(_, ident, _, hf_code) => {
                 assert!(!MAGIC_RELATIONS.contains(&ident.name.as_str()));
                 outputs.push((ident, hf_code))
             }
-            Declaration::Persist(_, ident) => {
+            Declaration::Persist(_, ident, _) => {
                 persists.insert(ident.name.clone());
             }
+            Declaration::Size(_, ident, size) => {
+                size_hints.insert(ident.name.clone(), size.value);
+            }
+            Declaration::Provenance(_, recorder) => {
+                if provenance.is_some() {
+                    return Err(vec![Diagnostic::spanned(
+                        get_span(recorder.code.span),
+                        Level::Error,
+                        "Only one `.provenance` may be declared",
+                    )]);
+                }
+                provenance = Some(recorder);
+            }
             Declaration::Async(_, ident, send_hf, recv_hf) => {
                 assert!(!MAGIC_RELATIONS.contains(&ident.name.as_str()));
                 asyncs.push((ident, send_hf, recv_hf))
@@ -120,6 +139,10 @@ pub fn gen_hydroflow_graph(
         }
     }
 
+    let schemas = typecheck::collect_schemas(&program, &get_span)?;
+    let arities = typecheck::check_rules(&rules, &schemas, &get_span)?;
+    let strata = stratify::compute_strata(&program, &get_span)?;
+
     let mut flat_graph_builder = FlatGraphBuilder::new();
     let mut tee_counter = HashMap::new();
     let mut union_counter = HashMap::new();
@@ -127,9 +150,11 @@ pub fn gen_hydroflow_graph(
     let mut created_rules = HashSet::new();
     for decl in &program.rules {
         let target_ident = match decl {
-            Declaration::Input(_, ident, _) => ident.clone(),
-            Declaration::Output(_, ident, _) => ident.clone(),
-            Declaration::Persist(_, ident) => ident.clone(),
+            Declaration::Input(_, ident, _, _) => ident.clone(),
+            Declaration::Output(_, ident, _, _) => ident.clone(),
+            Declaration::Persist(_, ident, _) => ident.clone(),
+            // Hints do not create a relation.
+            Declaration::Size(..) | Declaration::Provenance(..) => continue,
             Declaration::Async(_, ident, _, _) => ident.clone(),
             Declaration::Rule(rule) => rule.target.name.clone(),
             Declaration::Static(_, ident, _) => ident.clone(),
@@ -142,11 +167,24 @@ pub fn gen_hydroflow_graph(
                 get_span(target_ident.span),
             );
             let read_name = syn::Ident::new(&target_ident.name, get_span(target_ident.span));
+            // Declared field types are checked as rows are inserted, rather than deep inside the
+            // joins which use them.
+            let insert_pipeline: Pipeline = match schemas.get(&target_ident.name) {
+                Some(schema) => {
+                    let row_type = &schema.row_type;
+                    let check_type: Pipeline =
+                        parse_quote_spanned!(schema.span=> identity::<#row_type>());
+                    parse_quote_spanned!(get_span(target_ident.span)=> union() -> #check_type -> unique::<'tick>())
+                }
+                None => {
+                    parse_quote_spanned!(get_span(target_ident.span)=> union() -> unique::<'tick>())
+                }
+            };
 
             if persists.contains(&target_ident.value.name) {
                 // read outputs the *new* values for this tick
                 flat_graph_builder
-                    .add_statement(parse_quote_spanned!{get_span(target_ident.span)=> #insert_name = union() -> unique::<'tick>(); });
+                    .add_statement(parse_quote_spanned!{get_span(target_ident.span)=> #insert_name = #insert_pipeline; });
                 flat_graph_builder
                     .add_statement(parse_quote_spanned!{get_span(target_ident.span)=> #read_name = difference::<'tick, 'static>() -> tee(); });
                 flat_graph_builder
@@ -155,13 +193,22 @@ pub fn gen_hydroflow_graph(
                     .add_statement(parse_quote_spanned!{get_span(target_ident.span)=> #read_name -> defer_tick() -> [neg] #read_name; });
             } else {
                 flat_graph_builder
-                    .add_statement(parse_quote_spanned!{get_span(target_ident.span)=> #insert_name = union() -> unique::<'tick>(); });
+                    .add_statement(parse_quote_spanned!{get_span(target_ident.span)=> #insert_name = #insert_pipeline; });
                 flat_graph_builder
                     .add_statement(parse_quote_spanned!{get_span(target_ident.span)=> #read_name = #insert_name -> tee(); });
             }
         }
     }
 
+    let mut provenance_sink = match provenance {
+        Some(recorder) => Some(ProvenanceSink::new(
+            parse_static(&recorder.code, &get_span)?,
+            get_span(recorder.code.span),
+            &mut flat_graph_builder,
+        )),
+        None => None,
+    };
+
     for (target, hf_code) in inputs {
         let my_union_index = union_counter
             .entry(target.name.clone())
@@ -175,9 +222,20 @@ pub fn gen_hydroflow_graph(
 
         let input_pipeline: Pipeline = parse_pipeline(&hf_code.code, &get_span)?;
 
-        flat_graph_builder.add_statement(parse_quote_spanned! {get_span(target.span)=>
-            #input_pipeline -> [#my_union_index_lit] #name;
-        });
+        match (&mut provenance_sink, arities.get(&target.name)) {
+            (Some(sink), Some(&arity)) => sink.add_input(
+                &mut flat_graph_builder,
+                &target.name,
+                arity,
+                input_pipeline,
+                &my_union_index_lit,
+                &name,
+                get_span(target.span),
+            ),
+            _ => flat_graph_builder.add_statement(parse_quote_spanned! {get_span(target.span)=>
+                #input_pipeline -> [#my_union_index_lit] #name;
+            }),
+        }
     }
 
     for (target, hf_code) in outputs {
@@ -225,9 +283,20 @@ pub fn gen_hydroflow_graph(
             #async_send_pipeline = union() -> unique::<'tick>() -> #send_pipeline;
         });
 
-        flat_graph_builder.add_statement(parse_quote_spanned! {get_span(target.span)=>
-            #recv_pipeline -> [#recv_union_index_lit] #target_ident;
-        });
+        match (&mut provenance_sink, arities.get(&target.name)) {
+            (Some(sink), Some(&arity)) => sink.add_input(
+                &mut flat_graph_builder,
+                &target.name,
+                arity,
+                recv_pipeline,
+                &recv_union_index_lit,
+                &target_ident,
+                get_span(target.span),
+            ),
+            _ => flat_graph_builder.add_statement(parse_quote_spanned! {get_span(target.span)=>
+                #recv_pipeline -> [#recv_union_index_lit] #target_ident;
+            }),
+        }
     }
 
     for (target, hf_code) in statics {
@@ -242,16 +311,36 @@ pub fn gen_hydroflow_graph(
         let name = syn::Ident::new(&format!("{}_insert", target.name), get_span(target.span));
 
         let static_expression: syn::Expr = parse_static(&hf_code.code, &get_span)?;
+        let static_pipeline: Pipeline = parse_quote_spanned! {get_span(target.span)=>
+            source_iter(#static_expression) -> persist()
+        };
 
-        flat_graph_builder.add_statement(parse_quote_spanned! {get_span(target.span)=>
-            source_iter(#static_expression) -> persist() -> [#my_union_index_lit] #name;
-        });
+        match (&mut provenance_sink, arities.get(&target.name)) {
+            (Some(sink), Some(&arity)) => sink.add_input(
+                &mut flat_graph_builder,
+                &target.name,
+                arity,
+                static_pipeline,
+                &my_union_index_lit,
+                &name,
+                get_span(target.span),
+            ),
+            _ => flat_graph_builder.add_statement(parse_quote_spanned! {get_span(target.span)=>
+                #static_pipeline -> [#my_union_index_lit] #name;
+            }),
+        }
     }
 
     let mut next_join_idx = 0..;
+    let mut join_labels = Vec::new();
     let mut diagnostics = Vec::new();
-    for rule in rules {
-        let plan = compute_join_plan(&rule.sources, &persists);
+    let stats = RelationStats {
+        persisted: &persists,
+        size_hints: &size_hints,
+    };
+    for (rule_id, rule) in rules.into_iter().enumerate() {
+        let plan = compute_join_plan(&rule.sources, &stats);
+        let source = provenance::rule_source(&actual_str, rule.span);
         generate_rule(
             plan,
             rule,
@@ -259,7 +348,11 @@ pub fn gen_hydroflow_graph(
             &mut tee_counter,
             &mut union_counter,
             &mut next_join_idx,
+            &mut join_labels,
             &persists,
+            provenance_sink
+                .as_mut()
+                .map(|sink| (sink, rule_id, source.as_str())),
             &mut diagnostics,
             &get_span,
         );
@@ -281,48 +374,86 @@ pub fn gen_hydroflow_graph(
         return Err(diagnostics);
     }
 
+    // Show each join's planned order and size estimate on its node.
+    for (join_node, label) in join_labels {
+        set_node_labels(&mut flat_graph, &join_node, &["join", "anti_join"], &label);
+    }
+
+    // Show the stratum of each relation on the node deduplicating its facts, unless all relations
+    // are in the same stratum.
+    if 1 < strata.strata.len() {
+        for (stratum, relations) in strata.strata.iter().enumerate() {
+            for relation in relations {
+                let insert_name =
+                    syn::Ident::new(&format!("{}_insert", relation), Span::call_site());
+                let label = format!("stratum {}", stratum);
+                set_node_labels(&mut flat_graph, &insert_name, &["unique"], &label);
+            }
+        }
+    }
+
     eliminate_extra_unions_tees(&mut flat_graph);
     Ok(flat_graph)
 }
 
+/// Labels the nodes named `varname` which are instances of one of the operators `op_names`.
+fn set_node_labels(
+    flat_graph: &mut HydroflowGraph,
+    varname: &syn::Ident,
+    op_names: &[&str],
+    label: &str,
+) {
+    let node_ids = flat_graph
+        .node_ids()
+        .filter(|&node_id| {
+            flat_graph
+                .node_varname(node_id)
+                .is_some_and(|node_varname| node_varname == *varname)
+                && flat_graph
+                    .node_op_inst(node_id)
+                    .is_some_and(|op_inst| op_names.contains(&op_inst.op_constraints.name))
+        })
+        .collect::<Vec<_>>();
+    for node_id in node_ids {
+        flat_graph.set_node_label(node_id, label.to_owned());
+    }
+}
+
 fn handle_errors(
     errors: Vec<ParseError>,
     get_span: &impl Fn((usize, usize)) -> Span,
 ) -> Vec<Diagnostic> {
-    let mut diagnostics = vec![];
+    flatten_parse_errors(errors)
+        .into_iter()
+        .map(|(span, message)| Diagnostic::spanned(get_span(span), Level::Error, message))
+        .collect()
+}
+
+/// Flattens the errors from [`grammar::datalog::parse`] into `((start, end), message)` pairs, with
+/// byte offsets into the parsed string.
+pub fn flatten_parse_errors(errors: Vec<ParseError>) -> Vec<((usize, usize), String)> {
+    let mut flattened = vec![];
     for error in errors {
         let reason = error.reason;
-        let my_span = get_span((error.start, error.end));
+        let span = (error.start, error.end);
         match reason {
             ParseErrorReason::UnexpectedToken(msg) => {
-                diagnostics.push(Diagnostic::spanned(
-                    my_span,
-                    Level::Error,
-                    format!("Unexpected Token: '{msg}'", msg = msg),
-                ));
+                flattened.push((span, format!("Unexpected Token: '{msg}'", msg = msg)));
             }
             ParseErrorReason::MissingToken(msg) => {
-                diagnostics.push(Diagnostic::spanned(
-                    my_span,
-                    Level::Error,
-                    format!("Missing Token: '{msg}'", msg = msg),
-                ));
+                flattened.push((span, format!("Missing Token: '{msg}'", msg = msg)));
             }
             ParseErrorReason::FailedNode(parse_errors) => {
                 if parse_errors.is_empty() {
-                    diagnostics.push(Diagnostic::spanned(
-                        my_span,
-                        Level::Error,
-                        "Failed to parse",
-                    ));
+                    flattened.push((span, "Failed to parse".to_owned()));
                 } else {
-                    diagnostics.extend(handle_errors(parse_errors, get_span));
+                    flattened.extend(flatten_parse_errors(parse_errors));
                 }
             }
         }
     }
 
-    diagnostics
+    flattened
 }
 
 pub fn hydroflow_graph_to_program(flat_graph: HydroflowGraph, root: TokenStream) -> TokenStream {
@@ -334,7 +465,8 @@ pub fn hydroflow_graph_to_program(flat_graph: HydroflowGraph, root: TokenStream)
     // TODO(mingwei): Should this be done at a flat graph stage instead?
     let _ = propagate_flow_props::propagate_flow_props(&mut partitioned_graph, &mut diagnostics);
 
-    let code_tokens = partitioned_graph.as_code(&root, true, quote::quote!(), &mut diagnostics);
+    let code_tokens =
+        partitioned_graph.as_code(&root, true, false, false, quote::quote!(), &mut diagnostics);
     assert_eq!(
         0,
         diagnostics.len(),
@@ -352,7 +484,9 @@ fn generate_rule(
     tee_counter: &mut HashMap<String, Counter>,
     union_counter: &mut HashMap<String, Counter>,
     next_join_idx: &mut Counter,
+    join_labels: &mut Vec<(syn::Ident, String)>,
     persists: &HashSet<String>,
+    provenance: Option<(&mut ProvenanceSink, usize, &str)>,
     diagnostics: &mut Vec<Diagnostic>,
     get_span: &impl Fn((usize, usize)) -> Span,
 ) {
@@ -364,6 +498,7 @@ fn generate_rule(
         flat_graph_builder,
         tee_counter,
         next_join_idx,
+        join_labels,
         rule.span,
         diagnostics,
         get_span,
@@ -385,20 +520,20 @@ fn generate_rule(
 
     let my_union_index_lit = syn::LitInt::new(&format!("{}", my_union_index), Span::call_site());
 
-    let after_join_and_send: Pipeline = match rule.rule_type.value {
+    let to_target: Pipeline = match rule.rule_type.value {
         RuleType::Sync(_) => {
             if rule.target.at_node.is_some() {
                 panic!("Rule must be async to send data to other nodes")
             }
 
-            parse_quote_spanned!(get_span(rule.rule_type.span)=> #after_join -> [#my_union_index_lit] #target_ident)
+            parse_quote_spanned!(get_span(rule.rule_type.span)=> [#my_union_index_lit] #target_ident)
         }
         RuleType::NextTick(_) => {
             if rule.target.at_node.is_some() {
                 panic!("Rule must be async to send data to other nodes")
             }
 
-            parse_quote_spanned!(get_span(rule.rule_type.span)=> #after_join -> defer_tick() -> [#my_union_index_lit] #target_ident)
+            parse_quote_spanned!(get_span(rule.rule_type.span)=> defer_tick() -> [#my_union_index_lit] #target_ident)
         }
         RuleType::Async(_) => {
             if rule.target.at_node.is_none() {
@@ -427,11 +562,13 @@ fn generate_rule(
                 get_span(rule.target.name.span),
             );
 
-            parse_quote_spanned!(get_span(rule.rule_type.span)=> #after_join -> map(|v: #v_type| (v.#syn_target_index, (#(#exprs_get_data, )*))) -> #send_pipeline_ident)
+            parse_quote_spanned!(get_span(rule.rule_type.span)=> map(|v: #v_type| (v.#syn_target_index, (#(#exprs_get_data, )*))) -> #send_pipeline_ident)
         }
     };
+    let after_join_and_send: Pipeline =
+        parse_quote_spanned!(get_span(rule.rule_type.span)=> #after_join -> #to_target);
 
-    let out_name = out_expanded.name;
+    let out_name = &out_expanded.name;
     // If the output comes with a tee index, we must read with that. This only happens when we are
     // directly outputting a transformation of a single relation on the RHS.
     let out_indexing = out_expanded.tee_idx.map(|i| Indexing {
@@ -441,81 +578,45 @@ fn generate_rule(
             span: Span::call_site(),
         }),
     });
-    flat_graph_builder.add_statement(HfStatement::Pipeline(PipelineStatement {
-        pipeline: Pipeline::Link(PipelineLink {
-            lhs: Box::new(parse_quote!(#out_name #out_indexing)), // out_name[idx]
-            arrow: parse_quote!(->),
-            rhs: Box::new(after_join_and_send),
-        }),
-        semi_token: Token![;](Span::call_site()),
-    }));
-}
-
-fn compute_join_plan<'a>(sources: &'a [Atom], persisted_rules: &HashSet<String>) -> JoinPlan<'a> {
-    // TODO(shadaj): smarter plans
-    let mut plan: JoinPlan = sources
-        .iter()
-        .filter_map(|x| match x {
-            Atom::Relation(negated, e) => {
-                if negated.is_none() && !MAGIC_RELATIONS.contains(&e.name.name.as_str()) {
-                    Some(JoinPlan::Source(e, persisted_rules.contains(&e.name.name)))
-                } else {
-                    None
-                }
-            }
-            _ => None,
-        })
-        .reduce(|a, b| JoinPlan::Join(Box::new(a), Box::new(b)))
-        .unwrap();
-
-    plan = sources
-        .iter()
-        .filter_map(|x| match x {
-            Atom::Relation(negated, e) => {
-                if negated.is_some() {
-                    Some(JoinPlan::Source(e, persisted_rules.contains(&e.name.name)))
-                } else {
-                    None
-                }
-            }
-            _ => None,
-        })
-        .fold(plan, |a, b| JoinPlan::AntiJoin(Box::new(a), Box::new(b)));
+    let out_lhs: Pipeline = parse_quote!(#out_name #out_indexing); // out_name[idx]
+
+    let (sink, rule_id, source) = match provenance {
+        // Facts sent to other nodes are not recorded.
+        Some(provenance) if !matches!(rule.rule_type.value, RuleType::Async(_)) => provenance,
+        _ => {
+            flat_graph_builder.add_statement(HfStatement::Pipeline(PipelineStatement {
+                pipeline: Pipeline::Link(PipelineLink {
+                    lhs: Box::new(out_lhs),
+                    arrow: parse_quote!(->),
+                    rhs: Box::new(after_join_and_send),
+                }),
+                semi_token: Token![;](Span::call_site()),
+            }));
+            return;
+        }
+    };
 
-    let predicates = sources
+    let sink_port = sink.next_port();
+    let derived_by_row = rule
+        .target
+        .fields
         .iter()
-        .filter_map(|x| match x {
-            Atom::Predicate(e) => Some(e),
-            _ => None,
-        })
-        .collect::<Vec<_>>();
-
-    if !predicates.is_empty() {
-        plan = JoinPlan::Predicate(predicates, Box::new(plan))
+        .all(|field| matches!(field.value, TargetExpr::Expr(_)));
+    if derived_by_row {
+        // Records each row of the body along with the fact derived from it.
+        let body_name = syn::Ident::new(&format!("rule_{}_body", rule_id), get_span(rule.span));
+        let record = provenance::record_derivation(rule, rule_id, source, &out_expanded, get_span);
+        flat_graph_builder.add_statement(parse_quote! { #body_name = #out_lhs -> tee(); });
+        flat_graph_builder.add_statement(parse_quote! { #body_name -> #after_join_and_send; });
+        flat_graph_builder.add_statement(parse_quote! { #body_name -> #record -> #sink_port; });
+    } else {
+        let head_name = syn::Ident::new(&format!("rule_{}_head", rule_id), get_span(rule.span));
+        let record = provenance::record_aggregated(rule, rule_id, source);
+        flat_graph_builder
+            .add_statement(parse_quote! { #head_name = #out_lhs -> #after_join -> tee(); });
+        flat_graph_builder.add_statement(parse_quote! { #head_name -> #to_target; });
+        flat_graph_builder.add_statement(parse_quote! { #head_name -> #record -> #sink_port; });
     }
-
-    plan = sources.iter().fold(plan, |acc, atom| match atom {
-        Atom::Relation(negated, e) => {
-            if MAGIC_RELATIONS.contains(&e.name.name.as_str()) {
-                match e.name.name.as_str() {
-                    "less_than" => {
-                        assert!(negated.is_none());
-                        JoinPlan::MagicNatLt(
-                            Box::new(acc),
-                            e.fields[0].value.clone(),
-                            e.fields[1].value.clone(),
-                        )
-                    }
-                    o => panic!("Unknown magic relation {}", o),
-                }
-            } else {
-                acc
-            }
-        }
-        _ => acc,
-    });
-
-    plan
 }
 
 pub(crate) fn gen_value_expr(
@@ -812,9 +913,10 @@ fn apply_aggregations(
 
 #[cfg(test)]
 mod tests {
+    use proc_macro2::Span;
     use syn::parse_quote;
 
-    use super::{gen_hydroflow_graph, hydroflow_graph_to_program};
+    use super::{gen_hydroflow_graph, grammar, hydroflow_graph_to_program, stratify};
 
     macro_rules! test_snapshots {
         ($program:literal) => {
@@ -1190,4 +1292,156 @@ mod tests {
             "#
         );
     }
+
+    #[test]
+    fn test_join_order() {
+        // Joins `small`, then `other` which shares a variable with it, and `big` last, with the
+        // predicate on `big` applied as it is read.
+        test_snapshots!(
+            r#"
+            .input big `source_stream(big)`
+            .input other `source_stream(other)`
+            .input small `source_stream(small)`
+            .output out `for_each(|v| out.send(v).unwrap())`
+
+            .size big 1000000
+            .size small 10
+
+            out(a, c) :- big(a, b), other(c, d), small(b, c), (a > 5).
+            "#
+        );
+    }
+
+    #[test]
+    fn test_typed_relations() {
+        test_snapshots!(
+            r#"
+            .input edges(src: u32, dst: u32) `source_stream(edges)`
+            .output out(src: u32, dst: u32) `for_each(|v| out.send(v).unwrap())`
+            .persist path(src: u32, dst: u32)
+
+            path(x, y) :- edges(x, y).
+            path(x, z) :- path(x, y), edges(y, z).
+            out(x, y) :- path(x, y).
+            "#
+        );
+    }
+
+    #[test]
+    fn test_generic_and_tuple_types() {
+        gen_hydroflow_graph(parse_quote!(
+            r#"
+            .input edges(src: u32, dsts: Vec<u32>) `source_stream(edges)`
+            .input names(id: u32, name: (String, Option<[u8; 4]>)) `source_stream(names)`
+            .output out(dsts: Vec< u32 >, name: (String,Option<[u8;4]>)) `for_each(|v| out.send(v).unwrap())`
+
+            out(d, n) :- edges(x, d), names(x, n).
+            "#
+        ))
+        .unwrap();
+
+        let diagnostics = gen_hydroflow_graph(parse_quote!(
+            r#"
+            .input edges(src: u32, dsts: Vec<u32>) `source_stream(edges)`
+            .input names(id: u32, name: (String, u32)) `source_stream(names)`
+            .output out(dsts: Vec<u64>, name: (u32, String)) `for_each(|v| out.send(v).unwrap())`
+
+            out(d, n) :- edges(x, d), names(x, n).
+            "#
+        ))
+        .unwrap_err();
+        assert_eq!(
+            vec![
+                "Field `dsts` of `out` has type `Vec<u64>`, but `d` has type `Vec<u32>` in `edges`",
+                "Field `name` of `out` has type `(u32, String)`, but `n` has type `(String, u32)` in `names`",
+            ],
+            diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.message.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_provenance() {
+        test_snapshots!(
+            r#"
+            .input edges `source_stream(edges)`
+            .static nodes `vec![(1,), (2,), (3,), (4,)]`
+            .output unreachable `for_each(|v| unreachable.send(v).unwrap())`
+            .output num_paths `for_each(|v| num_paths.send(v).unwrap())`
+            .provenance `recorder`
+
+            path(x, y) :- edges(x, y).
+            path(x, z) :- path(x, y), edges(y, z).
+            source(x) :- edges(x, _).
+            unreachable(y) :- source(x), nodes(y), !path(x, y).
+            num_paths(count(y)) :- path(_, y).
+            "#
+        );
+    }
+
+    #[test]
+    fn test_strata() {
+        let program = grammar::datalog::parse(
+            r#"
+            .input nodes `source_stream(nodes)`
+            .input edges `source_stream(edges)`
+            .output num_unreachable `for_each(|v| num_unreachable.send(v).unwrap())`
+
+            reachable(x) :- nodes(x), (x == 0).
+            reachable(y) :- reachable(x), edges(x, y).
+            unreachable(x) :- nodes(x), !reachable(x).
+            num_unreachable(count(x)) :- unreachable(x).
+
+            # Next-tick rules do not constrain strata.
+            reachable(x) :+ unreachable(x), num_unreachable(x).
+            "#,
+        )
+        .unwrap();
+        let strata = stratify::compute_strata(&program, &|_| Span::call_site()).unwrap();
+        assert_eq!(
+            "0: `nodes`, `edges`, `reachable`; 1: `unreachable`; 2: `num_unreachable`",
+            strata.to_string()
+        );
+    }
+
+    #[test]
+    fn test_strata_labels() {
+        let flat_graph = gen_hydroflow_graph(parse_quote!(
+            r#"
+            .input nodes `source_stream(nodes)`
+            .input edges `source_stream(edges)`
+            .output num_unreachable `for_each(|v| num_unreachable.send(v).unwrap())`
+
+            reachable(x) :- nodes(x), (x == 0).
+            reachable(y) :- reachable(x), edges(x, y).
+            unreachable(x) :- nodes(x), !reachable(x).
+            num_unreachable(count(x)) :- unreachable(x).
+            "#
+        ))
+        .unwrap();
+
+        let mut labels = flat_graph
+            .node_ids()
+            .filter_map(|node_id| {
+                let label = flat_graph
+                    .node_label(node_id)
+                    .filter(|label| label.starts_with("stratum"))?;
+                let varname = flat_graph.node_varname(node_id)?;
+                Some(format!("{}: {}", varname, label))
+            })
+            .collect::<Vec<_>>();
+        labels.sort();
+        assert_eq!(
+            vec![
+                "edges_insert: stratum 0",
+                "nodes_insert: stratum 0",
+                "num_unreachable_insert: stratum 2",
+                "reachable_insert: stratum 0",
+                "unreachable_insert: stratum 1",
+            ],
+            labels
+        );
+    }
 }
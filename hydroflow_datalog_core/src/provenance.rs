@@ -0,0 +1,171 @@
+use hydroflow_lang::graph::FlatGraphBuilder;
+use hydroflow_lang::parse::Pipeline;
+use proc_macro2::Span;
+use syn::{parse_quote, parse_quote_spanned};
+
+use crate::grammar::datalog::{Atom, IdentOrUnderscore, Rule};
+use crate::join_plan::IntermediateJoinNode;
+use crate::util::{repeat_tuple, Counter};
+use crate::{gen_target_expr, MAGIC_RELATIONS};
+
+/// The `provenance_sink` node, which sends the records of facts and derivations to the
+/// `hydroflow::datalog_provenance::Provenance` handle given by `.provenance`.
+pub struct ProvenanceSink {
+    ports: Counter,
+}
+
+impl ProvenanceSink {
+    pub fn new(recorder: syn::Expr, span: Span, flat_graph_builder: &mut FlatGraphBuilder) -> Self {
+        flat_graph_builder.add_statement(parse_quote_spanned! {span=>
+            provenance_sink = union() -> for_each(|record| #recorder.record(record));
+        });
+        Self { ports: 0.. }
+    }
+
+    /// Returns the pipeline for the next input port of the sink.
+    ///
+    /// Records are delayed to the next stratum, so the sink is not placed in the same subgraph as
+    /// any of its inputs, which would create cycles between strata of the program.
+    pub fn next_port(&mut self) -> Pipeline {
+        let port = self.ports.next().expect("Out of union indices");
+        let port_lit = syn::LitInt::new(&format!("{}", port), Span::call_site());
+        parse_quote!(next_stratum() -> [#port_lit] provenance_sink)
+    }
+
+    /// Adds statements sending the facts of `input_pipeline` to `[union_index] insert_name`, and
+    /// recording them as inputs of `relation`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_input(
+        &mut self,
+        flat_graph_builder: &mut FlatGraphBuilder,
+        relation: &str,
+        arity: usize,
+        input_pipeline: Pipeline,
+        union_index: &syn::LitInt,
+        insert_name: &syn::Ident,
+        span: Span,
+    ) {
+        let tee_name = syn::Ident::new(
+            &format!("{}_input_{}", relation, union_index.base10_digits()),
+            span,
+        );
+        let row_type = repeat_tuple::<syn::Type, syn::Type>(|| parse_quote!(_), arity);
+        let fields = (0..arity).map(syn::Index::from);
+        let record_type = record_type();
+        let port = self.next_port();
+
+        flat_graph_builder.add_statement(parse_quote_spanned! {span=>
+            #tee_name = #input_pipeline -> tee();
+        });
+        flat_graph_builder.add_statement(parse_quote_spanned! {span=>
+            #tee_name -> [#union_index] #insert_name;
+        });
+        flat_graph_builder.add_statement(parse_quote_spanned! {span=>
+            #tee_name -> map(|row: #row_type| -> #record_type {
+                (#relation, vec![#(format!("{:?}", row.#fields)),*], None, Vec::new())
+            }) -> #port;
+        });
+    }
+}
+
+/// The type of the records consumed by `Provenance::record`.
+fn record_type() -> syn::Type {
+    parse_quote! {
+        (
+            &'static str,
+            ::std::vec::Vec<::std::string::String>,
+            ::std::option::Option<(usize, &'static str)>,
+            ::std::vec::Vec<(&'static str, bool, ::std::vec::Vec<::std::option::Option<::std::string::String>>)>,
+        )
+    }
+}
+
+/// The source of a rule, with whitespace collapsed to fit on one line.
+pub fn rule_source(program: &str, span: (usize, usize)) -> String {
+    program[span.0..span.1]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A `map` from the rows of `out_expanded`, the joined body of `rule`, to records of the derived
+/// fact and the facts of each relation atom it was derived from.
+pub fn record_derivation(
+    rule: &Rule,
+    rule_id: usize,
+    source: &str,
+    out_expanded: &IntermediateJoinNode,
+    get_span: &impl Fn((usize, usize)) -> Span,
+) -> Pipeline {
+    let lookup_column = |name: &str| -> Option<syn::Expr> {
+        let col = syn::Index::from(*out_expanded.variable_mapping.get(name)?);
+        Some(parse_quote!(row.#col))
+    };
+
+    let fact = rule
+        .target
+        .fields
+        .iter()
+        .map(|field| -> syn::Expr {
+            // Unbound variables are already reported by `apply_aggregations`. Columns are not
+            // cloned, as the type of `row` may not be inferred yet where this closure is checked.
+            let expr = gen_target_expr(
+                field,
+                &mut |ident| lookup_column(&ident.name).unwrap_or_else(|| parse_quote!(())),
+                get_span,
+            );
+            parse_quote!(format!("{:?}", #expr))
+        })
+        .collect::<Vec<_>>();
+
+    let premises = rule
+        .sources
+        .iter()
+        .filter_map(|atom| match atom {
+            Atom::Relation(negated, relation)
+                if !MAGIC_RELATIONS.contains(&relation.name.name.as_str()) =>
+            {
+                Some((negated.is_some(), relation))
+            }
+            _ => None,
+        })
+        .map(|(negated, relation)| -> syn::Expr {
+            let name = &relation.name.name;
+            let fields = relation.fields.iter().map(|field| -> syn::Expr {
+                let column = match &field.value {
+                    IdentOrUnderscore::Ident(ident) => lookup_column(&ident.name),
+                    IdentOrUnderscore::Underscore(_) => None,
+                };
+                match column {
+                    Some(column) => parse_quote!(Some(format!("{:?}", #column))),
+                    None => parse_quote!(None),
+                }
+            });
+            parse_quote!((#name, #negated, vec![#(#fields),*]))
+        })
+        .collect::<Vec<_>>();
+
+    let relation = &rule.target.name.name;
+    let tuple_type = &out_expanded.tuple_type;
+    let record_type = record_type();
+    parse_quote! {
+        map(|row: #tuple_type| -> #record_type {
+            (#relation, vec![#(#fact),*], Some((#rule_id, #source)), vec![#(#premises),*])
+        })
+    }
+}
+
+/// A `map` from the facts derived by `rule` to records of the rule alone, for rules whose facts
+/// are not derived from any single row of the body, i.e. with aggregations or `index()`.
+pub fn record_aggregated(rule: &Rule, rule_id: usize, source: &str) -> Pipeline {
+    let arity = rule.target.fields.len();
+    let row_type = repeat_tuple::<syn::Type, syn::Type>(|| parse_quote!(_), arity);
+    let fields = (0..arity).map(syn::Index::from);
+    let relation = &rule.target.name.name;
+    let record_type = record_type();
+    parse_quote! {
+        map(|row: #row_type| -> #record_type {
+            (#relation, vec![#(format!("{:?}", row.#fields)),*], Some((#rule_id, #source)), Vec::new())
+        })
+    }
+}
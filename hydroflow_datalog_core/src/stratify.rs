@@ -0,0 +1,289 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+
+use hydroflow_lang::diagnostic::{Diagnostic, Level};
+use proc_macro2::Span;
+
+use crate::grammar::datalog::{Atom, Declaration, Program, RuleType, TargetExpr};
+use crate::MAGIC_RELATIONS;
+
+/// An edge of a predicate dependency graph, from a relation in a rule's body to the rule's target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    pub src: usize,
+    pub dst: usize,
+    /// If `dst` must be fully computed in a later stratum than `src`, i.e. through negation or
+    /// aggregation.
+    pub negative: bool,
+}
+
+/// A negative [`Dependency`] inside a recursive cycle, which makes a program unstratifiable.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NegativeCycle {
+    /// Index of the negative dependency.
+    pub dependency: usize,
+    /// The relations of the cycle, starting and ending with the dependency's `dst`. Each relation
+    /// depends on the next.
+    pub cycle: Vec<usize>,
+}
+
+/// Assigns each of `num_relations` relations to a stratum, such that each relation is in the same
+/// or a later stratum than those it depends on, and a strictly later stratum than those it depends
+/// on negatively. Returns the stratum of each relation, or the negative dependencies inside a
+/// recursive cycle if there are any.
+pub fn stratify(
+    num_relations: usize,
+    dependencies: &[Dependency],
+) -> Result<Vec<usize>, Vec<NegativeCycle>> {
+    let mut adjacency = vec![Vec::new(); num_relations];
+    for dep in dependencies {
+        adjacency[dep.src].push(dep.dst);
+    }
+
+    let sccs = strongly_connected_components(&adjacency);
+
+    let mut negative_cycles = Vec::new();
+    for (idx, dep) in dependencies.iter().enumerate() {
+        if !dep.negative || sccs[dep.src] != sccs[dep.dst] {
+            continue;
+        }
+        // Path from `dst` back to `src`, within the component.
+        let mut parents = vec![None; num_relations];
+        let mut queue = VecDeque::from([dep.dst]);
+        while let Some(node) = queue.pop_front() {
+            if node == dep.src {
+                break;
+            }
+            for &next in &adjacency[node] {
+                if sccs[next] == sccs[dep.dst] && parents[next].is_none() && next != dep.dst {
+                    parents[next] = Some(node);
+                    queue.push_back(next);
+                }
+            }
+        }
+        let mut cycle = vec![dep.dst, dep.src];
+        let mut node = dep.src;
+        while let Some(parent) = parents[node] {
+            cycle.push(parent);
+            node = parent;
+        }
+        negative_cycles.push(NegativeCycle {
+            dependency: idx,
+            cycle,
+        });
+    }
+    if !negative_cycles.is_empty() {
+        return Err(negative_cycles);
+    }
+
+    // Components are numbered in reverse topological order.
+    let num_sccs = sccs.iter().copied().max().map_or(0, |max| max + 1);
+    let mut scc_strata = vec![0; num_sccs];
+    for scc in (0..num_sccs).rev() {
+        scc_strata[scc] = dependencies
+            .iter()
+            .filter(|dep| sccs[dep.dst] == scc && sccs[dep.src] != scc)
+            .map(|dep| scc_strata[sccs[dep.src]] + usize::from(dep.negative))
+            .max()
+            .unwrap_or(0);
+    }
+    Ok(sccs.into_iter().map(|scc| scc_strata[scc]).collect())
+}
+
+/// Tarjan's algorithm. Returns the component of each node, with components numbered in reverse
+/// topological order.
+fn strongly_connected_components(adjacency: &[Vec<usize>]) -> Vec<usize> {
+    struct Tarjan<'a> {
+        adjacency: &'a [Vec<usize>],
+        index: Vec<Option<usize>>,
+        lowlink: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next_index: usize,
+        sccs: Vec<usize>,
+        num_sccs: usize,
+    }
+    impl Tarjan<'_> {
+        fn visit(&mut self, node: usize) {
+            self.index[node] = Some(self.next_index);
+            self.lowlink[node] = self.next_index;
+            self.next_index += 1;
+            self.stack.push(node);
+            self.on_stack[node] = true;
+
+            for &next in &self.adjacency[node] {
+                match self.index[next] {
+                    None => {
+                        self.visit(next);
+                        self.lowlink[node] = self.lowlink[node].min(self.lowlink[next]);
+                    }
+                    Some(next_index) if self.on_stack[next] => {
+                        self.lowlink[node] = self.lowlink[node].min(next_index);
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            if Some(self.lowlink[node]) == self.index[node] {
+                loop {
+                    let member = self.stack.pop().unwrap();
+                    self.on_stack[member] = false;
+                    self.sccs[member] = self.num_sccs;
+                    if member == node {
+                        break;
+                    }
+                }
+                self.num_sccs += 1;
+            }
+        }
+    }
+
+    let num_nodes = adjacency.len();
+    let mut tarjan = Tarjan {
+        adjacency,
+        index: vec![None; num_nodes],
+        lowlink: vec![0; num_nodes],
+        on_stack: vec![false; num_nodes],
+        stack: Vec::new(),
+        next_index: 0,
+        sccs: vec![0; num_nodes],
+        num_sccs: 0,
+    };
+    for node in 0..num_nodes {
+        if tarjan.index[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+    tarjan.sccs
+}
+
+/// The relations of a program, grouped by stratum.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Strata {
+    pub strata: Vec<Vec<String>>,
+}
+
+impl Display for Strata {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (idx, relations) in self.strata.iter().enumerate() {
+            if 0 != idx {
+                write!(f, "; ")?;
+            }
+            let relations = relations
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>();
+            write!(f, "{}: {}", idx, relations.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the predicate dependency graph of a program and computes its [`Strata`].
+///
+/// A rule's target depends negatively on the relations in its body if they are negated with `!`,
+/// or if the rule aggregates. Next-tick (`:+`) and async (`:~`) rules do not constrain strata, as
+/// the facts they derive are not visible until a later tick. Programs with negative dependencies
+/// inside a recursive cycle are rejected, as they have no well-defined meaning.
+pub fn compute_strata(
+    program: &Program,
+    get_span: &impl Fn((usize, usize)) -> Span,
+) -> Result<Strata, Vec<Diagnostic>> {
+    let mut names = Vec::<String>::new();
+    let mut ids = HashMap::<String, usize>::new();
+    let mut id = |name: &str| {
+        *ids.entry(name.to_owned()).or_insert_with(|| {
+            names.push(name.to_owned());
+            names.len() - 1
+        })
+    };
+
+    // Each dependency, with the span of its body atom and whether it is due to an aggregation.
+    let mut dependencies = Vec::new();
+    let mut atoms = Vec::new();
+    for decl in &program.rules {
+        let rule = match decl {
+            Declaration::Input(_, ident, _, _)
+            | Declaration::Output(_, ident, _, _)
+            | Declaration::Persist(_, ident, _)
+            | Declaration::Async(_, ident, _, _)
+            | Declaration::Static(_, ident, _) => {
+                id(&ident.name);
+                continue;
+            }
+            Declaration::Size(..) | Declaration::Provenance(..) => continue,
+            Declaration::Rule(rule) => rule,
+        };
+        let dst = id(&rule.target.name.name);
+        if !matches!(rule.rule_type.value, RuleType::Sync(_)) {
+            continue;
+        }
+        let aggregates = rule
+            .target
+            .fields
+            .iter()
+            .any(|field| matches!(field.value, TargetExpr::Aggregation(_)));
+        for atom in rule.sources.iter() {
+            let Atom::Relation(negated, relation) = atom else {
+                continue;
+            };
+            if MAGIC_RELATIONS.contains(&relation.name.name.as_str()) {
+                continue;
+            }
+            dependencies.push(Dependency {
+                src: id(&relation.name.name),
+                dst,
+                negative: negated.is_some() || aggregates,
+            });
+            atoms.push((relation.span, negated.is_none()));
+        }
+    }
+
+    let strata_of = |relation_strata: &[usize]| {
+        let num_strata = relation_strata.iter().max().map_or(0, |max| max + 1);
+        let mut strata = vec![Vec::new(); num_strata];
+        for (name, &stratum) in names.iter().zip(relation_strata) {
+            strata[stratum].push(name.clone());
+        }
+        Strata { strata }
+    };
+
+    match stratify(names.len(), &dependencies) {
+        Ok(relation_strata) => Ok(strata_of(&relation_strata)),
+        Err(negative_cycles) => {
+            // Strata of the program as if the offending dependencies were positive, to show where
+            // each cycle falls relative to the rest of the program.
+            let mut relaxed = dependencies.clone();
+            for negative_cycle in negative_cycles.iter() {
+                relaxed[negative_cycle.dependency].negative = false;
+            }
+            let strata = strata_of(&stratify(names.len(), &relaxed).unwrap());
+
+            let diagnostics = negative_cycles
+                .into_iter()
+                .map(|NegativeCycle { dependency, cycle }| {
+                    let Dependency { src, dst, .. } = dependencies[dependency];
+                    let (span, through_aggregation) = atoms[dependency];
+                    let cycle = cycle
+                        .iter()
+                        .map(|&id| format!("`{}`", names[id]))
+                        .collect::<Vec<_>>()
+                        .join(" -> ");
+                    Diagnostic::spanned(
+                        get_span(span),
+                        Level::Error,
+                        format!(
+                            "Program is not stratifiable: `{}` depends on `{}` through {}, inside the recursive cycle {}. Computed strata: {}",
+                            names[dst],
+                            names[src],
+                            if through_aggregation { "aggregation" } else { "negation" },
+                            cycle,
+                            strata,
+                        ),
+                    )
+                })
+                .collect();
+            Err(diagnostics)
+        }
+    }
+}
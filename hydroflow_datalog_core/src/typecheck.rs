@@ -0,0 +1,307 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use hydroflow_lang::diagnostic::{Diagnostic, Level};
+use proc_macro2::Span;
+use quote::ToTokens;
+use rust_sitter::Spanned;
+use syn::parse_quote_spanned;
+
+use crate::grammar::datalog::{
+    Aggregation, Atom, Declaration, Ident, IdentOrUnderscore, IntExpr, NestedTypeToken, Program,
+    RelationSchema, Rule, RustType, TargetExpr, TypeToken,
+};
+use crate::MAGIC_RELATIONS;
+
+/// The fields of a relation, as declared with `.input`, `.output`, or `.persist`.
+pub struct Schema {
+    /// `(name, type)` of each field.
+    fields: Vec<(String, FieldType)>,
+    /// The tuple type of the relation's rows.
+    pub row_type: syn::Type,
+    /// Span of the declaration's schema.
+    pub span: Span,
+}
+
+impl Schema {
+    fn describe(&self) -> String {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(name, ty)| format!("{}: {}", name, ty))
+            .collect::<Vec<_>>();
+        format!("({})", fields.join(", "))
+    }
+}
+
+/// The declared type of a field. Types are compared by their tokens, so `Vec<u8>` and `Vec< u8 >`
+/// are the same type.
+pub struct FieldType {
+    /// The type as written, for diagnostics.
+    text: String,
+    /// The parsed type's tokens, as a string.
+    tokens: String,
+}
+
+impl PartialEq for FieldType {
+    fn eq(&self, other: &Self) -> bool {
+        self.tokens == other.tokens
+    }
+}
+
+impl Display for FieldType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Collects the schemas of all relations which declare one. A relation may be declared with a
+/// schema more than once, as long as the field types agree.
+pub fn collect_schemas(
+    program: &Program,
+    get_span: &impl Fn((usize, usize)) -> Span,
+) -> Result<HashMap<String, Schema>, Vec<Diagnostic>> {
+    let mut schemas = HashMap::<String, Schema>::new();
+    let mut diagnostics = Vec::new();
+    for decl in &program.rules {
+        let (ident, schema) = match decl {
+            Declaration::Input(_, ident, Some(schema), _)
+            | Declaration::Output(_, ident, Some(schema), _)
+            | Declaration::Persist(_, ident, Some(schema)) => (ident, schema),
+            _ => continue,
+        };
+        let Some(schema) = parse_schema(ident, schema, &mut diagnostics, get_span) else {
+            continue;
+        };
+        match schemas.entry(ident.name.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert(schema);
+            }
+            Entry::Occupied(entry) => {
+                let types_match = entry.get().fields.len() == schema.fields.len()
+                    && entry
+                        .get()
+                        .fields
+                        .iter()
+                        .zip(schema.fields.iter())
+                        .all(|((_, a), (_, b))| a == b);
+                if !types_match {
+                    diagnostics.push(Diagnostic::spanned(
+                        schema.span,
+                        Level::Error,
+                        format!(
+                            "Relation `{}` is declared as `{}` here, but as `{}` elsewhere",
+                            ident.name,
+                            schema.describe(),
+                            entry.get().describe(),
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    if diagnostics.is_empty() {
+        Ok(schemas)
+    } else {
+        Err(diagnostics)
+    }
+}
+
+fn parse_schema(
+    ident: &Spanned<Ident>,
+    schema: &RelationSchema,
+    diagnostics: &mut Vec<Diagnostic>,
+    get_span: &impl Fn((usize, usize)) -> Span,
+) -> Option<Schema> {
+    let mut fields = Vec::new();
+    let mut types = Vec::new();
+    for field in schema.fields.iter() {
+        let text = type_text(&field.ty.value);
+        let parsed = syn::LitStr::new(&text, get_span(field.ty.span)).parse::<syn::Type>();
+        match parsed {
+            Ok(ty) => {
+                let tokens = ty.to_token_stream().to_string();
+                fields.push((field.name.name.clone(), FieldType { text, tokens }));
+                types.push(ty);
+            }
+            Err(err) => {
+                diagnostics.push(Diagnostic::spanned(
+                    get_span(field.ty.span),
+                    Level::Error,
+                    format!(
+                        "Failed to parse type of field `{}`: {}",
+                        field.name.name, err
+                    ),
+                ));
+                return None;
+            }
+        }
+    }
+
+    let span = match (schema.fields.first(), schema.fields.last()) {
+        (Some(first), Some(last)) => get_span((first.span.0, last.span.1)),
+        _ => get_span(ident.span),
+    };
+    Some(Schema {
+        fields,
+        row_type: parse_quote_spanned!(span=> (#(#types,)*)),
+        span,
+    })
+}
+
+/// Writes out the tokens of a type. Whitespace is only kept between words, i.e. in `&'a str`.
+fn type_text(ty: &RustType) -> String {
+    fn write_token(token: &TypeToken, out: &mut String) {
+        let (open, inner, close) = match token {
+            TypeToken::Text(text) => {
+                if out.ends_with(|c: char| !"(<[ ".contains(c)) {
+                    out.push(' ');
+                }
+                out.push_str(text);
+                return;
+            }
+            TypeToken::Parens(_, inner, _) => ('(', inner, ')'),
+            TypeToken::Angles(_, inner, _) => ('<', inner, '>'),
+            TypeToken::Brackets(_, inner, _) => ('[', inner, ']'),
+        };
+        out.push(open);
+        for nested in inner {
+            match nested {
+                NestedTypeToken::Token(token) => write_token(token, out),
+                NestedTypeToken::Comma(_) => out.push_str(", "),
+            }
+        }
+        out.push(close);
+    }
+
+    let mut out = String::new();
+    for token in ty.tokens.iter() {
+        write_token(token, &mut out);
+    }
+    out
+}
+
+/// Checks that each relation is used with the same number of fields everywhere, which must match
+/// its schema if it has one, and that the types of variables agree between the declared fields
+/// they are bound to, and the declared fields of the rule's target.
+///
+/// Types are compared by their tokens, so `String` and `std::string::String` are still considered
+/// different.
+///
+/// Returns the number of fields of each relation which is declared with a schema or used by a
+/// rule.
+pub fn check_rules(
+    rules: &[&Spanned<Rule>],
+    schemas: &HashMap<String, Schema>,
+    get_span: &impl Fn((usize, usize)) -> Span,
+) -> Result<HashMap<String, usize>, Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+
+    let mut arities = schemas
+        .iter()
+        .map(|(name, schema)| (name.clone(), schema.fields.len()))
+        .collect::<HashMap<_, _>>();
+    let mut check_arity = |name: &Spanned<Ident>, arity: usize, span: (usize, usize)| {
+        if MAGIC_RELATIONS.contains(&name.name.as_str()) {
+            return true;
+        }
+        let expected = *arities.entry(name.name.clone()).or_insert(arity);
+        if expected == arity {
+            return true;
+        }
+        let message = if schemas.contains_key(&name.name) {
+            format!(
+                "Relation `{}` is declared with {} fields, but has {} fields here",
+                name.name, expected, arity
+            )
+        } else {
+            format!(
+                "Relation `{}` has {} fields here, but {} fields elsewhere",
+                name.name, arity, expected
+            )
+        };
+        diagnostics.push(Diagnostic::spanned(get_span(span), Level::Error, message));
+        false
+    };
+
+    let mut type_diagnostics = Vec::new();
+    for rule in rules {
+        let target = &rule.target;
+        let target_ok = check_arity(&target.name, target.fields.len(), target.name.span);
+
+        // The type of each variable, and the relation it was first bound by.
+        let mut var_types = HashMap::<&str, (&FieldType, &str)>::new();
+        for atom in rule.sources.iter() {
+            let Atom::Relation(_, relation) = atom else {
+                continue;
+            };
+            if !check_arity(&relation.name, relation.fields.len(), relation.span) {
+                continue;
+            }
+            let Some(schema) = schemas.get(&relation.name.name) else {
+                continue;
+            };
+            for (field, (_, ty)) in relation.fields.iter().zip(schema.fields.iter()) {
+                let IdentOrUnderscore::Ident(var) = &field.value else {
+                    continue;
+                };
+                match var_types.entry(var.name.as_str()) {
+                    Entry::Vacant(entry) => {
+                        entry.insert((ty, relation.name.name.as_str()));
+                    }
+                    Entry::Occupied(entry) => {
+                        let (other_ty, other_relation) = *entry.get();
+                        if other_ty != ty {
+                            type_diagnostics.push(Diagnostic::spanned(
+                                get_span(relation.span),
+                                Level::Error,
+                                format!(
+                                    "Variable `{}` has type `{}` here, but type `{}` in `{}`",
+                                    var.name, ty, other_ty, other_relation
+                                ),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        let Some(schema) = schemas.get(&target.name.name).filter(|_| target_ok) else {
+            continue;
+        };
+        for (field, (field_name, ty)) in target.fields.iter().zip(schema.fields.iter()) {
+            // Other expressions are left to the Rust compiler.
+            let var = match &field.value {
+                TargetExpr::Expr(IntExpr::Ident(var)) => var,
+                TargetExpr::Aggregation(
+                    Aggregation::Min(_, _, var, _)
+                    | Aggregation::Max(_, _, var, _)
+                    | Aggregation::Sum(_, _, var, _)
+                    | Aggregation::Choose(_, _, var, _),
+                ) => var,
+                _ => continue,
+            };
+            if let Some((var_ty, relation)) = var_types.get(var.name.as_str()) {
+                if *var_ty != ty {
+                    type_diagnostics.push(Diagnostic::spanned(
+                        get_span(field.span),
+                        Level::Error,
+                        format!(
+                            "Field `{}` of `{}` has type `{}`, but `{}` has type `{}` in `{}`",
+                            field_name, target.name.name, ty, var.name, var_ty, relation
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    diagnostics.extend(type_diagnostics);
+    if diagnostics.is_empty() {
+        Ok(arities)
+    } else {
+        Err(diagnostics)
+    }
+}
@@ -8,7 +8,9 @@ use syn::parse_quote;
 
 use super::hydroflow_graph::HydroflowGraph;
 use super::ops::{find_node_op_constraints, DelayType};
-use super::{graph_algorithms, Color, GraphEdgeId, GraphNode, GraphNodeId, GraphSubgraphId};
+use super::{
+    graph_algorithms, Color, GraphEdgeId, GraphEdgeType, GraphNode, GraphNodeId, GraphSubgraphId,
+};
 use crate::diagnostic::{Diagnostic, Level};
 use crate::union_find::UnionFind;
 
@@ -27,9 +29,20 @@ fn find_barrier_crossers(
         .collect()
 }
 
+/// Return all the value outputs of operators with multiple outputs, e.g. `tee()` branches.
+fn find_fan_out_edges(partitioned_graph: &HydroflowGraph) -> BTreeSet<GraphEdgeId> {
+    partitioned_graph
+        .node_ids()
+        .filter(|&node_id| 1 < partitioned_graph.node_degree_out(node_id))
+        .flat_map(|node_id| partitioned_graph.node_successor_edges(node_id))
+        .filter(|&edge_id| Some(GraphEdgeType::Value) == partitioned_graph.edge_type(edge_id))
+        .collect()
+}
+
 fn find_subgraph_unionfind(
     partitioned_graph: &HydroflowGraph,
     barrier_crossers: &SecondaryMap<GraphEdgeId, DelayType>,
+    split_edges: &BTreeSet<GraphEdgeId>,
 ) -> (UnionFind<GraphNodeId>, BTreeSet<GraphEdgeId>) {
     // Modality (color) of nodes, push or pull.
     // TODO(mingwei)? This does NOT consider `DelayType` barriers (which generally imply `Pull`),
@@ -62,6 +75,11 @@ fn find_subgraph_unionfind(
         progress = false;
         // TODO(mingwei): Could this iterate `handoff_edges` instead? (Modulo ownership). Then no case (1) below.
         for (edge_id, (src, dst)) in partitioned_graph.edges().collect::<Vec<_>>() {
+            // Edges which must be handoffs.
+            if split_edges.contains(&edge_id) {
+                continue;
+            }
+
             // Ignore (1) already added edges as well as (2) new self-cycles. (Unless reference edge).
             if subgraph_unionfind.same_set(src, dst) {
                 // Note that the _edge_ `edge_id` might not be in the subgraph even when both `src` and `dst` are. This prevents case 2.
@@ -140,9 +158,11 @@ fn make_subgraph_collect(
 /// Find subgraph and insert handoffs.
 /// Modifies barrier_crossers so that the edge OUT of an inserted handoff has
 /// the DelayType data.
+/// Edges in `split_edges` always become handoffs.
 fn make_subgraphs(
     partitioned_graph: &mut HydroflowGraph,
     barrier_crossers: &mut SecondaryMap<GraphEdgeId, DelayType>,
+    split_edges: &BTreeSet<GraphEdgeId>,
 ) {
     // Algorithm:
     // 1. Each node begins as its own subgraph.
@@ -153,7 +173,7 @@ fn make_subgraphs(
     // self.partitioned_graph.assert_valid();
 
     let (subgraph_unionfind, handoff_edges) =
-        find_subgraph_unionfind(partitioned_graph, barrier_crossers);
+        find_subgraph_unionfind(partitioned_graph, barrier_crossers, split_edges);
 
     // Insert handoffs between subgraphs (or on subgraph self-loop edges)
     for edge_id in handoff_edges {
@@ -388,7 +408,7 @@ fn find_subgraph_strata(
                     return Err(Diagnostic::spanned(dst_port.span(), Level::Error, "Negative edge creates a negative cycle which must be broken with a `defer_tick()` operator."));
                 }
             }
-            DelayType::MonotoneAccum => {
+            DelayType::MonotoneAccum | DelayType::Buffer => {
                 // cycles are actually fine
                 continue;
             }
@@ -461,6 +481,21 @@ pub fn assert_edgetypes_set(flat_graph: &HydroflowGraph) {
 ///
 /// Returns an error if a negative cycle exists in the graph. Negative cycles prevent partioning.
 pub fn partition_graph(flat_graph: HydroflowGraph) -> Result<HydroflowGraph, Diagnostic> {
+    partition_graph_split(flat_graph, Default::default())
+}
+
+/// [`partition_graph`], but the outputs of operators with multiple outputs (e.g. `tee()`) are
+/// always separated by handoffs, so each branch is its own subgraph. Used for parallel codegen,
+/// where independent subgraphs of the same stratum can run concurrently.
+pub fn partition_graph_parallel(flat_graph: HydroflowGraph) -> Result<HydroflowGraph, Diagnostic> {
+    let fan_out_edges = find_fan_out_edges(&flat_graph);
+    partition_graph_split(flat_graph, fan_out_edges)
+}
+
+fn partition_graph_split(
+    flat_graph: HydroflowGraph,
+    split_edges: BTreeSet<GraphEdgeId>,
+) -> Result<HydroflowGraph, Diagnostic> {
     assert_edgetypes_set(&flat_graph);
 
     // Pre-find barrier crossers (input edges with a `DelayType`).
@@ -468,7 +503,7 @@ pub fn partition_graph(flat_graph: HydroflowGraph) -> Result<HydroflowGraph, Dia
     let mut partitioned_graph = flat_graph;
 
     // Partition into subgraphs.
-    make_subgraphs(&mut partitioned_graph, &mut barrier_crossers);
+    make_subgraphs(&mut partitioned_graph, &mut barrier_crossers, &split_edges);
 
     // Find strata for subgraphs (early returns with error if negative cycle found).
     find_subgraph_strata(&mut partitioned_graph, &barrier_crossers)?;
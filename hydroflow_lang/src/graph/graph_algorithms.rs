@@ -23,16 +23,22 @@ where
     let topo_sort_order = {
         // Condensed each SCC into a single node for toposort.
         let mut condensed_preds: BTreeMap<Id, Vec<Id>> = Default::default();
+        let mut members: BTreeMap<Id, Vec<Id>> = Default::default();
         for u in (nodes_fn)() {
             condensed_preds
                 .entry(scc[&u])
                 .or_default()
                 .extend((preds_fn)(u).into_iter().map(|v| scc[&v]));
+            members.entry(scc[&u]).or_default().push(u);
         }
 
-        topo_sort((nodes_fn)(), |v| {
+        // Sort the SCCs by their representatives, then expand each SCC into its members.
+        topo_sort(members.keys().copied(), |v| {
             condensed_preds.get(&v).into_iter().flatten().cloned()
         })
+        .into_iter()
+        .flat_map(|v| members.remove(&v).unwrap())
+        .collect()
     };
     topo_sort_order
 }
@@ -235,4 +241,49 @@ mod test {
 
         assert_eq!(scc[&'f'], scc[&'g']);
     }
+
+    #[test]
+    pub fn test_topo_sort_scc() {
+        // Same graph as `test_scc_kosaraju`.
+        let edges = [
+            ('a', 'b'),
+            ('b', 'c'),
+            ('b', 'f'),
+            ('b', 'e'),
+            ('c', 'd'),
+            ('c', 'g'),
+            ('d', 'c'),
+            ('d', 'h'),
+            ('e', 'a'),
+            ('e', 'f'),
+            ('f', 'g'),
+            ('g', 'f'),
+            ('h', 'd'),
+            ('h', 'g'),
+        ];
+
+        // Nodes are given in reverse, so components are not already in order.
+        let sort = topo_sort_scc(
+            || ('a'..='h').rev(),
+            |v| {
+                edges
+                    .iter()
+                    .filter(move |&&(_, dst)| v == dst)
+                    .map(|&(src, _)| src)
+            },
+            |u| {
+                edges
+                    .iter()
+                    .filter(move |&&(src, _)| u == src)
+                    .map(|&(_, dst)| dst)
+            },
+        );
+
+        assert_eq!(8, sort.len());
+        let position: BTreeMap<_, _> = sort.iter().enumerate().map(|(i, &x)| (x, i)).collect();
+        // Edges between components go forwards.
+        for (src, dst) in [('b', 'c'), ('b', 'f'), ('e', 'f'), ('c', 'g'), ('h', 'g')] {
+            assert!(position[&src] < position[&dst]);
+        }
+    }
 }
@@ -178,7 +178,7 @@ where
                 Some(LatticeFlowType::Cumul) => "==",
             },
             arrow_head = match delay_type {
-                None | Some(DelayType::MonotoneAccum) => ">",
+                None | Some(DelayType::MonotoneAccum | DelayType::Buffer) => ">",
                 Some(DelayType::Stratum) => "x",
                 Some(DelayType::Tick | DelayType::TickLazy) => "o",
             },
@@ -200,6 +200,7 @@ where
                     | (Some(DelayType::Tick), _)
                     | (Some(DelayType::TickLazy), _) => "red",
                     (Some(DelayType::MonotoneAccum), _) | (None, Some(_)) => "#060",
+                    (Some(DelayType::Buffer), _) => "#00f",
                 }
             )?;
         }
@@ -12,6 +12,7 @@ use slotmap::{Key, SecondaryMap, SlotMap, SparseSecondaryMap};
 use syn::spanned::Spanned;
 
 use super::graph_write::{Dot, GraphWrite, Mermaid};
+use super::ops::bounded::BOUNDED;
 use super::ops::{find_op_op_constraints, OperatorWriteOutput, WriteContextArgs, OPERATORS};
 use super::{
     get_operator_generics, Color, DiMulGraph, FlowProps, GraphEdgeId, GraphEdgeType, GraphNode,
@@ -56,6 +57,9 @@ pub struct HydroflowGraph {
 
     /// What variable name each graph node belongs to (if any).
     node_varnames: SparseSecondaryMap<GraphNodeId, Varname>,
+    /// Notes attached to graph nodes, shown as comments when the graph is written.
+    #[serde(default, skip_serializing_if = "SparseSecondaryMap::is_empty")]
+    node_labels: SparseSecondaryMap<GraphNodeId, String>,
 
     // TODO(mingwei): #[serde(skip)] this and recompute as needed, to reduce codegen.
     /// Stream properties.
@@ -95,6 +99,17 @@ impl HydroflowGraph {
         self.node_varnames.get(node_id).map(|x| x.0.clone())
     }
 
+    /// Get the label attached to a graph node, if any.
+    pub fn node_label(&self, node_id: GraphNodeId) -> Option<&str> {
+        self.node_labels.get(node_id).map(String::as_str)
+    }
+
+    /// Attach a label to a graph node, shown as a comment under the node when the graph is
+    /// written (e.g. with [`Self::to_mermaid`]). Replaces any previous label.
+    pub fn set_node_label(&mut self, node_id: GraphNodeId, label: String) {
+        self.node_labels.insert(node_id, label);
+    }
+
     /// Get subgraph for node.
     pub fn node_subgraph(&self, node_id: GraphNodeId) -> Option<GraphSubgraphId> {
         self.node_subgraph.get(node_id).copied()
@@ -409,6 +424,7 @@ impl HydroflowGraph {
             self.graph.remove_intermediate_vertex(node_id).unwrap();
         self.operator_instances.remove(node_id);
         self.node_varnames.remove(node_id);
+        self.node_labels.remove(node_id);
 
         let (src_port, _) = self.ports.remove(pred_edge_id).unwrap();
         let (_, dst_port) = self.ports.remove(succ_edge_id).unwrap();
@@ -802,16 +818,30 @@ impl HydroflowGraph {
     }
 
     /// Emit this `HydroflowGraph` as runnable Rust source code tokens.
+    ///
+    /// If `checkpointed` is set, all operator state and handoffs will be added with checkpoint
+    /// support, which requires all their types to be serializable.
+    ///
+    /// If `parallel` is set, handoffs are thread-safe and subgraphs made only of
+    /// [`OperatorWriteOutput::is_parallel_safe`] operators are added with `add_subgraph_parallel`,
+    /// so they can run on worker threads. Not supported together with `checkpointed`.
     pub fn as_code(
         &self,
         root: &TokenStream,
         include_type_guards: bool,
+        checkpointed: bool,
+        parallel: bool,
         prefix: TokenStream,
         diagnostics: &mut Vec<Diagnostic>,
     ) -> TokenStream {
         let hf = Ident::new(HYDROFLOW, Span::call_site());
         let context = Ident::new(CONTEXT, Span::call_site());
 
+        let hoff_type = if parallel {
+            quote! { #root::scheduled::handoff::SendVecHandoff<_> }
+        } else {
+            quote! { #root::scheduled::handoff::VecHandoff<_> }
+        };
         let handoffs = self
             .nodes
             .iter()
@@ -824,9 +854,22 @@ impl HydroflowGraph {
                 let ident_send = Ident::new(&*format!("hoff_{:?}_send", node_id.data()), dst_span);
                 let ident_recv = Ident::new(&*format!("hoff_{:?}_recv", node_id.data()), src_span);
                 let hoff_name = Literal::string(&*format!("handoff {:?}", node_id));
-                quote! {
-                    let (#ident_send, #ident_recv) =
-                        #hf.make_edge::<_, #root::scheduled::handoff::VecHandoff<_>>(#hoff_name);
+                // Handoffs into `bounded(capacity)` operators are created with that capacity.
+                let capacity = self
+                    .node_successor_nodes(node_id)
+                    .filter_map(|succ_id| self.node_op_inst(succ_id))
+                    .find(|op_inst| BOUNDED.name == op_inst.op_constraints.name)
+                    .map(|op_inst| &op_inst.arguments[0]);
+                if let Some(capacity) = capacity {
+                    quote! {
+                        let (#ident_send, #ident_recv) =
+                            #hf.make_edge_bounded::<_, #hoff_type>(#hoff_name, #capacity);
+                    }
+                } else {
+                    quote! {
+                        let (#ident_send, #ident_recv) =
+                            #hf.make_edge::<_, #hoff_type>(#hoff_name);
+                    }
                 }
             });
 
@@ -849,6 +892,8 @@ impl HydroflowGraph {
                 .iter()
                 .chain(subgraphs_with_preds.iter())
             {
+                let mut subgraph_op_prologue_code = Vec::new();
+
                 let (recv_hoffs, send_hoffs) = &subgraph_handoffs[subgraph_id];
                 let recv_ports: Vec<Ident> = recv_hoffs
                     .iter()
@@ -875,6 +920,8 @@ impl HydroflowGraph {
 
                 let mut subgraph_op_iter_code = Vec::new();
                 let mut subgraph_op_iter_after_code = Vec::new();
+                let mut subgraph_is_parallel_safe = true;
+                let mut subgraph_state_handles = Vec::new();
                 {
                     let pull_to_push_idx = self.find_pull_to_push_idx(subgraph_nodes);
 
@@ -999,6 +1046,8 @@ impl HydroflowGraph {
                                 write_prologue,
                                 write_iterator,
                                 write_iterator_after,
+                                is_parallel_safe,
+                                state_handles,
                             } = write_result.unwrap_or_else(|()| {
                                 assert!(
                                     diagnostics.iter().any(Diagnostic::is_error),
@@ -1008,8 +1057,10 @@ impl HydroflowGraph {
                                 OperatorWriteOutput { write_iterator: null_write_iterator_fn(&context_args), ..Default::default() }
                             });
 
-                            op_prologue_code.push(write_prologue);
+                            subgraph_op_prologue_code.push(write_prologue);
                             subgraph_op_iter_code.push(write_iterator);
+                            subgraph_is_parallel_safe &= is_parallel_safe;
+                            subgraph_state_handles.extend(state_handles);
 
                             if include_type_guards {
                                 let source_info = {
@@ -1149,10 +1200,35 @@ impl HydroflowGraph {
                 };
 
                 let hoff_name = Literal::string(&*format!("Subgraph {:?}", subgraph_id));
+                op_prologue_code.extend(subgraph_op_prologue_code);
+                // Attribute state added by this subgraph's operators to the subgraph.
+                op_prologue_code.extend(subgraph_state_handles.iter().map(|state_handle| {
+                    quote! {
+                        #hf.__set_state_owner(#state_handle, #hoff_name);
+                    }
+                }));
+
                 let stratum = Literal::usize_unsuffixed(
                     self.subgraph_stratum.get(subgraph_id).cloned().unwrap_or(0),
                 );
                 let laziness = self.subgraph_laziness(subgraph_id);
+                if parallel && subgraph_is_parallel_safe && !laziness {
+                    subgraphs.push(quote! {
+                        #hf.add_subgraph_parallel(
+                            #hoff_name,
+                            #stratum,
+                            var_expr!( #( #recv_ports ),* ),
+                            var_expr!( #( #send_ports ),* ),
+                            move |#context, var_args!( #( #recv_ports ),* ), var_args!( #( #send_ports ),* )| {
+                                #( #recv_port_code )*
+                                #( #send_port_code )*
+                                #( #subgraph_op_iter_code )*
+                                #( #subgraph_op_iter_after_code )*
+                            },
+                        );
+                    });
+                    continue;
+                }
                 subgraphs.push(quote! {
                     #hf.add_subgraph_stratified(
                         #hoff_name,
@@ -1188,6 +1264,20 @@ impl HydroflowGraph {
         let diagnostics_json = serde_json::to_string(&*serde_diagnostics).unwrap();
         let diagnostics_json = Literal::string(&*diagnostics_json);
 
+        // In checkpointed mode, `CheckpointedBuilder` shadows `add_state` and `make_edge` with
+        // versions which register the state/handoffs for checkpointing.
+        let (hf_new, hf_build) = if checkpointed {
+            (
+                quote! { #root::scheduled::checkpoint::CheckpointedBuilder::new() },
+                quote! { #hf.build() },
+            )
+        } else {
+            (
+                quote! { #root::scheduled::graph::Hydroflow::new() },
+                quote! { #hf },
+            )
+        };
+
         quote! {
             {
                 #[allow(unused_qualifications)]
@@ -1196,13 +1286,13 @@ impl HydroflowGraph {
 
                     use #root::{var_expr, var_args};
 
-                    let mut #hf = #root::scheduled::graph::Hydroflow::new();
+                    let mut #hf = #hf_new;
                     #hf.__assign_meta_graph(#meta_graph_json);
                     #hf.__assign_diagnostics(#diagnostics_json);
 
                     #code
 
-                    #hf
+                    #hf_build
                 }
             }
         }
@@ -1347,13 +1437,17 @@ impl HydroflowGraph {
                     }
                 }
             }
+            let mut node_text = if write_config.op_short_text {
+                node.to_name_string()
+            } else {
+                node.to_pretty_string()
+            };
+            if let Some(label) = self.node_label(node_id) {
+                node_text = format!("{}\n// {}", node_text, label).into();
+            }
             graph_write.write_node(
                 node_id,
-                &*if write_config.op_short_text {
-                    node.to_name_string()
-                } else {
-                    node.to_pretty_string()
-                },
+                &node_text,
                 if write_config.no_pull_push {
                     None
                 } else {
@@ -31,7 +31,7 @@ use std::path::PathBuf;
 pub use di_mul_graph::DiMulGraph;
 pub use eliminate_extra_unions_tees::eliminate_extra_unions_tees;
 pub use flat_graph_builder::FlatGraphBuilder;
-pub use flat_to_partitioned::partition_graph;
+pub use flat_to_partitioned::{partition_graph, partition_graph_parallel};
 pub use flow_props::*;
 pub use hydroflow_graph::{HydroflowGraph, WriteConfig, WriteGraphType};
 
@@ -385,9 +385,13 @@ impl Display for PortIndexValue {
 
 /// The main function of this module. Compiles a [`HfCode`] AST into a [`HydroflowGraph`] and
 /// source code, or [`Diagnostic`] errors.
+///
+/// See [`HydroflowGraph::as_code`] for `checkpointed` and `parallel`.
 pub fn build_hfcode(
     hf_code: HfCode,
     root: &TokenStream,
+    checkpointed: bool,
+    parallel: bool,
     macro_invocation_path: PathBuf,
 ) -> (Option<(HydroflowGraph, TokenStream)>, Vec<Diagnostic>) {
     let flat_graph_builder = FlatGraphBuilder::from_hfcode(hf_code, macro_invocation_path);
@@ -399,7 +403,12 @@ pub fn build_hfcode(
         }
 
         eliminate_extra_unions_tees(&mut flat_graph);
-        match partition_graph(flat_graph) {
+        let partitioned_graph = if parallel {
+            partition_graph_parallel(flat_graph)
+        } else {
+            partition_graph(flat_graph)
+        };
+        match partitioned_graph {
             Ok(mut partitioned_graph) => {
                 // Propagate flow properties throughout the graph.
                 // TODO(mingwei): Should this be done at a flat graph stage instead?
@@ -410,6 +419,8 @@ pub fn build_hfcode(
                     let code = partitioned_graph.as_code(
                         root,
                         true,
+                        checkpointed,
+                        parallel,
                         quote::quote! { #( #uses )* },
                         &mut diagnostics,
                     );
@@ -104,6 +104,7 @@ pub const _LATTICE_FOLD_BATCH: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after: Default::default(),
+            state_handles: vec![lattice_ident],
             ..Default::default()
         })
     },
@@ -131,6 +131,8 @@ pub const _LATTICE_JOIN_FUSED_JOIN: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator: _,
             write_iterator_after,
+            state_handles,
+            ..
         } = (super::join_fused::JOIN_FUSED.write_fn)(&wc, diagnostics).unwrap();
 
         assert!(is_pull);
@@ -187,6 +189,8 @@ pub const _LATTICE_JOIN_FUSED_JOIN: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles,
+            ..Default::default()
         })
     },
 };
@@ -156,6 +156,7 @@ pub const ANTI_JOIN: OperatorConstraints = OperatorConstraints {
         Ok(OperatorWriteOutput {
             write_prologue,
             write_iterator,
+            state_handles: vec![neg_antijoindata_ident, pos_antijoindata_ident],
             ..Default::default()
         })
     },
@@ -190,6 +190,11 @@ pub const ANTI_JOIN_MULTISET: OperatorConstraints = OperatorConstraints {
         Ok(OperatorWriteOutput {
             write_prologue,
             write_iterator,
+            state_handles: if Persistence::Static == persistences[0] {
+                vec![neg_antijoindata_ident, pos_antijoindata_ident]
+            } else {
+                vec![neg_antijoindata_ident]
+            },
             ..Default::default()
         })
     },
@@ -84,6 +84,7 @@ pub const ASSERT_EQ: OperatorConstraints = OperatorConstraints {
 
             #write_prologue
         };
+        owo.state_handles.push(assert_index_ident);
 
         Ok(owo)
     },
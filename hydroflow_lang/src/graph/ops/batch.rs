@@ -0,0 +1,110 @@
+use quote::quote_spanned;
+
+use super::{
+    DelayType, OperatorCategory, OperatorConstraints, OperatorWriteOutput, WriteContextArgs,
+    RANGE_0, RANGE_1,
+};
+use crate::graph::{GraphEdgeType, OperatorInstance};
+
+/// > 1 input stream of type `T`, 1 output stream of type `Vec<T>`
+///
+/// > Arguments: the maximum number of items in a batch, `max_items`, and the maximum time an item
+/// may wait, `max_delay`, as a [`Duration`](https://doc.rust-lang.org/stable/std/time/struct.Duration.html).
+///
+/// Buffers input items across ticks, emitting them in order as a `Vec` once `max_items` items are
+/// buffered, or once the oldest buffered item has waited `max_delay`, whichever comes first.
+/// Several full batches may be emitted in one tick. Useful for grouping items before an expensive
+/// sink, such as batching writes to [`dest_sink`](#dest_sink).
+///
+/// When items are buffered, a timer task is requested with
+/// [`Context::request_task`](https://hydro-project.github.io/hydroflow/doc/hydroflow/scheduled/context/struct.Context.html#method.request_task)
+/// which wakes the operator at the deadline, so partial batches are emitted in a later tick even
+/// with no further input. Unlike [`defer_signal`](#defer_signal), which releases items when
+/// signalled, and [`defer_tick`](#defer_tick), which releases them in the next tick, `batch`
+/// releases items based on their count and age.
+///
+/// Note this operator must be used within a Tokio runtime, and the Hydroflow program must be
+/// launched with `run_async` for partial batches to be emitted.
+///
+/// ```rustbook
+/// # #[hydroflow::main]
+/// # async fn main() {
+/// let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<Vec<u32>>();
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_iter(0..5)
+///         -> batch(2, std::time::Duration::from_millis(10))
+///         -> for_each(|x| out_send.send(x).unwrap());
+/// };
+/// tokio::time::timeout(std::time::Duration::from_millis(100), flow.run_async())
+///     .await
+///     .expect_err("Expected time out");
+///
+/// let out: Vec<_> = hydroflow::util::ready_iter(&mut out_recv).collect();
+/// assert_eq!(&[vec![0, 1], vec![2, 3], vec![4]], &*out);
+/// # }
+/// ```
+pub const BATCH: OperatorConstraints = OperatorConstraints {
+    name: "batch",
+    categories: &[OperatorCategory::Control],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 2,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   is_pull,
+                   op_inst: OperatorInstance { arguments, .. },
+                   ..
+               },
+               _| {
+        assert!(is_pull);
+
+        let max_items = &arguments[0];
+        let max_delay = &arguments[1];
+
+        let state_ident = wc.make_ident("batch_state");
+
+        let write_prologue = quote_spanned! {op_span=>
+            let #state_ident = #hydroflow.add_state(::std::cell::RefCell::new(
+                #root::util::batch::BatchState::new(#max_items, #max_delay)
+            ));
+        };
+
+        let input = &inputs[0];
+        let write_iterator = quote_spanned! {op_span=>
+            let #ident = {
+                let now = #root::tokio::time::Instant::now();
+                let mut state = #context.state_ref(#state_ident).borrow_mut();
+                let batches = state.take_batches(#input, now);
+                let timer_request = state.take_timer_request();
+                ::std::mem::drop(state);
+                if let Some(timer) = timer_request {
+                    #root::util::batch::wake_at_deadline(&mut *#context, timer);
+                }
+                batches.into_iter()
+            };
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            state_handles: vec![state_ident],
+            ..Default::default()
+        })
+    },
+};
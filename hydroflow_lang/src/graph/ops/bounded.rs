@@ -0,0 +1,42 @@
+use super::{
+    DelayType, OperatorCategory, OperatorConstraints, IDENTITY_WRITE_FN, RANGE_0, RANGE_1,
+};
+use crate::graph::GraphEdgeType;
+
+/// > 1 input stream of type T, 1 output stream of type T
+///
+/// > Arguments: The capacity of the input handoff, a `usize`.
+///
+/// Passes all items through unchanged, but buffers its input in a bounded handoff which provides
+/// backpressure. While the handoff holds at least `capacity` items, the upstream subgraph is not
+/// run, so any `source_stream`s feeding it stop being polled until this operator's subgraph
+/// drains the handoff.
+///
+/// This is useful when a fast source feeds a slower operator like `join`, to keep input buffered
+/// in the source (e.g. a channel or socket) rather than growing the handoff without limit. The
+/// capacity is a soft limit: a single run of the upstream subgraph may overfill the handoff.
+///
+/// ```hydroflow
+/// source_iter(0..10)
+///     -> bounded(4)
+///     -> assert_eq([0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+/// ```
+pub const BOUNDED: OperatorConstraints = OperatorConstraints {
+    name: "bounded",
+    categories: &[OperatorCategory::Control],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 1,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Buffer),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: IDENTITY_WRITE_FN,
+};
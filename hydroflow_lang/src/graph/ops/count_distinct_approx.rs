@@ -0,0 +1,149 @@
+use quote::quote_spanned;
+
+use super::{
+    DelayType, OpInstGenerics, OperatorCategory, OperatorConstraints, OperatorInstance,
+    OperatorWriteOutput, Persistence, WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::diagnostic::{Diagnostic, Level};
+use crate::graph::GraphEdgeType;
+
+/// > 1 input stream, 1 output stream
+///
+/// > Arguments: The `precision` of the sketch, between 4 and 16. The sketch uses `2^precision`
+/// bytes, and estimates have a relative standard error of about `1.04 / sqrt(2^precision)`.
+///
+/// Approximately counts the distinct items in the input, in bounded space, using a
+/// [`HyperLogLog`](https://hydro-project.github.io/hydroflow/doc/lattices/sketch/struct.HyperLogLog.html)
+/// sketch. Emits a single `u64` estimate. Items must implement
+/// [`Hash`](https://doc.rust-lang.org/std/hash/trait.Hash.html).
+///
+/// Unlike `unique::<'static>()`, the items themselves are never stored, so this is suitable for
+/// high-cardinality streams.
+///
+/// ```hydroflow
+/// source_iter((0..1000).chain(0..1000))
+///     -> count_distinct_approx(12)
+///     -> assert(|&estimate| (950..=1050).contains(&estimate));
+/// ```
+///
+/// `count_distinct_approx` can also be provided with one generic lifetime persistence argument,
+/// either `'tick` or `'static`, to specify how data persists. The default is `'tick`. With
+/// `'tick`, only the items within the current tick are counted. With `'static`, the sketch is kept
+/// across ticks and the updated estimate of all items so far is emitted each tick.
+///
+/// The `HyperLogLog` sketch is also a lattice, so sketches of different streams can be merged with
+/// [`lattice_fold`](#lattice_fold) to count the distinct items of their union. Only sketches with
+/// the same `precision` can be merged: merging mismatched sketches panics in debug builds and
+/// ignores the other sketch in release builds. Use `HyperLogLog::try_merge` to check sketches
+/// received from other nodes.
+pub const COUNT_DISTINCT_APPROX: OperatorConstraints = OperatorConstraints {
+    name: "count_distinct_approx",
+    categories: &[OperatorCategory::Fold],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 1,
+    persistence_args: &(0..=1),
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   is_pull,
+                   op_inst:
+                       OperatorInstance {
+                           arguments,
+                           generics:
+                               OpInstGenerics {
+                                   persistence_args, ..
+                               },
+                           ..
+                       },
+                   ..
+               },
+               diagnostics| {
+        assert!(is_pull);
+
+        let persistence = match persistence_args[..] {
+            [] => Persistence::Tick,
+            [a] => a,
+            _ => unreachable!(),
+        };
+
+        let input = &inputs[0];
+        let precision = &arguments[0];
+
+        let sketchdata_ident = wc.make_ident("sketchdata");
+        let sketch_ident = wc.make_ident("sketch");
+
+        let (write_prologue, write_iterator, write_iterator_after) = match persistence {
+            Persistence::Tick => (
+                // Construct the empty sketch up front so an invalid `precision` panics when the
+                // graph is built, not on the first tick.
+                quote_spanned! {op_span=>
+                    let #sketchdata_ident = #root::lattices::sketch::HyperLogLog::new(#precision);
+                },
+                quote_spanned! {op_span=>
+                    let #ident = {
+                        let mut #sketch_ident = ::std::clone::Clone::clone(&#sketchdata_ident);
+                        for item in #input {
+                            #sketch_ident.insert(&item);
+                        }
+                        ::std::iter::once(#sketch_ident.estimate())
+                    };
+                },
+                Default::default(),
+            ),
+            Persistence::Static => (
+                quote_spanned! {op_span=>
+                    let #sketchdata_ident = #hydroflow.add_state(::std::cell::RefCell::new(
+                        #root::lattices::sketch::HyperLogLog::new(#precision)
+                    ));
+                },
+                quote_spanned! {op_span=>
+                    let #ident = {
+                        let mut #sketch_ident = #context.state_ref(#sketchdata_ident).borrow_mut();
+                        for item in #input {
+                            #sketch_ident.insert(&item);
+                        }
+                        ::std::iter::once(#sketch_ident.estimate())
+                    };
+                },
+                quote_spanned! {op_span=>
+                    #context.schedule_subgraph(#context.current_subgraph(), false);
+                },
+            ),
+            Persistence::Mutable => {
+                diagnostics.push(Diagnostic::spanned(
+                    op_span,
+                    Level::Error,
+                    "An implementation of 'mutable does not exist",
+                ));
+                return Err(());
+            }
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            write_iterator_after,
+            state_handles: if Persistence::Static == persistence {
+                vec![sketchdata_ident]
+            } else {
+                vec![]
+            },
+            ..Default::default()
+        })
+    },
+};
@@ -81,6 +81,7 @@ pub const DEFER_SIGNAL: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after: Default::default(),
+            state_handles: vec![internal_buffer],
             ..Default::default()
         })
     },
@@ -181,6 +181,7 @@ pub const DEMUX: OperatorConstraints = OperatorConstraints {
 
         Ok(OperatorWriteOutput {
             write_iterator,
+            is_parallel_safe: true,
             ..Default::default()
         })
     },
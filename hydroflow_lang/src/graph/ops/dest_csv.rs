@@ -0,0 +1,97 @@
+use quote::quote_spanned;
+use syn::parse_quote_spanned;
+
+use super::{
+    make_missing_runtime_msg, OperatorCategory, OperatorConstraints, OperatorInstance,
+    OperatorWriteOutput, WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::graph::GraphEdgeType;
+
+/// > 1 input stream, 0 output streams
+///
+/// > Arguments: (1) An [`AsRef`](https://doc.rust-lang.org/std/convert/trait.AsRef.html)`<`[`Path`](https://doc.rust-lang.org/nightly/std/path/struct.Path.html)`>`
+/// for a file to write to, and (2) a bool `append`.
+///
+/// Consumes records by writing them as rows to a CSV file. A header row is written first, with the
+/// field names of struct records or the field indices of other records (such as tuples). Fields
+/// must be scalars, such as numbers, strings, or `Option`s of those, where `None` is an empty
+/// field. The file will be created if it doesn't exist. Rows will be appended to the file if
+/// `append` is true, without another header row if the file is not empty, otherwise the file will
+/// be truncated before rows are written.
+///
+/// Like [`dest_sink_serde`](#dest_sink_serde), records may be of any type which implements
+/// [`Serialize`](https://docs.rs/serde/latest/serde/ser/trait.Serialize.html), and are written
+/// asynchronously. Note this operator must be used within a Tokio runtime. To ensure all records
+/// are written and flushed, launch the Hydroflow program with `run_until`.
+///
+/// ```hydroflow
+/// source_iter([("hello", 1), ("world", 2)])
+///     -> dest_csv(std::env::temp_dir().join("dest.csv"), false);
+/// ```
+pub const DEST_CSV: OperatorConstraints = OperatorConstraints {
+    name: "dest_csv",
+    categories: &[OperatorCategory::Sink],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_0,
+    soft_range_out: RANGE_0,
+    num_args: 2,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| None,
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   op_span,
+                   op_name,
+                   op_inst: OperatorInstance { arguments, .. },
+                   ..
+               },
+               diagnostics| {
+        let filename_arg = &arguments[0];
+        let append_arg = &arguments[1];
+
+        let ident_filesink = wc.make_ident("filesink");
+
+        let missing_runtime_msg = make_missing_runtime_msg(op_name);
+
+        let write_prologue = quote_spanned! {op_span=>
+            let #ident_filesink = #root::util::serde_file::csv_file_sink(#filename_arg, #append_arg);
+        };
+        let wc = WriteContextArgs {
+            op_inst: &OperatorInstance {
+                arguments: parse_quote_spanned!(op_span=> #ident_filesink),
+                ..wc.op_inst.clone()
+            },
+            ..wc.clone()
+        };
+
+        let OperatorWriteOutput {
+            write_prologue: write_prologue_sink,
+            write_iterator,
+            write_iterator_after,
+            ..
+        } = (super::dest_sink::DEST_SINK.write_fn)(&wc, diagnostics)?;
+
+        let write_prologue = quote_spanned! {op_span=>
+            #write_prologue
+            #write_prologue_sink
+        };
+        let write_iterator = quote_spanned! {op_span=>
+            ::std::debug_assert!(#root::tokio::runtime::Handle::try_current().is_ok(), #missing_runtime_msg);
+            #write_iterator
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            write_iterator_after,
+            ..Default::default()
+        })
+    },
+};
@@ -83,6 +83,7 @@ pub const DEST_FILE: OperatorConstraints = OperatorConstraints {
             write_prologue: write_prologue_sink,
             write_iterator,
             write_iterator_after,
+            ..
         } = (super::dest_sink::DEST_SINK.write_fn)(&wc, diagnostics)?;
 
         let write_prologue = quote_spanned! {op_span=>
@@ -98,6 +99,7 @@ pub const DEST_FILE: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            ..Default::default()
         })
     },
 };
@@ -12,7 +12,8 @@ use crate::graph::GraphEdgeType;
 /// A `Sink` is a thing into which values can be sent, asynchronously. For example, sending items
 /// into a bounded channel.
 ///
-/// Note this operator must be used within a Tokio runtime, and the Hydroflow program must be launched with `run_async`.
+/// Note this operator must be used within a Tokio runtime, and the Hydroflow program must be launched with `run_async`
+/// or `run_until`. `run_until` waits for all items to be sent and flushed before returning.
 ///
 /// ```rustbook
 /// # #[hydroflow::main]
@@ -55,6 +55,7 @@ pub const DEST_SINK_SERDE: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            ..
         } = (super::dest_sink::DEST_SINK.write_fn)(wc, diagnostics)?;
 
         let write_iterator = quote_spanned! {op_span=>
@@ -70,6 +71,7 @@ pub const DEST_SINK_SERDE: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            ..Default::default()
         })
     },
 };
@@ -0,0 +1,86 @@
+use quote::quote_spanned;
+
+use super::{
+    make_missing_runtime_msg, OperatorCategory, OperatorConstraints, OperatorWriteOutput,
+    WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::graph::{GraphEdgeType, OperatorInstance};
+
+/// > 1 input stream of `(T, SocketAddr)`, 0 output streams
+///
+/// > Arguments: An [`Encoder`](https://docs.rs/tokio-util/latest/tokio_util/codec/trait.Encoder.html)`<T>` codec.
+///
+/// Sends each `(item, addr)` pair to `addr` as a UDP datagram, encoding `item` with the codec.
+/// Datagrams are sent from an ephemeral port. Items which fail to encode or send are dropped.
+///
+/// Note this operator must be used within a Tokio runtime.
+///
+/// ```rustbook
+/// async fn udp_out() {
+///     let remote = hydroflow::util::ipv4_resolve("localhost:9000").unwrap();
+///     let mut flow = hydroflow::hydroflow_syntax! {
+///         source_iter(["hello", "world"])
+///             -> map(|line| (line, remote))
+///             -> dest_udp(hydroflow::tokio_util::codec::LinesCodec::new());
+///     };
+///     flow.run_async().await;
+/// }
+/// ```
+pub const DEST_UDP: OperatorConstraints = OperatorConstraints {
+    name: "dest_udp",
+    categories: &[OperatorCategory::Sink],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_0,
+    soft_range_out: RANGE_0,
+    num_args: 1,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| None,
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   op_name,
+                   is_pull,
+                   op_inst: OperatorInstance { arguments, .. },
+                   ..
+               },
+               _| {
+        assert!(!is_pull);
+
+        let codec_arg = &arguments[0];
+
+        let send_ident = wc.make_ident("item_send");
+        let recv_ident = wc.make_ident("item_recv");
+
+        let missing_runtime_msg = make_missing_runtime_msg(op_name);
+
+        let write_prologue = quote_spanned! {op_span=>
+            let (#send_ident, #recv_ident) = #root::tokio::sync::mpsc::unbounded_channel();
+            #hydroflow.request_task(#root::util::udp_sender(#recv_ident, #codec_arg));
+        };
+
+        let write_iterator = quote_spanned! {op_span=>
+            ::std::debug_assert!(#root::tokio::runtime::Handle::try_current().is_ok(), #missing_runtime_msg);
+            let #ident = #root::pusherator::for_each::ForEach::new(|item| {
+                if let Err(err) = #send_ident.send(item) {
+                    panic!("Failed to send UDP item for processing: {}", err);
+                }
+            });
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            ..Default::default()
+        })
+    },
+};
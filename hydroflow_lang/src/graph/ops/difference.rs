@@ -57,6 +57,8 @@ pub const DIFFERENCE: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles,
+            ..
         } = (super::anti_join::ANTI_JOIN.write_fn)(wc, diagnostics)?;
 
         let pos = &inputs[1];
@@ -70,6 +72,8 @@ pub const DIFFERENCE: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles,
+            ..Default::default()
         })
     },
 };
@@ -0,0 +1,92 @@
+use quote::quote_spanned;
+use syn::parse_quote;
+
+use super::{
+    DelayType, OperatorCategory, OperatorConstraints, OperatorWriteOutput, WriteContextArgs,
+    RANGE_0, RANGE_1,
+};
+use crate::graph::GraphEdgeType;
+
+/// > 2 input streams of `(T, isize)` diffs, 1 output stream of `(T, isize)` diffs
+///
+/// The differential version of [`difference`](#difference). Diffs are accumulated on both the
+/// `pos` and `neg` inputs across ticks. The output contains each value with its `pos`
+/// multiplicity, unless its `neg` multiplicity is positive, in which case it is absent. Each tick
+/// only the changes to the output are emitted, so retracting a value from `neg` re-inserts it in
+/// the output.
+///
+/// ```rustbook
+/// let (pos_send, pos_recv) = hydroflow::util::unbounded_channel::<(&str, isize)>();
+/// let (neg_send, neg_recv) = hydroflow::util::unbounded_channel::<(&str, isize)>();
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_stream(pos_recv) -> [pos]diff;
+///     source_stream(neg_recv) -> [neg]diff;
+///     diff = difference_diff() -> for_each(|diff| println!("{:?}", diff));
+/// };
+///
+/// pos_send.send(("dog", 1)).unwrap();
+/// pos_send.send(("cat", 1)).unwrap();
+/// neg_send.send(("cat", 1)).unwrap();
+/// flow.run_tick();
+/// // ("dog", 1)
+///
+/// neg_send.send(("cat", -1)).unwrap();
+/// flow.run_tick();
+/// // ("cat", 1)
+/// ```
+pub const DIFFERENCE_DIFF: OperatorConstraints = OperatorConstraints {
+    name: "difference_diff",
+    categories: &[OperatorCategory::MultiIn],
+    hard_range_inn: &(2..=2),
+    soft_range_inn: &(2..=2),
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 0,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: Some(|| super::PortListSpec::Fixed(parse_quote! { pos, neg })),
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   is_pull,
+                   ..
+               },
+               _| {
+        assert!(is_pull);
+
+        // Inputs are sorted by port name.
+        let neg = &inputs[0];
+        let pos = &inputs[1];
+        let state_ident = wc.make_ident("state");
+
+        let write_prologue = quote_spanned! {op_span=>
+            let #state_ident = #hydroflow.add_state(::std::cell::RefCell::new(
+                #root::util::diff::DifferenceDiffState::default()
+            ));
+        };
+        let write_iterator = quote_spanned! {op_span=>
+            let #ident = #context
+                .state_ref(#state_ident)
+                .borrow_mut()
+                .apply(#pos, #neg)
+                .into_iter();
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            state_handles: vec![state_ident],
+            ..Default::default()
+        })
+    },
+};
@@ -58,6 +58,8 @@ pub const DIFFERENCE_MULTISET: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles,
+            ..
         } = (super::anti_join_multiset::ANTI_JOIN_MULTISET.write_fn)(wc, diagnostics)?;
 
         let pos = &inputs[1];
@@ -71,6 +73,8 @@ pub const DIFFERENCE_MULTISET: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles,
+            ..Default::default()
         })
     },
 };
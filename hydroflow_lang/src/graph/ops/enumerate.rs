@@ -118,6 +118,7 @@ pub const ENUMERATE: OperatorConstraints = OperatorConstraints {
         Ok(OperatorWriteOutput {
             write_prologue,
             write_iterator,
+            state_handles: vec![counter_ident],
             ..Default::default()
         })
     },
@@ -63,6 +63,7 @@ pub const FILTER: OperatorConstraints = OperatorConstraints {
         };
         Ok(OperatorWriteOutput {
             write_iterator,
+            is_parallel_safe: true,
             ..Default::default()
         })
     },
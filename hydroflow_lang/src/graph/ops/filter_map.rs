@@ -58,6 +58,7 @@ pub const FILTER_MAP: OperatorConstraints = OperatorConstraints {
         };
         Ok(OperatorWriteOutput {
             write_iterator,
+            is_parallel_safe: true,
             ..Default::default()
         })
     },
@@ -65,6 +65,7 @@ pub const FLAT_MAP: OperatorConstraints = OperatorConstraints {
         };
         Ok(OperatorWriteOutput {
             write_iterator,
+            is_parallel_safe: true,
             ..Default::default()
         })
     },
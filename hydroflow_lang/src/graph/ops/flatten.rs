@@ -55,6 +55,7 @@ pub const FLATTEN: OperatorConstraints = OperatorConstraints {
         };
         Ok(OperatorWriteOutput {
             write_iterator,
+            is_parallel_safe: true,
             ..Default::default()
         })
     },
@@ -117,7 +117,7 @@ pub const FOLD: OperatorConstraints = OperatorConstraints {
 
                     #[allow(clippy::redundant_closure_call)]
                     let #folddata_ident = #hydroflow.add_state(
-                        ::std::cell::Cell::new(::std::option::Option::Some((#initializer_func_ident)()))
+                        ::std::cell::RefCell::new(::std::option::Option::Some((#initializer_func_ident)()))
                     );
                 },
                 quote_spanned! {op_span=>
@@ -137,7 +137,7 @@ pub const FOLD: OperatorConstraints = OperatorConstraints {
                             call_comb_type(&mut #accumulator_ident, #iterator_item_ident, #func);
                         }
 
-                        #context.state_ref(#folddata_ident).set(
+                        #context.state_ref(#folddata_ident).replace(
                             ::std::option::Option::Some(::std::clone::Clone::clone(&#accumulator_ident))
                         );
 
@@ -162,6 +162,12 @@ pub const FOLD: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles: if Persistence::Static == persistence {
+                vec![folddata_ident]
+            } else {
+                vec![]
+            },
+            ..Default::default()
         })
     },
 };
@@ -128,9 +128,9 @@ pub const FOLD_KEYED: OperatorConstraints = OperatorConstraints {
         let initfn = &arguments[0];
         let aggfn = &arguments[1];
 
+        let groupbydata_ident = wc.make_ident("groupbydata");
         let (write_prologue, write_iterator, write_iterator_after) = match persistence {
             Persistence::Tick => {
-                let groupbydata_ident = wc.make_ident("groupbydata");
                 let hashtable_ident = wc.make_ident("hashtable");
 
                 (
@@ -167,7 +167,6 @@ pub const FOLD_KEYED: OperatorConstraints = OperatorConstraints {
                 )
             }
             Persistence::Static => {
-                let groupbydata_ident = wc.make_ident("groupbydata");
                 let hashtable_ident = wc.make_ident("hashtable");
 
                 (
@@ -209,7 +208,6 @@ pub const FOLD_KEYED: OperatorConstraints = OperatorConstraints {
                 )
             }
             Persistence::Mutable => {
-                let groupbydata_ident = wc.make_ident("groupbydata");
                 let hashtable_ident = wc.make_ident("hashtable");
 
                 (
@@ -260,6 +258,8 @@ pub const FOLD_KEYED: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles: vec![groupbydata_ident],
+            ..Default::default()
         })
     },
 };
@@ -0,0 +1,101 @@
+use quote::quote_spanned;
+
+use super::{
+    DelayType, OperatorCategory, OperatorConstraints, OperatorWriteOutput, WriteContextArgs,
+    RANGE_0, RANGE_1,
+};
+use crate::graph::{GraphEdgeType, OperatorInstance};
+
+/// > 1 input stream of `((K, V), isize)` diffs, 1 output stream of `((K, A), isize)` diffs
+///
+/// > Arguments: two Rust closures. The first generates an initial value per group. The second
+/// takes an accumulator `&mut A` and a value `V`, and folds the value into the accumulator.
+///
+/// The differential version of [`fold_keyed`](#fold_keyed). Diffs are accumulated across ticks
+/// into a multiset of values per key. Each tick, only the groups touched by that tick's diffs are
+/// refolded. When a group's aggregate changes, `((key, old), -1)` and `((key, new), 1)` are
+/// emitted; a group left with no values retracts its aggregate. Values are folded in once per copy
+/// in no particular order, so the fold should be commutative, and the aggregate type must
+/// implement `Clone` and `Eq`.
+///
+/// Since a fold cannot be undone, each touched group is refolded from all of its values, so a tick
+/// costs time proportional to the total multiplicity of the groups it touches, not to the number
+/// of diffs. This is best suited to groups which stay small.
+///
+/// > Note: The closures have access to the [`context` object](surface_flows.md#the-context-object).
+///
+/// ```rustbook
+/// let (input_send, input_recv) = hydroflow::util::unbounded_channel::<((&str, u32), isize)>();
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_stream(input_recv)
+///         -> fold_keyed_diff(|| 0, |sum: &mut u32, val: u32| *sum += val)
+///         -> for_each(|diff| println!("{:?}", diff));
+/// };
+///
+/// input_send.send((("toy", 1), 1)).unwrap();
+/// input_send.send((("toy", 2), 1)).unwrap();
+/// flow.run_tick();
+/// // (("toy", 3), 1)
+///
+/// input_send.send((("toy", 1), -1)).unwrap();
+/// flow.run_tick();
+/// // (("toy", 3), -1)
+/// // (("toy", 2), 1)
+/// ```
+pub const FOLD_KEYED_DIFF: OperatorConstraints = OperatorConstraints {
+    name: "fold_keyed_diff",
+    categories: &[OperatorCategory::KeyedFold],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 2,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   is_pull,
+                   op_inst: OperatorInstance { arguments, .. },
+                   ..
+               },
+               _| {
+        assert!(is_pull);
+
+        let input = &inputs[0];
+        let initfn = &arguments[0];
+        let aggfn = &arguments[1];
+        let state_ident = wc.make_ident("state");
+
+        let write_prologue = quote_spanned! {op_span=>
+            let #state_ident = #hydroflow.add_state(::std::cell::RefCell::new(
+                #root::util::diff::FoldKeyedDiffState::default()
+            ));
+        };
+        let write_iterator = quote_spanned! {op_span=>
+            let #ident = #context
+                .state_ref(#state_ident)
+                .borrow_mut()
+                .apply(#input, #initfn, #aggfn)
+                .into_iter();
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            state_handles: vec![state_ident],
+            ..Default::default()
+        })
+    },
+};
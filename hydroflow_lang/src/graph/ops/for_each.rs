@@ -50,6 +50,7 @@ pub const FOR_EACH: OperatorConstraints = OperatorConstraints {
         };
         Ok(OperatorWriteOutput {
             write_iterator,
+            is_parallel_safe: true,
             ..Default::default()
         })
     },
@@ -0,0 +1,150 @@
+use quote::quote_spanned;
+
+use super::{
+    DelayType, OpInstGenerics, OperatorCategory, OperatorConstraints, OperatorInstance,
+    OperatorWriteOutput, Persistence, WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::diagnostic::{Diagnostic, Level};
+use crate::graph::GraphEdgeType;
+
+/// > 1 input stream, 1 output stream
+///
+/// > Arguments: (1) `k`, the number of most frequent items to emit, and (2) the `width` and (3)
+/// `depth` of the Count-Min sketch used to estimate counts.
+///
+/// Approximately finds the `k` most frequent items in the input, in bounded space, using a
+/// [`CountMinSketch`](https://hydro-project.github.io/hydroflow/doc/lattices/sketch/struct.CountMinSketch.html).
+/// Emits `(item, estimated_count)` pairs, from greatest to least count. Items must implement
+/// [`Hash`](https://doc.rust-lang.org/std/hash/trait.Hash.html), `Eq`, and `Clone`.
+///
+/// Only the `k` candidate items are stored, along with `width * depth` counters. Estimated counts
+/// are never less than the true counts. With probability `1 - e^-depth`, they are more by at most
+/// `e / width` times the total count of all items.
+///
+/// ```hydroflow
+/// source_iter(["a", "b", "a", "c", "a", "b"])
+///     -> heavy_hitters(2, 1024, 4)
+///     -> assert_eq([("a", 3), ("b", 2)]);
+/// ```
+///
+/// `heavy_hitters` can also be provided with one generic lifetime persistence argument, either
+/// `'tick` or `'static`, to specify how data persists. The default is `'tick`. With `'tick`, only
+/// the items within the current tick are counted. With `'static`, the sketch is kept across ticks
+/// and the updated heavy hitters of all items so far are emitted each tick.
+///
+/// `CountMinSketch`es can only be merged with sketches of the same `width` and `depth`: merging
+/// mismatched sketches, for example with [`lattice_fold`](#lattice_fold), panics in debug builds
+/// and ignores the other sketch in release builds. Use `CountMinSketch::try_merge` to check
+/// sketches received from other nodes.
+pub const HEAVY_HITTERS: OperatorConstraints = OperatorConstraints {
+    name: "heavy_hitters",
+    categories: &[OperatorCategory::Fold],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 3,
+    persistence_args: &(0..=1),
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   is_pull,
+                   op_inst:
+                       OperatorInstance {
+                           arguments,
+                           generics:
+                               OpInstGenerics {
+                                   persistence_args, ..
+                               },
+                           ..
+                       },
+                   ..
+               },
+               diagnostics| {
+        assert!(is_pull);
+
+        let persistence = match persistence_args[..] {
+            [] => Persistence::Tick,
+            [a] => a,
+            _ => unreachable!(),
+        };
+
+        let input = &inputs[0];
+        let k = &arguments[0];
+        let width = &arguments[1];
+        let depth = &arguments[2];
+
+        let sketchdata_ident = wc.make_ident("sketchdata");
+        let sketch_ident = wc.make_ident("sketch");
+        let (write_prologue, write_iterator, write_iterator_after) = match persistence {
+            Persistence::Tick => (
+                // Construct the empty sketch up front so invalid dimensions panic when the graph is
+                // built, not on the first tick.
+                quote_spanned! {op_span=>
+                    let #sketchdata_ident = #root::util::heavy_hitters::HeavyHitters::new(#k, #width, #depth);
+                },
+                quote_spanned! {op_span=>
+                    let #ident = {
+                        let mut #sketch_ident = ::std::clone::Clone::clone(&#sketchdata_ident);
+                        for item in #input {
+                            #sketch_ident.insert(item);
+                        }
+                        #sketch_ident.top().into_iter()
+                    };
+                },
+                Default::default(),
+            ),
+            Persistence::Static => (
+                quote_spanned! {op_span=>
+                    let #sketchdata_ident = #hydroflow.add_state(::std::cell::RefCell::new(
+                        #root::util::heavy_hitters::HeavyHitters::new(#k, #width, #depth)
+                    ));
+                },
+                quote_spanned! {op_span=>
+                    let #ident = {
+                        let mut #sketch_ident = #context.state_ref(#sketchdata_ident).borrow_mut();
+                        for item in #input {
+                            #sketch_ident.insert(item);
+                        }
+                        #sketch_ident.top().into_iter()
+                    };
+                },
+                quote_spanned! {op_span=>
+                    #context.schedule_subgraph(#context.current_subgraph(), false);
+                },
+            ),
+            Persistence::Mutable => {
+                diagnostics.push(Diagnostic::spanned(
+                    op_span,
+                    Level::Error,
+                    "An implementation of 'mutable does not exist",
+                ));
+                return Err(());
+            }
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            write_iterator_after,
+            state_handles: if Persistence::Static == persistence {
+                vec![sketchdata_ident]
+            } else {
+                vec![]
+            },
+            ..Default::default()
+        })
+    },
+};
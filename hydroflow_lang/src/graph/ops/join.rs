@@ -234,6 +234,8 @@ pub const JOIN: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles: vec![lhs_joindata_ident, rhs_joindata_ident],
+            ..Default::default()
         })
     },
 };
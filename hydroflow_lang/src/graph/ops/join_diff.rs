@@ -0,0 +1,88 @@
+use quote::quote_spanned;
+use syn::parse_quote;
+
+use super::{
+    DelayType, OperatorCategory, OperatorConstraints, OperatorWriteOutput, WriteContextArgs,
+    RANGE_0, RANGE_1,
+};
+use crate::graph::GraphEdgeType;
+
+/// > 2 input streams of `((K, V1), isize)` and `((K, V2), isize)` diffs, 1 output stream of
+/// `((K, (V1, V2)), isize)` diffs
+///
+/// The differential version of [`join_multiset`](#join_multiset). Diffs are accumulated on both
+/// inputs across ticks, and each joined pair has the product of the multiplicities of its two
+/// halves. Each tick only the changes to the join are emitted, computed from the changed keys
+/// alone: retracting a tuple from either input retracts every pair it was joined into.
+///
+/// ```rustbook
+/// let (lhs_send, lhs_recv) = hydroflow::util::unbounded_channel::<((&str, &str), isize)>();
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_stream(lhs_recv) -> [0]my_join;
+///     source_iter([(("hello", "cleveland"), 1)]) -> [1]my_join;
+///     my_join = join_diff() -> for_each(|diff| println!("{:?}", diff));
+/// };
+///
+/// lhs_send.send((("hello", "world"), 1)).unwrap();
+/// flow.run_tick();
+/// // (("hello", ("world", "cleveland")), 1)
+///
+/// lhs_send.send((("hello", "world"), -1)).unwrap();
+/// flow.run_tick();
+/// // (("hello", ("world", "cleveland")), -1)
+/// ```
+pub const JOIN_DIFF: OperatorConstraints = OperatorConstraints {
+    name: "join_diff",
+    categories: &[OperatorCategory::MultiIn],
+    hard_range_inn: &(2..=2),
+    soft_range_inn: &(2..=2),
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 0,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: Some(|| super::PortListSpec::Fixed(parse_quote! { 0, 1 })),
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   is_pull,
+                   ..
+               },
+               _| {
+        assert!(is_pull);
+
+        let lhs = &inputs[0];
+        let rhs = &inputs[1];
+        let state_ident = wc.make_ident("state");
+
+        let write_prologue = quote_spanned! {op_span=>
+            let #state_ident = #hydroflow.add_state(::std::cell::RefCell::new(
+                #root::util::diff::JoinDiffState::default()
+            ));
+        };
+        let write_iterator = quote_spanned! {op_span=>
+            let #ident = #context
+                .state_ref(#state_ident)
+                .borrow_mut()
+                .apply(#lhs, #rhs)
+                .into_iter();
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            state_handles: vec![state_ident],
+            ..Default::default()
+        })
+    },
+};
@@ -0,0 +1,179 @@
+use quote::quote_spanned;
+use syn::parse_quote;
+
+use super::{
+    DelayType, OperatorCategory, OperatorConstraints, OperatorWriteOutput, Persistence,
+    WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::diagnostic::{Diagnostic, Level};
+use crate::graph::{GraphEdgeType, OpInstGenerics, OperatorInstance};
+
+/// > 2 input streams of type <(K, V1)> and <(K, V2)>, 1 output stream of type <(K, (Option<V1>, Option<V2>))>
+///
+/// Forms the full outer join of the tuples in the input streams by their first (key) attribute.
+/// Keys which appear in both inputs are joined like [`join`](#join). Keys which only appear in one
+/// input are emitted with `None` for the other input's value.
+///
+/// ```hydroflow
+/// source_iter(vec![("a", 0), ("b", 1)]) -> [0]my_join;
+/// source_iter(vec![("a", 2), ("c", 3)]) -> [1]my_join;
+/// my_join = join_full()
+///     -> sort()
+///     -> assert_eq([("a", (Some(0), Some(2))), ("b", (Some(1), None)), ("c", (None, Some(3)))]);
+/// ```
+///
+/// Whether a key is unmatched is only known once all of a tick's input has arrived, so both inputs
+/// are blocking (stratum) edges, and the entire result is emitted at once.
+///
+/// Like `join`, `join_full` takes one or two generic lifetime persistence arguments, either
+/// `'tick` or `'static`. The first maps to port `0` and the second to port `1`, and a single
+/// argument applies to both. With `'static`, pairs are remembered across ticks. Whenever new input
+/// arrives, the entire outer join of all remembered pairs is re-emitted, not just the changed
+/// rows, so a row emitted with `None` in one tick is replaced by matched rows once a matching key
+/// arrives. Ticks without new input emit nothing.
+///
+/// ```rustbook
+/// let (input_send, input_recv) = hydroflow::util::unbounded_channel::<(&str, &str)>();
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_iter([("hello", "world")]) -> [0]my_join;
+///     source_stream(input_recv) -> [1]my_join;
+///     my_join = join_full::<'static>() -> for_each(|(k, (v1, v2))| println!("({}, ({:?}, {:?}))", k, v1, v2));
+/// };
+/// flow.run_tick();
+/// input_send.send(("hello", "oakland")).unwrap();
+/// flow.run_tick();
+/// ```
+/// Prints out `"(hello, (Some("world"), None))"` in the first tick, then
+/// `"(hello, (Some("world"), Some("oakland")))"` in the second tick.
+///
+/// `join_full` treats its inputs as *sets*, eliminating duplicate pairs, like `join`.
+pub const JOIN_FULL: OperatorConstraints = OperatorConstraints {
+    name: "join_full",
+    categories: &[OperatorCategory::MultiIn],
+    hard_range_inn: &(2..=2),
+    soft_range_inn: &(2..=2),
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 0,
+    persistence_args: &(0..=2),
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: Some(|| super::PortListSpec::Fixed(parse_quote! { 0, 1 })),
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   op_inst:
+                       OperatorInstance {
+                           generics:
+                               OpInstGenerics {
+                                   persistence_args, ..
+                               },
+                           ..
+                       },
+                   ..
+               },
+               diagnostics| {
+        let join_type = quote_spanned!(op_span=>
+            #root::compiled::pull::HalfSetJoinState
+        );
+
+        let mut make_joindata = |persistence, side| {
+            let joindata_ident = wc.make_ident(format!("joindata_{}", side));
+            let borrow_ident = wc.make_ident(format!("joindata_{}_borrow", side));
+            let (init, borrow) = match persistence {
+                Persistence::Tick => (
+                    quote_spanned! {op_span=>
+                        #root::util::monotonic_map::MonotonicMap::new_init(
+                            #join_type::default()
+                        )
+                    },
+                    quote_spanned! {op_span=>
+                        &mut *#borrow_ident.get_mut_clear(#context.current_tick())
+                    },
+                ),
+                Persistence::Static => (
+                    quote_spanned! {op_span=>
+                        #join_type::default()
+                    },
+                    quote_spanned! {op_span=>
+                        &mut *#borrow_ident
+                    },
+                ),
+                Persistence::Mutable => {
+                    diagnostics.push(Diagnostic::spanned(
+                        op_span,
+                        Level::Error,
+                        "An implementation of 'mutable does not exist",
+                    ));
+                    return Err(());
+                }
+            };
+            Ok((joindata_ident, borrow_ident, init, borrow))
+        };
+
+        let persistences = match persistence_args[..] {
+            [] => [Persistence::Tick, Persistence::Tick],
+            [a] => [a, a],
+            [a, b] => [a, b],
+            _ => unreachable!(),
+        };
+
+        let (lhs_joindata_ident, lhs_borrow_ident, lhs_init, lhs_borrow) =
+            make_joindata(persistences[0], "lhs")?;
+        let (rhs_joindata_ident, rhs_borrow_ident, rhs_init, rhs_borrow) =
+            make_joindata(persistences[1], "rhs")?;
+
+        let write_prologue = quote_spanned! {op_span=>
+            let #lhs_joindata_ident = #hydroflow.add_state(std::cell::RefCell::new(
+                #lhs_init
+            ));
+            let #rhs_joindata_ident = #hydroflow.add_state(std::cell::RefCell::new(
+                #rhs_init
+            ));
+        };
+
+        let lhs = &inputs[0];
+        let rhs = &inputs[1];
+        let write_iterator = quote_spanned! {op_span=>
+            let mut #lhs_borrow_ident = #context.state_ref(#lhs_joindata_ident).borrow_mut();
+            let mut #rhs_borrow_ident = #context.state_ref(#rhs_joindata_ident).borrow_mut();
+            let #ident = {
+                // Limit error propagation by bounding locally, erasing output iterator type.
+                #[inline(always)]
+                fn check_inputs<'a, K, I1, V1, I2, V2>(
+                    lhs: I1,
+                    rhs: I2,
+                    lhs_state: &'a mut #join_type<K, V1, V2>,
+                    rhs_state: &'a mut #join_type<K, V2, V1>,
+                ) -> impl 'a + Iterator<Item = (K, (::std::option::Option<V1>, ::std::option::Option<V2>))>
+                where
+                    K: Eq + std::hash::Hash + Clone,
+                    V1: Clone + ::std::cmp::Eq,
+                    V2: Clone + ::std::cmp::Eq,
+                    I1: 'a + Iterator<Item = (K, V1)>,
+                    I2: 'a + Iterator<Item = (K, V2)>,
+                {
+                    #root::compiled::pull::full_outer_join_into_iter(lhs, rhs, lhs_state, rhs_state)
+                }
+
+                check_inputs(#lhs, #rhs, #lhs_borrow, #rhs_borrow)
+            };
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            state_handles: vec![lhs_joindata_ident, rhs_joindata_ident],
+            ..Default::default()
+        })
+    },
+};
@@ -174,6 +174,12 @@ pub const JOIN_FUSED_LHS: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles: if Persistence::Static == persistences[1] {
+                vec![rhs_joindata_ident]
+            } else {
+                vec![]
+            },
+            ..Default::default()
         })
     },
 };
@@ -50,6 +50,8 @@ pub const JOIN_FUSED_RHS: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles,
+            ..
         } = (super::join_fused_lhs::JOIN_FUSED_LHS.write_fn)(&wc, diagnostics)?;
 
         let write_iterator = quote_spanned! {op_span=>
@@ -61,6 +63,8 @@ pub const JOIN_FUSED_RHS: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles,
+            ..Default::default()
         })
     },
 };
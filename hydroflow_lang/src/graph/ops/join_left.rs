@@ -0,0 +1,54 @@
+use quote::quote_spanned;
+use syn::parse_quote;
+
+use super::{
+    DelayType, OperatorCategory, OperatorConstraints, WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::graph::GraphEdgeType;
+
+/// > 2 input streams of type <(K, V1)> and <(K, V2)>, 1 output stream of type <(K, (V1, Option<V2>))>
+///
+/// Forms the left outer join of the tuples in the input streams by their first (key) attribute.
+/// Every pair in input `0` is emitted, joined with each matching value of input `1` like
+/// [`join`](#join), or with `None` if there are no matches.
+///
+/// ```hydroflow
+/// source_iter(vec![("a", 0), ("b", 1)]) -> [0]my_join;
+/// source_iter(vec![("a", 2), ("c", 3)]) -> [1]my_join;
+/// my_join = join_left()
+///     -> sort()
+///     -> assert_eq([("a", (0, Some(2))), ("b", (1, None))]);
+/// ```
+///
+/// Persistence arguments and blocking behavior are the same as [`join_full`](#join_full). With
+/// `'static`, the entire join of all remembered pairs is re-emitted whenever new input arrives, so a
+/// row emitted with `None` in one tick is replaced by matched rows once a matching key arrives.
+pub const JOIN_LEFT: OperatorConstraints = OperatorConstraints {
+    name: "join_left",
+    categories: &[OperatorCategory::MultiIn],
+    hard_range_inn: &(2..=2),
+    soft_range_inn: &(2..=2),
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 0,
+    persistence_args: &(0..=2),
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: Some(|| super::PortListSpec::Fixed(parse_quote! { 0, 1 })),
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs { op_span, ident, .. }, diagnostics| {
+        let mut output = (super::join_full::JOIN_FULL.write_fn)(wc, diagnostics)?;
+
+        let write_iterator = output.write_iterator;
+        output.write_iterator = quote_spanned!(op_span=>
+            #write_iterator
+            let #ident = #ident.filter_map(|(k, (v1, v2))| Some((k, (v1?, v2))));
+        );
+
+        Ok(output)
+    },
+};
@@ -72,6 +72,8 @@ pub const LATTICE_REDUCE: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles,
+            ..
         } = (super::reduce::REDUCE.write_fn)(&wc, diagnostics)?;
 
         assert_eq!(1, inputs.len());
@@ -96,6 +98,8 @@ pub const LATTICE_REDUCE: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles,
+            ..Default::default()
         })
     },
 };
@@ -0,0 +1,157 @@
+use quote::quote_spanned;
+
+use super::{
+    OpInstGenerics, OperatorCategory, OperatorConstraints, OperatorInstance, OperatorWriteOutput,
+    Persistence, WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::diagnostic::{Diagnostic, Level};
+use crate::graph::GraphEdgeType;
+
+/// > 1 input stream, 1 output stream
+///
+/// > Arguments: `n`, the maximum number of items to emit.
+///
+/// Passes through only the first `n` items, and drops the rest. Unlike [`top_k`](#top_k), `limit`
+/// does not block, and items are emitted in the order they are received.
+///
+/// ```hydroflow
+/// source_iter(0..10)
+///     -> limit(3)
+///     -> assert_eq([0, 1, 2]);
+/// ```
+///
+/// `limit` can also be provided with one generic lifetime persistence argument, either `'tick` or
+/// `'static`, to specify how data persists. The default is `'tick`. With `'tick`, up to `n` items
+/// are emitted each tick. With `'static`, only `n` items are emitted in total, across all ticks.
+///
+/// ```rustbook
+/// let (input_send, input_recv) = hydroflow::util::unbounded_channel::<usize>();
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_stream(input_recv)
+///         -> limit::<'static>(3)
+///         -> for_each(|n| println!("{}", n));
+/// };
+///
+/// input_send.send(0).unwrap();
+/// input_send.send(1).unwrap();
+/// flow.run_available();
+/// // 0, 1
+///
+/// input_send.send(2).unwrap();
+/// input_send.send(3).unwrap();
+/// flow.run_available();
+/// // 2
+/// ```
+pub const LIMIT: OperatorConstraints = OperatorConstraints {
+    name: "limit",
+    categories: &[OperatorCategory::Filter],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 1,
+    persistence_args: &(0..=1),
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| None,
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   op_span,
+                   context,
+                   hydroflow,
+                   ident,
+                   inputs,
+                   outputs,
+                   is_pull,
+                   op_inst:
+                       OperatorInstance {
+                           arguments,
+                           generics:
+                               OpInstGenerics {
+                                   persistence_args, ..
+                               },
+                           ..
+                       },
+                   ..
+               },
+               diagnostics| {
+        let persistence = match persistence_args[..] {
+            [] => Persistence::Tick,
+            [a] => a,
+            _ => unreachable!(),
+        };
+
+        let n = &arguments[0];
+        let limitdata_ident = wc.make_ident("limitdata");
+        let limit_ident = wc.make_ident("limit");
+
+        let (write_prologue, get_count) = match persistence {
+            Persistence::Tick => {
+                let write_prologue = quote_spanned! {op_span=>
+                    let #limit_ident: usize = #n;
+                    let #limitdata_ident = #hydroflow.add_state(::std::cell::RefCell::new(
+                        #root::util::monotonic_map::MonotonicMap::<_, usize>::default(),
+                    ));
+                };
+                let get_count = quote_spanned! {op_span=>
+                    let mut borrow = #context.state_ref(#limitdata_ident).borrow_mut();
+                    let count = borrow.get_mut_with((#context.current_tick(), #context.current_stratum()), || 0);
+                };
+                (write_prologue, get_count)
+            }
+            Persistence::Static => {
+                let write_prologue = quote_spanned! {op_span=>
+                    let #limit_ident: usize = #n;
+                    let #limitdata_ident = #hydroflow.add_state(::std::cell::RefCell::new(0_usize));
+                };
+                let get_count = quote_spanned! {op_span=>
+                    let mut count = #context.state_ref(#limitdata_ident).borrow_mut();
+                };
+                (write_prologue, get_count)
+            }
+            Persistence::Mutable => {
+                diagnostics.push(Diagnostic::spanned(
+                    op_span,
+                    Level::Error,
+                    "An implementation of 'mutable does not exist",
+                ));
+                return Err(());
+            }
+        };
+
+        let filter_fn = quote_spanned! {op_span=>
+            |_item| {
+                #get_count
+                if *count < #limit_ident {
+                    *count += 1;
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+        let write_iterator = if is_pull {
+            let input = &inputs[0];
+            quote_spanned! {op_span=>
+                let #ident = #input.filter(#filter_fn);
+            }
+        } else {
+            let output = &outputs[0];
+            quote_spanned! {op_span=>
+                let #ident = #root::pusherator::filter::Filter::new(#filter_fn, #output);
+            }
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            state_handles: vec![limitdata_ident],
+            ..Default::default()
+        })
+    },
+};
@@ -0,0 +1,112 @@
+use quote::quote_spanned;
+
+use super::{
+    DelayType, OperatorCategory, OperatorConstraints, OperatorWriteOutput, WriteContextArgs,
+    RANGE_0, RANGE_1,
+};
+use crate::graph::{GraphEdgeType, OperatorInstance};
+
+/// > 1 input stream, 1 output stream
+///
+/// > Arguments: A Rust closure which returns a `Future`, and the maximum number of futures to run
+/// at once, `max_in_flight`.
+///
+/// For each item passed in, apply the closure to create a future, and emit the future's output
+/// once it completes. Useful for calling external async services, such as a database lookup, from
+/// within a flow. Outputs are emitted in the same order as their input items. To emit outputs as
+/// soon as they are ready instead, use [`map_async_unordered`](#map_async_unordered).
+///
+/// At most `max_in_flight` items are started but not yet emitted at once, and further items are
+/// queued until earlier outputs are emitted. This includes completed outputs waiting for an earlier
+/// item to complete, so one slow item holds back later items rather than letting their outputs pile
+/// up. Futures are spawned as tasks, using
+/// [`Context::request_task`](https://hydro-project.github.io/hydroflow/doc/hydroflow/scheduled/context/struct.Context.html#method.request_task),
+/// which wake the operator when they complete, so outputs are emitted in a later tick.
+///
+/// Note this operator must be used within a Tokio runtime, and the Hydroflow program must be
+/// launched with `run_async` or `run_until`. `run_until` waits for any running futures to complete
+/// before returning, but does not emit their outputs.
+///
+/// ```rustbook
+/// # #[hydroflow::main]
+/// # async fn main() {
+/// let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<u64>();
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_iter([3, 1, 2])
+///         -> map_async(|x| async move {
+///             tokio::time::sleep(std::time::Duration::from_millis(10 * x)).await;
+///             x * 10
+///         }, 2)
+///         -> for_each(|x| out_send.send(x).unwrap());
+/// };
+/// tokio::time::timeout(std::time::Duration::from_secs(1), flow.run_async())
+///     .await
+///     .expect_err("Expected time out");
+///
+/// let out: Vec<_> = hydroflow::util::ready_iter(&mut out_recv).collect();
+/// assert_eq!(&[30, 10, 20], &*out);
+/// # }
+/// ```
+pub const MAP_ASYNC: OperatorConstraints = OperatorConstraints {
+    name: "map_async",
+    categories: &[OperatorCategory::Map],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 2,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   is_pull,
+                   op_inst: OperatorInstance { arguments, .. },
+                   ..
+               },
+               _| {
+        assert!(is_pull);
+
+        let func = &arguments[0];
+        let max_in_flight = &arguments[1];
+
+        let state_ident = wc.make_ident("map_async_state");
+
+        let write_prologue = quote_spanned! {op_span=>
+            let #state_ident = #hydroflow.add_state(
+                #root::util::map_async::MapAsyncState::new(#max_in_flight, true)
+            );
+        };
+
+        let input = &inputs[0];
+        let write_iterator = quote_spanned! {op_span=>
+            let #ident = {
+                let state = ::std::clone::Clone::clone(#context.state_ref(#state_ident));
+                for item in #input {
+                    state.push(item);
+                }
+                let completed = state.take_completed();
+                state.start(&mut *#context, #func);
+                completed.into_iter()
+            };
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            state_handles: vec![state_ident],
+            ..Default::default()
+        })
+    },
+};
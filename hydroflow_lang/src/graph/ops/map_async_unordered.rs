@@ -0,0 +1,74 @@
+use quote::quote_spanned;
+
+use super::{
+    DelayType, OperatorCategory, OperatorConstraints, WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::graph::{GraphEdgeType, OperatorInstance};
+
+/// > 1 input stream, 1 output stream
+///
+/// > Arguments: A Rust closure which returns a `Future`, and the maximum number of futures to run
+/// at once, `max_in_flight`.
+///
+/// Same as [`map_async`](#map_async), but emits each future's output as soon as it completes,
+/// rather than in the order of the input items. Outputs which complete before the same tick are
+/// emitted in the order they completed.
+///
+/// ```rustbook
+/// # #[hydroflow::main]
+/// # async fn main() {
+/// let (out_send, mut out_recv) = hydroflow::util::unbounded_channel::<u64>();
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_iter([3, 1, 2])
+///         -> map_async_unordered(|x| async move {
+///             tokio::time::sleep(std::time::Duration::from_millis(100 * x)).await;
+///             x * 10
+///         }, 3)
+///         -> for_each(|x| out_send.send(x).unwrap());
+/// };
+/// tokio::time::timeout(std::time::Duration::from_secs(1), flow.run_async())
+///     .await
+///     .expect_err("Expected time out");
+///
+/// let out: Vec<_> = hydroflow::util::ready_iter(&mut out_recv).collect();
+/// assert_eq!(&[10, 20, 30], &*out);
+/// # }
+/// ```
+pub const MAP_ASYNC_UNORDERED: OperatorConstraints = OperatorConstraints {
+    name: "map_async_unordered",
+    categories: &[OperatorCategory::Map],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 2,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   hydroflow,
+                   op_span,
+                   op_inst: OperatorInstance { arguments, .. },
+                   ..
+               },
+               diagnostics| {
+        let mut output = (super::map_async::MAP_ASYNC.write_fn)(wc, diagnostics)?;
+
+        let max_in_flight = &arguments[1];
+        let state_ident = wc.make_ident("map_async_state");
+        output.write_prologue = quote_spanned! {op_span=>
+            let #state_ident = #hydroflow.add_state(
+                #root::util::map_async::MapAsyncState::new(#max_in_flight, false)
+            );
+        };
+
+        Ok(output)
+    },
+};
@@ -30,6 +30,8 @@ pub enum DelayType {
     Tick,
     /// Input must be collected over the previous tick but also not cause a new tick to occur.
     TickLazy,
+    /// Input must be buffered in a (bounded) handoff, but is not delayed.
+    Buffer,
 }
 
 /// Specification of the named (or unnamed) ports for an operator's inputs or outputs.
@@ -168,6 +170,14 @@ pub struct OperatorWriteOutput {
     pub write_iterator: TokenStream,
     /// Code which runs after iterators have been run. Mainly for flushing IO.
     pub write_iterator_after: TokenStream,
+    /// Set if the emitted code may run on a worker thread, i.e. it does not use the state API and
+    /// only uses the parts of `context` which are also provided by `ParallelContext`. Subgraphs
+    /// made only of such operators run in parallel in graphs built with
+    /// `hydroflow_syntax_parallel!`.
+    pub is_parallel_safe: bool,
+    /// Variables holding the `StateHandle`s added by `write_prologue`. Used to attribute the
+    /// operator's state to its subgraph, e.g. for introspection.
+    pub state_handles: Vec<Ident>,
 }
 
 /// Convenience range: zero or more (any number).
@@ -224,6 +234,7 @@ pub const IDENTITY_WRITE_FN: WriteFn = |write_context_args, _| {
     let write_iterator = identity_write_iterator_fn(write_context_args);
     Ok(OperatorWriteOutput {
         write_iterator,
+        is_parallel_safe: true,
         ..Default::default()
     })
 };
@@ -331,6 +342,7 @@ pub const NULL_WRITE_FN: WriteFn = |write_context_args, _| {
     let write_iterator = null_write_iterator_fn(write_context_args);
     Ok(OperatorWriteOutput {
         write_iterator,
+        is_parallel_safe: true,
         ..Default::default()
     })
 };
@@ -349,15 +361,24 @@ declare_ops![
     anti_join_multiset::ANTI_JOIN_MULTISET,
     assert::ASSERT,
     assert_eq::ASSERT_EQ,
+    batch::BATCH,
+    bounded::BOUNDED,
     cast::CAST,
+    count_distinct_approx::COUNT_DISTINCT_APPROX,
     cross_join::CROSS_JOIN,
     cross_join_multiset::CROSS_JOIN_MULTISET,
     demux::DEMUX,
     demux_enum::DEMUX_ENUM,
+    dest_csv::DEST_CSV,
     dest_file::DEST_FILE,
+    dest_file_serde::DEST_FILE_SERDE,
+    dest_jsonl::DEST_JSONL,
     dest_sink::DEST_SINK,
     dest_sink_serde::DEST_SINK_SERDE,
+    dest_tcp::DEST_TCP,
+    dest_udp::DEST_UDP,
     difference::DIFFERENCE,
+    difference_diff::DIFFERENCE_DIFF,
     difference_multiset::DIFFERENCE_MULTISET,
     enumerate::ENUMERATE,
     filter::FILTER,
@@ -366,21 +387,30 @@ declare_ops![
     flatten::FLATTEN,
     fold::FOLD,
     for_each::FOR_EACH,
+    heavy_hitters::HEAVY_HITTERS,
     identity::IDENTITY,
     initialize::INITIALIZE,
     inspect::INSPECT,
     join::JOIN,
+    join_diff::JOIN_DIFF,
+    join_full::JOIN_FULL,
     join_fused::JOIN_FUSED,
     join_fused_lhs::JOIN_FUSED_LHS,
     join_fused_rhs::JOIN_FUSED_RHS,
+    join_left::JOIN_LEFT,
     join_multiset::JOIN_MULTISET,
+    join_right::JOIN_RIGHT,
     fold_keyed::FOLD_KEYED,
+    fold_keyed_diff::FOLD_KEYED_DIFF,
     reduce_keyed::REDUCE_KEYED,
     _lattice_fold_batch::_LATTICE_FOLD_BATCH,
     lattice_fold::LATTICE_FOLD,
     _lattice_join_fused_join::_LATTICE_JOIN_FUSED_JOIN,
     lattice_reduce::LATTICE_REDUCE,
+    limit::LIMIT,
     map::MAP,
+    map_async::MAP_ASYNC,
+    map_async_unordered::MAP_ASYNC_UNORDERED,
     union::UNION,
     _upcast::_UPCAST,
     multiset_delta::MULTISET_DELTA,
@@ -394,23 +424,36 @@ declare_ops![
     persist_mut::PERSIST_MUT,
     persist_mut_keyed::PERSIST_MUT_KEYED,
     py_udf::PY_UDF,
+    quantiles_approx::QUANTILES_APPROX,
     reduce::REDUCE,
     spin::SPIN,
     sort::SORT,
     sort_by_key::SORT_BY_KEY,
+    source_csv::SOURCE_CSV,
     source_file::SOURCE_FILE,
+    source_file_serde::SOURCE_FILE_SERDE,
+    source_file_tail::SOURCE_FILE_TAIL,
     source_interval::SOURCE_INTERVAL,
     source_iter::SOURCE_ITER,
     source_iter_delta::SOURCE_ITER_DELTA,
     source_json::SOURCE_JSON,
+    source_jsonl::SOURCE_JSONL,
     source_stdin::SOURCE_STDIN,
     source_stream::SOURCE_STREAM,
     source_stream_serde::SOURCE_STREAM_SERDE,
+    source_tcp::SOURCE_TCP,
+    source_udp::SOURCE_UDP,
     state::STATE,
     state_join::STATE_JOIN,
     tee::TEE,
+    top_k::TOP_K,
+    top_k_keyed::TOP_K_KEYED,
     unique::UNIQUE,
+    unique_diff::UNIQUE_DIFF,
     unzip::UNZIP,
+    window_session::WINDOW_SESSION,
+    window_sliding::WINDOW_SLIDING,
+    window_tumbling::WINDOW_TUMBLING,
     zip::ZIP,
     zip_longest::ZIP_LONGEST,
 ];
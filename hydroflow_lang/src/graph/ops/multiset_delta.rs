@@ -117,6 +117,7 @@ pub const MULTISET_DELTA: OperatorConstraints = OperatorConstraints {
         Ok(OperatorWriteOutput {
             write_prologue,
             write_iterator,
+            state_handles: vec![prev_data, curr_data],
             ..Default::default()
         })
     },
@@ -170,6 +170,7 @@ pub const PARTITION: OperatorConstraints = OperatorConstraints {
 
         Ok(OperatorWriteOutput {
             write_iterator,
+            is_parallel_safe: true,
             ..Default::default()
         })
     },
@@ -145,6 +145,8 @@ pub const PERSIST: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles: vec![persistdata_ident],
+            ..Default::default()
         })
     },
 };
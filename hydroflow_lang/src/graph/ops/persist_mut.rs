@@ -88,6 +88,8 @@ pub const PERSIST_MUT: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles: vec![persistdata_ident],
+            ..Default::default()
         })
     },
 };
@@ -96,6 +96,8 @@ pub const PERSIST_MUT_KEYED: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles: vec![persistdata_ident],
+            ..Default::default()
         })
     },
 };
@@ -144,6 +144,7 @@ pub const PY_UDF: OperatorConstraints = OperatorConstraints {
         Ok(OperatorWriteOutput {
             write_prologue,
             write_iterator,
+            state_handles: vec![py_func_ident],
             ..Default::default()
         })
     },
@@ -0,0 +1,165 @@
+use quote::quote_spanned;
+
+use super::{
+    DelayType, OpInstGenerics, OperatorCategory, OperatorConstraints, OperatorInstance,
+    OperatorWriteOutput, Persistence, WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::diagnostic::{Diagnostic, Level};
+use crate::graph::GraphEdgeType;
+
+/// > 1 input stream of `f64`, 1 output stream of `(f64, f64)`
+///
+/// > Arguments: (1) The `relative_accuracy` of estimates, between 0 and 1, and (2) an iterable of
+/// quantiles to estimate, each between 0 and 1, such as `[0.5, 0.99]` for the median and 99th
+/// percentile.
+///
+/// Approximately computes quantiles of the input values, in bounded space, using a
+/// [`QuantileSketch`](https://hydro-project.github.io/hydroflow/doc/lattices/sketch/struct.QuantileSketch.html)
+/// ([DDSketch](https://arxiv.org/abs/1908.10693)). Emits a `(quantile, value)` pair for each
+/// requested quantile, where `value` is within `relative_accuracy` times the true quantile value.
+/// Emits nothing if there are no input values. `NaN` values are ignored.
+///
+/// ```hydroflow
+/// source_iter((1..=100).map(|x| x as f64))
+///     -> quantiles_approx(0.01, [0.5, 0.99])
+///     -> assert(|&(q, value): &(f64, f64)| (value - 100.0 * q).abs() <= 0.01 * 100.0 * q)
+///     -> map(|(q, _value)| q)
+///     -> assert_eq([0.5, 0.99]);
+/// ```
+///
+/// `quantiles_approx` can also be provided with one generic lifetime persistence argument, either
+/// `'tick` or `'static`, to specify how data persists. The default is `'tick`. With `'tick`, only
+/// the values within the current tick are included. With `'static`, the sketch is kept across
+/// ticks and the updated quantiles of all values so far are emitted each tick.
+///
+/// `QuantileSketch`es can only be merged with sketches of the same `relative_accuracy`: merging
+/// mismatched sketches, for example with [`lattice_fold`](#lattice_fold), panics in debug builds
+/// and ignores the other sketch in release builds. Use `QuantileSketch::try_merge` to check
+/// sketches received from other nodes.
+pub const QUANTILES_APPROX: OperatorConstraints = OperatorConstraints {
+    name: "quantiles_approx",
+    categories: &[OperatorCategory::Fold],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 2,
+    persistence_args: &(0..=1),
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   is_pull,
+                   op_inst:
+                       OperatorInstance {
+                           arguments,
+                           generics:
+                               OpInstGenerics {
+                                   persistence_args, ..
+                               },
+                           ..
+                       },
+                   ..
+               },
+               diagnostics| {
+        assert!(is_pull);
+
+        let persistence = match persistence_args[..] {
+            [] => Persistence::Tick,
+            [a] => a,
+            _ => unreachable!(),
+        };
+
+        let input = &inputs[0];
+        let relative_accuracy = &arguments[0];
+        let quantiles = &arguments[1];
+        let quantiles_ident = wc.make_ident("quantiles");
+
+        let sketchdata_ident = wc.make_ident("sketchdata");
+        let sketch_ident = wc.make_ident("sketch");
+        let write_prologue = quote_spanned! {op_span=>
+            let #quantiles_ident: ::std::vec::Vec<f64> =
+                ::std::iter::IntoIterator::into_iter(#quantiles).collect();
+        };
+
+        let (write_prologue, write_iterator, write_iterator_after) = match persistence {
+            Persistence::Tick => (
+                // Construct the empty sketch up front so an invalid `relative_accuracy` panics
+                // when the graph is built, not on the first tick.
+                quote_spanned! {op_span=>
+                    #write_prologue
+                    let #sketchdata_ident = #root::lattices::sketch::QuantileSketch::new(#relative_accuracy);
+                },
+                quote_spanned! {op_span=>
+                    let #ident = {
+                        let mut #sketch_ident = ::std::clone::Clone::clone(&#sketchdata_ident);
+                        for item in #input {
+                            #sketch_ident.insert(item);
+                        }
+                        #quantiles_ident
+                            .iter()
+                            .filter_map(|&q| ::std::option::Option::Some((q, #sketch_ident.quantile(q)?)))
+                            .collect::<::std::vec::Vec<_>>()
+                            .into_iter()
+                    };
+                },
+                Default::default(),
+            ),
+            Persistence::Static => (
+                quote_spanned! {op_span=>
+                    #write_prologue
+                    let #sketchdata_ident = #hydroflow.add_state(::std::cell::RefCell::new(
+                        #root::lattices::sketch::QuantileSketch::new(#relative_accuracy)
+                    ));
+                },
+                quote_spanned! {op_span=>
+                    let #ident = {
+                        let mut #sketch_ident = #context.state_ref(#sketchdata_ident).borrow_mut();
+                        for item in #input {
+                            #sketch_ident.insert(item);
+                        }
+                        #quantiles_ident
+                            .iter()
+                            .filter_map(|&q| ::std::option::Option::Some((q, #sketch_ident.quantile(q)?)))
+                            .collect::<::std::vec::Vec<_>>()
+                            .into_iter()
+                    };
+                },
+                quote_spanned! {op_span=>
+                    #context.schedule_subgraph(#context.current_subgraph(), false);
+                },
+            ),
+            Persistence::Mutable => {
+                diagnostics.push(Diagnostic::spanned(
+                    op_span,
+                    Level::Error,
+                    "An implementation of 'mutable does not exist",
+                ));
+                return Err(());
+            }
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            write_iterator_after,
+            state_handles: if Persistence::Static == persistence {
+                vec![sketchdata_ident]
+            } else {
+                vec![]
+            },
+            ..Default::default()
+        })
+    },
+};
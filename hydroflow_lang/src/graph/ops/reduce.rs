@@ -113,7 +113,7 @@ pub const REDUCE: OperatorConstraints = OperatorConstraints {
             Persistence::Static => (
                 quote_spanned! {op_span=>
                     let #reducedata_ident = #hydroflow.add_state(
-                        ::std::cell::Cell::new(::std::option::Option::None)
+                        ::std::cell::RefCell::new(::std::option::Option::None)
                     );
                 },
                 quote_spanned! {op_span=>
@@ -143,7 +143,7 @@ pub const REDUCE: OperatorConstraints = OperatorConstraints {
                             ::std::option::Option::None
                         };
 
-                        #context.state_ref(#reducedata_ident).set(::std::clone::Clone::clone(&#ret_ident));
+                        #context.state_ref(#reducedata_ident).replace(::std::clone::Clone::clone(&#ret_ident));
 
                         #ret_ident.into_iter()
                     };
@@ -166,6 +166,12 @@ pub const REDUCE: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles: if Persistence::Static == persistence {
+                vec![reducedata_ident]
+            } else {
+                vec![]
+            },
+            ..Default::default()
         })
     },
 };
@@ -117,9 +117,9 @@ pub const REDUCE_KEYED: OperatorConstraints = OperatorConstraints {
         let input = &inputs[0];
         let aggfn = &arguments[0];
 
+        let groupbydata_ident = wc.make_ident("groupbydata");
         let (write_prologue, write_iterator, write_iterator_after) = match persistence {
             Persistence::Tick => {
-                let groupbydata_ident = wc.make_ident("groupbydata");
                 let hashtable_ident = wc.make_ident("hashtable");
 
                 (
@@ -159,7 +159,6 @@ pub const REDUCE_KEYED: OperatorConstraints = OperatorConstraints {
                 )
             }
             Persistence::Static => {
-                let groupbydata_ident = wc.make_ident("groupbydata");
                 let hashtable_ident = wc.make_ident("hashtable");
 
                 (
@@ -218,6 +217,8 @@ pub const REDUCE_KEYED: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            state_handles: vec![groupbydata_ident],
+            ..Default::default()
         })
     },
 };
@@ -0,0 +1,81 @@
+use quote::quote_spanned;
+
+use super::{
+    OperatorCategory, OperatorConstraints, OperatorWriteOutput, WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::graph::{GraphEdgeType, OpInstGenerics, OperatorInstance};
+
+/// > 0 input streams, 1 output stream
+///
+/// > Arguments: An [`AsRef`](https://doc.rust-lang.org/std/convert/trait.AsRef.html)`<`[`Path`](https://doc.rust-lang.org/nightly/std/path/struct.Path.html)`>`
+/// for a file to read.
+///
+/// Reads records from a CSV file with a header row, and emits them all in the first tick. Struct
+/// records are deserialized by matching their field names with the header, other records (such as
+/// tuples) by field order. Fields must be scalars, such as numbers, strings, or `Option`s of those,
+/// where `None` is an empty field.
+///
+/// `source_csv` may take one generic type argument, the type of the records, which must implement
+/// [`Deserialize`](https://docs.rs/serde/latest/serde/de/trait.Deserialize.html).
+///
+/// Will panic if the file could not be read, or if a record could not be deserialized.
+///
+/// ```rustbook
+/// let path = std::env::temp_dir().join("source.csv");
+/// std::fs::write(&path, "name,count\nhello,1\nworld,2\n").unwrap();
+///
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_csv::<(String, u32)>(&path)
+///         -> assert_eq([("hello".to_owned(), 1), ("world".to_owned(), 2)]);
+/// };
+/// flow.run_available();
+/// ```
+pub const SOURCE_CSV: OperatorConstraints = OperatorConstraints {
+    name: "source_csv",
+    categories: &[OperatorCategory::Source],
+    hard_range_inn: RANGE_0,
+    soft_range_inn: RANGE_0,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 1,
+    persistence_args: RANGE_0,
+    type_args: &(0..=1),
+    is_external_input: true,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| None,
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   op_span,
+                   ident,
+                   op_inst:
+                       OperatorInstance {
+                           generics: OpInstGenerics { type_args, .. },
+                           arguments,
+                           ..
+                       },
+                   ..
+               },
+               _| {
+        let generic_type = type_args
+            .first()
+            .map(quote::ToTokens::to_token_stream)
+            .unwrap_or(quote_spanned!(op_span=> _));
+
+        let ident_records = wc.make_ident("records");
+        let write_prologue = quote_spanned! {op_span=>
+            let mut #ident_records = #root::util::serde_file::read_csv::<#generic_type>(#arguments);
+        };
+        let write_iterator = quote_spanned! {op_span=>
+            let #ident = #ident_records.by_ref();
+        };
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            ..Default::default()
+        })
+    },
+};
@@ -74,6 +74,7 @@ pub const SOURCE_FILE: OperatorConstraints = OperatorConstraints {
             write_prologue: write_prologue_stream,
             write_iterator,
             write_iterator_after,
+            ..
         } = (super::source_stream::SOURCE_STREAM.write_fn)(&wc, diagnostics)?;
 
         let write_prologue = quote_spanned! {op_span=>
@@ -91,6 +92,7 @@ pub const SOURCE_FILE: OperatorConstraints = OperatorConstraints {
             write_prologue,
             write_iterator,
             write_iterator_after,
+            ..Default::default()
         })
     },
 };
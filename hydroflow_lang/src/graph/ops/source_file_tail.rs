@@ -0,0 +1,129 @@
+use quote::quote_spanned;
+use syn::parse_quote_spanned;
+
+use super::{
+    make_missing_runtime_msg, OperatorCategory, OperatorConstraints, OperatorWriteOutput,
+    WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::graph::{GraphEdgeType, OperatorInstance};
+
+/// > 0 input streams, 1 output stream
+///
+/// > Arguments: (1) An [`AsRef`](https://doc.rust-lang.org/std/convert/trait.AsRef.html)`<`[`Path`](https://doc.rust-lang.org/nightly/std/path/struct.Path.html)`>`
+/// for a file to follow, and (2) a `u64` byte offset to start reading from.
+///
+/// Follows the referenced file as it grows, like `tail -F`, emitting lines across ticks as they are
+/// appended. Each line is emitted as a `(offset, line)` pair, where `offset` is the byte offset just
+/// past the line's ending. Store the offset of the last line handled and pass it as the start offset
+/// to resume after that line, or pass `0` to read the whole file. The line will NOT include the
+/// line ending, and a final line without a line ending is held back until it is completed.
+///
+/// The file is checked for new data every 100 milliseconds, and may not exist yet. Log rotation is
+/// handled: if the file is truncated it is read again from the beginning, and if the file is
+/// renamed (on unix) the rest of the old file is read, then the new file at the same path is read
+/// from the beginning. Offsets are relative to the current file, so they restart from `0` after
+/// rotation. See [`hydroflow::util::file_tail::FileTail`](https://hydro-project.github.io/hydroflow/doc/hydroflow/util/file_tail/struct.FileTail.html).
+///
+/// Invalid UTF-8 is replaced with `U+FFFD REPLACEMENT CHARACTER`. If the file could not be read, a
+/// warning is logged and it is read again on the next check.
+///
+/// Note that this requires the hydroflow instance be run within a [Tokio `Runtime`](https://docs.rs/tokio/1/tokio/runtime/struct.Runtime.html),
+/// with `run_async` or `run_until`.
+///
+/// ```rustbook
+/// # #[hydroflow::main]
+/// # async fn main() {
+/// let path = std::env::temp_dir().join("hydroflow-source-file-tail-example.log");
+/// std::fs::write(&path, "hello\n").unwrap();
+///
+/// let (out_send, mut out_recv) = hydroflow::util::unbounded_channel();
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_file_tail(&path, 0) -> for_each(|line| out_send.send(line).unwrap());
+/// };
+/// tokio::time::timeout(std::time::Duration::from_millis(200), flow.run_async())
+///     .await
+///     .expect_err("Expected time out");
+///
+/// // Lines appended later are emitted in later ticks.
+/// use std::io::Write;
+/// let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+/// file.write_all(b"world\n").unwrap();
+/// tokio::time::timeout(std::time::Duration::from_millis(200), flow.run_async())
+///     .await
+///     .expect_err("Expected time out");
+///
+/// let lines: Vec<_> = hydroflow::util::collect_ready_async(&mut out_recv).await;
+/// assert_eq!(vec![(6, "hello".to_owned()), (12, "world".to_owned())], lines);
+/// # }
+/// ```
+pub const SOURCE_FILE_TAIL: OperatorConstraints = OperatorConstraints {
+    name: "source_file_tail",
+    categories: &[OperatorCategory::Source],
+    hard_range_inn: RANGE_0,
+    soft_range_inn: RANGE_0,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 2,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: true,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| None,
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   op_span,
+                   op_name,
+                   op_inst: OperatorInstance { arguments, .. },
+                   ..
+               },
+               diagnostics| {
+        let filename_arg = &arguments[0];
+        let offset_arg = &arguments[1];
+
+        let ident_filetail = wc.make_ident("filetail");
+
+        let missing_runtime_msg = make_missing_runtime_msg(op_name);
+
+        let write_prologue = quote_spanned! {op_span=>
+            let #ident_filetail = #root::util::file_tail::tail_file(
+                #filename_arg,
+                #offset_arg,
+                #root::util::file_tail::DEFAULT_POLL_INTERVAL,
+            );
+        };
+        let wc = WriteContextArgs {
+            op_inst: &OperatorInstance {
+                arguments: parse_quote_spanned!(op_span=> #ident_filetail),
+                ..wc.op_inst.clone()
+            },
+            ..wc.clone()
+        };
+
+        let OperatorWriteOutput {
+            write_prologue: write_prologue_stream,
+            write_iterator,
+            write_iterator_after,
+            ..
+        } = (super::source_stream::SOURCE_STREAM.write_fn)(&wc, diagnostics)?;
+
+        let write_prologue = quote_spanned! {op_span=>
+            #write_prologue
+            #write_prologue_stream
+        };
+        let write_iterator = quote_spanned! {op_span=>
+            ::std::debug_assert!(#root::tokio::runtime::Handle::try_current().is_ok(), #missing_runtime_msg);
+            #write_iterator
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            write_iterator_after,
+            ..Default::default()
+        })
+    },
+};
@@ -58,6 +58,7 @@ pub const SOURCE_ITER: OperatorConstraints = OperatorConstraints {
         Ok(OperatorWriteOutput {
             write_prologue,
             write_iterator,
+            is_parallel_safe: true,
             ..Default::default()
         })
     },
@@ -66,6 +66,10 @@ pub const SOURCE_STREAM: OperatorConstraints = OperatorConstraints {
         };
         let write_iterator = quote_spanned! {op_span=>
             let #ident = std::iter::from_fn(|| {
+                // Stop polling if downstream bounded handoffs are full.
+                if !#context.take_output_budget() {
+                    return None;
+                }
                 match #root::futures::stream::Stream::poll_next(::std::pin::Pin::new(&mut #stream_ident), &mut std::task::Context::from_waker(&#context.waker())) {
                     std::task::Poll::Ready(maybe) => maybe,
                     std::task::Poll::Pending => None,
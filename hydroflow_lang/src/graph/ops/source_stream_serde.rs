@@ -59,6 +59,10 @@ pub const SOURCE_STREAM_SERDE: OperatorConstraints = OperatorConstraints {
         };
         let write_iterator = quote_spanned! {op_span=>
             let #ident = std::iter::from_fn(|| {
+                // Stop polling if downstream bounded handoffs are full.
+                if !#context.take_output_budget() {
+                    return None;
+                }
                 match #root::futures::stream::Stream::poll_next(#stream_ident.as_mut(), &mut std::task::Context::from_waker(&#context.waker())) {
                     std::task::Poll::Ready(Some(std::result::Result::Ok((payload, addr)))) => Some(#root::util::deserialize_from_bytes(payload).map(|payload| (payload, addr))),
                     std::task::Poll::Ready(Some(Err(_))) => None,
@@ -0,0 +1,99 @@
+use quote::quote_spanned;
+use syn::parse_quote_spanned;
+
+use super::{
+    make_missing_runtime_msg, OperatorCategory, OperatorConstraints, OperatorWriteOutput,
+    WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::graph::{GraphEdgeType, OperatorInstance};
+
+/// > 0 input streams, 1 output stream
+///
+/// > Arguments: (1) An address to bind to, anything implementing
+/// [`ToSocketAddrs`](https://doc.rust-lang.org/std/net/trait.ToSocketAddrs.html), and (2) a
+/// [`Decoder`](https://docs.rs/tokio-util/latest/tokio_util/codec/trait.Decoder.html) codec.
+///
+/// Binds a UDP socket to the given address, emitting an `(item, sender_addr)` pair for each
+/// datagram received. Datagrams which fail to decode are skipped. The socket is bound when the
+/// flow is built, and will panic if binding fails. To bind to port `0` and find out which port the
+/// OS picked, call
+/// [`hydroflow::util::bind_udp_source`](https://hydro-project.github.io/hydroflow/doc/hydroflow/util/fn.bind_udp_source.html)
+/// directly and pass its stream to [`source_stream`](#source_stream).
+///
+/// Note that this requires the hydroflow instance be run within a [Tokio `Runtime`](https://docs.rs/tokio/1/tokio/runtime/struct.Runtime.html),
+/// with `run_async` or `run_until`.
+///
+/// ```rustbook
+/// async fn udp_in() {
+///     let mut flow = hydroflow::hydroflow_syntax! {
+///         source_udp("127.0.0.1:9000", hydroflow::tokio_util::codec::LinesCodec::new())
+///             -> for_each(|(line, peer_addr)| println!("{}: {}", peer_addr, line));
+///     };
+///     flow.run_async().await;
+/// }
+/// ```
+pub const SOURCE_UDP: OperatorConstraints = OperatorConstraints {
+    name: "source_udp",
+    categories: &[OperatorCategory::Source],
+    hard_range_inn: RANGE_0,
+    soft_range_inn: RANGE_0,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 2,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: true,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| None,
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   op_span,
+                   op_name,
+                   op_inst: OperatorInstance { arguments, .. },
+                   ..
+               },
+               diagnostics| {
+        let addr_arg = &arguments[0];
+        let codec_arg = &arguments[1];
+
+        let ident_source = wc.make_ident("source");
+
+        let missing_runtime_msg = make_missing_runtime_msg(op_name);
+
+        let write_prologue = quote_spanned! {op_span=>
+            ::std::debug_assert!(#root::tokio::runtime::Handle::try_current().is_ok(), #missing_runtime_msg);
+            let (#ident_source, _) = #root::util::bind_udp_source(#addr_arg, #codec_arg)
+                .unwrap_or_else(|err| ::std::panic!("Failed to bind UDP socket: {}", err));
+        };
+        let wc = WriteContextArgs {
+            op_inst: &OperatorInstance {
+                arguments: parse_quote_spanned!(op_span=> #ident_source),
+                ..wc.op_inst.clone()
+            },
+            ..wc.clone()
+        };
+
+        let OperatorWriteOutput {
+            write_prologue: write_prologue_stream,
+            write_iterator,
+            write_iterator_after,
+            ..
+        } = (super::source_stream::SOURCE_STREAM.write_fn)(&wc, diagnostics)?;
+
+        let write_prologue = quote_spanned! {op_span=>
+            #write_prologue
+            #write_prologue_stream
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            write_iterator_after,
+            ..Default::default()
+        })
+    },
+};
@@ -110,6 +110,7 @@ pub const STATE: OperatorConstraints = OperatorConstraints {
         Ok(OperatorWriteOutput {
             write_prologue,
             write_iterator,
+            state_handles: vec![state_ident.clone()],
             ..Default::default()
         })
     },
@@ -0,0 +1,169 @@
+use quote::quote_spanned;
+
+use super::{
+    DelayType, OpInstGenerics, OperatorCategory, OperatorConstraints, OperatorInstance,
+    OperatorWriteOutput, Persistence, WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::diagnostic::{Diagnostic, Level};
+use crate::graph::GraphEdgeType;
+
+/// > 1 input stream, 1 output stream
+///
+/// > Arguments: (1) `k`, the maximum number of items to keep, and (2) a closure which extracts an
+/// [`Ord`](https://doc.rust-lang.org/std/cmp/trait.Ord.html) key from a reference to an item.
+///
+/// Emits the `k` items with the greatest keys, from greatest to least. Among items with equal keys,
+/// the earliest received are kept.
+///
+/// > Note: The closure has access to the [`context` object](surface_flows.md#the-context-object).
+///
+/// ```hydroflow
+/// source_iter([("alice", 30), ("bob", 50), ("carol", 10), ("dave", 40)])
+///     -> top_k(2, |&(_name, score)| score)
+///     -> assert_eq([("bob", 50), ("dave", 40)]);
+/// ```
+///
+/// `top_k` can also be provided with one generic lifetime persistence argument, either `'tick` or
+/// `'static`, to specify how data persists. The default is `'tick`. With `'tick`, only the items
+/// within the current tick are ranked. With `'static`, the top `k` items are kept across ticks, and
+/// the updated top `k` of all items so far is emitted each tick. Only `k` items are ever stored, and
+/// each new item is inserted in `O(log k)` time, without re-sorting earlier items.
+///
+/// ```rustbook
+/// let (input_send, input_recv) = hydroflow::util::unbounded_channel::<(&str, u32)>();
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_stream(input_recv)
+///         -> top_k::<'static>(2, |&(_name, score)| score)
+///         -> fold(Vec::new, Vec::push)
+///         -> for_each(|leaderboard| println!("{:?}", leaderboard));
+/// };
+///
+/// input_send.send(("alice", 30)).unwrap();
+/// input_send.send(("bob", 50)).unwrap();
+/// flow.run_tick();
+/// // [("bob", 50), ("alice", 30)]
+///
+/// input_send.send(("carol", 40)).unwrap();
+/// flow.run_tick();
+/// // [("bob", 50), ("carol", 40)]
+/// ```
+pub const TOP_K: OperatorConstraints = OperatorConstraints {
+    name: "top_k",
+    categories: &[OperatorCategory::Fold],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 2,
+    persistence_args: &(0..=1),
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   is_pull,
+                   op_inst:
+                       OperatorInstance {
+                           arguments,
+                           generics:
+                               OpInstGenerics {
+                                   persistence_args, ..
+                               },
+                           ..
+                       },
+                   ..
+               },
+               diagnostics| {
+        assert!(is_pull);
+
+        let persistence = match persistence_args[..] {
+            [] => Persistence::Tick,
+            [a] => a,
+            _ => unreachable!(),
+        };
+
+        let input = &inputs[0];
+        let k = &arguments[0];
+        let key_fn = &arguments[1];
+
+        let topkdata_ident = wc.make_ident("topkdata");
+        let topk_ident = wc.make_ident("topk");
+
+        let insert_all = quote_spanned! {op_span=>
+            #[inline(always)]
+            fn check_key_fn<T, K>(f: impl Fn(&T) -> K) -> impl Fn(&T) -> K {
+                f
+            }
+            let key_fn = check_key_fn(#key_fn);
+            for item in #input {
+                let key = (key_fn)(&item);
+                #topk_ident.insert(key, item);
+            }
+        };
+
+        let (write_prologue, write_iterator, write_iterator_after) = match persistence {
+            Persistence::Tick => (
+                Default::default(),
+                quote_spanned! {op_span=>
+                    let #ident = {
+                        let mut #topk_ident = #root::util::top_k::TopK::new(#k);
+                        #insert_all
+                        #topk_ident.into_sorted_vec().into_iter()
+                    };
+                },
+                Default::default(),
+            ),
+            Persistence::Static => (
+                quote_spanned! {op_span=>
+                    let #topkdata_ident = #hydroflow.add_state(::std::cell::RefCell::new(
+                        #root::util::top_k::TopK::new(#k)
+                    ));
+                },
+                quote_spanned! {op_span=>
+                    let #ident = {
+                        let mut #topk_ident = #context.state_ref(#topkdata_ident).borrow_mut();
+                        #insert_all
+                        #topk_ident
+                            .iter_sorted()
+                            .cloned()
+                            .collect::<::std::vec::Vec<_>>()
+                            .into_iter()
+                    };
+                },
+                quote_spanned! {op_span=>
+                    #context.schedule_subgraph(#context.current_subgraph(), false);
+                },
+            ),
+            Persistence::Mutable => {
+                diagnostics.push(Diagnostic::spanned(
+                    op_span,
+                    Level::Error,
+                    "An implementation of 'mutable does not exist",
+                ));
+                return Err(());
+            }
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            write_iterator_after,
+            state_handles: if Persistence::Static == persistence {
+                vec![topkdata_ident]
+            } else {
+                vec![]
+            },
+            ..Default::default()
+        })
+    },
+};
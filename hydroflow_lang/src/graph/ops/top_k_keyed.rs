@@ -0,0 +1,188 @@
+use quote::quote_spanned;
+
+use super::{
+    DelayType, OpInstGenerics, OperatorCategory, OperatorConstraints, OperatorInstance,
+    OperatorWriteOutput, Persistence, WriteContextArgs, RANGE_0, RANGE_1,
+};
+use crate::diagnostic::{Diagnostic, Level};
+use crate::graph::GraphEdgeType;
+
+/// > 1 input stream of type `(G, V)`, 1 output stream of type `(G, V)`.
+///
+/// > Arguments: (1) `k`, the maximum number of values to keep per group, and (2) a closure which
+/// extracts an [`Ord`](https://doc.rust-lang.org/std/cmp/trait.Ord.html) key from a reference to a
+/// value.
+///
+/// Like [`top_k`](#top_k), but the input is partitioned into groups by the first field, and the `k`
+/// values with the greatest keys are emitted for each group. Within each group values are emitted
+/// from greatest to least key, but groups are emitted in no particular order.
+///
+/// > Note: The closure has access to the [`context` object](surface_flows.md#the-context-object).
+///
+/// ```hydroflow
+/// source_iter([("a", 1), ("b", 5), ("a", 3), ("a", 2), ("b", 4)])
+///     -> top_k_keyed(2, |&v| v)
+///     -> sort()
+///     -> assert_eq([("a", 2), ("a", 3), ("b", 4), ("b", 5)]);
+/// ```
+///
+/// `top_k_keyed` can also be provided with one generic lifetime persistence argument, either
+/// `'tick` or `'static`, to specify how data persists. The default is `'tick`. With `'tick`, only
+/// the values within the current tick are ranked. With `'static`, the top `k` values of each group
+/// are kept across ticks, and the updated top `k` of every group is emitted each tick. For example,
+/// the most recent `n` events per user:
+///
+/// ```rustbook
+/// let (input_send, input_recv) = hydroflow::util::unbounded_channel::<(&str, (u64, &str))>();
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_stream(input_recv)
+///         -> top_k_keyed::<'static>(2, |&(timestamp, _event)| timestamp)
+///         -> for_each(|(user, (timestamp, event))| println!("{} {} {}", user, timestamp, event));
+/// };
+///
+/// input_send.send(("alice", (1, "login"))).unwrap();
+/// input_send.send(("alice", (2, "view"))).unwrap();
+/// flow.run_tick();
+/// // alice 2 view
+/// // alice 1 login
+///
+/// input_send.send(("alice", (3, "logout"))).unwrap();
+/// flow.run_tick();
+/// // alice 3 logout
+/// // alice 2 view
+/// ```
+pub const TOP_K_KEYED: OperatorConstraints = OperatorConstraints {
+    name: "top_k_keyed",
+    categories: &[OperatorCategory::KeyedFold],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 2,
+    persistence_args: &(0..=1),
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   is_pull,
+                   op_inst:
+                       OperatorInstance {
+                           arguments,
+                           generics:
+                               OpInstGenerics {
+                                   persistence_args, ..
+                               },
+                           ..
+                       },
+                   ..
+               },
+               diagnostics| {
+        assert!(is_pull);
+
+        let persistence = match persistence_args[..] {
+            [] => Persistence::Tick,
+            [a] => a,
+            _ => unreachable!(),
+        };
+
+        let input = &inputs[0];
+        let k = &arguments[0];
+        let key_fn = &arguments[1];
+
+        let topkdata_ident = wc.make_ident("topkdata");
+        let groups_ident = wc.make_ident("groups");
+
+        let insert_all = quote_spanned! {op_span=>
+            #[inline(always)]
+            fn check_input<Iter: ::std::iter::Iterator<Item = (G, V)>, G: ::std::cmp::Eq + ::std::hash::Hash, V>(iter: Iter)
+                -> impl ::std::iter::Iterator<Item = (G, V)> { iter }
+            #[inline(always)]
+            fn check_key_fn<T, K>(f: impl Fn(&T) -> K) -> impl Fn(&T) -> K {
+                f
+            }
+            let key_fn = check_key_fn(#key_fn);
+            for (group, value) in check_input(#input) {
+                let key = (key_fn)(&value);
+                #groups_ident
+                    .entry(group)
+                    .or_insert_with(|| #root::util::top_k::TopK::new(#k))
+                    .insert(key, value);
+            }
+        };
+
+        let (write_prologue, write_iterator, write_iterator_after) = match persistence {
+            Persistence::Tick => (
+                Default::default(),
+                quote_spanned! {op_span=>
+                    let #ident = {
+                        let mut #groups_ident = #root::rustc_hash::FxHashMap::default();
+                        #insert_all
+                        #groups_ident.into_iter().flat_map(|(group, top_k)| {
+                            top_k
+                                .into_sorted_vec()
+                                .into_iter()
+                                .map(move |value| (::std::clone::Clone::clone(&group), value))
+                        })
+                    };
+                },
+                Default::default(),
+            ),
+            Persistence::Static => (
+                quote_spanned! {op_span=>
+                    let #topkdata_ident = #hydroflow.add_state(::std::cell::RefCell::new(
+                        #root::rustc_hash::FxHashMap::default()
+                    ));
+                },
+                quote_spanned! {op_span=>
+                    let #ident = {
+                        let mut #groups_ident = #context.state_ref(#topkdata_ident).borrow_mut();
+                        #insert_all
+                        #groups_ident
+                            .iter()
+                            .flat_map(|(group, top_k)| {
+                                top_k.iter_sorted().map(move |value| {
+                                    (::std::clone::Clone::clone(group), ::std::clone::Clone::clone(value))
+                                })
+                            })
+                            .collect::<::std::vec::Vec<_>>()
+                            .into_iter()
+                    };
+                },
+                quote_spanned! {op_span=>
+                    #context.schedule_subgraph(#context.current_subgraph(), false);
+                },
+            ),
+            Persistence::Mutable => {
+                diagnostics.push(Diagnostic::spanned(
+                    op_span,
+                    Level::Error,
+                    "An implementation of 'mutable does not exist",
+                ));
+                return Err(());
+            }
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            write_iterator_after,
+            state_handles: if Persistence::Static == persistence {
+                vec![topkdata_ident]
+            } else {
+                vec![]
+            },
+            ..Default::default()
+        })
+    },
+};
@@ -147,6 +147,7 @@ pub const UNIQUE: OperatorConstraints = OperatorConstraints {
         Ok(OperatorWriteOutput {
             write_prologue,
             write_iterator,
+            state_handles: vec![uniquedata_ident],
             ..Default::default()
         })
     },
@@ -0,0 +1,104 @@
+use quote::quote_spanned;
+
+use super::{
+    DelayType, OperatorCategory, OperatorConstraints, OperatorWriteOutput, WriteContextArgs,
+    RANGE_0, RANGE_1,
+};
+use crate::graph::GraphEdgeType;
+
+/// > 1 input stream of `(T, isize)` diffs, 1 output stream of `(T, isize)` diffs
+///
+/// The differential version of [`unique`](#unique). Each input item is a diff: a value paired with
+/// a signed multiplicity, where a positive multiplicity inserts copies of the value and a negative
+/// multiplicity retracts them. Emits `(value, 1)` when a value's total multiplicity becomes
+/// positive, and `(value, -1)` when it stops being positive.
+///
+/// Multiplicities are always accumulated across ticks, so `unique_diff` takes no persistence
+/// arguments. Each tick only the changes caused by that tick's diffs are emitted.
+///
+/// ```hydroflow
+/// source_iter([("a", 1), ("a", 1), ("b", 1), ("b", -1), ("c", -1)])
+///     -> unique_diff()
+///     -> assert_eq([("a", 1)]);
+/// ```
+///
+/// A stream of `hydroflow::util::Persistence` events, as used by [`persist_mut`](#persist_mut), can
+/// be converted to diffs with a `map`:
+///
+/// ```rustbook
+/// use hydroflow::util::Persistence;
+///
+/// let (input_send, input_recv) = hydroflow::util::unbounded_channel::<Persistence<&str>>();
+/// let mut flow = hydroflow::hydroflow_syntax! {
+///     source_stream(input_recv)
+///         -> map(|event| match event {
+///             Persistence::Persist(x) => (x, 1),
+///             Persistence::Delete(x) => (x, -1),
+///         })
+///         -> unique_diff()
+///         -> for_each(|diff| println!("{:?}", diff));
+/// };
+///
+/// input_send.send(Persistence::Persist("hello")).unwrap();
+/// input_send.send(Persistence::Persist("hello")).unwrap();
+/// flow.run_tick();
+/// // ("hello", 1)
+///
+/// input_send.send(Persistence::Delete("hello")).unwrap();
+/// flow.run_tick();
+/// // (nothing, "hello" is still present once)
+///
+/// input_send.send(Persistence::Delete("hello")).unwrap();
+/// flow.run_tick();
+/// // ("hello", -1)
+/// ```
+pub const UNIQUE_DIFF: OperatorConstraints = OperatorConstraints {
+    name: "unique_diff",
+    categories: &[OperatorCategory::Persistence],
+    hard_range_inn: RANGE_1,
+    soft_range_inn: RANGE_1,
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 0,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: None,
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   is_pull,
+                   ..
+               },
+               _| {
+        assert!(is_pull);
+
+        let input = &inputs[0];
+        let state_ident = wc.make_ident("state");
+
+        let write_prologue = quote_spanned! {op_span=>
+            let #state_ident = #hydroflow.add_state(::std::cell::RefCell::new(
+                #root::util::diff::UniqueDiffState::default()
+            ));
+        };
+        let write_iterator = quote_spanned! {op_span=>
+            let #ident = #context.state_ref(#state_ident).borrow_mut().apply(#input).into_iter();
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            state_handles: vec![state_ident],
+            ..Default::default()
+        })
+    },
+};
@@ -0,0 +1,118 @@
+use quote::quote_spanned;
+use syn::parse_quote;
+
+use super::{
+    DelayType, OperatorCategory, OperatorConstraints, OperatorWriteOutput, WriteContextArgs,
+    RANGE_0, RANGE_1,
+};
+use crate::graph::{GraphEdgeType, OperatorInstance};
+
+/// > 2 input streams, `input` of type `(K, V)` and `watermark` of type `u64`, 1 output stream of
+/// > type `(K, Window, A)`.
+///
+/// > Arguments: the session `gap`, the `allowed_lateness`, and three closures. The first closure
+/// extracts the event time (a `u64`) from a value `&V`. The second generates an initial
+/// accumulator value per key and window, and the third folds a value into the accumulator.
+///
+/// Groups items of each key in the first field into sessions of activity, separated by at least
+/// `gap` of event time without any items. Each item at `time` starts a session window
+/// `[time, time + gap)`, and overlapping sessions are merged. Within each session, the values in
+/// the second field are folded, like [`fold_keyed`](#fold_keyed). As sessions may merge, values are
+/// buffered and folded when the session is emitted, so values must be `Clone`.
+///
+/// The result of a window is emitted once the `watermark` input reaches the window's end, as a
+/// [`Window`](https://hydro-project.github.io/hydroflow/doc/hydroflow/util/window/struct.Window.html)
+/// along with the key and accumulator. Items which arrive after that but within the
+/// `allowed_lateness` update the window, and its result is emitted again. Later items are dropped.
+/// Times and watermarks may be in any unit, e.g. milliseconds since the epoch.
+///
+/// State persists across ticks until each window is past its allowed lateness.
+///
+/// ```hydroflow
+/// windows = window_session(5, 0, |&(time, _value)| time, || 0, |acc: &mut u32, (_time, value)| *acc += value)
+///     -> map(|(_key, window, sum)| (window.start, window.end, sum))
+///     -> assert_eq([(1, 9, 3), (12, 17, 10)]);
+///
+/// source_iter([("a", (1, 1)), ("a", (4, 2)), ("a", (12, 10)), ("a", (25, 100))])
+///     -> [input]windows;
+/// source_iter([20]) -> [watermark]windows;
+/// ```
+pub const WINDOW_SESSION: OperatorConstraints = OperatorConstraints {
+    name: "window_session",
+    categories: &[OperatorCategory::KeyedFold],
+    hard_range_inn: &(2..=2),
+    soft_range_inn: &(2..=2),
+    hard_range_out: RANGE_1,
+    soft_range_out: RANGE_1,
+    num_args: 5,
+    persistence_args: RANGE_0,
+    type_args: RANGE_0,
+    is_external_input: false,
+    ports_inn: Some(|| super::PortListSpec::Fixed(parse_quote! { input, watermark })),
+    ports_out: None,
+    input_delaytype_fn: |_| Some(DelayType::Stratum),
+    input_edgetype_fn: |_| Some(GraphEdgeType::Value),
+    output_edgetype_fn: |_| GraphEdgeType::Value,
+    flow_prop_fn: None,
+    write_fn: |wc @ &WriteContextArgs {
+                   root,
+                   context,
+                   hydroflow,
+                   op_span,
+                   ident,
+                   inputs,
+                   is_pull,
+                   op_inst: OperatorInstance { arguments, .. },
+                   ..
+               },
+               _| {
+        assert!(is_pull);
+
+        let gap = &arguments[0];
+        let allowed_lateness = &arguments[1];
+        let time_fn = &arguments[2];
+        let init_fn = &arguments[3];
+        let fold_fn = &arguments[4];
+
+        let input = &inputs[0];
+        let watermark = &inputs[1];
+
+        let windows_ident = wc.make_ident("windows");
+        let borrow_ident = wc.make_ident("windows_borrow");
+
+        let write_prologue = quote_spanned! {op_span=>
+            let #windows_ident = #hydroflow.add_state(::std::cell::RefCell::new(
+                #root::util::window::SessionWindows::new(#gap, #allowed_lateness)
+            ));
+        };
+        let write_iterator = quote_spanned! {op_span=>
+            let mut #borrow_ident = #context.state_ref(#windows_ident).borrow_mut();
+            {
+                #[inline(always)]
+                fn check_input<Iter: ::std::iter::Iterator<Item = (K, V)>, K, V>(iter: Iter)
+                    -> impl ::std::iter::Iterator<Item = (K, V)> { iter }
+
+                #[inline(always)]
+                fn call_time_fn<V>(value: &V, f: impl Fn(&V) -> u64) -> u64 {
+                    f(value)
+                }
+
+                for (key, value) in check_input(#input) {
+                    let time = call_time_fn(&value, #time_fn);
+                    #borrow_ident.insert(key, time, value);
+                }
+                if let ::std::option::Option::Some(watermark) = ::std::iter::Iterator::max(#watermark) {
+                    #borrow_ident.advance_watermark(watermark);
+                }
+            }
+            let #ident = #borrow_ident.take_output(#init_fn, #fold_fn).into_iter();
+        };
+
+        Ok(OperatorWriteOutput {
+            write_prologue,
+            write_iterator,
+            state_handles: vec![windows_ident],
+            ..Default::default()
+        })
+    },
+};
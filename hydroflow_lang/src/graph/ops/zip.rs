@@ -114,6 +114,7 @@ pub const ZIP: OperatorConstraints = OperatorConstraints {
         Ok(OperatorWriteOutput {
             write_prologue,
             write_iterator,
+            state_handles: vec![zipbuf_ident],
             ..Default::default()
         })
     },
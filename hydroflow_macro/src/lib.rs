@@ -24,7 +24,7 @@ use syn::{
 // TODO(mingwei): rustdoc examples inline.
 #[proc_macro]
 pub fn hydroflow_syntax(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    hydroflow_syntax_internal(input, Some(Level::Help))
+    hydroflow_syntax_internal(input, Some(Level::Help), false, false)
 }
 
 /// [`hydroflow_syntax!`] but will not emit any diagnostics (errors, warnings, etc.).
@@ -32,7 +32,29 @@ pub fn hydroflow_syntax(input: proc_macro::TokenStream) -> proc_macro::TokenStre
 /// Used for testing, users will want to use [`hydroflow_syntax!`] instead.
 #[proc_macro]
 pub fn hydroflow_syntax_noemit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    hydroflow_syntax_internal(input, None)
+    hydroflow_syntax_internal(input, None, false, false)
+}
+
+/// [`hydroflow_syntax!`] but all operator state and handoff contents are included in checkpoints
+/// taken with `Hydroflow::checkpoint`, and can be restored with `Hydroflow::restore`.
+///
+/// Requires all state and item types in the graph to implement `serde::Serialize` and
+/// `serde::Deserialize`.
+#[proc_macro]
+pub fn hydroflow_syntax_checkpointed(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    hydroflow_syntax_internal(input, Some(Level::Help), true, false)
+}
+
+/// [`hydroflow_syntax!`] but independent subgraphs of the same stratum, such as separate `tee()`
+/// branches, may run concurrently on the worker threads set with `Hydroflow::set_worker_threads`.
+///
+/// The outputs of `tee()` and other operators with multiple outputs are each split into their own
+/// subgraph. Subgraphs made only of stateless operators (`map`, `filter`, `for_each`, etc.) run on
+/// the worker threads, all others run on the current thread. Closures in parallel subgraphs must
+/// be `Send`, and items passed between subgraphs must be `Send`.
+#[proc_macro]
+pub fn hydroflow_syntax_parallel(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    hydroflow_syntax_internal(input, Some(Level::Help), false, true)
 }
 
 fn root() -> proc_macro2::TokenStream {
@@ -80,12 +102,15 @@ fn macro_invocation_path() -> PathBuf {
 fn hydroflow_syntax_internal(
     input: proc_macro::TokenStream,
     min_diagnostic_level: Option<Level>,
+    checkpointed: bool,
+    parallel: bool,
 ) -> proc_macro::TokenStream {
     let macro_invocation_path = macro_invocation_path();
 
     let input = parse_macro_input!(input as HfCode);
     let root = root();
-    let (graph_code_opt, diagnostics) = build_hfcode(input, &root, macro_invocation_path);
+    let (graph_code_opt, diagnostics) =
+        build_hfcode(input, &root, checkpointed, parallel, macro_invocation_path);
     let tokens = graph_code_opt
         .map(|(_graph, code)| code)
         .unwrap_or_else(|| quote! { #root::scheduled::graph::Hydroflow::new() });
@@ -125,7 +125,8 @@ fn build_inner<'a, D: LocalDeploy<'a>>(me: &FlowBuilder<'a, D>, id: TokenStream)
         let _ =
             propagate_flow_props::propagate_flow_props(&mut partitioned_graph, &mut diagnostics);
 
-        let tokens = partitioned_graph.as_code(&root, true, quote::quote!(), &mut diagnostics);
+        let tokens =
+            partitioned_graph.as_code(&root, true, false, false, quote::quote!(), &mut diagnostics);
 
         if let Some(conditioned_tokens) = conditioned_tokens.as_mut() {
             *conditioned_tokens = parse_quote! {
@@ -16,6 +16,7 @@ mod pair;
 mod point;
 pub mod set_union;
 pub mod set_union_with_tombstones;
+pub mod sketch;
 pub mod test;
 pub mod union_find;
 mod unit;
@@ -0,0 +1,970 @@
+//! Probabilistic sketch lattices, which summarize high-cardinality streams in bounded space.
+//!
+//! * [`HyperLogLog`] - approximate count of distinct items.
+//! * [`CountMinSketch`] - approximate count of each item, for finding heavy hitters.
+//! * [`QuantileSketch`] - approximate quantiles of `f64` values, with relative accuracy.
+//! * [`DisjointSketches`] - [`CountMinSketch`]es or [`QuantileSketch`]es of different nodes'
+//!   streams, which are added when queried.
+//!
+//! Each sketch is a lattice whose merge takes the pointwise max of its registers or counters, so
+//! merging is associative, commutative, and idempotent. For [`HyperLogLog`] this computes the
+//! sketch of the union of the two input streams, so sketches from different nodes can be combined
+//! directly, for example with `lattice_fold`.
+//!
+//! For [`CountMinSketch`] and [`QuantileSketch`] the pointwise max is only exact when merging
+//! versions of the _same_ stream's sketch, such as a node's sketch resent as it grows. The counts
+//! of _disjoint_ streams must be added with [`AddDisjoint::add_disjoint`] instead, which is not
+//! idempotent. To combine sketches from different nodes, key each node's sketch by its node ID in
+//! a [`DisjointSketches`] lattice, then query the sum with [`DisjointSketches::combined`].
+//!
+//! Sketches can only be merged with sketches of the same parameters. `merge` panics in debug
+//! builds if the parameters differ, and otherwise ignores the other sketch. Use each sketch's
+//! `try_merge` to detect mismatched parameters, for example from misconfigured peers.
+
+use std::cmp::Ordering::{self, *};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use siphasher::sip::SipHasher24;
+
+use crate::map_union::MapUnionBTreeMap;
+use crate::{IsBot, IsTop, LatticeFrom, LatticeOrd, Merge};
+
+/// Hashes `item` with a `seed`, using SipHash-2-4 keyed with `(seed, 0)`. This must be the same on
+/// every node and Rust version so sketches built on different nodes can be merged, so unlike
+/// [`DefaultHasher`](std::collections::hash_map::DefaultHasher) the algorithm is fixed, and
+/// integers are hashed as little-endian with `usize` widened to 64 bits.
+fn hash_with_seed<T: Hash + ?Sized>(seed: u64, item: &T) -> u64 {
+    let mut hasher = SketchHasher(SipHasher24::new_with_keys(seed, 0));
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// [`Hasher`] which is independent of the platform, see [`hash_with_seed`].
+struct SketchHasher(SipHasher24);
+impl Hasher for SketchHasher {
+    fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.write(bytes);
+    }
+    fn write_u8(&mut self, i: u8) {
+        self.0.write(&[i]);
+    }
+    fn write_u16(&mut self, i: u16) {
+        self.0.write(&i.to_le_bytes());
+    }
+    fn write_u32(&mut self, i: u32) {
+        self.0.write(&i.to_le_bytes());
+    }
+    fn write_u64(&mut self, i: u64) {
+        self.0.write(&i.to_le_bytes());
+    }
+    fn write_u128(&mut self, i: u128) {
+        self.0.write(&i.to_le_bytes());
+    }
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+/// Compares two sketches pointwise, returning `None` if each has some greater counter.
+fn pointwise_cmp<T: Ord>(pairs: impl IntoIterator<Item = (T, T)>) -> Option<Ordering> {
+    let mut self_any_greater = false;
+    let mut other_any_greater = false;
+    for (self_val, other_val) in pairs {
+        match self_val.cmp(&other_val) {
+            Less => other_any_greater = true,
+            Greater => self_any_greater = true,
+            Equal => {}
+        }
+        if self_any_greater && other_any_greater {
+            return None;
+        }
+    }
+    match (self_any_greater, other_any_greater) {
+        (true, false) => Some(Greater),
+        (false, true) => Some(Less),
+        (false, false) => Some(Equal),
+        (true, true) => unreachable!(),
+    }
+}
+
+/// Merges `other` into `this` by pointwise max, returning if `this` changed.
+fn pointwise_max<T: Ord + Copy>(this: &mut [T], other: &[T]) -> bool {
+    let mut changed = false;
+    for (self_val, &other_val) in this.iter_mut().zip(other) {
+        if *self_val < other_val {
+            *self_val = other_val;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Error returned by `try_merge` when two sketches have different parameters and so cannot be
+/// merged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SketchMismatch {
+    message: &'static str,
+}
+impl fmt::Display for SketchMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message)
+    }
+}
+impl std::error::Error for SketchMismatch {}
+
+/// A [HyperLogLog](https://en.wikipedia.org/wiki/HyperLogLog) sketch, which approximately counts
+/// distinct items using `2^precision` bytes.
+///
+/// The relative standard error of the estimate is about `1.04 / sqrt(2^precision)`, for example
+/// 1.6% with the default precision of 12.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+}
+impl HyperLogLog {
+    /// The precision used by [`Default::default()`].
+    pub const DEFAULT_PRECISION: u8 = 12;
+
+    /// Creates a new empty sketch with `2^precision` registers. `precision` must be between 4 and
+    /// 16 inclusive.
+    pub fn new(precision: u8) -> Self {
+        assert!(
+            (4..=16).contains(&precision),
+            "HyperLogLog precision must be between 4 and 16, got {}.",
+            precision
+        );
+        Self {
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// The precision of this sketch, the log2 of the number of registers.
+    pub fn precision(&self) -> u8 {
+        self.registers.len().trailing_zeros() as u8
+    }
+
+    /// Inserts an item. Returns `true` if the sketch changed.
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        let precision = self.precision();
+        let hash = hash_with_seed(0, item);
+        let index = (hash >> (64 - precision)) as usize;
+        let rank = ((hash << precision).leading_zeros() + 1).min(self.max_rank() as u32) as u8;
+        if self.registers[index] < rank {
+            self.registers[index] = rank;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Estimates the number of distinct items inserted.
+    pub fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&rank| (-(rank as f64)).exp2())
+            .sum();
+        let raw = alpha * m * m / sum;
+
+        let zeros = self.registers.iter().filter(|&&rank| 0 == rank).count();
+        let estimate = if raw <= 2.5 * m && 0 < zeros {
+            // Small range correction, linear counting.
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        };
+        estimate.round() as u64
+    }
+
+    /// Merges `other` into this sketch, returning if this sketch changed, or an error without
+    /// changing this sketch if the two have different precisions.
+    pub fn try_merge(&mut self, other: &Self) -> Result<bool, SketchMismatch> {
+        if self.registers.len() != other.registers.len() {
+            return Err(SketchMismatch {
+                message: "Cannot merge `HyperLogLog` sketches with different precisions.",
+            });
+        }
+        Ok(pointwise_max(&mut self.registers, &other.registers))
+    }
+
+    fn max_rank(&self) -> u8 {
+        64 - self.precision() + 1
+    }
+}
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_PRECISION)
+    }
+}
+
+impl Merge<HyperLogLog> for HyperLogLog {
+    /// # Panics
+    ///
+    /// In debug builds, if the sketches have different precisions. In release builds `other` is
+    /// ignored instead. Use [`HyperLogLog::try_merge`] to handle this case.
+    fn merge(&mut self, other: HyperLogLog) -> bool {
+        let result = self.try_merge(&other);
+        debug_assert!(result.is_ok(), "{}", result.unwrap_err());
+        result.unwrap_or(false)
+    }
+}
+
+impl LatticeFrom<HyperLogLog> for HyperLogLog {
+    fn lattice_from(other: HyperLogLog) -> Self {
+        other
+    }
+}
+
+impl PartialOrd for HyperLogLog {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.registers.len() != other.registers.len() {
+            return None;
+        }
+        pointwise_cmp(self.registers.iter().zip(other.registers.iter()))
+    }
+}
+impl LatticeOrd for HyperLogLog {}
+
+impl IsBot for HyperLogLog {
+    fn is_bot(&self) -> bool {
+        self.registers.iter().all(|&rank| 0 == rank)
+    }
+}
+
+impl IsTop for HyperLogLog {
+    fn is_top(&self) -> bool {
+        let max_rank = self.max_rank();
+        self.registers.iter().all(|&rank| max_rank == rank)
+    }
+}
+
+/// A [Count-Min sketch](https://en.wikipedia.org/wiki/Count%E2%80%93min_sketch), which
+/// approximately counts occurrences of each item using a `depth` by `width` table of counters.
+///
+/// Estimates never undercount. With probability `1 - e^-depth`, an estimate overcounts by at most
+/// `e / width` times the total count of all items.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CountMinSketch {
+    width: usize,
+    depth: usize,
+    /// `depth` rows of `width` counters.
+    counters: Vec<u64>,
+    total: u64,
+}
+impl CountMinSketch {
+    /// The width used by [`Default::default()`].
+    pub const DEFAULT_WIDTH: usize = 2048;
+    /// The depth used by [`Default::default()`].
+    pub const DEFAULT_DEPTH: usize = 4;
+
+    /// Creates a new empty sketch with `depth` rows of `width` counters.
+    pub fn new(width: usize, depth: usize) -> Self {
+        assert!(
+            0 < width && 0 < depth,
+            "Count-Min sketch width and depth must be positive."
+        );
+        Self {
+            width,
+            depth,
+            counters: vec![0; width * depth],
+            total: 0,
+        }
+    }
+
+    /// Creates a new empty sketch where, with probability `1 - delta`, estimates overcount by at
+    /// most `epsilon` times the total count.
+    pub fn with_error(epsilon: f64, delta: f64) -> Self {
+        assert!(
+            0.0 < epsilon && 0.0 < delta && delta < 1.0,
+            "Count-Min sketch `epsilon` must be positive and `delta` must be between 0 and 1."
+        );
+        let width = (std::f64::consts::E / epsilon).ceil() as usize;
+        let depth = (1.0 / delta).ln().ceil().max(1.0) as usize;
+        Self::new(width, depth)
+    }
+
+    /// The number of counters in each row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The number of rows.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The total count of all items inserted.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Inserts one occurrence of an item.
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        self.insert_count(item, 1);
+    }
+
+    /// Inserts `count` occurrences of an item.
+    pub fn insert_count<T: Hash + ?Sized>(&mut self, item: &T, count: u64) {
+        self.total = self.total.saturating_add(count);
+        for row in 0..self.depth {
+            let index = self.index(row, item);
+            self.counters[index] = self.counters[index].saturating_add(count);
+        }
+    }
+
+    /// Estimates the number of occurrences of an item.
+    pub fn estimate<T: Hash + ?Sized>(&self, item: &T) -> u64 {
+        (0..self.depth)
+            .map(|row| self.counters[self.index(row, item)])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Adds the counts of `other`, a sketch of a stream disjoint from this one's. Unlike
+    /// [`Merge::merge`] this is not idempotent, so it is not a lattice merge.
+    pub fn add_disjoint(&mut self, other: &Self) {
+        assert!(
+            self.width == other.width && self.depth == other.depth,
+            "Cannot add `CountMinSketch`es with different dimensions."
+        );
+        self.total = self.total.saturating_add(other.total);
+        for (self_val, &other_val) in self.counters.iter_mut().zip(&other.counters) {
+            *self_val = self_val.saturating_add(other_val);
+        }
+    }
+
+    /// Merges `other`, another version of this sketch's stream, into this sketch, returning if
+    /// this sketch changed, or an error without changing this sketch if the two have different
+    /// dimensions.
+    pub fn try_merge(&mut self, other: &Self) -> Result<bool, SketchMismatch> {
+        if self.width != other.width || self.depth != other.depth {
+            return Err(SketchMismatch {
+                message: "Cannot merge `CountMinSketch`es with different dimensions.",
+            });
+        }
+        let mut changed = pointwise_max(&mut self.counters, &other.counters);
+        if self.total < other.total {
+            self.total = other.total;
+            changed = true;
+        }
+        Ok(changed)
+    }
+
+    fn index<T: Hash + ?Sized>(&self, row: usize, item: &T) -> usize {
+        row * self.width + (hash_with_seed(row as u64, item) % self.width as u64) as usize
+    }
+}
+impl Default for CountMinSketch {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_WIDTH, Self::DEFAULT_DEPTH)
+    }
+}
+impl AddDisjoint for CountMinSketch {
+    fn add_disjoint(&mut self, other: &Self) {
+        CountMinSketch::add_disjoint(self, other)
+    }
+}
+
+impl Merge<CountMinSketch> for CountMinSketch {
+    /// # Panics
+    ///
+    /// In debug builds, if the sketches have different dimensions. In release builds `other` is
+    /// ignored instead. Use [`CountMinSketch::try_merge`] to handle this case.
+    fn merge(&mut self, other: CountMinSketch) -> bool {
+        let result = self.try_merge(&other);
+        debug_assert!(result.is_ok(), "{}", result.unwrap_err());
+        result.unwrap_or(false)
+    }
+}
+
+impl LatticeFrom<CountMinSketch> for CountMinSketch {
+    fn lattice_from(other: CountMinSketch) -> Self {
+        other
+    }
+}
+
+impl PartialOrd for CountMinSketch {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.width != other.width || self.depth != other.depth {
+            return None;
+        }
+        pointwise_cmp(
+            std::iter::once((self.total, other.total)).chain(
+                self.counters
+                    .iter()
+                    .copied()
+                    .zip(other.counters.iter().copied()),
+            ),
+        )
+    }
+}
+impl LatticeOrd for CountMinSketch {}
+
+impl IsBot for CountMinSketch {
+    fn is_bot(&self) -> bool {
+        0 == self.total && self.counters.iter().all(|&count| 0 == count)
+    }
+}
+
+impl IsTop for CountMinSketch {
+    fn is_top(&self) -> bool {
+        u64::MAX == self.total && self.counters.iter().all(|&count| u64::MAX == count)
+    }
+}
+
+/// A [DDSketch](https://arxiv.org/abs/1908.10693), which approximates quantiles of `f64` values
+/// with a relative accuracy guarantee: each quantile estimate is within `relative_accuracy` times
+/// the true quantile value.
+///
+/// Values are counted in logarithmically sized buckets, so the space used grows with the log of
+/// the range of the values, not with the number of values. For example, values between a
+/// nanosecond and a day with 1% relative accuracy need at most about 1600 buckets. `NaN` values are
+/// ignored.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuantileSketch {
+    relative_accuracy: f64,
+    /// Counts of positive values, by bucket index. Zero counts are not stored.
+    positive: BTreeMap<i32, u64>,
+    /// Counts of negative values, by bucket index of their magnitude.
+    negative: BTreeMap<i32, u64>,
+    /// Count of values too close to zero to be bucketed.
+    zero_count: u64,
+}
+impl QuantileSketch {
+    /// The relative accuracy used by [`Default::default()`].
+    pub const DEFAULT_RELATIVE_ACCURACY: f64 = 0.01;
+
+    /// Creates a new empty sketch. `relative_accuracy` must be between 0 and 1 exclusive.
+    pub fn new(relative_accuracy: f64) -> Self {
+        assert!(
+            0.0 < relative_accuracy && relative_accuracy < 1.0,
+            "Quantile sketch relative accuracy must be between 0 and 1, got {}.",
+            relative_accuracy
+        );
+        Self {
+            relative_accuracy,
+            positive: BTreeMap::new(),
+            negative: BTreeMap::new(),
+            zero_count: 0,
+        }
+    }
+
+    /// The relative accuracy of quantile estimates.
+    pub fn relative_accuracy(&self) -> f64 {
+        self.relative_accuracy
+    }
+
+    /// The number of values inserted.
+    pub fn count(&self) -> u64 {
+        self.zero_count + self.positive.values().sum::<u64>() + self.negative.values().sum::<u64>()
+    }
+
+    /// Inserts a value.
+    pub fn insert(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        let ln_gamma = self.gamma().ln();
+        let (buckets, magnitude) = if f64::MIN_POSITIVE < value {
+            (&mut self.positive, value)
+        } else if value < -f64::MIN_POSITIVE {
+            (&mut self.negative, -value)
+        } else {
+            self.zero_count += 1;
+            return;
+        };
+        let index = (magnitude.ln() / ln_gamma).ceil() as i32;
+        *buckets.entry(index).or_insert(0) += 1;
+    }
+
+    /// Estimates the `q`-quantile, for `q` between 0 and 1 inclusive, for example `0.5` for the
+    /// median. Returns `None` if the sketch is empty.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let count = self.count();
+        if 0 == count {
+            return None;
+        }
+        let rank = (q.clamp(0.0, 1.0) * (count - 1) as f64).floor() as u64;
+
+        // Buckets from least to greatest value.
+        let buckets = self
+            .negative
+            .iter()
+            .rev()
+            .map(|(&index, &count)| (-self.bucket_value(index), count))
+            .chain(std::iter::once((0.0, self.zero_count)))
+            .chain(
+                self.positive
+                    .iter()
+                    .map(|(&index, &count)| (self.bucket_value(index), count)),
+            );
+        let mut seen = 0;
+        for (value, count) in buckets {
+            seen += count;
+            if rank < seen {
+                return Some(value);
+            }
+        }
+        unreachable!()
+    }
+
+    /// Adds the counts of `other`, a sketch of a stream disjoint from this one's. Unlike
+    /// [`Merge::merge`] this is not idempotent, so it is not a lattice merge.
+    pub fn add_disjoint(&mut self, other: &Self) {
+        self.assert_same_accuracy(other);
+        self.zero_count += other.zero_count;
+        for (&index, &count) in other.positive.iter() {
+            *self.positive.entry(index).or_insert(0) += count;
+        }
+        for (&index, &count) in other.negative.iter() {
+            *self.negative.entry(index).or_insert(0) += count;
+        }
+    }
+
+    /// Merges `other`, another version of this sketch's stream, into this sketch, returning if
+    /// this sketch changed, or an error without changing this sketch if the two have different
+    /// relative accuracies.
+    pub fn try_merge(&mut self, other: &Self) -> Result<bool, SketchMismatch> {
+        if self.relative_accuracy != other.relative_accuracy {
+            return Err(SketchMismatch {
+                message: "Cannot merge `QuantileSketch`es with different relative accuracies.",
+            });
+        }
+        let mut changed = map_pointwise_max(&mut self.positive, &other.positive);
+        changed |= map_pointwise_max(&mut self.negative, &other.negative);
+        if self.zero_count < other.zero_count {
+            self.zero_count = other.zero_count;
+            changed = true;
+        }
+        Ok(changed)
+    }
+
+    fn gamma(&self) -> f64 {
+        (1.0 + self.relative_accuracy) / (1.0 - self.relative_accuracy)
+    }
+
+    /// The value which represents a bucket, with at most `relative_accuracy` error.
+    fn bucket_value(&self, index: i32) -> f64 {
+        let gamma = self.gamma();
+        2.0 * gamma.powi(index) / (gamma + 1.0)
+    }
+
+    fn assert_same_accuracy(&self, other: &Self) {
+        assert!(
+            self.relative_accuracy == other.relative_accuracy,
+            "Cannot combine `QuantileSketch`es with different relative accuracies."
+        );
+    }
+}
+impl Default for QuantileSketch {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_RELATIVE_ACCURACY)
+    }
+}
+impl AddDisjoint for QuantileSketch {
+    fn add_disjoint(&mut self, other: &Self) {
+        QuantileSketch::add_disjoint(self, other)
+    }
+}
+// `relative_accuracy` is never `NaN`.
+impl Eq for QuantileSketch {}
+
+/// Merges `other` into `this` by pointwise max, returning if `this` changed.
+fn map_pointwise_max(this: &mut BTreeMap<i32, u64>, other: &BTreeMap<i32, u64>) -> bool {
+    let mut changed = false;
+    for (&index, &other_count) in other {
+        let self_count = this.entry(index).or_insert(0);
+        if *self_count < other_count {
+            *self_count = other_count;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Pairs of counts for each bucket in either map, possibly repeated.
+fn map_pointwise_pairs<'a>(
+    this: &'a BTreeMap<i32, u64>,
+    other: &'a BTreeMap<i32, u64>,
+) -> impl 'a + Iterator<Item = (u64, u64)> {
+    this.keys().chain(other.keys()).map(|index| {
+        (
+            this.get(index).copied().unwrap_or(0),
+            other.get(index).copied().unwrap_or(0),
+        )
+    })
+}
+
+impl Merge<QuantileSketch> for QuantileSketch {
+    /// # Panics
+    ///
+    /// In debug builds, if the sketches have different relative accuracies. In release builds
+    /// `other` is ignored instead. Use [`QuantileSketch::try_merge`] to handle this case.
+    fn merge(&mut self, other: QuantileSketch) -> bool {
+        let result = self.try_merge(&other);
+        debug_assert!(result.is_ok(), "{}", result.unwrap_err());
+        result.unwrap_or(false)
+    }
+}
+
+impl LatticeFrom<QuantileSketch> for QuantileSketch {
+    fn lattice_from(other: QuantileSketch) -> Self {
+        other
+    }
+}
+
+impl PartialOrd for QuantileSketch {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.relative_accuracy != other.relative_accuracy {
+            return None;
+        }
+        pointwise_cmp(
+            std::iter::once((self.zero_count, other.zero_count))
+                .chain(map_pointwise_pairs(&self.positive, &other.positive))
+                .chain(map_pointwise_pairs(&self.negative, &other.negative)),
+        )
+    }
+}
+impl LatticeOrd for QuantileSketch {}
+
+impl IsBot for QuantileSketch {
+    fn is_bot(&self) -> bool {
+        0 == self.zero_count && self.positive.is_empty() && self.negative.is_empty()
+    }
+}
+
+impl IsTop for QuantileSketch {
+    fn is_top(&self) -> bool {
+        false
+    }
+}
+
+/// Sketches whose counts can be added to get the sketch of the union of disjoint streams.
+pub trait AddDisjoint {
+    /// Adds the counts of `other`, a sketch of a stream disjoint from this one's. Unlike
+    /// [`Merge::merge`] this is not idempotent, so it is not a lattice merge.
+    fn add_disjoint(&mut self, other: &Self);
+}
+
+/// A lattice of sketches of disjoint streams, keyed by stream, such as each node's sketch of its
+/// local stream keyed by node ID.
+///
+/// Merging merges the sketches of each key, so a node's sketch may be resent as it grows, and
+/// received any number of times. [`Self::combined`] adds the sketches of all keys with
+/// [`AddDisjoint::add_disjoint`] to get the sketch of the union of all the streams.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(deserialize = "K: Ord + serde::Deserialize<'de>, S: serde::Deserialize<'de>"))
+)]
+pub struct DisjointSketches<K, S> {
+    sketches: MapUnionBTreeMap<K, S>,
+}
+impl<K: Ord, S> DisjointSketches<K, S> {
+    /// Creates a new `DisjointSketches` containing the single `sketch` of the stream `key`.
+    pub fn new_single(key: K, sketch: S) -> Self {
+        Self {
+            sketches: MapUnionBTreeMap::new_from([(key, sketch)]),
+        }
+    }
+
+    /// The sketch of each stream.
+    pub fn sketches(&self) -> &BTreeMap<K, S> {
+        self.sketches.as_reveal_ref()
+    }
+
+    /// Returns the sketch of the union of all the streams, or `None` if there are none.
+    pub fn combined(&self) -> Option<S>
+    where
+        S: AddDisjoint + Clone,
+    {
+        let mut sketches = self.sketches().values();
+        let mut combined = sketches.next()?.clone();
+        for sketch in sketches {
+            combined.add_disjoint(sketch);
+        }
+        Some(combined)
+    }
+}
+
+impl<K, S> Default for DisjointSketches<K, S> {
+    fn default() -> Self {
+        Self {
+            sketches: Default::default(),
+        }
+    }
+}
+
+impl<K: Ord, S> Merge<DisjointSketches<K, S>> for DisjointSketches<K, S>
+where
+    S: Merge<S> + LatticeFrom<S> + IsBot,
+{
+    fn merge(&mut self, other: DisjointSketches<K, S>) -> bool {
+        self.sketches.merge(other.sketches)
+    }
+}
+
+impl<K, S> LatticeFrom<DisjointSketches<K, S>> for DisjointSketches<K, S> {
+    fn lattice_from(other: DisjointSketches<K, S>) -> Self {
+        other
+    }
+}
+
+impl<K: Ord, S> PartialOrd for DisjointSketches<K, S>
+where
+    S: PartialOrd + IsBot,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.sketches.partial_cmp(&other.sketches)
+    }
+}
+impl<K: Ord, S> LatticeOrd for DisjointSketches<K, S> where Self: PartialOrd {}
+
+impl<K: Ord, S> PartialEq for DisjointSketches<K, S>
+where
+    S: PartialEq + IsBot,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.sketches == other.sketches
+    }
+}
+impl<K: Ord, S> Eq for DisjointSketches<K, S> where Self: PartialEq {}
+
+impl<K, S> IsBot for DisjointSketches<K, S>
+where
+    S: IsBot,
+{
+    fn is_bot(&self) -> bool {
+        self.sketches.is_bot()
+    }
+}
+
+impl<K, S> IsTop for DisjointSketches<K, S> {
+    fn is_top(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::check_all;
+
+    #[test]
+    fn hash_is_fixed() {
+        // Sketches from different nodes can only be merged if these never change. `str` hashes
+        // its bytes followed by `0xFF`.
+        assert_eq!(1135563785245924819, hash_with_seed(0, &42_u64));
+        assert_eq!(506549100735965593, hash_with_seed(1, "hello"));
+        assert_eq!(hash_with_seed(3, &42_u64), hash_with_seed(3, &42_usize));
+    }
+
+    #[test]
+    fn hyperloglog_estimate() {
+        let mut hll = HyperLogLog::default();
+        for i in 0..10_000 {
+            hll.insert(&i);
+            // Duplicates are not counted.
+            hll.insert(&i);
+        }
+        let estimate = hll.estimate();
+        assert!((9_500..=10_500).contains(&estimate), "{}", estimate);
+
+        let mut other = HyperLogLog::default();
+        for i in 5_000..15_000 {
+            other.insert(&i);
+        }
+        assert!(hll.merge(other.clone()));
+        assert!(!hll.merge(other));
+        let estimate = hll.estimate();
+        assert!((14_250..=15_750).contains(&estimate), "{}", estimate);
+    }
+
+    #[test]
+    fn try_merge_mismatch() {
+        let mut hll = HyperLogLog::new(8);
+        hll.insert(&1);
+        let before = hll.clone();
+        assert!(hll.try_merge(&HyperLogLog::new(10)).is_err());
+        assert_eq!(before, hll);
+        assert_eq!(Ok(false), hll.try_merge(&before));
+        assert_eq!(None, hll.partial_cmp(&HyperLogLog::new(10)));
+
+        let mut cms = CountMinSketch::new(16, 2);
+        cms.insert(&1);
+        let before = cms.clone();
+        assert!(cms.try_merge(&CountMinSketch::new(16, 3)).is_err());
+        assert!(cms.try_merge(&CountMinSketch::new(32, 2)).is_err());
+        assert_eq!(before, cms);
+
+        let mut quantiles = QuantileSketch::new(0.01);
+        quantiles.insert(1.0);
+        let before = quantiles.clone();
+        let err = quantiles.try_merge(&QuantileSketch::new(0.02)).unwrap_err();
+        assert_eq!(
+            "Cannot merge `QuantileSketch`es with different relative accuracies.",
+            err.to_string()
+        );
+        assert_eq!(before, quantiles);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "different precisions")]
+    fn merge_mismatch_panics_in_debug() {
+        HyperLogLog::new(8).merge(HyperLogLog::new(10));
+    }
+
+    #[test]
+    fn hyperloglog_consistency() {
+        let items = [vec![], vec![0], vec![1], vec![0, 1], vec![2, 3, 4]].map(|items| {
+            let mut hll = HyperLogLog::default();
+            for item in items {
+                hll.insert(&item);
+            }
+            hll
+        });
+        check_all(&items);
+    }
+
+    #[test]
+    fn count_min_estimate() {
+        let mut sketch = CountMinSketch::with_error(0.001, 0.01);
+        for i in 0..1_000_u64 {
+            sketch.insert_count(&i, i % 10);
+        }
+        sketch.insert_count("heavy", 5_000);
+        assert_eq!(9_500, sketch.total());
+        assert!(5_000 <= sketch.estimate("heavy"));
+        assert!(sketch.estimate("heavy") <= 5_000 + 20);
+        assert!(7 <= sketch.estimate(&7_u64));
+
+        let mut other = CountMinSketch::with_error(0.001, 0.01);
+        other.insert_count("heavy", 1_000);
+        sketch.add_disjoint(&other);
+        assert!(6_000 <= sketch.estimate("heavy"));
+    }
+
+    #[test]
+    fn count_min_consistency() {
+        let items = [vec![], vec!["a"], vec!["b"], vec!["a", "b"], vec!["a", "a"]].map(|items| {
+            let mut sketch = CountMinSketch::default();
+            for item in items {
+                sketch.insert(item);
+            }
+            sketch
+        });
+        check_all(&items);
+    }
+
+    #[test]
+    fn quantile_estimate() {
+        let mut sketch = QuantileSketch::default();
+        for i in 1..=1000 {
+            sketch.insert(i as f64);
+        }
+        sketch.insert(f64::NAN);
+        assert_eq!(1000, sketch.count());
+        for (q, expected) in [(0.0, 1.0), (0.5, 500.0), (0.99, 990.0), (1.0, 1000.0)] {
+            let estimate = sketch.quantile(q).unwrap();
+            assert!(
+                (estimate - expected).abs() <= 0.01 * expected,
+                "q={} estimate={} expected={}",
+                q,
+                estimate,
+                expected
+            );
+        }
+
+        let mut negative = QuantileSketch::default();
+        for value in [-10.0, 0.0, 10.0] {
+            negative.insert(value);
+        }
+        assert!((negative.quantile(0.0).unwrap() + 10.0).abs() <= 0.1);
+        assert_eq!(Some(0.0), negative.quantile(0.5));
+        assert_eq!(None, QuantileSketch::default().quantile(0.5));
+    }
+
+    #[test]
+    fn quantile_consistency() {
+        let items = [
+            vec![],
+            vec![1.0],
+            vec![-1.0],
+            vec![0.0, 1.0],
+            vec![1.0, 1.0],
+            vec![-1.0, 100.0],
+        ]
+        .map(|values| {
+            let mut sketch = QuantileSketch::default();
+            for value in values {
+                sketch.insert(value);
+            }
+            sketch
+        });
+        check_all(&items);
+    }
+
+    #[test]
+    fn disjoint_sketches_combined() {
+        let mut node_a = CountMinSketch::default();
+        node_a.insert_count("heavy", 100);
+        let mut node_b = CountMinSketch::default();
+        node_b.insert_count("heavy", 50);
+
+        let mut sketches = DisjointSketches::new_single("a", node_a.clone());
+        assert!(sketches.merge(DisjointSketches::new_single("b", node_b)));
+        // Receiving a node's sketch again does not double count it.
+        assert!(!sketches.merge(DisjointSketches::new_single("a", node_a.clone())));
+        assert_eq!(150, sketches.combined().unwrap().estimate("heavy"));
+
+        // A node's sketch may be resent as it grows.
+        node_a.insert_count("heavy", 10);
+        assert!(sketches.merge(DisjointSketches::new_single("a", node_a)));
+        let combined = sketches.combined().unwrap();
+        assert_eq!(160, combined.estimate("heavy"));
+        assert_eq!(160, combined.total());
+
+        assert_eq!(
+            None,
+            DisjointSketches::<&str, CountMinSketch>::default().combined()
+        );
+    }
+
+    #[test]
+    fn disjoint_sketches_consistency() {
+        let items = [
+            vec![],
+            vec![("a", 1.0)],
+            vec![("b", 1.0)],
+            vec![("a", 1.0), ("a", 2.0)],
+            vec![("a", 1.0), ("b", -1.0)],
+        ]
+        .map(|values| {
+            let mut sketches = DisjointSketches::default();
+            for (key, value) in values {
+                let mut sketch = QuantileSketch::default();
+                sketch.insert(value);
+                sketches.merge(DisjointSketches::new_single(key, sketch));
+            }
+            sketches
+        });
+        check_all(&items);
+    }
+}
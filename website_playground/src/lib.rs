@@ -109,7 +109,7 @@ pub fn compile_hydroflow(program: String) -> JsValue {
     let out = match syn::parse_str(&program) {
         Ok(input) => {
             let (graph_code_opt, diagnostics) =
-                build_hfcode(input, &quote!(hydroflow), PathBuf::default());
+                build_hfcode(input, &quote!(hydroflow), false, false, PathBuf::default());
             let output = graph_code_opt.map(|(graph, code)| {
                 let mermaid = graph.to_mermaid(&Default::default());
                 let file = syn::parse_quote! {
@@ -154,6 +154,8 @@ pub fn compile_datalog(program: String) -> JsValue {
                         let out = part_graph.as_code(
                             &quote!(hydroflow),
                             true,
+                            false,
+                            false,
                             quote!(),
                             &mut diagnostics,
                         );